@@ -0,0 +1,85 @@
+//! Measures deserializing a struct with many fields whose keys are written with a single
+//! `write_str` call each since they are precomputed when the field deserializers are created.
+//! The number of writes per deserialized account is printed before the benchmark runs.
+
+use std::fmt;
+
+use chainparser::{
+    borsh::BorshDeserializer,
+    json::{
+        JsonIdlTypeDefinitionDeserializer, JsonSerializationOpts, JsonWrite,
+    },
+};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use solana_idl::{IdlField, IdlType, IdlTypeDefinition, IdlTypeDefinitionTy};
+
+const FIELDS: usize = 32;
+
+/// Counts the writes issued by the deserializer while collecting the JSON.
+#[derive(Default)]
+struct CountingWriter {
+    json: String,
+    writes: usize,
+}
+
+impl fmt::Write for CountingWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.writes += 1;
+        self.json.write_str(s)
+    }
+
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        self.writes += 1;
+        self.json.write_char(c)
+    }
+}
+
+impl JsonWrite for CountingWriter {}
+
+fn wide_definition() -> IdlTypeDefinition {
+    let fields = (0..FIELDS)
+        .map(|i| IdlField {
+            name: format!("field_{i}"),
+            ty: IdlType::U8,
+            attrs: None,
+        })
+        .collect();
+    IdlTypeDefinition {
+        name: "Wide".to_string(),
+        ty: IdlTypeDefinitionTy::Struct { fields },
+    }
+}
+
+fn field_keys(c: &mut Criterion) {
+    let opts = JsonSerializationOpts::default();
+    let type_map = Default::default();
+    let account_de = JsonIdlTypeDefinitionDeserializer::new(
+        &wide_definition(),
+        type_map,
+        &opts,
+    );
+    let data = [1u8; FIELDS];
+
+    let mut writer = CountingWriter::default();
+    account_de
+        .deserialize(&BorshDeserializer, &mut writer, &mut &data[..])
+        .unwrap();
+    println!(
+        "{} writes to deserialize {FIELDS} fields ({} bytes of JSON)",
+        writer.writes,
+        writer.json.len()
+    );
+
+    c.bench_function("wide account field keys", |b| {
+        b.iter(|| {
+            let mut writer = CountingWriter::default();
+            account_de
+                .deserialize(&BorshDeserializer, &mut writer, &mut &data[..])
+                .unwrap();
+            black_box(writer.writes)
+        })
+    });
+}
+
+criterion_group!(benches, field_keys);
+criterion_main!(benches);
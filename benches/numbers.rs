@@ -0,0 +1,89 @@
+//! Measures deserializing an account holding a large `Vec<u64>`.
+//! The number of heap allocations per deserialized account is printed before the benchmark
+//! runs, writing into a preallocated [String] so only allocations of the deserializer count.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use chainparser::{
+    borsh::BorshDeserializer,
+    json::{JsonIdlTypeDefinitionDeserializer, JsonSerializationOpts},
+};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use solana_idl::{IdlField, IdlType, IdlTypeDefinition, IdlTypeDefinitionTy};
+
+const LEN: u32 = 10_000;
+
+/// Counts the allocations made through the global allocator.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn numbers_definition() -> IdlTypeDefinition {
+    IdlTypeDefinition {
+        name: "Numbers".to_string(),
+        ty: IdlTypeDefinitionTy::Struct {
+            fields: vec![IdlField {
+                name: "values".to_string(),
+                ty: IdlType::Vec(Box::new(IdlType::U64)),
+                attrs: None,
+            }],
+        },
+    }
+}
+
+fn numbers_data() -> Vec<u8> {
+    LEN.to_le_bytes()
+        .into_iter()
+        .chain((0..LEN as u64).flat_map(|n| (n * 1_000_003).to_le_bytes()))
+        .collect()
+}
+
+fn numbers(c: &mut Criterion) {
+    let opts = JsonSerializationOpts::default();
+    let account_de = JsonIdlTypeDefinitionDeserializer::new(
+        &numbers_definition(),
+        Default::default(),
+        &opts,
+    );
+    let data = numbers_data();
+
+    let mut json = String::with_capacity(data.len() * 4);
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    account_de
+        .deserialize(&BorshDeserializer, &mut json, &mut &data[..])
+        .unwrap();
+    println!(
+        "{} allocations to deserialize a Vec<u64> of {LEN} elements",
+        ALLOCATIONS.load(Ordering::Relaxed) - before
+    );
+
+    c.bench_function("vec of u64 account", |b| {
+        b.iter(|| {
+            json.clear();
+            account_de
+                .deserialize(&BorshDeserializer, &mut json, &mut &data[..])
+                .unwrap();
+            black_box(json.len())
+        })
+    });
+}
+
+criterion_group!(benches, numbers);
+criterion_main!(benches);
@@ -0,0 +1,89 @@
+//! Compares deserializing a deeply nested account with a [SharedTypeMap] vs. a [LocalTypeMap].
+//! Each nested defined type is resolved via the type map, thus the cost of accessing it is paid
+//! once per level.
+
+use chainparser::{
+    borsh::BorshDeserializer,
+    json::{
+        JsonIdlTypeDefinitionDeserializer, JsonSerializationOpts, LocalTypeMap,
+        SharedTypeMap, TypeMapKind,
+    },
+};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use solana_idl::{IdlField, IdlType, IdlTypeDefinition, IdlTypeDefinitionTy};
+
+const DEPTH: usize = 64;
+
+fn field(name: &str, ty: IdlType) -> IdlField {
+    IdlField {
+        name: name.to_string(),
+        ty,
+        attrs: None,
+    }
+}
+
+/// Defines `Level0` which nests `Level1` and so on down to `Level{DEPTH}` which only holds a
+/// value.
+fn nested_definitions() -> Vec<IdlTypeDefinition> {
+    (0..=DEPTH)
+        .map(|level| {
+            let mut fields = vec![field("value", IdlType::U32)];
+            if level < DEPTH {
+                fields.push(field(
+                    "child",
+                    IdlType::Defined(format!("Level{}", level + 1)),
+                ));
+            }
+            IdlTypeDefinition {
+                name: format!("Level{level}"),
+                ty: IdlTypeDefinitionTy::Struct { fields },
+            }
+        })
+        .collect()
+}
+
+fn nested_data() -> Vec<u8> {
+    (0..=DEPTH as u32)
+        .flat_map(|level| level.to_le_bytes())
+        .collect()
+}
+
+fn bench_type_map<K: TypeMapKind>(c: &mut Criterion, name: &str) {
+    let opts = JsonSerializationOpts::default();
+    let type_map = K::new_map();
+    let definitions = nested_definitions();
+    for definition in &definitions {
+        K::insert(
+            &type_map,
+            definition.name.clone(),
+            JsonIdlTypeDefinitionDeserializer::<K>::with_type_map(
+                definition,
+                type_map.clone(),
+                &opts,
+            ),
+        );
+    }
+    let account_de =
+        K::get(&type_map, "Level0").expect("Level0 should be defined");
+    let data = nested_data();
+
+    c.bench_function(name, |b| {
+        b.iter(|| {
+            let mut json = String::new();
+            account_de
+                .deserialize(&BorshDeserializer, &mut json, &mut &data[..])
+                .unwrap();
+            black_box(json)
+        })
+    });
+
+    K::with_map(&type_map, |map| map.clear());
+}
+
+fn nested_account(c: &mut Criterion) {
+    bench_type_map::<SharedTypeMap>(c, "nested account shared type map");
+    bench_type_map::<LocalTypeMap>(c, "nested account local type map");
+}
+
+criterion_group!(benches, nested_account);
+criterion_main!(benches);
@@ -0,0 +1,253 @@
+//! Generates typed account structs from an IDL, see [macro@idl_account].
+
+use std::{fs, path::PathBuf};
+
+use heck::ToSnakeCase;
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use solana_idl::{Idl, IdlType, IdlTypeDefinitionTy};
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Ident, LitStr};
+
+/// Generates the fields of a unit struct from the account with the same name defined in an IDL
+/// and implements `chainparser::typed::IdlAccount` for it, which provides a
+/// `from_account_data(&[u8])` constructor.
+///
+/// ```ignore
+/// #[idl_account(idl = "idls/vault.json")]
+/// pub struct VaultInfo;
+///
+/// let vault = VaultInfo::from_account_data(&data)?;
+/// println!("{}", vault.authority);
+/// ```
+///
+/// - `idl` is the path of the IDL JSON relative to the manifest of the crate
+/// - `account` is the name of the account in the IDL and defaults to the name of the struct
+/// - `provider` is `anchor` (default), `shank` or `codama`, for anchor accounts the data is
+///   expected to be prefixed with the account discriminator
+///
+/// Only struct accounts are supported whose fields are primitives, strings, bytes, public keys
+/// or a `Vec` or `Option` of those.
+#[proc_macro_attribute]
+pub fn idl_account(args: TokenStream, input: TokenStream) -> TokenStream {
+    let mut args_parser = IdlAccountArgs::default();
+    let parser = syn::meta::parser(|meta| args_parser.parse(meta));
+    parse_macro_input!(args with parser);
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand(args_parser, input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[derive(Default)]
+struct IdlAccountArgs {
+    idl: Option<LitStr>,
+    account: Option<LitStr>,
+    provider: Option<LitStr>,
+}
+
+impl IdlAccountArgs {
+    fn parse(&mut self, meta: syn::meta::ParseNestedMeta) -> syn::Result<()> {
+        if meta.path.is_ident("idl") {
+            self.idl = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("account") {
+            self.account = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("provider") {
+            self.provider = Some(meta.value()?.parse()?);
+        } else {
+            return Err(meta.error("expected `idl`, `account` or `provider`"));
+        }
+        Ok(())
+    }
+}
+
+fn expand(
+    args: IdlAccountArgs,
+    input: DeriveInput,
+) -> syn::Result<TokenStream2> {
+    let idl_path = args.idl.ok_or_else(|| {
+        syn::Error::new(Span::call_site(), "missing `idl = \"path\"` argument")
+    })?;
+    match &input.data {
+        Data::Struct(data)
+            if matches!(data.fields, Fields::Unit) && input.generics.params.is_empty() => {}
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "#[idl_account] needs to be applied to a unit struct without generics",
+            ))
+        }
+    }
+
+    let struct_name = &input.ident;
+    let account_name = args
+        .account
+        .map(|account| account.value())
+        .unwrap_or_else(|| struct_name.to_string());
+    let provider = match args.provider.as_ref().map(LitStr::value).as_deref() {
+        None | Some("anchor") => quote!(Anchor),
+        Some("shank") => quote!(Shank),
+        Some("codama") => quote!(Codama),
+        Some(_) => {
+            return Err(syn::Error::new_spanned(
+                &args.provider,
+                "expected `anchor`, `shank` or `codama`",
+            ))
+        }
+    };
+
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let full_path = PathBuf::from(manifest_dir).join(idl_path.value());
+    let idl_json = fs::read_to_string(&full_path).map_err(|e| {
+        syn::Error::new_spanned(
+            &idl_path,
+            format!("failed to read IDL at {}: {e}", full_path.display()),
+        )
+    })?;
+    let idl: Idl = serde_json::from_str(&idl_json).map_err(|e| {
+        syn::Error::new_spanned(&idl_path, format!("failed to parse IDL: {e}"))
+    })?;
+
+    let account = idl
+        .accounts
+        .iter()
+        .find(|account| account.name == account_name)
+        .ok_or_else(|| {
+            syn::Error::new_spanned(
+                &idl_path,
+                format!("IDL has no account named '{account_name}'"),
+            )
+        })?;
+    let IdlTypeDefinitionTy::Struct { fields } = &account.ty else {
+        return Err(syn::Error::new_spanned(
+            &idl_path,
+            format!("account '{account_name}' is not a struct"),
+        ));
+    };
+
+    let fields = fields
+        .iter()
+        .map(|field| {
+            let (ty, deserialize_with) = rust_type(&field.ty).ok_or_else(|| {
+                syn::Error::new_spanned(
+                    &idl_path,
+                    format!(
+                        "field '{}' of account '{account_name}' has the unsupported type {:?}",
+                        field.name, field.ty
+                    ),
+                )
+            })?;
+            let ident = field_ident(&field.name);
+            let name = &field.name;
+            let deserialize_with = deserialize_with.map(|path| {
+                let path = format!("::chainparser::de::{path}");
+                quote!(#[serde(deserialize_with = #path)])
+            });
+            Ok(quote! {
+                #[serde(rename = #name)]
+                #deserialize_with
+                pub #ident: #ty
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let attrs = &input.attrs;
+    let vis = &input.vis;
+    let full_path = full_path.display().to_string();
+    Ok(quote! {
+        #(#attrs)*
+        #[derive(
+            ::std::fmt::Debug,
+            ::std::clone::Clone,
+            ::std::cmp::PartialEq,
+            ::chainparser::typed::__private::serde::Deserialize,
+        )]
+        #[serde(crate = "::chainparser::typed::__private::serde")]
+        #vis struct #struct_name {
+            #(#fields),*
+        }
+
+        impl ::chainparser::typed::IdlAccount for #struct_name {
+            const ACCOUNT_NAME: &'static str = #account_name;
+            const IDL_JSON: &'static str = ::std::include_str!(#full_path);
+            const IDL_PROVIDER: ::chainparser::idl::IdlProvider =
+                ::chainparser::idl::IdlProvider::#provider;
+
+            fn deserializer_cell() -> &'static ::std::sync::OnceLock<
+                ::chainparser::ChainparserDeserializer<'static>,
+            > {
+                static CELL: ::std::sync::OnceLock<
+                    ::chainparser::ChainparserDeserializer<'static>,
+                > = ::std::sync::OnceLock::new();
+                &CELL
+            }
+        }
+
+        impl #struct_name {
+            /// Deserializes the account from its data, see
+            /// `chainparser::typed::IdlAccount::from_account_data`.
+            pub fn from_account_data(
+                data: &[u8],
+            ) -> ::chainparser::errors::ChainparserResult<Self> {
+                <Self as ::chainparser::typed::IdlAccount>::from_account_data(data)
+            }
+        }
+    })
+}
+
+/// Resolves the Rust type of a field of the given IDL [ty] along with the function in
+/// `chainparser::de` that is needed to deserialize it from the JSON if any.
+fn rust_type(ty: &IdlType) -> Option<(TokenStream2, Option<&'static str>)> {
+    let pubkey = quote!(::chainparser::typed::__private::Pubkey);
+    let resolved = match ty {
+        IdlType::PublicKey => (pubkey, Some("pubkey_from_base58")),
+        IdlType::U128 => (quote!(u128), Some("u128_from_string")),
+        IdlType::I128 => (quote!(i128), Some("i128_from_string")),
+        IdlType::Option(inner) if matches!(**inner, IdlType::PublicKey) => (
+            quote!(::std::option::Option<#pubkey>),
+            Some("opt_pubkey_from_base58"),
+        ),
+        IdlType::Vec(inner) if matches!(**inner, IdlType::PublicKey) => (
+            quote!(::std::vec::Vec<#pubkey>),
+            Some("vec_pubkey_from_base58"),
+        ),
+        IdlType::Option(inner) => {
+            let inner = primitive_type(inner)?;
+            (quote!(::std::option::Option<#inner>), None)
+        }
+        IdlType::Vec(inner) => {
+            let inner = primitive_type(inner)?;
+            (quote!(::std::vec::Vec<#inner>), None)
+        }
+        ty => (primitive_type(ty)?, None),
+    };
+    Some(resolved)
+}
+
+/// Resolves the Rust type of primitives that deserialize from JSON as is.
+fn primitive_type(ty: &IdlType) -> Option<TokenStream2> {
+    let ty = match ty {
+        IdlType::Bool => quote!(bool),
+        IdlType::U8 => quote!(u8),
+        IdlType::U16 => quote!(u16),
+        IdlType::U32 => quote!(u32),
+        IdlType::U64 => quote!(u64),
+        IdlType::I8 => quote!(i8),
+        IdlType::I16 => quote!(i16),
+        IdlType::I32 => quote!(i32),
+        IdlType::I64 => quote!(i64),
+        IdlType::F32 => quote!(f32),
+        IdlType::F64 => quote!(f64),
+        IdlType::String => quote!(::std::string::String),
+        IdlType::Bytes => quote!(::std::vec::Vec<u8>),
+        _ => return None,
+    };
+    Some(ty)
+}
+
+/// Converts the IDL field [name] to a snake case identifier, escaping keywords.
+fn field_ident(name: &str) -> Ident {
+    let name = name.to_snake_case();
+    syn::parse_str::<Ident>(&name).unwrap_or_else(|_| format_ident!("r#{name}"))
+}
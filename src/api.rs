@@ -1,14 +1,42 @@
-use std::collections::{HashMap, HashSet};
 pub use std::fmt::Write;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs, io,
+    ops::Range,
+    path::Path,
+    str::FromStr,
+};
 
-use solana_idl::Idl;
-use solana_sdk::pubkey::Pubkey;
+use base64::{prelude::BASE64_STANDARD, Engine as _};
+use solana_idl::{Idl, IdlConst, IdlTypeDefinition};
 
-pub use crate::json::{JsonAccountsDeserializer, JsonSerializationOpts};
+pub use crate::json::{
+    JsonAccountsDeserializer, JsonSerializationOpts, OptsRef,
+};
 use crate::{
     deserializer::DeserializeProvider,
+    discriminator::{
+        match_discriminator::{AmbiguousMatchPolicy, MatchConfidence},
+        DiscriminatorCollision, DiscriminatorStrategy,
+    },
     errors::{ChainparserError, ChainparserResult},
-    idl::{try_find_idl_for_program, IdlProvider, IDL_PROVIDERS},
+    format::ChainparserVisitor,
+    idl::{
+        apply_field_type_overrides, error_code_from_log, find_idl_error,
+        spl_token_2022_extensions, spl_token_2022_idl, spl_token_idl,
+        try_idl_address, unzip_idl_account_json, verify_acyclic_types,
+        FieldTypeOverrides, IdlProvider, IDL_PROVIDERS,
+        SPL_TOKEN_2022_ACCOUNT_ACCOUNT_TYPE,
+        SPL_TOKEN_2022_ACCOUNT_TYPE_OFFSET, SPL_TOKEN_2022_MINT_ACCOUNT_TYPE,
+        SPL_TOKEN_2022_PROGRAM_ID, SPL_TOKEN_ACCOUNT_SIZE, SPL_TOKEN_MINT_SIZE,
+        SPL_TOKEN_PROGRAM_ID,
+    },
+    json::{
+        flatten_json_value, FieldInfo, FmtJsonWriter, IoJsonWriter, JsonWrite,
+        TypeRegistry,
+    },
+    pubkey::Pubkey,
+    rpc::RpcAccountInfo,
     traits::AccountProvider,
 };
 
@@ -23,7 +51,35 @@ pub struct ChainparserDeserializer<'opts> {
         HashMap<String, JsonAccountsDeserializer<'opts>>,
 
     /// The [JsonSerializationOpts] specifying how specific data types should be deserialized.
-    json_serialization_opts: &'opts JsonSerializationOpts,
+    json_serialization_opts: OptsRef<'opts>,
+
+    /// Stores the type deserializers shared by all IDLs if enabled, see
+    /// [ChainparserDeserializer::with_shared_types].
+    type_registry: Option<TypeRegistry<'opts>>,
+
+    /// Used to fetch the IDLs of programs that weren't added yet if set, see
+    /// [ChainparserDeserializer::set_account_provider].
+    account_provider: Option<Box<dyn AccountProvider + Send + Sync + 'opts>>,
+
+    /// Programs for which no IDL account was found, see
+    /// [ChainparserDeserializer::try_add_idl_for_program].
+    programs_without_idl: HashSet<Pubkey>,
+}
+
+impl ChainparserDeserializer<'static> {
+    /// Creates an instance of a [ChainparserDeserializer] that owns its [JsonSerializationOpts].
+    /// The resulting deserializer is `'static` as well as `Send + Sync` and thus can be shared
+    /// across threads behind an [std::sync::Arc], i.e. by the request handlers of a server.
+    ///
+    /// The opts are shared via an [std::sync::Arc] by all deserializers that refer to them and
+    /// are dropped together with the [ChainparserDeserializer].
+    ///
+    /// - [serialization_opts] specifying how specific data types should be deserialized.
+    pub fn with_owned_opts(
+        json_serialization_opts: JsonSerializationOpts,
+    ) -> Self {
+        Self::new(json_serialization_opts)
+    }
 }
 
 impl<'opts> ChainparserDeserializer<'opts> {
@@ -32,42 +88,127 @@ impl<'opts> ChainparserDeserializer<'opts> {
     /// to deserialize accounts for it.
     ///
     /// - [serialization_opts] specifying how specific data types should be deserialized.
-    pub fn new(json_serialization_opts: &'opts JsonSerializationOpts) -> Self {
+    pub fn new(json_serialization_opts: impl Into<OptsRef<'opts>>) -> Self {
+        let json_serialization_opts = json_serialization_opts.into();
         Self {
             json_account_deserializers: HashMap::new(),
             json_serialization_opts,
+            type_registry: None,
+            account_provider: None,
+            programs_without_idl: HashSet::new(),
         }
     }
 
+    /// Creates an instance of a [ChainparserDeserializer] that stores defined types which are
+    /// identical across the IDLs it adds only once, see [TypeRegistry].
+    /// This reduces memory when many IDLs of programs that share types are loaded, while types
+    /// with the same name but different definitions are still kept apart.
+    ///
+    /// NOTE: types of IDLs added this way cannot be configured via
+    /// [ChainparserDeserializer::set_type_serializer] nor
+    /// [ChainparserDeserializer::set_type_repr_c], except for the `repr(C)` layout of accounts.
+    ///
+    /// - [serialization_opts] specifying how specific data types should be deserialized.
+    pub fn with_shared_types(
+        json_serialization_opts: impl Into<OptsRef<'opts>>,
+    ) -> Self {
+        let json_serialization_opts = json_serialization_opts.into();
+        Self {
+            type_registry: Some(TypeRegistry::new(
+                json_serialization_opts.clone(),
+            )),
+            ..Self::new(json_serialization_opts)
+        }
+    }
+
+    /// The number of distinct types stored for all IDLs if types are shared, see
+    /// [ChainparserDeserializer::with_shared_types].
+    pub fn shared_type_count(&self) -> Option<usize> {
+        self.type_registry.as_ref().map(TypeRegistry::len)
+    }
+
     /// Attempts to find the IDL account for the given [program_id] and adds it to the
     /// deserializer.
-    /// It first tries to find an anchor IDl account and then tries shank.
+    /// It first tries to find an anchor IDl account and then tries shank and codama.
     /// Returns [Some::<IdlProvider>] if the IDL was found and added, and [None::<IdlProvider>] if
-    /// neither a shank nor an anchor IDL account was found.
+    /// no anchor, shank nor codama IDL account was found.
+    ///
+    /// Programs without an IDL are remembered and not looked up again until they are
+    /// invalidated via [ChainparserDeserializer::invalidate], i.e. once they upload an IDL.
     pub fn try_add_idl_for_program<T: AccountProvider>(
         &mut self,
         account_provider: &T,
         program_id: &Pubkey,
     ) -> ChainparserResult<Option<IdlProvider>> {
+        if self.programs_without_idl.contains(program_id) {
+            return Ok(None);
+        }
         for idl_provider in IDL_PROVIDERS {
-            if let Some(idl) = try_find_idl_for_program(
-                account_provider,
-                program_id,
-                idl_provider,
-            )? {
-                self.add_idl(
+            let idl_address = try_idl_address(idl_provider, program_id)?;
+            if let Some((account, _)) =
+                account_provider.get_account(&idl_address)
+            {
+                self.add_idl_from_account_data(
                     program_id.to_string(),
-                    idl,
+                    &account.data,
                     idl_provider.clone(),
                 )?;
                 return Ok(Some(idl_provider.clone()));
             }
         }
+        self.programs_without_idl.insert(*program_id);
         Ok(None)
     }
 
+    /// Forgets that no IDL account was found for the [program_id] so that the next
+    /// [ChainparserDeserializer::try_add_idl_for_program] looks it up again.
+    /// Returns `true` if the program was known to have no IDL.
+    pub fn invalidate(&mut self, program_id: &Pubkey) -> bool {
+        self.programs_without_idl.remove(program_id)
+    }
+
+    /// Sets the [account_provider] used to fetch the IDL of a program on demand when one of its
+    /// accounts is deserialized via
+    /// [ChainparserDeserializer::deserialize_account_to_json_resolving] before its IDL was
+    /// added, turning the deserializer into a self-populating cache.
+    /// Without it no IDLs are fetched, thus all other methods never perform any IO.
+    pub fn set_account_provider(
+        &mut self,
+        account_provider: impl AccountProvider + Send + Sync + 'opts,
+    ) {
+        self.account_provider = Some(Box::new(account_provider));
+    }
+
+    /// Adds the IDL for the [id] via the account provider, see
+    /// [ChainparserDeserializer::set_account_provider], unless it was added already.
+    /// Returns `true` if the IDL is present afterwards.
+    ///
+    /// - [id] is the program id whose IDL account is fetched
+    pub fn resolve_idl(&mut self, id: &str) -> ChainparserResult<bool> {
+        if self.has_idl(id) {
+            return Ok(true);
+        }
+        let Some(account_provider) = self.account_provider.take() else {
+            return Ok(false);
+        };
+        let res = Pubkey::from_str(id)
+            .map_err(|err| {
+                ChainparserError::FailedToParseIdlProgramPubkey(
+                    id.to_string(),
+                    format!("{:#?}", err),
+                )
+            })
+            .and_then(|program_id| {
+                self.try_add_idl_for_program(&account_provider, &program_id)
+            });
+        self.account_provider = Some(account_provider);
+        Ok(res?.is_some())
+    }
+
     /// Parses an [IDL] specification from the provided [idl_json] for the [id] and adds a
     /// json accounts deserializer derived from it.
+    /// Both the classic and the new anchor IDL format are supported, see
+    /// [crate::parse_idl_json].
     /// The id is usually the program id, possibly combined with the slot at which the IDL was
     /// uploaded.
     pub fn add_idl_json(
@@ -76,16 +217,74 @@ impl<'opts> ChainparserDeserializer<'opts> {
         idl_json: &str,
         provider: IdlProvider,
     ) -> ChainparserResult<()> {
-        let json_deserializer = JsonAccountsDeserializer::try_from_idl(
-            idl_json,
-            provider,
-            self.json_serialization_opts,
-        )?;
+        let json_deserializer = match &mut self.type_registry {
+            Some(registry) => {
+                JsonAccountsDeserializer::try_from_idl_with_type_registry(
+                    idl_json,
+                    provider,
+                    self.json_serialization_opts.clone(),
+                    registry,
+                )?
+            }
+            None => JsonAccountsDeserializer::try_from_idl(
+                idl_json,
+                provider,
+                self.json_serialization_opts.clone(),
+            )?,
+        };
         self.json_account_deserializers
             .insert(id, json_deserializer);
         Ok(())
     }
 
+    /// Reads the IDL JSON file at the given [path] and adds a json accounts deserializer derived
+    /// from it for the [id], see [ChainparserDeserializer::add_idl_json].
+    /// Both the classic and the new anchor IDL format are supported.
+    pub fn add_idl_file(
+        &mut self,
+        id: String,
+        path: impl AsRef<Path>,
+        provider: IdlProvider,
+    ) -> ChainparserResult<()> {
+        let path = path.as_ref();
+        let idl_json = fs::read_to_string(path).map_err(|err| {
+            ChainparserError::IdlFileReadError(path.display().to_string(), err)
+        })?;
+        self.add_idl_json(id, &idl_json, provider).map_err(|err| {
+            ChainparserError::IdlFileError(
+                path.display().to_string(),
+                Box::new(err),
+            )
+        })
+    }
+
+    /// Parses an [IDL] specification from the provided raw, i.e. already decompressed, JSON
+    /// [idl_bytes] for the [id] and adds a json accounts deserializer derived from it, see
+    /// [ChainparserDeserializer::add_idl_json].
+    pub fn add_idl_bytes(
+        &mut self,
+        id: String,
+        idl_bytes: &[u8],
+        provider: IdlProvider,
+    ) -> ChainparserResult<()> {
+        let idl_json = std::str::from_utf8(idl_bytes)
+            .map_err(|err| ChainparserError::IdlInvalidUtf8(err.to_string()))?;
+        self.add_idl_json(id, idl_json, provider)
+    }
+
+    /// Decodes the [IDL] from the data of an IDL account as stored on chain, stripping its
+    /// header and unzipping the JSON, and adds a json accounts deserializer derived from it for
+    /// the [id], see [ChainparserDeserializer::add_idl_json].
+    pub fn add_idl_from_account_data(
+        &mut self,
+        id: String,
+        account_data: &[u8],
+        provider: IdlProvider,
+    ) -> ChainparserResult<()> {
+        let idl_json = unzip_idl_account_json(account_data)?;
+        self.add_idl_json(id, &idl_json, provider)
+    }
+
     /// Adds [IDL] specification from the provided [idl] for the [id] and adds a
     /// json accounts deserializer derived from it.
     /// The id is usually the program id, possibly combined with the slot at which the IDL was
@@ -96,25 +295,366 @@ impl<'opts> ChainparserDeserializer<'opts> {
         idl: Idl,
         provider: IdlProvider,
     ) -> ChainparserResult<()> {
+        self.json_serialization_opts.validate()?;
+        verify_acyclic_types(&idl)?;
         let de_provider = DeserializeProvider::try_from(&idl)?;
 
-        let json_deserializer = JsonAccountsDeserializer::from_idl(
-            &idl,
-            de_provider,
-            provider,
-            self.json_serialization_opts,
-        );
+        let json_deserializer = match &mut self.type_registry {
+            Some(registry) => {
+                JsonAccountsDeserializer::from_idl_with_type_registry(
+                    &idl,
+                    &HashMap::new(),
+                    de_provider,
+                    provider,
+                    self.json_serialization_opts.clone(),
+                    registry,
+                )
+            }
+            None => JsonAccountsDeserializer::from_idl(
+                &idl,
+                de_provider,
+                provider,
+                self.json_serialization_opts.clone(),
+            ),
+        };
         self.json_account_deserializers
             .insert(id, json_deserializer);
         Ok(())
     }
 
+    /// Adds the built-in IDL of the SPL token program, see [spl_token_idl], keyed by the SPL
+    /// token program id so that token `Account`s and `Mint`s can be decoded without supplying
+    /// an IDL.
+    pub fn add_spl_token_idl(&mut self) -> ChainparserResult<()> {
+        self.add_idl(
+            SPL_TOKEN_PROGRAM_ID.to_string(),
+            spl_token_idl(),
+            IdlProvider::Shank,
+        )
+    }
+
+    /// Adds the built-in IDL of the Token-2022 program, see [spl_token_2022_idl], keyed by the
+    /// Token-2022 program id.
+    /// The extensions following the base `Account`s and `Mint`s are decoded into a `tlv` field,
+    /// see [spl_token_2022_extensions] for the extensions with known layouts.
+    pub fn add_spl_token_2022_idl(&mut self) -> ChainparserResult<()> {
+        let id = SPL_TOKEN_2022_PROGRAM_ID;
+        self.add_idl(id.to_string(), spl_token_2022_idl(), IdlProvider::Shank)?;
+        let deserializer = self
+            .json_account_deserializers
+            .get_mut(id)
+            .expect("IDL was just added");
+
+        // The account type directly precedes the extensions, mints are padded to the size of
+        // token accounts before it
+        let extensions = spl_token_2022_extensions();
+        for (account_name, size, account_type) in [
+            (
+                "Mint",
+                SPL_TOKEN_MINT_SIZE,
+                SPL_TOKEN_2022_MINT_ACCOUNT_TYPE,
+            ),
+            (
+                "Account",
+                SPL_TOKEN_ACCOUNT_SIZE,
+                SPL_TOKEN_2022_ACCOUNT_ACCOUNT_TYPE,
+            ),
+        ] {
+            deserializer.set_account_tag_byte(
+                account_name,
+                SPL_TOKEN_2022_ACCOUNT_TYPE_OFFSET,
+                account_type,
+            )?;
+            deserializer.set_account_tlv_entries_at(
+                account_name,
+                SPL_TOKEN_2022_ACCOUNT_TYPE_OFFSET - size + 1,
+                &extensions,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Same as [ChainparserDeserializer::add_idl], but decodes the fields in [overrides] with
+    /// the provided type instead of the one declared in the [idl].
+    /// This allows to fix incorrect IDLs that were already published on chain.
+    ///
+    /// - [overrides] maps `(account name, field path)` to the type to use, the field path is a
+    ///   field name of the account or a dotted path into fields of defined types, i.e. `config.fee`
+    pub fn add_idl_with_overrides(
+        &mut self,
+        id: String,
+        mut idl: Idl,
+        provider: IdlProvider,
+        overrides: &FieldTypeOverrides,
+    ) -> ChainparserResult<()> {
+        apply_field_type_overrides(&mut idl, overrides)?;
+        self.add_idl(id, idl, provider)
+    }
+
+    /// Makes the accounts and types of the IDL with the given [from_id] available to the IDL with
+    /// the given [id], i.e. when a program references types of another program's CPI interface.
+    ///
+    /// The IDL with the given [id] references them by namespaced names of the form
+    /// `<IDL name>::<type name>`, i.e. `{ "defined": "other_program::SharedState" }` where
+    /// `other_program` is the `name` declared by the IDL with the given [from_id].
+    /// Both IDLs need to be added before.
+    pub fn import_idl_types(
+        &mut self,
+        id: &str,
+        from_id: &str,
+    ) -> ChainparserResult<()> {
+        let from_idl = self.idl(from_id).cloned().ok_or_else(|| {
+            ChainparserError::CannotFindAccountDeserializerForProgramId(
+                from_id.to_string(),
+            )
+        })?;
+        let deserializer =
+            self.json_account_deserializers.get(id).ok_or_else(|| {
+                ChainparserError::CannotFindAccountDeserializerForProgramId(
+                    id.to_string(),
+                )
+            })?;
+        deserializer.import_types(&from_idl.name, &from_idl)
+    }
+
+    /// Configures the type with the given [type_name] of the IDL with the given [id] to be
+    /// deserialized with the given [serializer] (i.e. "borsh" or "spl") instead of the
+    /// serializer of the account that includes it.
+    pub fn set_type_serializer(
+        &self,
+        id: &str,
+        type_name: &str,
+        serializer: &str,
+    ) -> ChainparserResult<()> {
+        let deserializer =
+            self.json_account_deserializers.get(id).ok_or_else(|| {
+                ChainparserError::CannotFindAccountDeserializerForProgramId(
+                    id.to_string(),
+                )
+            })?;
+        let de_provider = DeserializeProvider::try_from(Some(serializer))?;
+        deserializer.set_type_de_provider(type_name, de_provider)
+    }
+
+    /// Configures the account or type with the given [type_name] of the IDL with the given [id]
+    /// to be deserialized per the `repr(C, align(N))` layout, as is the case for zero-copy
+    /// accounts.
+    /// Pass an [align] of `1` for types declared with plain `repr(C)`.
+    ///
+    /// Accounts configured this way are matched by their padded layout when the IDL doesn't
+    /// declare discriminators, and [ChainparserDeserializer::field_offsets] resolves their
+    /// padded field ranges.
+    pub fn set_type_repr_c(
+        &mut self,
+        id: &str,
+        type_name: &str,
+        align: usize,
+    ) -> ChainparserResult<()> {
+        let deserializer =
+            self.json_account_deserializers.get_mut(id).ok_or_else(|| {
+                ChainparserError::CannotFindAccountDeserializerForProgramId(
+                    id.to_string(),
+                )
+            })?;
+        deserializer.set_type_repr_c(type_name, align)
+    }
+
+    /// Configures the account with the given [account_name] of the IDL with the given [id] to
+    /// be followed by a region of TLV entries, as is the case for extensible accounts.
+    /// The layouts of known entries are provided via [entries] keyed by their type
+    /// discriminator, unknown entries are emitted as hex.
+    pub fn set_account_tlv_entries(
+        &mut self,
+        id: &str,
+        account_name: &str,
+        entries: &HashMap<u16, IdlTypeDefinition>,
+    ) -> ChainparserResult<()> {
+        let deserializer =
+            self.json_account_deserializers.get_mut(id).ok_or_else(|| {
+                ChainparserError::CannotFindAccountDeserializerForProgramId(
+                    id.to_string(),
+                )
+            })?;
+        deserializer.set_account_tlv_entries(account_name, entries)
+    }
+
+    /// Configures how to proceed when multiple accounts of the IDL with the given [id] match
+    /// the account data equally well.
+    /// Only applies to accounts that are resolved by matching their shape, i.e. shank accounts.
+    pub fn set_ambiguous_match_policy(
+        &mut self,
+        id: &str,
+        policy: AmbiguousMatchPolicy,
+    ) -> ChainparserResult<()> {
+        let deserializer =
+            self.json_account_deserializers.get_mut(id).ok_or_else(|| {
+                ChainparserError::CannotFindAccountDeserializerForProgramId(
+                    id.to_string(),
+                )
+            })?;
+        deserializer.set_ambiguous_match_policy(policy);
+        Ok(())
+    }
+
+    /// Enables preferring accounts of the IDL with the given [id] whose leading pubkey field
+    /// holds a key on the ed25519 curve, i.e. a keypair authority, when multiple accounts match
+    /// the account data equally well.
+    /// This is opt-in since the check isn't definitive, i.e. authorities may be PDAs which are
+    /// off the curve.
+    /// Only applies to accounts that are resolved by matching their shape, i.e. shank accounts.
+    pub fn set_pubkey_on_curve_tie_breaker(
+        &mut self,
+        id: &str,
+        enabled: bool,
+    ) -> ChainparserResult<()> {
+        let deserializer =
+            self.json_account_deserializers.get_mut(id).ok_or_else(|| {
+                ChainparserError::CannotFindAccountDeserializerForProgramId(
+                    id.to_string(),
+                )
+            })?;
+        deserializer.set_pubkey_on_curve_tie_breaker(enabled);
+        Ok(())
+    }
+
+    /// Configures the accounts of the IDL with the given [id] to be resolved via discriminators
+    /// derived with the provided [namespace] instead of the anchor `account` namespace.
+    /// This supports frameworks that copied the anchor hashing scheme with a different prefix,
+    /// i.e. `"state:<name>"`.
+    ///
+    /// NOTE: this recreates the account deserializers, thus call it before configuring accounts
+    /// via [ChainparserDeserializer::set_type_repr_c] or
+    /// [ChainparserDeserializer::set_account_tlv_entries].
+    pub fn set_account_discriminator_namespace(
+        &mut self,
+        id: &str,
+        namespace: &str,
+    ) -> ChainparserResult<()> {
+        let deserializer =
+            self.json_account_deserializers.get_mut(id).ok_or_else(|| {
+                ChainparserError::CannotFindAccountDeserializerForProgramId(
+                    id.to_string(),
+                )
+            })?;
+        deserializer.set_account_discriminator_namespace(namespace)
+    }
+
+    /// Configures the accounts of the IDL with the given [id] to be resolved via discriminators
+    /// derived with the provided [strategy] instead of the anchor one, i.e. for frameworks that
+    /// hash account names with a different hash function.
+    ///
+    /// NOTE: this recreates the account deserializers, thus call it before configuring accounts
+    /// via [ChainparserDeserializer::set_type_repr_c] or
+    /// [ChainparserDeserializer::set_account_tlv_entries].
+    pub fn set_account_discriminator_strategy(
+        &mut self,
+        id: &str,
+        strategy: &impl DiscriminatorStrategy,
+    ) -> ChainparserResult<()> {
+        let deserializer =
+            self.json_account_deserializers.get_mut(id).ok_or_else(|| {
+                ChainparserError::CannotFindAccountDeserializerForProgramId(
+                    id.to_string(),
+                )
+            })?;
+        deserializer.set_account_discriminator_strategy(strategy)
+    }
+
     pub fn account_name(&self, id: &str, account_data: &[u8]) -> Option<&str> {
         self.json_account_deserializers
             .get(id)
             .and_then(|deserializer| deserializer.account_name(account_data))
     }
 
+    /// Same as [ChainparserDeserializer::account_name], but also returns how confident the match
+    /// is for accounts that are resolved by matching their shape, i.e. shank accounts.
+    /// Indexers may treat matches with few matchers and no exact size as unknown accounts.
+    /// The confidence is `None` for accounts that are resolved via their discriminator.
+    pub fn account_name_with_confidence(
+        &self,
+        id: &str,
+        account_data: &[u8],
+    ) -> Option<(&str, Option<MatchConfidence>)> {
+        self.json_account_deserializers
+            .get(id)
+            .and_then(|deserializer| {
+                deserializer.account_name_with_confidence(account_data)
+            })
+    }
+
+    /// Resolves the name of the account of the IDL with the given [id] for the [account_data]
+    /// without deserializing it, i.e. to decide whether or how to deserialize an account.
+    /// This is free of side effects, the [account_data] is only read and nothing is cached.
+    ///
+    /// Returns the account name along with the hex encoded discriminator for accounts that are
+    /// prefixed with one or `None` for accounts that are resolved by matching their data, i.e.
+    /// shank accounts.
+    /// Returns `None` if no IDL was added for the [id] or no account matches.
+    pub fn peek_account_name(
+        &self,
+        id: &str,
+        account_data: &[u8],
+    ) -> Option<(&str, Option<String>)> {
+        self.json_account_deserializers
+            .get(id)
+            .and_then(|deserializer| {
+                deserializer.peek_account_name(account_data)
+            })
+    }
+
+    /// Counts how many of the [accounts] resolve to each account type of the IDL with the given
+    /// [id], i.e. to take a census of the accounts of a program.
+    /// Only the account names are resolved which is far cheaper than deserializing the accounts.
+    /// Accounts that don't match any account type are counted under `None`.
+    pub fn classify_accounts(
+        &self,
+        id: &str,
+        accounts: &[&[u8]],
+    ) -> HashMap<Option<String>, usize> {
+        let mut counts = HashMap::new();
+        for account_data in accounts {
+            let name = self.account_name(id, account_data).map(str::to_string);
+            *counts.entry(name).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Decodes the anchor events that were logged as `Program data: <base64>` into the [logs] of a
+    /// transaction using the events of the IDL with the given [id], see
+    /// [crate::events::EventDecoder::decode_event_logs].
+    ///
+    /// Returns the name of each decoded event along with its fields as JSON.
+    pub fn decode_event_logs<S: AsRef<str>>(
+        &self,
+        id: &str,
+        logs: &[S],
+    ) -> ChainparserResult<Vec<(String, String)>> {
+        let deserializer =
+            self.json_account_deserializers.get(id).ok_or_else(|| {
+                ChainparserError::CannotFindAccountDeserializerForProgramId(
+                    id.to_string(),
+                )
+            })?;
+        deserializer.event_decoder()?.decode_event_logs(logs)
+    }
+
+    /// Describes how the account for the [account_data] is matched for the IDL with the given
+    /// [id], listing each candidate account, which of its checks passed and which account is
+    /// selected.
+    /// Returns `None` if no IDL was added for the [id] or its accounts are resolved via their
+    /// discriminator bytes, i.e. for anchor programs.
+    pub fn explain_account_match(
+        &self,
+        id: &str,
+        account_data: &[u8],
+    ) -> Option<String> {
+        self.json_account_deserializers
+            .get(id)
+            .and_then(|deserializer| {
+                deserializer.explain_account_match(account_data)
+            })
+    }
+
     /// Returns `true` if the IDL of the given [id] has been added to the deserializer.
     /// The id is usually the program id, possibly combined with the slot at which the IDL was
     /// uploaded.
@@ -122,6 +662,66 @@ impl<'opts> ChainparserDeserializer<'opts> {
         self.json_account_deserializers.contains_key(id)
     }
 
+    /// Returns the [Idl] that was added for the given [id].
+    pub fn idl(&self, id: &str) -> Option<&Idl> {
+        self.json_account_deserializers
+            .get(id)
+            .map(|deserializer| &deserializer.idl)
+    }
+
+    /// Returns the constant with the given [name] declared in the IDL of the given [id].
+    /// IDL constants are commonly used for seed strings or max sizes which are needed to derive
+    /// PDAs or validate account data.
+    pub fn idl_constant(&self, id: &str, name: &str) -> Option<&IdlConst> {
+        self.json_account_deserializers
+            .get(id)
+            .and_then(|deserializer| deserializer.idl_constant(name))
+    }
+
+    /// Resolves the error of the IDL with the given [id] that caused the custom program error
+    /// logged in the [log_line], i.e. `Program <id> failed: custom program error: 0x1771`.
+    /// Both hex and decimal error codes are supported, see [error_code_from_log].
+    ///
+    /// Returns the name of the error along with its message if the IDL declares one.
+    /// Returns `None` if no IDL was added for the [id], the [log_line] doesn't include a custom
+    /// program error or the IDL doesn't declare an error with that code.
+    pub fn explain_error_log(
+        &self,
+        id: &str,
+        log_line: &str,
+    ) -> Option<(&str, Option<&str>)> {
+        let code = error_code_from_log(log_line)?;
+        let deserializer = self.json_account_deserializers.get(id)?;
+        let err = find_idl_error(&deserializer.idl, code)?;
+        Some((err.name.as_str(), err.msg.as_deref()))
+    }
+
+    /// Returns the byte range of each field of the fixed size account with the given
+    /// [account_name] inside its account data, i.e. to read fields of zero-copy accounts in
+    /// place without deserializing them.
+    /// Returns `None` if no IDL was added for the [id], the account is unknown or any of its
+    /// fields is variable sized.
+    pub fn field_offsets(
+        &self,
+        id: &str,
+        account_name: &str,
+    ) -> Option<Vec<(String, Range<usize>)>> {
+        self.json_account_deserializers
+            .get(id)
+            .and_then(|deserializer| deserializer.field_offsets(account_name))
+    }
+
+    /// Returns the accounts of the IDL of the given [id] whose discriminators collide.
+    /// Only the account defined first in the IDL is deserialized for a colliding discriminator.
+    pub fn discriminator_collisions(
+        &self,
+        id: &str,
+    ) -> Option<&[DiscriminatorCollision]> {
+        self.json_account_deserializers
+            .get(id)
+            .map(|deserializer| deserializer.discriminator_collisions())
+    }
+
     /// Returns all program ids for which IDLs have been added to the deserializer.
     pub fn added_idls(&self) -> HashSet<String> {
         self.json_account_deserializers.keys().cloned().collect()
@@ -129,11 +729,11 @@ impl<'opts> ChainparserDeserializer<'opts> {
 
     /// Deserializes an account to a JSON string.
     ///
-    /// In order to specify a custom [Write] writer, i.e. a socket connection to write to, use
-    /// [deserialize_account] instead.
+    /// In order to specify a custom writer, i.e. a socket connection to write to, use
+    /// [ChainparserDeserializer::deserialize_account_to_io_writer] instead.
     ///
     /// - [id] is the program id of program that owns the account, possibly combined with the slot
-    /// at which the IDL to use for deserialization was uploaded.
+    ///   at which the IDL to use for deserialization was uploaded.
     ///   make sure to add it's IDL before via [ChainparserDeserializer::add_idl_json].
     /// - [account_data] is the raw account data as a byte array
     pub fn deserialize_account_to_json_string(
@@ -142,23 +742,190 @@ impl<'opts> ChainparserDeserializer<'opts> {
         account_data: &mut &[u8],
     ) -> ChainparserResult<String> {
         let mut f = String::new();
-        self.deserialize_account_to_json(id, account_data, &mut f)?;
+        self.deserialize_account_into(id, account_data, &mut f)?;
         Ok(f)
     }
 
-    /// Deserializes an account and writes the resulting JSON to the provided [Write] write [f].
+    /// Same as [ChainparserDeserializer::deserialize_account_to_json_string], but also returns
+    /// the name of the account that was resolved for the [account_data], if any.
+    ///
+    /// If the account fails to deserialize after its name was resolved, the error is wrapped in
+    /// [ChainparserError::AccountDeserializeError] to tell which account type failed, i.e. to log
+    /// "failed to deserialize a Mint account", see [ChainparserError::account_name].
+    ///
+    /// - [id] is the program id of program that owns the account, possibly combined with the slot
+    ///   at which the IDL to use for deserialization was uploaded.
+    /// - [account_data] is the raw account data as a byte array
+    pub fn deserialize_named_account(
+        &self,
+        id: &str,
+        account_data: &mut &[u8],
+    ) -> ChainparserResult<(Option<String>, String)> {
+        let name = self.account_name(id, account_data).map(str::to_string);
+        match self.deserialize_account_to_json_string(id, account_data) {
+            Ok(json) => Ok((name, json)),
+            Err(err) => match name {
+                Some(name) => Err(ChainparserError::AccountDeserializeError(
+                    name,
+                    Box::new(err),
+                )),
+                None => Err(err),
+            },
+        }
+    }
+
+    /// Same as [ChainparserDeserializer::deserialize_account_to_json_string], but writes the JSON
+    /// into the provided [out] string in order to reuse its allocation when deserializing many
+    /// accounts.
+    ///
+    /// The [out] string is cleared first and then holds only the JSON of this account.
+    /// If deserialization fails it may hold part of that JSON.
+    ///
+    /// - [id] is the program id of program that owns the account, possibly combined with the slot
+    ///   at which the IDL to use for deserialization was uploaded.
+    /// - [account_data] is the raw account data as a byte array
+    /// - [out] is the string to write the JSON to
+    pub fn deserialize_account_into(
+        &self,
+        id: &str,
+        account_data: &mut &[u8],
+        out: &mut String,
+    ) -> ChainparserResult<()> {
+        out.clear();
+        self.deserialize_account_to_json(id, account_data, out)
+    }
+
+    /// Deserializes an account to a JSON string and returns it along with the number of bytes of
+    /// the [account_data] that the account occupied, i.e. to parse multiple records laid out
+    /// back to back in one buffer.
     ///
     /// - [id] is the program id of program that owns the account, possibly combined with the slot
-    /// at which the IDL to use for deserialization was uploaded. Make sure to add it's IDL before
-    /// via [ChainparserDeserializer::add_idl_json].
+    ///   at which the IDL to use for deserialization was uploaded.
     /// - [account_data] is the raw account data as a byte array
-    /// - [f] is the [Write] writer to write the resulting JSON to, i.e. `std::io::stdout()` or
-    /// `String::new()`
+    pub fn deserialize_account_to_json_string_counted(
+        &self,
+        id: &str,
+        account_data: &[u8],
+    ) -> ChainparserResult<(String, usize)> {
+        let deserializer =
+            self.json_account_deserializers.get(id).ok_or_else(|| {
+                ChainparserError::CannotFindAccountDeserializerForProgramId(
+                    id.to_string(),
+                )
+            })?;
+        deserializer.deserialize_account_data_counted(account_data)
+    }
+
+    /// Deserializes the account that starts at the given [offset] of the [account_data] to a JSON
+    /// string, i.e. when a wrapper prepends metadata to the account or the account is part of a
+    /// larger buffer.
+    /// The discriminator of the account is expected at the [offset] as well.
+    ///
+    /// - [id] is the program id of program that owns the account, possibly combined with the slot
+    ///   at which the IDL to use for deserialization was uploaded.
+    /// - [account_data] is the raw data that includes the account
+    /// - [offset] is the index of the first byte of the account inside the [account_data]
+    pub fn deserialize_account_at(
+        &self,
+        id: &str,
+        account_data: &[u8],
+        offset: usize,
+    ) -> ChainparserResult<String> {
+        let mut data = account_data.get(offset..).ok_or(
+            ChainparserError::AccountDataOffsetOutOfBounds(
+                offset,
+                account_data.len(),
+            ),
+        )?;
+        self.deserialize_account_to_json_string(id, &mut data)
+    }
+
+    /// Fetches the account at the given [address] via the [account_provider] and deserializes it
+    /// to a JSON string after verifying that it is owned by the program with the given
+    /// [program_id].
+    /// This guards against decoding an account with the IDL of a program that doesn't own it
+    /// which results in garbage output or confusing errors.
+    ///
+    /// - [account_provider] to fetch the account from
+    /// - [program_id] is the program id of the program that is expected to own the account and
+    ///   whose IDL is used for deserialization
+    /// - [address] is the address of the account
+    pub fn deserialize_owned_account<T: AccountProvider>(
+        &self,
+        account_provider: &T,
+        program_id: &Pubkey,
+        address: &Pubkey,
+    ) -> ChainparserResult<String> {
+        let (account, _) =
+            account_provider.get_account(address).ok_or_else(|| {
+                ChainparserError::AccountNotFound(address.to_string())
+            })?;
+        if account.owner != *program_id {
+            return Err(ChainparserError::OwnerMismatch(
+                program_id.to_string(),
+                account.owner.to_string(),
+            ));
+        }
+        self.deserialize_account_to_json_string(
+            &program_id.to_string(),
+            &mut account.data.as_slice(),
+        )
+    }
+
+    /// Deserializes an account from the `value` of a `getAccountInfo` RPC response to a JSON
+    /// string after verifying that it is owned by the program with the given [program_id].
+    ///
+    /// - [program_id] is the program id of the program that is expected to own the account and
+    ///   whose IDL is used for deserialization
+    /// - [account_info] is the account info of the response, see [RpcAccountInfo::from_value]
+    pub fn deserialize_rpc_account_to_json_string(
+        &self,
+        program_id: &str,
+        account_info: &RpcAccountInfo,
+    ) -> ChainparserResult<String> {
+        if account_info.owner != program_id {
+            return Err(ChainparserError::OwnerMismatch(
+                program_id.to_string(),
+                account_info.owner.to_string(),
+            ));
+        }
+        let account_data = account_info.decode_data()?;
+        self.deserialize_account_to_json_string(
+            program_id,
+            &mut account_data.as_slice(),
+        )
+    }
+
+    /// Deserializes an account and writes the resulting JSON to the provided write [f].
+    ///
+    /// - [id] is the program id of program that owns the account, possibly combined with the slot
+    ///   at which the IDL to use for deserialization was uploaded. Make sure to add it's IDL before
+    ///   via [ChainparserDeserializer::add_idl_json].
+    /// - [account_data] is the raw account data as a byte array
+    /// - [f] is the [Write] writer to write the resulting JSON to, i.e. `String::new()`, use
+    ///   [ChainparserDeserializer::deserialize_account_to_io_writer] for `std::io::stdout()`
     pub fn deserialize_account_to_json<W: Write>(
         &self,
         id: &str,
         account_data: &mut &[u8],
         f: &mut W,
+    ) -> ChainparserResult<()> {
+        self.deserialize_account_to_json_writer(
+            id,
+            account_data,
+            &mut FmtJsonWriter(f),
+        )
+    }
+
+    /// Same as [ChainparserDeserializer::deserialize_account_to_json], but writes to a
+    /// [JsonWrite] writer [f] which is flushed after each element of a `Vec` if it is
+    /// [JsonWrite::STREAMING], i.e. to stream the elements of large collections while the
+    /// account is still being deserialized.
+    pub fn deserialize_account_to_json_writer<W: JsonWrite>(
+        &self,
+        id: &str,
+        account_data: &mut &[u8],
+        f: &mut W,
     ) -> ChainparserResult<()> {
         let deserializer =
             self.json_account_deserializers.get(id).ok_or_else(|| {
@@ -167,10 +934,331 @@ impl<'opts> ChainparserDeserializer<'opts> {
                 )
             })?;
 
-        deserializer.deserialize_account_data(account_data, f)?;
+        deserializer
+            .deserialize_account_data_to_json_writer(account_data, f)?;
         Ok(())
     }
 
+    /// Same as [ChainparserDeserializer::deserialize_account_to_json], but first fetches and adds
+    /// the IDL for the [id] via the account provider if it wasn't added yet, see
+    /// [ChainparserDeserializer::resolve_idl].
+    ///
+    /// - [id] is the program id of program that owns the account
+    /// - [account_data] is the raw account data as a byte array
+    /// - [f] is the [Write] writer to write the resulting JSON to
+    pub fn deserialize_account_to_json_resolving<W: Write>(
+        &mut self,
+        id: &str,
+        account_data: &mut &[u8],
+        f: &mut W,
+    ) -> ChainparserResult<()> {
+        self.resolve_idl(id)?;
+        self.deserialize_account_to_json(id, account_data, f)
+    }
+
+    /// Deserializes an account and emits each of its values to the [visitor], i.e. to map it
+    /// into a custom data model or to write it via a [crate::format::JsonFormatWriter] or a
+    /// [crate::format::MsgpackWriter].
+    ///
+    /// - [id] is the program id of program that owns the account, possibly combined with the slot
+    ///   at which the IDL to use for deserialization was uploaded.
+    /// - [account_data] is the raw account data as a byte array
+    /// - [visitor] is the [ChainparserVisitor] receiving the deserialized values
+    pub fn deserialize_account_with_visitor<V: ChainparserVisitor>(
+        &self,
+        id: &str,
+        account_data: &mut &[u8],
+        visitor: &mut V,
+    ) -> ChainparserResult<()> {
+        let deserializer =
+            self.json_account_deserializers.get(id).ok_or_else(|| {
+                ChainparserError::CannotFindAccountDeserializerForProgramId(
+                    id.to_string(),
+                )
+            })?;
+        deserializer
+            .deserialize_account_data_with_visitor(account_data, visitor)
+    }
+
+    /// Deserializes an account to MessagePack, see [crate::format::MsgpackWriter] for how the
+    /// IDL types are represented.
+    ///
+    /// - [id] is the program id of program that owns the account, possibly combined with the slot
+    ///   at which the IDL to use for deserialization was uploaded.
+    /// - [account_data] is the raw account data as a byte array
+    #[cfg(feature = "msgpack")]
+    pub fn deserialize_account_to_msgpack(
+        &self,
+        id: &str,
+        account_data: &mut &[u8],
+    ) -> ChainparserResult<Vec<u8>> {
+        let mut w =
+            crate::format::MsgpackWriter::new(&self.json_serialization_opts);
+        self.deserialize_account_with_visitor(id, account_data, &mut w)?;
+        Ok(w.into_inner())
+    }
+
+    /// Deserializes an account directly into a BSON document, i.e. to store it in MongoDB, see
+    /// [crate::format::BsonWriter] for how the IDL types are represented.
+    ///
+    /// - [id] is the program id of program that owns the account, possibly combined with the slot
+    ///   at which the IDL to use for deserialization was uploaded.
+    /// - [account_data] is the raw account data as a byte array
+    #[cfg(feature = "bson")]
+    pub fn deserialize_account_to_bson(
+        &self,
+        id: &str,
+        account_data: &mut &[u8],
+    ) -> ChainparserResult<bson::Document> {
+        let mut w =
+            crate::format::BsonWriter::new(&self.json_serialization_opts);
+        self.deserialize_account_with_visitor(id, account_data, &mut w)?;
+        match w.into_bson() {
+            Some(bson::Bson::Document(doc)) => Ok(doc),
+            _ => Err(ChainparserError::BsonNotADocument),
+        }
+    }
+
+    /// Deserializes an account whose data is base64 encoded, as returned by the `getAccountInfo`
+    /// RPC method, to a JSON string.
+    ///
+    /// - [id] is the program id of program that owns the account, possibly combined with the slot
+    ///   at which the IDL to use for deserialization was uploaded.
+    /// - [data_b64] is the base64 encoded account data
+    pub fn deserialize_account_base64(
+        &self,
+        id: &str,
+        data_b64: &str,
+    ) -> ChainparserResult<String> {
+        let account_data = BASE64_STANDARD.decode(data_b64.trim())?;
+        self.deserialize_account_to_json_string(id, &mut &account_data[..])
+    }
+
+    /// Same as [ChainparserDeserializer::deserialize_account_base64], but deserializes the
+    /// account with the given [name] instead of deriving it from the account data, see
+    /// [ChainparserDeserializer::deserialize_account_to_json_by_name].
+    pub fn deserialize_account_base64_by_name(
+        &self,
+        id: &str,
+        name: &str,
+        data_b64: &str,
+    ) -> ChainparserResult<String> {
+        let account_data = BASE64_STANDARD.decode(data_b64.trim())?;
+        let mut f = String::new();
+        self.deserialize_account_to_json_by_name(
+            id,
+            name,
+            &mut &account_data[..],
+            &mut f,
+        )?;
+        Ok(f)
+    }
+
+    /// Deserializes many accounts of the same program to JSON strings in one call, i.e. all
+    /// accounts of a program that changed in a slot.
+    ///
+    /// The deserializer for the [id] is looked up once and all accounts are deserialized into a
+    /// shared scratch buffer which only grows as needed, thus each result is allocated at its
+    /// exact size.
+    ///
+    /// Errors are returned per account and don't abort the batch, i.e. the result at a given
+    /// index belongs to the account at that index in [accounts].
+    ///
+    /// - [id] is the program id of program that owns the accounts, possibly combined with the
+    ///   slot at which the IDL to use for deserialization was uploaded.
+    /// - [accounts] is the raw data of each account
+    pub fn deserialize_accounts(
+        &self,
+        id: &str,
+        accounts: &[&[u8]],
+    ) -> Vec<ChainparserResult<String>> {
+        let Some(deserializer) = self.json_account_deserializers.get(id) else {
+            return accounts
+                .iter()
+                .map(|_| {
+                    Err(
+                        ChainparserError::CannotFindAccountDeserializerForProgramId(
+                            id.to_string(),
+                        ),
+                    )
+                })
+                .collect();
+        };
+
+        let mut scratch = String::new();
+        accounts
+            .iter()
+            .map(|account_data| {
+                scratch.clear();
+                deserializer
+                    .deserialize_account_data(
+                        &mut &account_data[..],
+                        &mut scratch,
+                    )
+                    .map(|_| scratch.as_str().to_string())
+            })
+            .collect()
+    }
+
+    /// Deserializes an account and writes the resulting JSON to the provided [io::Write] writer
+    /// [w], i.e. `std::io::stdout()` or a socket connection.
+    ///
+    /// The writer is flushed after each element of a `Vec` so that consumers can process the
+    /// elements of large collections while the account is still being deserialized.
+    ///
+    /// - [id] is the program id of program that owns the account, possibly combined with the slot
+    ///   at which the IDL to use for deserialization was uploaded.
+    /// - [account_data] is the raw account data as a byte array
+    /// - [w] is the [io::Write] writer to write the resulting JSON to
+    pub fn deserialize_account_to_io_writer<W: io::Write>(
+        &self,
+        id: &str,
+        account_data: &mut &[u8],
+        w: W,
+    ) -> ChainparserResult<()> {
+        let mut f = IoJsonWriter::new(w);
+        self.deserialize_account_to_json_writer(id, account_data, &mut f)
+            .and_then(|_| Ok(JsonWrite::flush(&mut f)?))
+            .map_err(|err| {
+                f.take_error().map_or(err, ChainparserError::WriterIoError)
+            })
+    }
+
+    /// Deserializes the account with the given [account_name] and describes each of its fields
+    /// via a [FieldInfo] holding its name, type, value, offset and length, i.e. to render it in
+    /// an account viewer.
+    ///
+    /// - [id] is the program id of program that owns the account, possibly combined with the slot
+    ///   at which the IDL to use for deserialization was uploaded.
+    /// - [account_name] is the name of the account type as declared in the IDL
+    /// - [account_data] is the raw account data, including the discriminator for anchor
+    ///   accounts, thus field offsets match the offsets inside the account data
+    pub fn deserialize_account_fields(
+        &self,
+        id: &str,
+        account_name: &str,
+        account_data: &[u8],
+    ) -> ChainparserResult<Vec<FieldInfo>> {
+        let deserializer =
+            self.json_account_deserializers.get(id).ok_or_else(|| {
+                ChainparserError::CannotFindAccountDeserializerForProgramId(
+                    id.to_string(),
+                )
+            })?;
+        deserializer.deserialize_account_fields(account_name, account_data)
+    }
+
+    /// Deserializes an account but only includes the top level [fields] with the given names in
+    /// the returned object, i.e. when only a few fields of a wide account are needed.
+    ///
+    /// The bytes of the remaining fields are consumed without rendering them and fields following
+    /// the last requested field aren't read at all. Names of fields the account doesn't have are
+    /// ignored.
+    ///
+    /// - [id] is the program id of program that owns the account, possibly combined with the slot
+    ///   at which the IDL to use for deserialization was uploaded.
+    /// - [account_data] is the raw account data as a byte array
+    /// - [fields] are the names of the fields to include as declared in the IDL
+    pub fn deserialize_account_selected_fields(
+        &self,
+        id: &str,
+        account_data: &[u8],
+        fields: &[&str],
+    ) -> ChainparserResult<serde_json::Map<String, serde_json::Value>> {
+        let deserializer =
+            self.json_account_deserializers.get(id).ok_or_else(|| {
+                ChainparserError::CannotFindAccountDeserializerForProgramId(
+                    id.to_string(),
+                )
+            })?;
+        deserializer.deserialize_selected_fields(account_data, fields)
+    }
+
+    /// Deserializes an account into a flat map of field paths to values, i.e.
+    /// `{"composite.uno.value": 1, "ns[0]": 1}`, which suits columnar storage.
+    /// Object keys are joined with dots and array items are indexed via `[i]`.
+    ///
+    /// - [id] is the program id of program that owns the account, possibly combined with the slot
+    ///   at which the IDL to use for deserialization was uploaded.
+    /// - [account_data] is the raw account data as a byte array
+    pub fn deserialize_account_to_flat_map(
+        &self,
+        id: &str,
+        account_data: &mut &[u8],
+    ) -> ChainparserResult<BTreeMap<String, serde_json::Value>> {
+        let json = self.deserialize_account_to_json_string(id, account_data)?;
+        let value = serde_json::from_str(&json)?;
+        Ok(flatten_json_value(&value))
+    }
+
+    /// Deserializes multiple accounts into a single JSON object keyed by the pubkey of each
+    /// account, i.e. `{"<pubkey1>": {...}, "<pubkey2>": {...}}`.
+    /// Accounts that cannot be deserialized map to an object holding the error, i.e.
+    /// `{"error": "..."}`, which makes this suitable for bulk dumps of account snapshots.
+    ///
+    /// - [id] is the program id of program that owns the accounts, possibly combined with the
+    ///   slot at which the IDL to use for deserialization was uploaded.
+    /// - [accounts] are the pubkeys of the accounts along with their raw account data
+    pub fn deserialize_snapshot_json(
+        &self,
+        id: &str,
+        accounts: &[(Pubkey, &[u8])],
+    ) -> ChainparserResult<String> {
+        let deserializer =
+            self.json_account_deserializers.get(id).ok_or_else(|| {
+                ChainparserError::CannotFindAccountDeserializerForProgramId(
+                    id.to_string(),
+                )
+            })?;
+
+        let mut f = String::new();
+        f.write_char('{')?;
+        for (i, (pubkey, account_data)) in accounts.iter().enumerate() {
+            if i > 0 {
+                f.write_char(',')?;
+            }
+            write!(f, "\"{pubkey}\":")?;
+
+            let mut account_json = String::new();
+            match deserializer.deserialize_account_data(
+                &mut &account_data[..],
+                &mut account_json,
+            ) {
+                Ok(()) => f.write_str(&account_json)?,
+                Err(err) => write!(
+                    f,
+                    "{{\"error\":{}}}",
+                    serde_json::to_string(&err.to_string())?
+                )?,
+            }
+        }
+        f.write_char('}')?;
+        Ok(f)
+    }
+
+    /// Serializes the JSON of an account back into account data, i.e. after modifying an account
+    /// that was deserialized via [ChainparserDeserializer::deserialize_account_to_json_string].
+    ///
+    /// - [id] is the program id of program that owns the account, possibly combined with the slot
+    ///   at which the IDL to use for serialization was uploaded.
+    /// - [account_name] is the name of the account type as defined in the IDL
+    /// - [account_json] is the JSON of the account
+    pub fn serialize_account_json(
+        &self,
+        id: &str,
+        account_name: &str,
+        account_json: &str,
+    ) -> ChainparserResult<Vec<u8>> {
+        let deserializer =
+            self.json_account_deserializers.get(id).ok_or_else(|| {
+                ChainparserError::CannotFindAccountDeserializerForProgramId(
+                    id.to_string(),
+                )
+            })?;
+        let value = serde_json::from_str(account_json)?;
+        deserializer.serialize_account_data(account_name, &value)
+    }
+
     pub fn deserialize_account_to_json_by_name<W: Write>(
         &self,
         id: &str,
@@ -1,45 +1,55 @@
 use std::str::FromStr;
 
 use serde::{de::Error, Deserialize, Deserializer};
-use solana_sdk::pubkey::Pubkey;
+
+use crate::pubkey::{Pubkey, PUBKEY_BYTES};
 
 /// Function to provide to [serde] in order to deserialize a [Pubkey] from a base58 string.
+/// Any pubkey type that converts from its bytes is supported, i.e. the one of the Solana SDK.
 /// Use as follows: `#[serde(deserialize_with = "pubkey_from_base58")]`
-pub fn pubkey_from_base58<'de, D>(deserializer: D) -> Result<Pubkey, D::Error>
+pub fn pubkey_from_base58<'de, D, P>(deserializer: D) -> Result<P, D::Error>
 where
     D: Deserializer<'de>,
+    P: From<[u8; PUBKEY_BYTES]>,
 {
     let s: &str = Deserialize::deserialize(deserializer)?;
-    Pubkey::from_str(s).map_err(D::Error::custom)
+    parse_pubkey(s)
 }
 
 /// Function to provide to [serde] in order to deserialize a [Vec<Pubkey>] from a a vec of base58
 /// strings.
 /// Use as follows: `#[serde(deserialize_with = "vec_pubkey_from_base58")]`
-pub fn vec_pubkey_from_base58<'de, D>(
+pub fn vec_pubkey_from_base58<'de, D, P>(
     deserializer: D,
-) -> Result<Vec<Pubkey>, D::Error>
+) -> Result<Vec<P>, D::Error>
 where
     D: Deserializer<'de>,
+    P: From<[u8; PUBKEY_BYTES]>,
 {
     let xs: Vec<&str> = Deserialize::deserialize(deserializer)?;
-    xs.into_iter()
-        .map(|s| Pubkey::from_str(s).map_err(D::Error::custom))
-        .collect()
+    xs.into_iter().map(parse_pubkey).collect()
 }
 
 /// Function to provide to [serde] in order to deserialize a [Option<Pubkey>] from a base58 string
 /// option.
 /// Use as follows: `#[serde(deserialize_with = "opt_pubkey_from_base58")]`
-pub fn opt_pubkey_from_base58<'de, D>(
+pub fn opt_pubkey_from_base58<'de, D, P>(
     deserializer: D,
-) -> Result<Option<Pubkey>, D::Error>
+) -> Result<Option<P>, D::Error>
 where
     D: Deserializer<'de>,
+    P: From<[u8; PUBKEY_BYTES]>,
 {
     let opt: Option<&str> = Deserialize::deserialize(deserializer)?;
-    opt.map(|s| Pubkey::from_str(s).map_err(D::Error::custom))
-        .transpose()
+    opt.map(parse_pubkey).transpose()
+}
+
+fn parse_pubkey<P: From<[u8; PUBKEY_BYTES]>, E: Error>(
+    s: &str,
+) -> Result<P, E> {
+    Pubkey::from_str(s)
+        .map(|pubkey| pubkey.to_bytes().into())
+        .map_err(E::custom)
 }
 
 /// Function to provide to [serde] in order to deserialize a [u64] from a string.
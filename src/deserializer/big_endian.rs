@@ -0,0 +1,165 @@
+use solana_idl::IdlType;
+
+use super::{borsh::BorshDeserializer, ChainparserDeserialize};
+use crate::{
+    errors::{ChainparserError, ChainparserResult as Result},
+    pubkey::Pubkey,
+};
+
+/// Wraps another deserializer, [BorshDeserializer] by default, and reads all integers wider
+/// than a byte in big-endian order, i.e. for programs that store data in the byte order of the
+/// chain they are bridging to.
+///
+/// NOTE: this applies to the whole account that is deserialized with it, including the `u32`
+/// length prefixes of strings, bytes, vecs, sets and maps, while floats, bools, pubkeys and
+/// options are read by the wrapped deserializer as is.
+#[derive(Clone, Copy)]
+pub struct BigEndianDeserializer<D: ChainparserDeserialize = BorshDeserializer>
+{
+    inner: D,
+}
+
+impl<D: ChainparserDeserialize> BigEndianDeserializer<D> {
+    /// Creates a deserializer that reads integers big-endian and delegates all other types to
+    /// the [inner] deserializer.
+    pub fn new(inner: D) -> Self {
+        Self { inner }
+    }
+}
+
+impl Default for BigEndianDeserializer {
+    fn default() -> Self {
+        Self::new(BorshDeserializer)
+    }
+}
+
+/// Reads the next `N` bytes of the [buf] via the [inner] deserializer.
+fn be_bytes<const N: usize>(
+    inner: &impl ChainparserDeserialize,
+    buf: &mut &[u8],
+) -> Result<[u8; N]> {
+    let bytes = inner.u8_array(buf, N)?;
+    Ok(bytes
+        .try_into()
+        .expect("u8_array returns the requested length"))
+}
+
+impl<D: ChainparserDeserialize> ChainparserDeserialize
+    for BigEndianDeserializer<D>
+{
+    fn u8(&self, buf: &mut &[u8]) -> Result<u8> {
+        self.inner.u8(buf)
+    }
+
+    fn u16(&self, buf: &mut &[u8]) -> Result<u16> {
+        be_bytes(&self.inner, buf).map(u16::from_be_bytes)
+    }
+
+    fn u32(&self, buf: &mut &[u8]) -> Result<u32> {
+        be_bytes(&self.inner, buf).map(u32::from_be_bytes)
+    }
+
+    fn u64(&self, buf: &mut &[u8]) -> Result<u64> {
+        be_bytes(&self.inner, buf).map(u64::from_be_bytes)
+    }
+
+    fn u128(&self, buf: &mut &[u8]) -> Result<u128> {
+        be_bytes(&self.inner, buf).map(u128::from_be_bytes)
+    }
+
+    fn i8(&self, buf: &mut &[u8]) -> Result<i8> {
+        self.inner.i8(buf)
+    }
+
+    fn i16(&self, buf: &mut &[u8]) -> Result<i16> {
+        be_bytes(&self.inner, buf).map(i16::from_be_bytes)
+    }
+
+    fn i32(&self, buf: &mut &[u8]) -> Result<i32> {
+        be_bytes(&self.inner, buf).map(i32::from_be_bytes)
+    }
+
+    fn i64(&self, buf: &mut &[u8]) -> Result<i64> {
+        be_bytes(&self.inner, buf).map(i64::from_be_bytes)
+    }
+
+    fn i128(&self, buf: &mut &[u8]) -> Result<i128> {
+        be_bytes(&self.inner, buf).map(i128::from_be_bytes)
+    }
+
+    fn f32(&self, buf: &mut &[u8]) -> Result<f32> {
+        self.inner.f32(buf)
+    }
+
+    fn f64(&self, buf: &mut &[u8]) -> Result<f64> {
+        self.inner.f64(buf)
+    }
+
+    fn bool(&self, buf: &mut &[u8]) -> Result<bool> {
+        self.inner.bool(buf)
+    }
+
+    fn string(&self, buf: &mut &[u8]) -> Result<String> {
+        let bytes = self.bytes(buf)?;
+        String::from_utf8(bytes).map_err(|e| {
+            ChainparserError::InvalidDataToDeserialize(
+                "String".to_string(),
+                e.to_string(),
+                e.into_bytes(),
+            )
+        })
+    }
+
+    fn bytes(&self, buf: &mut &[u8]) -> Result<Vec<u8>> {
+        let len = self.u32(buf)?;
+        self.inner
+            .u8_array(buf, len as usize)
+            .map(|bytes| bytes.to_vec())
+    }
+
+    fn u8_array<'a>(&self, buf: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+        self.inner.u8_array(buf, len)
+    }
+
+    fn pubkey(&self, buf: &mut &[u8]) -> Result<Pubkey> {
+        self.inner.pubkey(buf)
+    }
+
+    fn option(&self, buf: &mut &[u8]) -> Result<bool> {
+        self.inner.option(buf)
+    }
+
+    fn coption(&self, buf: &mut &[u8], inner: &IdlType) -> Result<bool> {
+        self.inner.coption(buf, inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_integers_big_endian() {
+        let le = BorshDeserializer;
+        let be = BigEndianDeserializer::new(BorshDeserializer);
+        let data = [0x01, 0x02, 0x03, 0x04];
+
+        assert_eq!(le.u32(&mut &data[..]).unwrap(), 0x04030201);
+        assert_eq!(be.u32(&mut &data[..]).unwrap(), 0x01020304);
+        assert_eq!(le.i16(&mut &data[..]).unwrap(), 0x0201);
+        assert_eq!(be.i16(&mut &data[..]).unwrap(), 0x0102);
+
+        let data = (-2i128).to_be_bytes();
+        assert_eq!(be.i128(&mut &data[..]).unwrap(), -2);
+        assert!(be.u64(&mut &data[..4]).is_err());
+    }
+
+    #[test]
+    fn read_length_prefixes_big_endian() {
+        let be = BigEndianDeserializer::new(BorshDeserializer);
+        let data = [0, 0, 0, 2, b'h', b'i'];
+        let buf = &mut &data[..];
+        assert_eq!(be.string(buf).unwrap(), "hi");
+        assert!(buf.is_empty());
+    }
+}
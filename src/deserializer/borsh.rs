@@ -1,12 +1,14 @@
 use borsh::BorshDeserialize;
 use solana_idl::IdlType;
-use solana_sdk::pubkey::Pubkey;
 
 use super::{
     floats::{deserialize_f32, deserialize_f64},
     ChainparserDeserialize,
 };
-use crate::errors::{ChainparserError, ChainparserResult as Result};
+use crate::{
+    errors::{ChainparserError, ChainparserResult as Result},
+    pubkey::{Pubkey, PUBKEY_BYTES},
+};
 
 #[derive(Clone, Copy)]
 pub struct BorshDeserializer;
@@ -150,14 +152,32 @@ impl ChainparserDeserialize for BorshDeserializer {
         })
     }
 
-    fn pubkey(&self, buf: &mut &[u8]) -> Result<Pubkey> {
-        Pubkey::deserialize(buf).map_err(|e| {
-            ChainparserError::BorshDeserializeTypeError(
-                "Pubkey".to_string(),
-                e,
+    fn u8_array<'a>(&self, buf: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+        if buf.len() < len {
+            return Err(ChainparserError::BorshDeserializeTypeError(
+                format!("[u8; {len}]"),
+                borsh::maybestd::io::Error::new(
+                    borsh::maybestd::io::ErrorKind::InvalidInput,
+                    "Unexpected length of input",
+                ),
                 buf.to_vec(),
-            )
-        })
+            ));
+        }
+        let (bytes, rest) = buf.split_at(len);
+        *buf = rest;
+        Ok(bytes)
+    }
+
+    fn pubkey(&self, buf: &mut &[u8]) -> Result<Pubkey> {
+        <[u8; PUBKEY_BYTES]>::deserialize(buf)
+            .map(Pubkey::new_from_array)
+            .map_err(|e| {
+                ChainparserError::BorshDeserializeTypeError(
+                    "Pubkey".to_string(),
+                    e,
+                    buf.to_vec(),
+                )
+            })
     }
 
     fn option(&self, buf: &mut &[u8]) -> Result<bool> {
@@ -1,12 +1,12 @@
+pub mod big_endian;
 pub mod borsh;
 mod floats;
 pub mod spl;
 
 use solana_idl::{Idl, IdlType};
-use solana_sdk::pubkey::Pubkey;
 
-use crate::errors::ChainparserError;
 pub use crate::errors::ChainparserResult as Result;
+use crate::{errors::ChainparserError, pubkey::Pubkey};
 pub trait ChainparserDeserialize: Clone {
     fn u8(&self, buf: &mut &[u8]) -> Result<u8>;
     fn u16(&self, buf: &mut &[u8]) -> Result<u16>;
@@ -27,12 +27,46 @@ pub trait ChainparserDeserialize: Clone {
     fn string(&self, buf: &mut &[u8]) -> Result<String>;
 
     fn bytes(&self, buf: &mut &[u8]) -> Result<Vec<u8>>;
+    /// Reads [len] bytes at once, i.e. for a `[u8; N]` array.
+    fn u8_array<'a>(&self, buf: &mut &'a [u8], len: usize) -> Result<&'a [u8]>;
     fn pubkey(&self, buf: &mut &[u8]) -> Result<Pubkey>;
 
     fn option(&self, buf: &mut &[u8]) -> Result<bool>;
     fn coption(&self, buf: &mut &[u8], inner: &IdlType) -> Result<bool>;
+
+    /// Reads the length of a `Vec`, set or map and fails if the remaining [buf] cannot hold
+    /// that many elements of at least [min_elem_bytes] each.
+    /// This rejects crafted lengths before looping over or allocating for them, which could
+    /// exhaust memory especially on 32-bit targets like wasm32.
+    /// The check is skipped for elements that may take up no bytes, i.e. empty structs.
+    fn collection_len(
+        &self,
+        buf: &mut &[u8],
+        min_elem_bytes: usize,
+    ) -> Result<usize> {
+        let data = *buf;
+        let len = self.u32(buf)?;
+        let fits = |len: usize| {
+            min_elem_bytes == 0
+                || len
+                    .checked_mul(min_elem_bytes)
+                    .is_some_and(|bytes| bytes <= buf.len())
+        };
+        match usize::try_from(len) {
+            Ok(len) if fits(len) => Ok(len),
+            _ => Err(ChainparserError::InvalidDataToDeserialize(
+                "collection length".to_string(),
+                format!(
+                    "{len} elements of at least {min_elem_bytes} bytes exceed the {} remaining bytes",
+                    buf.len()
+                ),
+                data[..data.len() - buf.len()].to_vec(),
+            )),
+        }
+    }
 }
 
+#[derive(Clone)]
 pub enum DeserializeProvider {
     Borsh(borsh::BorshDeserializer),
     Spl(spl::SplDeserializer),
@@ -67,6 +101,10 @@ impl DeserializeProvider {
         Self::Borsh(borsh::BorshDeserializer)
     }
 
+    pub fn spl() -> Self {
+        Self::Spl(spl::SplDeserializer::new())
+    }
+
     pub fn is_spl(&self) -> bool {
         matches!(self, DeserializeProvider::Spl(_))
     }
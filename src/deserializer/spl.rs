@@ -1,11 +1,11 @@
 use solana_idl::IdlType;
-use solana_sdk::pubkey::Pubkey;
 use TryFrom;
 
 use super::{borsh::BorshDeserializer, ChainparserDeserialize};
 use crate::{
     errors::{ChainparserError, ChainparserResult as Result},
     idl,
+    pubkey::Pubkey,
 };
 
 #[derive(Clone, Copy)]
@@ -82,6 +82,10 @@ impl ChainparserDeserialize for SplDeserializer {
         self.borsh.bytes(buf)
     }
 
+    fn u8_array<'a>(&self, buf: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+        self.borsh.u8_array(buf, len)
+    }
+
     fn pubkey(&self, buf: &mut &[u8]) -> Result<Pubkey> {
         let key = &buf[0..32];
         let res = Pubkey::try_from(key).map_err(|e| {
@@ -124,7 +128,13 @@ impl ChainparserDeserialize for SplDeserializer {
                 // resolve defined types, otherwise we can't deserialize COption with defined types
                 // as inner
                 if let Some(byte_len) = idl::idl_type_bytes(inner, None) {
-                    *buf = &buf[byte_len..];
+                    *buf = buf.get(byte_len..).ok_or_else(|| {
+                        ChainparserError::InvalidDataToDeserialize(
+                            "coption".to_string(),
+                            format!("buf too short to skip {byte_len} bytes of None"),
+                            buf.to_vec(),
+                        )
+                    })?;
                     Ok(false)
                 } else {
                     Err(ChainparserError::InvalidDataToDeserialize(
@@ -1,9 +1,12 @@
-use std::{collections::HashMap, ops::Deref};
+use std::{collections::HashMap, fmt::Write, ops::Deref};
 
-use arrayref::array_ref;
-use solana_idl::{IdlType, IdlTypeDefinition, IdlTypeDefinitionTy};
+use solana_idl::{EnumFields, IdlType, IdlTypeDefinition, IdlTypeDefinitionTy};
 
-use crate::idl;
+use crate::{
+    errors::{ChainparserError, ChainparserResult},
+    idl,
+    pubkey::Pubkey,
+};
 
 // -----------------
 // Matcher
@@ -12,6 +15,102 @@ use crate::idl;
 pub enum Matcher {
     COption(usize, usize),
     Bool(usize),
+    /// Validates the 4 byte length prefix of a `String` or `Bytes` field at the given offset.
+    /// The length must not exceed the bytes remaining in the buffer.
+    StringLength(usize),
+    /// Validates that the variant index of an enum field is within its number of variants.
+    EnumDiscriminant {
+        offset: usize,
+        variant_count: usize,
+    },
+    /// Validates the byte at the given offset that tags the account type, i.e. the account type
+    /// of Token-2022 accounts which follows the base account when extensions are present.
+    /// Passes if the buffer ends before the offset since the tag is only present then.
+    TagByte {
+        offset: usize,
+        value: u8,
+    },
+    /// Checks that the 32 bytes at the given offset are a pubkey on the ed25519 curve, i.e. a
+    /// keypair authority rather than a PDA.
+    /// This isn't definitive and thus only used to break ties between equally well matching
+    /// accounts, see [MatchDiscriminators::set_pubkey_on_curve_tie_breaker].
+    PubkeyOnCurve {
+        offset: usize,
+    },
+    /// Applies the [matcher] to a field that follows the [var_fields] whose sizes are only known
+    /// once their lengths are read from the buffer, its offset is relative to the end of the
+    /// last of them.
+    Relative {
+        var_fields: Vec<VarSizedField>,
+        matcher: Box<Matcher>,
+    },
+}
+
+/// A field whose size is read from the buffer, located at [VarSizedField::offset] bytes after
+/// the end of the previous variable sized field or the start of the buffer.
+#[derive(Debug, Clone)]
+pub struct VarSizedField {
+    offset: usize,
+    kind: VarSizedKind,
+}
+
+#[derive(Debug, Clone)]
+enum VarSizedKind {
+    /// A 4 byte length prefix followed by that many elements of the given size, i.e. a
+    /// `String`, `Bytes` or a `Vec` of fixed sized elements.
+    LengthPrefixed(usize),
+    /// A 1 byte tag followed by a value of the given size if it is `1`.
+    Option(usize),
+}
+
+impl TryFrom<(&IdlType, &HashMap<String, &IdlTypeDefinitionTy>, usize)>
+    for VarSizedField
+{
+    type Error = ();
+
+    fn try_from(
+        (ty, type_map, offset): (
+            &IdlType,
+            &HashMap<String, &IdlTypeDefinitionTy>,
+            usize,
+        ),
+    ) -> Result<Self, Self::Error> {
+        let inner_size =
+            |inner: &IdlType| idl::idl_type_bytes(inner, Some(type_map));
+        let kind = match ty {
+            IdlType::String | IdlType::Bytes => VarSizedKind::LengthPrefixed(1),
+            IdlType::Vec(inner) => {
+                VarSizedKind::LengthPrefixed(inner_size(inner).ok_or(())?)
+            }
+            IdlType::Option(inner) => {
+                VarSizedKind::Option(inner_size(inner).ok_or(())?)
+            }
+            _ => return Err(()),
+        };
+        Ok(Self { offset, kind })
+    }
+}
+
+impl VarSizedField {
+    /// Returns the offset at which the field ends given that it is located relative to [start].
+    /// Returns `None` if the [buf] doesn't hold it or its tag is invalid.
+    fn end(&self, buf: &[u8], start: usize) -> Option<usize> {
+        let offset = start.checked_add(self.offset)?;
+        let end = match self.kind {
+            VarSizedKind::LengthPrefixed(elem_size) => {
+                let len = u32::from_le_bytes(*read_bytes::<4>(buf, offset)?);
+                (len as usize)
+                    .checked_mul(elem_size)?
+                    .checked_add(offset + 4)?
+            }
+            VarSizedKind::Option(size) => match buf.get(offset)? {
+                0 => offset + 1,
+                1 => size.checked_add(offset + 1)?,
+                _ => return None,
+            },
+        };
+        (end <= buf.len()).then_some(end)
+    }
 }
 
 impl TryFrom<(&IdlType, &HashMap<String, &IdlTypeDefinitionTy>, usize)>
@@ -33,32 +132,81 @@ impl TryFrom<(&IdlType, &HashMap<String, &IdlTypeDefinitionTy>, usize)>
                 Ok(Matcher::COption(offset, inner_size))
             }
             IdlType::Bool => Ok(Matcher::Bool(offset)),
+            IdlType::String | IdlType::Bytes => {
+                Ok(Matcher::StringLength(offset))
+            }
+            IdlType::Defined(name) => match type_map.get(name) {
+                Some(IdlTypeDefinitionTy::Enum { variants }) => {
+                    Ok(Matcher::EnumDiscriminant {
+                        offset,
+                        variant_count: variants.len(),
+                    })
+                }
+                _ => Err(()),
+            },
             _ => Err(()),
         }
     }
 }
 
 impl Matcher {
+    /// Returns `true` if the [buf] matches the expected pattern.
+    /// Returns `false` if the [buf] is too short to hold the matched bytes.
+    /// Never panics since the [buf] holds arbitrary untrusted account data.
     fn matches(&self, buf: &[u8]) -> bool {
         use Matcher::*;
         match self {
-            COption(offset, _) => {
-                let src = array_ref![buf, *offset, 4];
-                matches!(src, [1, 0, 0, 0]) || matches!(src, [0, 0, 0, 0])
-            }
-            Bool(offset) => {
-                let src = array_ref![buf, *offset, 1];
-                matches!(src, [0] | [1])
+            COption(offset, _) => matches!(
+                read_bytes::<4>(buf, *offset),
+                Some([1, 0, 0, 0] | [0, 0, 0, 0])
+            ),
+            Bool(offset) => matches!(buf.get(*offset), Some(0 | 1)),
+            StringLength(offset) => match read_bytes::<4>(buf, *offset) {
+                Some(src) => {
+                    let len = u32::from_le_bytes(*src) as usize;
+                    len <= buf.len() - *offset - 4
+                }
+                None => false,
+            },
+            EnumDiscriminant {
+                offset,
+                variant_count,
+            } => match buf.get(*offset) {
+                Some(variant) => (*variant as usize) < *variant_count,
+                None => false,
+            },
+            TagByte { offset, value } => {
+                buf.get(*offset).is_none_or(|tag| tag == value)
             }
+            PubkeyOnCurve { offset } => match read_bytes::<32>(buf, *offset) {
+                Some(src) => Pubkey::new_from_array(*src).is_on_curve(),
+                None => false,
+            },
+            Relative {
+                var_fields,
+                matcher,
+            } => var_fields
+                .iter()
+                .try_fold(0, |start, field| field.end(buf, start))
+                .is_some_and(|start| matcher.matches(&buf[start..])),
         }
     }
 }
 
+/// Reads the [N] bytes at [offset] of the [buf], returning `None` if they exceed it.
+fn read_bytes<const N: usize>(buf: &[u8], offset: usize) -> Option<&[u8; N]> {
+    buf.get(offset..offset.checked_add(N)?)?.try_into().ok()
+}
+
 // -----------------
 // MatchDiscriminators
 // -----------------
 #[derive(Debug)]
-pub struct MatchDiscriminators(Vec<MatchDiscriminator>);
+pub struct MatchDiscriminators {
+    discs: Vec<MatchDiscriminator>,
+    /// Prefers accounts whose leading pubkey is on the curve among equally well matching ones.
+    pubkey_on_curve_tie_breaker: bool,
+}
 impl From<(&[IdlTypeDefinition], &HashMap<String, &IdlTypeDefinitionTy>)>
     for MatchDiscriminators
 {
@@ -68,12 +216,15 @@ impl From<(&[IdlTypeDefinition], &HashMap<String, &IdlTypeDefinitionTy>)>
             &HashMap<String, &IdlTypeDefinitionTy>,
         ),
     ) -> Self {
-        let mut discs = accounts
-            .iter()
-            .flat_map(|acc| MatchDiscriminator::new(acc.clone(), type_map))
-            .collect::<Vec<_>>();
-        discs.sort_by_key(|f| f.min_total_size);
-        Self(discs)
+        let mut discs = Self {
+            discs: accounts
+                .iter()
+                .flat_map(|acc| MatchDiscriminator::new(acc.clone(), type_map))
+                .collect(),
+            pubkey_on_curve_tie_breaker: false,
+        };
+        discs.sort();
+        discs
     }
 }
 
@@ -81,7 +232,7 @@ impl Deref for MatchDiscriminators {
     type Target = Vec<MatchDiscriminator>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.discs
     }
 }
 
@@ -95,34 +246,266 @@ impl MatchDiscriminators {
         self.find_matching_disc(buf).map(|disc| disc.account_name())
     }
 
+    /// Same as [Self::find_match_name], but fails with the names of all candidates if the
+    /// [policy] requires matches to be unambiguous and multiple accounts match equally well.
+    pub fn try_find_match_name(
+        &self,
+        buf: &[u8],
+        policy: AmbiguousMatchPolicy,
+    ) -> ChainparserResult<Option<&str>> {
+        Ok(self
+            .try_find_match_with_confidence(buf, policy)?
+            .map(|(name, _)| name))
+    }
+
+    /// Same as [Self::try_find_match_name], but also returns how confident the match is, see
+    /// [MatchConfidence].
+    pub fn try_find_match_with_confidence(
+        &self,
+        buf: &[u8],
+        policy: AmbiguousMatchPolicy,
+    ) -> ChainparserResult<Option<(&str, MatchConfidence)>> {
+        let best_matches = self.find_best_matches(buf);
+        match policy {
+            AmbiguousMatchPolicy::Error if best_matches.len() > 1 => {
+                Err(ChainparserError::AmbiguousAccountMatch(
+                    best_matches
+                        .iter()
+                        .map(|disc| disc.account_name().to_string())
+                        .collect(),
+                ))
+            }
+            _ => Ok(best_matches
+                .first()
+                .map(|disc| (disc.account_name(), disc.confidence(buf)))),
+        }
+    }
+
+    /// Describes how the account for the [buf] is resolved, listing for each candidate account
+    /// whether the [buf] is large enough to hold it, which matchers passed and finally which
+    /// account is selected given the [policy].
+    /// This is meant for debugging why an account matched or didn't.
+    pub fn explain(&self, buf: &[u8], policy: AmbiguousMatchPolicy) -> String {
+        let mut explanation = format!(
+            "Matching {} bytes against {} candidate accounts\n",
+            buf.len(),
+            self.len()
+        );
+        for disc in self.iter() {
+            let name = disc.account_name();
+            let min_size = disc.min_total_size;
+            if buf.len() < min_size {
+                let _ = writeln!(
+                    explanation,
+                    "- {name}: rejected, needs at least {min_size} bytes"
+                );
+                continue;
+            }
+            let tie_breakers = self
+                .pubkey_on_curve_tie_breaker
+                .then_some(&disc.tie_breakers);
+            let results = disc
+                .matchers
+                .iter()
+                .chain(tie_breakers.into_iter().flatten())
+                .map(|matcher| {
+                    let outcome = if matcher.matches(buf) {
+                        "passed"
+                    } else {
+                        "failed"
+                    };
+                    format!("{matcher:?} {outcome}")
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            let outcome = if !disc.matches_account(buf) {
+                "rejected"
+            } else if min_size == buf.len() {
+                "matched exact size"
+            } else {
+                "matched"
+            };
+            let _ = writeln!(
+                explanation,
+                "- {name}: {outcome}, size {min_size} bytes, matchers: {results}"
+            );
+        }
+
+        let best_matches = self
+            .find_best_matches(buf)
+            .iter()
+            .map(|disc| disc.account_name())
+            .collect::<Vec<_>>();
+        let _ = match best_matches.as_slice() {
+            [] => write!(explanation, "Selected: none"),
+            [name] => write!(explanation, "Selected: {name}"),
+            names if policy == AmbiguousMatchPolicy::Error => write!(
+                explanation,
+                "Selected: none, ambiguous match between {}",
+                names.join(", ")
+            ),
+            [name, rest @ ..] => write!(
+                explanation,
+                "Selected: {name}, equally well matched {}",
+                rest.join(", ")
+            ),
+        };
+        explanation
+    }
+
+    /// Requires the byte at [offset] of the data of the account with the given [account_name]
+    /// to be [value] whenever the data extends that far, see [Matcher::TagByte].
+    /// Returns `false` if no such account is known.
+    pub fn add_tag_byte_matcher(
+        &mut self,
+        account_name: &str,
+        offset: usize,
+        value: u8,
+    ) -> bool {
+        match self
+            .discs
+            .iter_mut()
+            .find(|disc| disc.account_name() == account_name)
+        {
+            Some(disc) => {
+                disc.matchers.push(Matcher::TagByte { offset, value });
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Matches the [account] per its `repr(C, align(N))` layout, i.e. for anchor zero-copy
+    /// accounts, see [MatchDiscriminator::new_repr_c].
+    pub fn set_repr_c(
+        &mut self,
+        account: &IdlTypeDefinition,
+        align: usize,
+        type_map: &HashMap<String, &IdlTypeDefinitionTy>,
+    ) {
+        self.discs
+            .retain(|disc| disc.account_name() != account.name);
+        if let Some(disc) =
+            MatchDiscriminator::new_repr_c(account.clone(), type_map, align)
+        {
+            self.discs.push(disc);
+        }
+        self.sort();
+    }
+
+    /// Equally well matching accounts are picked in this order, thus sort by name as well in
+    /// order to not depend on the order in which the IDL declares accounts.
+    fn sort(&mut self) {
+        self.discs.sort_by(|a, b| {
+            a.min_total_size
+                .cmp(&b.min_total_size)
+                .then_with(|| a.account_name().cmp(b.account_name()))
+        });
+    }
+
+    /// Configures whether accounts whose leading pubkey field holds a key on the ed25519 curve
+    /// are preferred over equally well matching accounts, see [Matcher::PubkeyOnCurve].
+    /// This is disabled by default since authorities may be PDAs which are off the curve.
+    pub fn set_pubkey_on_curve_tie_breaker(&mut self, enabled: bool) {
+        self.pubkey_on_curve_tie_breaker = enabled;
+    }
+
+    /// The discriminators of accounts that fit into [len] bytes.
+    /// Since they are sorted by size these are found via binary search, skipping all larger
+    /// accounts without checking them.
+    fn candidates(&self, len: usize) -> &[MatchDiscriminator] {
+        let end = self
+            .discs
+            .partition_point(|disc| disc.min_total_size <= len);
+        &self.discs[..end]
+    }
+
     fn find_matching_disc(&self, buf: &[u8]) -> Option<&MatchDiscriminator> {
+        self.find_best_matches(buf).first().copied()
+    }
+
+    /// Finds all discriminators that match the [buf] equally well.
+    /// Discriminators whose size matches exactly are the best matches, otherwise the ones that
+    /// had to match the most fields are.
+    /// They are ordered by size and then by account name, thus the first is the smallest
+    /// account, picking the lexicographically smallest name among accounts of the same size.
+    /// Ties are broken via the tie breaker matchers if enabled.
+    fn find_best_matches(&self, buf: &[u8]) -> Vec<&MatchDiscriminator> {
+        let best_matches = self.find_best_shape_matches(buf);
+        if !self.pubkey_on_curve_tie_breaker || best_matches.len() < 2 {
+            return best_matches;
+        }
+        let passed = |disc: &MatchDiscriminator| {
+            disc.tie_breakers
+                .iter()
+                .filter(|matcher| matcher.matches(buf))
+                .count()
+        };
+        let most_passed = best_matches
+            .iter()
+            .map(|disc| passed(disc))
+            .max()
+            .unwrap_or(0);
+        best_matches
+            .into_iter()
+            .filter(|disc| passed(disc) == most_passed)
+            .collect()
+    }
+
+    fn find_best_shape_matches(&self, buf: &[u8]) -> Vec<&MatchDiscriminator> {
+        let mut exact_matches = Vec::new();
         let mut candidates = Vec::new();
-        for disc in self.iter() {
+        for disc in self.candidates(buf.len()) {
             if disc.matches_account(buf) {
                 // if sizes match exactly as well then this is the best match
                 if disc.min_total_size == buf.len() {
-                    return Some(disc);
+                    exact_matches.push(disc);
                 } else {
                     candidates.push(disc);
                 }
             }
         }
-        // Did not find exact size match, thus we pick the discriminator
-        // that had to match most fields
-        let mut best_candidate = None::<&MatchDiscriminator>;
-        for candidate in candidates {
-            if let Some(disc) = best_candidate {
-                if candidate.matchers.len() > disc.matchers.len() {
-                    best_candidate = Some(candidate);
-                }
-            } else {
-                best_candidate = Some(candidate);
-            }
+        if !exact_matches.is_empty() {
+            return exact_matches;
         }
-        best_candidate
+        // Did not find exact size match, thus we pick the discriminators
+        // that had to match most fields
+        let most_matchers = candidates
+            .iter()
+            .map(|disc| disc.matchers.len())
+            .max()
+            .unwrap_or_default();
+        candidates
+            .into_iter()
+            .filter(|disc| disc.matchers.len() == most_matchers)
+            .collect()
     }
 }
 
+/// Describes how well account data matched the shape of the account it was resolved to.
+/// An account that matched few fields and not the exact size may be a coincidental match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatchConfidence {
+    /// The number of matchers, i.e. checks of field values, that the account data satisfied.
+    pub matchers: usize,
+    /// Whether the account data has exactly the size of the account.
+    pub exact_size: bool,
+    /// The number of trailing fields that weren't checked since they follow a field whose size
+    /// can't be determined without deserializing it, i.e. a `Vec` of strings.
+    pub unchecked_fields: usize,
+}
+
+/// Specifies how to proceed when multiple accounts match the account data equally well.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AmbiguousMatchPolicy {
+    /// Picks the first of the equally well matching accounts, ordered by size and then by
+    /// name.
+    #[default]
+    PickFirst,
+    /// Fails with an error listing all equally well matching accounts.
+    Error,
+}
+
 // -----------------
 // MatchDiscriminator
 // -----------------
@@ -131,6 +514,12 @@ pub struct MatchDiscriminator {
     pub account: IdlTypeDefinition,
     min_total_size: usize,
     matchers: Vec<Matcher>,
+    /// The number of trailing fields that no matchers were derived for, see
+    /// [MatchConfidence::unchecked_fields].
+    unchecked_fields: usize,
+    /// Matchers that aren't definitive and only break ties between equally well matching
+    /// accounts.
+    tie_breakers: Vec<Matcher>,
 }
 
 impl MatchDiscriminator {
@@ -138,31 +527,82 @@ impl MatchDiscriminator {
         account: IdlTypeDefinition,
         type_map: &HashMap<String, &IdlTypeDefinitionTy>,
     ) -> Option<Self> {
-        let account_sizes = base_account_sizes(&account, type_map);
-        match account_sizes {
-            Some((field_sizes, field_offsets)) => {
-                let min_total_size = field_sizes.iter().sum();
-                let matchers =
-                    account_matchers(&account, type_map, &field_offsets);
-                // TODO(thlorenz): should require at least have one multi byte matcher
-                if matchers.is_empty() {
-                    None
-                } else {
-                    Some(Self {
-                        account,
-                        min_total_size,
-                        matchers,
-                    })
-                }
+        match base_account_size(&account, type_map) {
+            Some(min_total_size) => {
+                let (matchers, unchecked_fields) =
+                    account_matchers(&account, type_map);
+                Self::with_matchers(account, min_total_size, matchers).map(
+                    |disc| Self {
+                        unchecked_fields,
+                        ..disc
+                    },
+                )
             }
             _ => None,
         }
     }
 
+    /// Same as [Self::new], but for accounts laid out per `repr(C, align(N))` whose fields are
+    /// padded to their alignment, i.e. anchor zero-copy accounts.
+    /// Pass an [align] of `1` for accounts declared with plain `repr(C)`.
+    pub fn new_repr_c(
+        account: IdlTypeDefinition,
+        type_map: &HashMap<String, &IdlTypeDefinitionTy>,
+        align: usize,
+    ) -> Option<Self> {
+        let IdlTypeDefinitionTy::Struct { fields } = &account.ty else {
+            // Enums are laid out per `repr(u8)` the same way as borsh does
+            return Self::new(account, type_map);
+        };
+        let (size, _) =
+            idl::repr_c_def_layout(&account.ty, Some(type_map), align)?;
+        let (offsets, _) = idl::repr_c_field_offsets(
+            fields.iter().map(|field| &field.ty),
+            Some(type_map),
+        )?;
+        let matchers = fields
+            .iter()
+            .zip(offsets)
+            .filter_map(|(field, range)| {
+                Matcher::try_from((&field.ty, type_map, range.start)).ok()
+            })
+            .collect();
+        Self::with_matchers(account, size, matchers)
+    }
+
+    fn with_matchers(
+        account: IdlTypeDefinition,
+        min_total_size: usize,
+        matchers: Vec<Matcher>,
+    ) -> Option<Self> {
+        // TODO(thlorenz): should require at least have one multi byte matcher
+        if matchers.is_empty() {
+            None
+        } else {
+            let tie_breakers = account_tie_breakers(&account);
+            Some(Self {
+                account,
+                min_total_size,
+                matchers,
+                unchecked_fields: 0,
+                tie_breakers,
+            })
+        }
+    }
+
     pub fn account_name(&self) -> &str {
         &self.account.name
     }
 
+    /// The confidence of matching the [buf], which is expected to match this account.
+    fn confidence(&self, buf: &[u8]) -> MatchConfidence {
+        MatchConfidence {
+            matchers: self.matchers.len(),
+            exact_size: self.min_total_size == buf.len(),
+            unchecked_fields: self.unchecked_fields,
+        }
+    }
+
     fn matches_account(&self, buf: &[u8]) -> bool {
         if buf.len() < self.min_total_size {
             return false;
@@ -171,49 +611,701 @@ impl MatchDiscriminator {
     }
 }
 
+/// Derives the matchers for the fields of the [account] along with the number of trailing
+/// fields that can't be matched.
+/// Fields following variable sized fields are matched relative to the end of those, see
+/// [Matcher::Relative], unless the size of such a field can only be determined by
+/// deserializing it, i.e. a `Vec` of strings, in which case the remaining fields aren't
+/// matched.
 fn account_matchers(
     account: &IdlTypeDefinition,
     type_map: &HashMap<String, &IdlTypeDefinitionTy>,
-    offsets: &[usize],
-) -> Vec<Matcher> {
+) -> (Vec<Matcher>, usize) {
     match &account.ty {
         IdlTypeDefinitionTy::Struct { fields } => {
             let mut matchers = Vec::new();
-            for (field, offset) in fields.iter().zip(offsets) {
+            let mut var_fields = Vec::<VarSizedField>::new();
+            // Relative to the end of the last variable sized field
+            let mut offset = 0;
+            for (idx, field) in fields.iter().enumerate() {
                 if let Ok(matcher) =
-                    Matcher::try_from((&field.ty, type_map, *offset))
+                    Matcher::try_from((&field.ty, type_map, offset))
                 {
-                    matchers.push(matcher)
+                    matchers.push(if var_fields.is_empty() {
+                        matcher
+                    } else {
+                        Matcher::Relative {
+                            var_fields: var_fields.clone(),
+                            matcher: Box::new(matcher),
+                        }
+                    })
+                }
+                match idl::idl_type_bytes(&field.ty, Some(type_map)) {
+                    Some(size) => offset += size,
+                    None => match VarSizedField::try_from((
+                        &field.ty, type_map, offset,
+                    )) {
+                        Ok(var_field) => {
+                            var_fields.push(var_field);
+                            offset = 0;
+                        }
+                        Err(_) => return (matchers, fields.len() - idx - 1),
+                    },
                 }
             }
-            matchers
+            (matchers, 0)
+        }
+        // The variant index leads enum accounts, the offsets of variant fields differ
+        // per variant and thus aren't matched
+        IdlTypeDefinitionTy::Enum { variants } => (
+            vec![Matcher::EnumDiscriminant {
+                offset: 0,
+                variant_count: variants.len(),
+            }],
+            0,
+        ),
+    }
+}
+
+/// A leading pubkey is likely an authority which is on the curve unless it is a PDA.
+fn account_tie_breakers(account: &IdlTypeDefinition) -> Vec<Matcher> {
+    match &account.ty {
+        IdlTypeDefinitionTy::Struct { fields }
+            if matches!(
+                fields.first().map(|field| &field.ty),
+                Some(IdlType::PublicKey)
+            ) =>
+        {
+            vec![Matcher::PubkeyOnCurve { offset: 0 }]
         }
         _ => Vec::new(),
     }
 }
 
-fn base_account_sizes(
+fn base_account_size(
     account: &IdlTypeDefinition,
     type_map: &HashMap<String, &IdlTypeDefinitionTy>,
-) -> Option<(Vec<usize>, Vec<usize>)> {
-    let mut offsets = Vec::new();
-    let mut sizes = Vec::new();
-
-    let mut offset = 0;
-
+) -> Option<usize> {
     match &account.ty {
         IdlTypeDefinitionTy::Struct { fields } => {
-            for field in fields {
-                if let Some(size) =
-                    idl::idl_type_bytes(&field.ty, Some(type_map))
-                {
-                    offsets.push(offset);
-                    sizes.push(size);
-                    offset += size;
+            Some(fixed_size(fields.iter().map(|field| &field.ty), type_map))
+        }
+        // Enum accounts hold at least the variant index followed by the smallest variant
+        IdlTypeDefinitionTy::Enum { variants } => variants
+            .iter()
+            .map(|variant| match &variant.fields {
+                Some(EnumFields::Named(fields)) => {
+                    fixed_size(fields.iter().map(|field| &field.ty), type_map)
                 }
+                Some(EnumFields::Tuple(tys)) => {
+                    fixed_size(tys.iter(), type_map)
+                }
+                None => 0,
+            })
+            .min()
+            .map(|size| 1 + size),
+    }
+}
+
+/// Sums up the sizes of the [tys] that have a fixed size.
+fn fixed_size<'a>(
+    tys: impl Iterator<Item = &'a IdlType>,
+    type_map: &HashMap<String, &IdlTypeDefinitionTy>,
+) -> usize {
+    tys.filter_map(|ty| idl::idl_type_bytes(ty, Some(type_map)))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use solana_idl::IdlField;
+
+    use super::*;
+
+    fn account(json: &str) -> IdlTypeDefinition {
+        serde_json::from_str(json).expect("valid account definition")
+    }
+
+    fn disc(json: &str) -> MatchDiscriminator {
+        MatchDiscriminator::new(account(json), &HashMap::new())
+            .expect("should create discriminator")
+    }
+
+    #[test]
+    fn string_length_matcher() {
+        let disc = disc(
+            r#"{
+              "name": "Profile",
+              "type": {
+                "kind": "struct",
+                "fields": [
+                  { "name": "level", "type": "u8" },
+                  { "name": "handle", "type": "string" },
+                  { "name": "active", "type": "bool" }
+                ]
+              }
+            }"#,
+        );
+        // The bool follows the string, thus it is matched relative to its end
+        assert_eq!(disc.matchers.len(), 2);
+        assert!(matches!(disc.matchers[0], Matcher::StringLength(1)));
+        assert!(matches!(
+            &disc.matchers[1],
+            Matcher::Relative { var_fields, matcher }
+                if var_fields.len() == 1 && matches!(**matcher, Matcher::Bool(0))
+        ));
+
+        let valid = [&[7u8][..], &3u32.to_le_bytes(), b"bob", &[1]].concat();
+        assert!(disc.matches_account(&valid));
+
+        let invalid_bool =
+            [&[7u8][..], &3u32.to_le_bytes(), b"bob", &[2]].concat();
+        assert!(!disc.matches_account(&invalid_bool));
+
+        let too_long = [&[7u8][..], &9u32.to_le_bytes(), b"bob", &[1]].concat();
+        assert!(!disc.matches_account(&too_long));
+
+        let truncated = [7u8, 3, 0];
+        assert!(!disc.matches_account(&truncated));
+    }
+
+    #[test]
+    fn match_fields_following_variable_sized_fields() {
+        let acc_with = |last: &str| {
+            account(&format!(
+                r#"{{
+                  "name": "Acc",
+                  "type": {{
+                    "kind": "struct",
+                    "fields": [
+                      {{ "name": "label", "type": "string" }},
+                      {{ "name": "amounts", "type": {{ "vec": "u16" }} }},
+                      {{ "name": "bump", "type": {{ "option": "u8" }} }},
+                      {{ "name": "padding", "type": "u8" }},
+                      {{ "name": "last", "type": {last} }}
+                    ]
+                  }}
+                }}"#
+            ))
+        };
+        let type_map = HashMap::new();
+        let flagged =
+            MatchDiscriminator::new(acc_with(r#""bool""#), &type_map).unwrap();
+        let keyed = MatchDiscriminator::new(
+            acc_with(r#"{ "coption": "publicKey" }"#),
+            &type_map,
+        )
+        .unwrap();
+
+        let data = |last: &[u8]| {
+            [
+                &2u32.to_le_bytes()[..],
+                b"ab",
+                &2u32.to_le_bytes(),
+                &[1, 0, 2, 0],
+                &[1, 9],
+                &[0],
+                last,
+            ]
+            .concat()
+        };
+        let with_bool = data(&[1]);
+        assert!(flagged.matches_account(&with_bool));
+        assert!(!keyed.matches_account(&with_bool));
+
+        assert!(!flagged.matches_account(&data(&[5])));
+
+        let with_coption = data(&[0; 36]);
+        assert!(keyed.matches_account(&with_coption));
+        assert!(!keyed.matches_account(&data(&[2; 36])));
+
+        // An invalid option tag leaves the following fields unlocated
+        let mut invalid_tag = with_bool.clone();
+        invalid_tag[14] = 2;
+        assert!(!flagged.matches_account(&invalid_tag));
+        assert_eq!(flagged.confidence(&with_bool).unchecked_fields, 0);
+    }
+
+    #[test]
+    fn report_fields_following_unsized_fields_as_unchecked() {
+        let disc = disc(
+            r#"{
+              "name": "Names",
+              "type": {
+                "kind": "struct",
+                "fields": [
+                  { "name": "enabled", "type": "bool" },
+                  { "name": "names", "type": { "vec": "string" } },
+                  { "name": "active", "type": "bool" },
+                  { "name": "bump", "type": "u8" }
+                ]
+              }
+            }"#,
+        );
+        assert_eq!(disc.matchers.len(), 1);
+        let data = [&[1u8][..], &0u32.to_le_bytes(), &[1, 2]].concat();
+        assert_eq!(
+            disc.confidence(&data),
+            MatchConfidence {
+                matchers: 1,
+                exact_size: false,
+                unchecked_fields: 2
+            }
+        );
+    }
+
+    #[test]
+    fn enum_discriminant_matcher() {
+        let kind = account(
+            r#"{
+              "name": "Kind",
+              "type": {
+                "kind": "enum",
+                "variants": [{ "name": "A" }, { "name": "B" }, { "name": "C" }]
+              }
+            }"#,
+        );
+        let type_map = HashMap::from([(kind.name.clone(), &kind.ty)]);
+        let disc = MatchDiscriminator::new(
+            account(
+                r#"{
+                  "name": "Tagged",
+                  "type": {
+                    "kind": "struct",
+                    "fields": [
+                      { "name": "kind", "type": { "defined": "Kind" } },
+                      { "name": "amount", "type": "u64" }
+                    ]
+                  }
+                }"#,
+            ),
+            &type_map,
+        )
+        .expect("should create discriminator for enum leading account");
+
+        assert!(matches!(
+            disc.matchers[..],
+            [Matcher::EnumDiscriminant {
+                offset: 0,
+                variant_count: 3
+            }]
+        ));
+        assert!(disc.matches_account(&[2, 0, 0, 0, 0, 0, 0, 0, 0]));
+        assert!(!disc.matches_account(&[3, 0, 0, 0, 0, 0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn enum_account() {
+        let disc = disc(
+            r#"{
+              "name": "Vault",
+              "type": {
+                "kind": "enum",
+                "variants": [
+                  { "name": "Uninitialized" },
+                  {
+                    "name": "Active",
+                    "fields": [
+                      { "name": "owner", "type": "publicKey" },
+                      { "name": "amount", "type": "u64" }
+                    ]
+                  },
+                  { "name": "Frozen", "fields": ["i64", "string"] }
+                ]
+              }
+            }"#,
+        );
+        assert_eq!(disc.min_total_size, 1);
+        assert!(matches!(
+            disc.matchers[..],
+            [Matcher::EnumDiscriminant {
+                offset: 0,
+                variant_count: 3
+            }]
+        ));
+        assert!(disc.matches_account(&[0]));
+        assert!(disc.matches_account(&[2; 13]));
+        assert!(!disc.matches_account(&[3; 41]));
+        assert!(!disc.matches_account(&[]));
+    }
+
+    #[test]
+    fn pubkey_on_curve_tie_breaker() {
+        let accounts = [
+            account(
+                r#"{
+                  "name": "Seeded",
+                  "type": {
+                    "kind": "struct",
+                    "fields": [
+                      { "name": "seed", "type": { "array": ["u8", 32] } },
+                      { "name": "flag", "type": "bool" }
+                    ]
+                  }
+                }"#,
+            ),
+            account(
+                r#"{
+                  "name": "Authorized",
+                  "type": {
+                    "kind": "struct",
+                    "fields": [
+                      { "name": "authority", "type": "publicKey" },
+                      { "name": "flag", "type": "bool" }
+                    ]
+                  }
+                }"#,
+            ),
+        ];
+        let mut discs =
+            MatchDiscriminators::from((&accounts[..], &HashMap::new()));
+        let on_curve =
+            Pubkey::from_str("9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin")
+                .unwrap();
+        let (pda, _) =
+            Pubkey::find_program_address(&[b"seed"], &Pubkey::default());
+        assert!(on_curve.is_on_curve());
+        let data = |pubkey: Pubkey| [&pubkey.to_bytes()[..], &[1]].concat();
+
+        let find = |discs: &MatchDiscriminators, buf: &[u8]| {
+            discs
+                .try_find_match_name(buf, AmbiguousMatchPolicy::Error)
+                .map(|name| name.map(str::to_string))
+        };
+        assert!(find(&discs, &data(on_curve)).is_err());
+
+        discs.set_pubkey_on_curve_tie_breaker(true);
+        assert_eq!(
+            find(&discs, &data(on_curve)).unwrap().as_deref(),
+            Some("Authorized")
+        );
+        // Not matching the tie breaker doesn't rule out accounts
+        assert!(find(&discs, &data(pda)).is_err());
+        assert!(discs
+            .explain(&data(on_curve), AmbiguousMatchPolicy::Error)
+            .contains("Bool(32) passed, PubkeyOnCurve { offset: 0 } passed"));
+    }
+
+    #[test]
+    fn matchers_do_not_panic_on_short_buffers() {
+        let matchers = [
+            Matcher::COption(4, 32),
+            Matcher::Bool(4),
+            Matcher::StringLength(4),
+            Matcher::EnumDiscriminant {
+                offset: 4,
+                variant_count: 2,
+            },
+            Matcher::PubkeyOnCurve { offset: 4 },
+        ];
+        for matcher in matchers {
+            assert!(!matcher.matches(&[0; 4]), "{matcher:?}");
+            assert!(!matcher.matches(&[]), "{matcher:?}");
+        }
+        // COption needs 4 bytes starting at its offset
+        assert!(!Matcher::COption(4, 32).matches(&[0; 7]));
+        assert!(Matcher::COption(4, 32).matches(&[0; 8]));
+    }
+
+    #[test]
+    fn size_match_with_arrays_of_defined_and_coption() {
+        let point = account(
+            r#"{
+              "name": "Point",
+              "type": {
+                "kind": "struct",
+                "fields": [
+                  { "name": "x", "type": "i64" },
+                  { "name": "y", "type": "i64" }
+                ]
+              }
+            }"#,
+        );
+        let type_map = HashMap::from([(point.name.clone(), &point.ty)]);
+        let field = |name: &str, ty: IdlType| IdlField {
+            name: name.to_string(),
+            ty,
+            attrs: None,
+        };
+        let shape = IdlTypeDefinition {
+            name: "Shape".to_string(),
+            ty: IdlTypeDefinitionTy::Struct {
+                fields: vec![
+                    field(
+                        "points",
+                        IdlType::Array(
+                            Box::new(IdlType::Defined(point.name.clone())),
+                            2,
+                        ),
+                    ),
+                    field(
+                        "delegates",
+                        IdlType::Array(
+                            Box::new(IdlType::COption(Box::new(IdlType::U64))),
+                            2,
+                        ),
+                    ),
+                    field("closed", IdlType::Bool),
+                ],
+            },
+        };
+        let disc = MatchDiscriminator::new(shape, &type_map)
+            .expect("should create discriminator");
+
+        assert_eq!(disc.min_total_size, 32 + 24 + 1);
+        assert!(matches!(disc.matchers[..], [Matcher::Bool(56)]));
+
+        let mut buf = vec![0; 57];
+        buf[56] = 1;
+        assert!(disc.matches_account(&buf));
+        buf[56] = 2;
+        assert!(!disc.matches_account(&buf));
+    }
+
+    #[test]
+    fn ambiguous_matches() {
+        let fields = r#"[
+          { "name": "flag", "type": "bool" },
+          { "name": "value", "type": "u64" }
+        ]"#;
+        let accounts = ["First", "Second"].map(|name| {
+            account(&format!(
+                r#"{{
+                  "name": "{name}",
+                  "type": {{ "kind": "struct", "fields": {fields} }}
+                }}"#
+            ))
+        });
+        let discs = MatchDiscriminators::from((&accounts[..], &HashMap::new()));
+        let buf = [1, 0, 0, 0, 0, 0, 0, 0, 0];
+
+        assert_eq!(discs.find_match_name(&buf), Some("First"));
+        assert_eq!(
+            discs
+                .try_find_match_name(&buf, AmbiguousMatchPolicy::PickFirst)
+                .unwrap(),
+            Some("First")
+        );
+        match discs.try_find_match_name(&buf, AmbiguousMatchPolicy::Error) {
+            Err(ChainparserError::AmbiguousAccountMatch(names)) => {
+                assert_eq!(names, vec!["First", "Second"])
             }
-            Some((sizes, offsets))
+            res => panic!("expected ambiguous match error, got {res:?}"),
+        }
+        // No match is not ambiguous
+        assert_eq!(
+            discs
+                .try_find_match_name(&[2; 9], AmbiguousMatchPolicy::Error)
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn equally_well_matching_accounts_are_ordered_by_size_and_name() {
+        let account_with = |name: &str, fields: &str| {
+            account(&format!(
+                r#"{{
+                  "name": "{name}",
+                  "type": {{ "kind": "struct", "fields": {fields} }}
+                }}"#
+            ))
+        };
+        let small = r#"[{ "name": "flag", "type": "bool" }]"#;
+        let large = r#"[
+          { "name": "flag", "type": "bool" },
+          { "name": "value", "type": "u64" }
+        ]"#;
+        let accounts = [
+            account_with("Zeta", large),
+            account_with("Beta", small),
+            account_with("Alpha", large),
+        ];
+        let discs = MatchDiscriminators::from((&accounts[..], &HashMap::new()));
+
+        // None matches the size exactly and all have one matcher, thus the smallest wins
+        let buf = [1; 12];
+        assert_eq!(discs.find_match_name(&buf), Some("Beta"));
+
+        // Without the smaller account the name breaks the tie regardless of IDL order
+        let accounts =
+            [account_with("Zeta", large), account_with("Alpha", large)];
+        let discs = MatchDiscriminators::from((&accounts[..], &HashMap::new()));
+        assert_eq!(discs.find_match_name(&[1; 9]), Some("Alpha"));
+        match discs.try_find_match_name(&[1; 9], AmbiguousMatchPolicy::Error) {
+            Err(ChainparserError::AmbiguousAccountMatch(names)) => {
+                assert_eq!(names, vec!["Alpha", "Zeta"])
+            }
+            res => panic!("expected ambiguous match error, got {res:?}"),
+        }
+    }
+
+    #[test]
+    fn find_match_by_leading_string() {
+        let accounts = [
+            account(
+                r#"{
+                  "name": "Named",
+                  "type": {
+                    "kind": "struct",
+                    "fields": [
+                      { "name": "name", "type": "string" },
+                      { "name": "value", "type": "u64" }
+                    ]
+                  }
+                }"#,
+            ),
+            account(
+                r#"{
+                  "name": "Flagged",
+                  "type": {
+                    "kind": "struct",
+                    "fields": [
+                      { "name": "value", "type": "u64" },
+                      { "name": "flag", "type": "bool" }
+                    ]
+                  }
+                }"#,
+            ),
+        ];
+        let discs = MatchDiscriminators::from((&accounts[..], &HashMap::new()));
+
+        let named =
+            [&5u32.to_le_bytes()[..], b"hello", &1u64.to_le_bytes()].concat();
+        assert_eq!(discs.find_match_name(&named), Some("Named"));
+
+        let flagged = [&u64::MAX.to_le_bytes()[..], &[1]].concat();
+        assert_eq!(discs.find_match_name(&flagged), Some("Flagged"));
+
+        let explanation =
+            discs.explain(&named, AmbiguousMatchPolicy::PickFirst);
+        assert_eq!(
+            explanation,
+            "Matching 17 bytes against 2 candidate accounts\n\
+             - Named: matched, size 8 bytes, matchers: StringLength(0) passed\n\
+             - Flagged: rejected, size 9 bytes, matchers: Bool(8) failed\n\
+             Selected: Named"
+        );
+        assert!(discs
+            .explain(&[0; 4], AmbiguousMatchPolicy::PickFirst)
+            .contains("- Flagged: rejected, needs at least 9 bytes"));
+
+        let names = |len| {
+            discs
+                .candidates(len)
+                .iter()
+                .map(|disc| disc.account_name())
+                .collect::<Vec<_>>()
+        };
+        assert!(names(7).is_empty());
+        assert_eq!(names(8), ["Named"]);
+        assert_eq!(names(9), ["Named", "Flagged"]);
+        assert_eq!(discs.find_match_name(&[1; 8]), None);
+
+        let policy = AmbiguousMatchPolicy::PickFirst;
+        assert_eq!(
+            discs
+                .try_find_match_with_confidence(&named, policy)
+                .unwrap(),
+            Some((
+                "Named",
+                MatchConfidence {
+                    matchers: 1,
+                    exact_size: false,
+                    unchecked_fields: 0
+                }
+            ))
+        );
+        assert_eq!(
+            discs
+                .try_find_match_with_confidence(&flagged, policy)
+                .unwrap(),
+            Some((
+                "Flagged",
+                MatchConfidence {
+                    matchers: 1,
+                    exact_size: true,
+                    unchecked_fields: 0
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn matching_random_short_buffers_never_panics() {
+        let field = |name: &str, ty| IdlField {
+            name: name.to_string(),
+            ty,
+            attrs: None,
+        };
+        let optional = IdlTypeDefinition {
+            name: "Optional".to_string(),
+            ty: IdlTypeDefinitionTy::Struct {
+                fields: vec![
+                    field("flag", IdlType::Bool),
+                    field(
+                        "delegate",
+                        IdlType::COption(Box::new(IdlType::PublicKey)),
+                    ),
+                    field("owner", IdlType::PublicKey),
+                ],
+            },
+        };
+        let accounts = [
+            r#"{ "name": "Named", "type": { "kind": "struct", "fields": [
+              { "name": "owner", "type": "publicKey" },
+              { "name": "label", "type": "string" },
+              { "name": "active", "type": "bool" }
+            ] } }"#,
+            r#"{ "name": "Flags", "type": { "kind": "struct", "fields": [
+              { "name": "first", "type": "bool" },
+              { "name": "second", "type": "bool" }
+            ] } }"#,
+        ]
+        .map(account);
+        let accounts = [&[optional][..], &accounts].concat();
+        let mut discs =
+            MatchDiscriminators::from((&accounts[..], &HashMap::new()));
+        discs.set_pubkey_on_curve_tie_breaker(true);
+        assert!(discs.add_tag_byte_matcher("Optional", 80, 2));
+
+        // Deterministic xorshift so failures can be reproduced
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        for _ in 0..10_000 {
+            let len = (next() % 96) as usize;
+            // Mostly small bytes so that bools and option tags match now and then
+            let buf = (0..len)
+                .map(|_| match next() % 4 {
+                    0 => next() as u8,
+                    _ => (next() % 2) as u8,
+                })
+                .collect::<Vec<_>>();
+            discs.find_match_name(&buf);
+            for policy in
+                [AmbiguousMatchPolicy::PickFirst, AmbiguousMatchPolicy::Error]
+            {
+                let _ = discs.try_find_match_with_confidence(&buf, policy);
+            }
+        }
+
+        let out_of_range = [
+            Matcher::COption(usize::MAX - 1, 32),
+            Matcher::Bool(usize::MAX),
+            Matcher::StringLength(usize::MAX - 2),
+            Matcher::PubkeyOnCurve {
+                offset: usize::MAX - 16,
+            },
+        ];
+        for matcher in out_of_range {
+            assert!(!matcher.matches(&[0; 64]), "{matcher:?}");
         }
-        _ => None, // accounts should always be structs
     }
 }
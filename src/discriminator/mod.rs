@@ -1,18 +1,87 @@
 pub mod match_discriminator;
 
-use solana_sdk::hash::hash;
+use crate::hash::sha256;
 
 pub type DiscriminatorBytes = [u8; 8];
 
+/// The namespace anchor uses to derive account discriminators.
+pub const ANCHOR_ACCOUNT_NAMESPACE: &str = "account";
+
+/// The namespace anchor uses to derive event discriminators.
+pub const ANCHOR_EVENT_NAMESPACE: &str = "event";
+
 /// Derives the account discriminator form the account name using the same algorithm that anchor
 /// uses.
 pub fn account_discriminator(name: &str) -> DiscriminatorBytes {
+    account_discriminator_with_namespace(ANCHOR_ACCOUNT_NAMESPACE, name)
+}
+
+/// Derives the event discriminator from the event name using the same algorithm that anchor
+/// uses.
+pub fn event_discriminator(name: &str) -> DiscriminatorBytes {
+    account_discriminator_with_namespace(ANCHOR_EVENT_NAMESPACE, name)
+}
+
+/// Derives the account discriminator from the account name using the anchor algorithm, but
+/// with the provided [namespace] instead of `account`, i.e. `state` for frameworks that hash
+/// `"state:<name>"`.
+pub fn account_discriminator_with_namespace(
+    namespace: &str,
+    name: &str,
+) -> DiscriminatorBytes {
     let mut discriminator = [0u8; 8];
-    let hashed = hash(format!("account:{name}").as_bytes()).to_bytes();
+    let hashed = sha256(&[format!("{namespace}:{name}").as_bytes()]);
     discriminator.copy_from_slice(&hashed[..8]);
     discriminator
 }
 
+/// Derives the discriminator that prefixes the data of an account from the name of its type.
+/// Implement it for programs whose framework derives discriminators differently than anchor,
+/// i.e. with a different hash function, and provide it to
+/// [crate::json::PrefixDiscriminator::with_strategy].
+pub trait DiscriminatorStrategy {
+    fn discriminator(&self, name: &str) -> DiscriminatorBytes;
+}
+
+/// The [DiscriminatorStrategy] anchor uses, hashing `"<namespace>:<name>"` with sha256 and
+/// keeping the first 8 bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnchorDiscriminatorStrategy {
+    namespace: String,
+}
+
+impl AnchorDiscriminatorStrategy {
+    /// Uses the provided [namespace] instead of `account`, see
+    /// [account_discriminator_with_namespace].
+    pub fn with_namespace(namespace: &str) -> Self {
+        Self {
+            namespace: namespace.to_string(),
+        }
+    }
+}
+
+impl Default for AnchorDiscriminatorStrategy {
+    fn default() -> Self {
+        Self::with_namespace(ANCHOR_ACCOUNT_NAMESPACE)
+    }
+}
+
+impl DiscriminatorStrategy for AnchorDiscriminatorStrategy {
+    fn discriminator(&self, name: &str) -> DiscriminatorBytes {
+        account_discriminator_with_namespace(&self.namespace, name)
+    }
+}
+
+/// Two account types of an IDL that resolve to the same discriminator.
+/// Only the [first] account can be deserialized via that discriminator since the [second] one is
+/// shadowed by it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscriminatorCollision {
+    pub discriminator: Vec<u8>,
+    pub first: String,
+    pub second: String,
+}
+
 pub fn discriminator_from_data(data: &[u8]) -> DiscriminatorBytes {
     let mut discriminator = [0u8; 8];
     discriminator.copy_from_slice(&data[..8]);
@@ -29,4 +98,34 @@ mod test {
         let discriminator = account_discriminator(name);
         assert_eq!(discriminator, [133, 250, 161, 78, 246, 27, 55, 187]);
     }
+
+    #[test]
+    fn account_discriminator_with_namespace_test() {
+        let name = "VaultInfo";
+        assert_eq!(
+            account_discriminator_with_namespace(
+                ANCHOR_ACCOUNT_NAMESPACE,
+                name
+            ),
+            account_discriminator(name)
+        );
+        assert_ne!(
+            account_discriminator_with_namespace("state", name),
+            account_discriminator(name)
+        );
+    }
+
+    #[test]
+    fn anchor_discriminator_strategy_test() {
+        let name = "VaultInfo";
+        assert_eq!(
+            AnchorDiscriminatorStrategy::default().discriminator(name),
+            account_discriminator(name)
+        );
+        assert_eq!(
+            AnchorDiscriminatorStrategy::with_namespace("state")
+                .discriminator(name),
+            account_discriminator_with_namespace("state", name)
+        );
+    }
 }
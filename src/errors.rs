@@ -40,9 +40,39 @@ pub enum ChainparserError {
     #[error("Borsh failed to deserialize type for enum '{0}' ({1})")]
     EnumDeserializeError(String, Box<ChainparserError>),
 
+    #[error("Failed to deserialize account '{0}' ({1})")]
+    AccountDeserializeError(String, #[source] Box<ChainparserError>),
+
+    #[error("Borsh failed to deserialize args for instruction '{0}' ({1})")]
+    InstructionDeserializeError(String, Box<ChainparserError>),
+
+    #[error("Borsh failed to deserialize fields for event '{0}' ({1})")]
+    EventDeserializeError(String, Box<ChainparserError>),
+
+    #[error("Unable to decode base64 event data '{0}' ({1})")]
+    EventDataBase64DecodeError(String, base64::DecodeError),
+
+    #[error("Failed to serialize type '{0}' ({1})")]
+    CompositeSerializeError(String, Box<ChainparserError>),
+
+    #[error("Failed to serialize type for field '{0}' ({1})")]
+    FieldSerializeError(String, Box<ChainparserError>),
+
+    #[error("Failed to serialize type for struct '{0}' ({1})")]
+    StructSerializeError(String, Box<ChainparserError>),
+
+    #[error("Failed to serialize args for instruction '{0}' ({1})")]
+    InstructionSerializeError(String, Box<ChainparserError>),
+
     #[error("The '{0}' deserializer does not support type '{1}'")]
     DeserializerDoesNotSupportType(String, String),
 
+    #[error("The '{0}' serializer does not support type '{1}'")]
+    SerializerDoesNotSupportType(String, String),
+
+    #[error("Encountered '{1}' when trying to serialize type '{0}'")]
+    InvalidDataToSerialize(String, String),
+
     #[error(
         "Encountered '{1}' when trying to deserizalize type '{0}' from {2:?}"
     )]
@@ -59,34 +89,136 @@ pub enum ChainparserError {
     )]
     CannotFindDeserializerForAccount,
 
+    #[error("Account data matches multiple accounts equally well: {0:?}")]
+    AmbiguousAccountMatch(Vec<String>),
+
     #[error("Account is requested to be deserialized Idl {0} version {1} has no accounts")]
     IdlHasNoAccountsAndCannotDeserializeAccountData(String, String),
 
     #[error("Account is requested to be via discriminator bytes but Idl {0} version {1} has no such accounts")]
     IdlHasNoAccountsDiscriminatedByDiscriminatorBytes(String, String),
 
+    #[error("Could not find an instruction that matches the provided instruction data {0:?}")]
+    CannotFindInstructionForData(Vec<u8>),
+
     #[error("Type {0} is referenced but was not defined in the IDL")]
     CannotFindDefinedType(String),
 
+    #[error("Cannot override type of field '{1}' of account '{0}' since it was not found in the IDL")]
+    CannotFindFieldToOverride(String, String),
+
+    #[error("Exceeded the max depth of {0} nested defined types")]
+    MaxDepthExceeded(usize),
+
+    #[error("Idl {0} has types {1:?} that reference themselves without an Option, Vec or other indirection")]
+    CyclicTypeDefinitions(String, Vec<String>),
+
+    #[error("Type {0} is an enum and thus has no fields")]
+    TypeHasNoFields(String),
+
     #[error("Variant with discriminant {0} does not exist")]
     InvalidEnumVariantDiscriminator(u8),
 
+    #[error("Enum '{0}' has no variant with value {1}")]
+    InvalidEnumVariantValue(String, u64),
+
+    #[error("Scaled decimal type '{0}' needs to be a struct with a single integer field")]
+    InvalidScaledDecimalType(String),
+
+    #[error("Expected {0} bytes of padding but only {1} bytes remain")]
+    InsufficientDataForPadding(usize, usize),
+
+    #[error("Ring buffer '{0}' refers to field '{1}' which needs to be an unsigned integer declared before it")]
+    RingBufferFieldNotFound(String, String),
+
+    #[error("Ring buffer '{0}' has a head of {1} which exceeds the addressable range")]
+    RingBufferHeadTooLarge(String, u64),
+
+    #[error("TLV entry of type {0} has length {1} but only {2} bytes remain")]
+    TlvEntryExceedsData(u16, usize, usize),
+
+    #[error("Failed to write deserialized JSON ({0})")]
+    WriterIoError(std::io::Error),
+
+    #[error("Deserialized JSON exceeds the max size of {0} bytes")]
+    OutputTooLarge(usize),
+
+    #[error("Unable to decode base64 account data")]
+    Base64DecodeError(#[from] base64::DecodeError),
+
+    #[error("Account data encoded as '{0}' is not supported, request it as 'base64' instead")]
+    UnsupportedRpcDataEncoding(String),
+
+    #[error("Account is owned by {1} instead of the expected program {0}")]
+    OwnerMismatch(String, String),
+
+    #[error("Account {0} does not exist")]
+    AccountNotFound(String),
+
+    #[error("Offset {0} exceeds the {1} bytes of account data")]
+    AccountDataOffsetOutOfBounds(usize, usize),
+
     #[error("Unable to parse JSON")]
     ParseJsonError(#[from] serde_json::Error),
 
+    #[error("Serialization opts {0} and {1} are mutually exclusive")]
+    ConflictingSerializationOpts(String, String),
+
+    #[cfg(feature = "msgpack")]
+    #[error("Failed to encode MessagePack ({0})")]
+    MsgpackEncodeError(#[from] rmp::encode::ValueWriteError),
+
     #[cfg(feature = "bson")]
     #[error("Raw Bson Error")]
     RawBsonError(#[from] bson::raw::Error),
 
+    #[cfg(feature = "bson")]
+    #[error("Account does not deserialize to a BSON document")]
+    BsonNotADocument,
+
     #[error("No IDL was added for the program {0}.")]
     CannotFindAccountDeserializerForProgramId(String),
 
+    #[error("No IDL was added for the program {0} at or before slot {1}.")]
+    CannotFindIdlVersionForSlot(String, u64),
+
+    #[error("Invalid base58 pubkey {0}")]
+    InvalidPubkey(String),
+
     #[error("Unable to derive pubkey for the IDL to fetch")]
-    IdlPubkeyError(#[from] solana_sdk::pubkey::PubkeyError),
+    IdlPubkeyError(#[from] crate::pubkey::PubkeyError),
+
+    #[error("Failed to read IDL file '{0}' ({1})")]
+    IdlFileReadError(String, std::io::Error),
 
+    #[error("Failed to add IDL from file '{0}' ({1})")]
+    IdlFileError(String, Box<ChainparserError>),
+
+    #[deprecated(
+        since = "0.3.0",
+        note = "use IdlNotZlib, IdlTruncated, IdlCorrupt or IdlInvalidUtf8 instead"
+    )]
     #[error("Unable to inflate IDl data ({0})")]
     IdlContainerShouldContainZlibData(String),
 
+    #[error("IDL data is neither zlib nor gzip compressed ({0})")]
+    IdlNotZlib(String),
+
+    #[error("IDL data ends mid-stream after inflating {0} bytes")]
+    IdlTruncated(usize),
+
+    #[error("IDL data is corrupt after inflating {0} bytes ({1})")]
+    IdlCorrupt(usize, String),
+
+    #[error("Inflated IDL data is not valid UTF-8 ({0})")]
+    IdlInvalidUtf8(String),
+
+    #[error("Unzipped IDL data exceeds the maximum of {0} bytes")]
+    IdlDecompressionTooLarge(usize),
+
+    #[error("IDL account data has {0} bytes but needs at least {1} bytes")]
+    IdlAccountDataTooShort(usize, usize),
+
     #[error(
         "Failed to parse pubkey of the program to add IDL for '{0}' ({1})"
     )]
@@ -96,4 +228,25 @@ pub enum ChainparserError {
         "Cannot parse account data with {0} bytes since the discriminator is at least {1} bytes"
     )]
     AccountDataTooShortForDiscriminatorBytes(usize, usize),
+
+    #[error(
+        "Account data does not start with the discriminator of account '{0}'"
+    )]
+    AccountDiscriminatorMismatch(String),
+
+    #[error(
+        "Type '{0}' is shared with other IDLs via a type registry and cannot be modified"
+    )]
+    SharedTypeCannotBeModified(String),
+}
+
+impl ChainparserError {
+    /// Returns the name of the account that failed to deserialize if it was resolved before the
+    /// failure occurred, see [crate::ChainparserDeserializer::deserialize_named_account].
+    pub fn account_name(&self) -> Option<&str> {
+        match self {
+            Self::AccountDeserializeError(name, _) => Some(name),
+            _ => None,
+        }
+    }
 }
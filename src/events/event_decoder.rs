@@ -0,0 +1,295 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use base64::{prelude::BASE64_STANDARD, Engine as _};
+use solana_idl::{Idl, IdlEvent};
+
+use crate::{
+    deserializer::DeserializeProvider,
+    discriminator::{event_discriminator, DiscriminatorBytes},
+    errors::{ChainparserError, ChainparserResult},
+    json::{
+        JsonIdlEventDeserializer, JsonIdlTypeDefinitionDeserializer,
+        JsonSerializationOpts, JsonTypeDefinitionDeserializerMap, OptsRef,
+    },
+};
+
+/// The prefix of the log lines that anchor programs emit events with.
+const PROGRAM_DATA_LOG_PREFIX: &str = "Program data: ";
+
+/// Decodes raw event [data] that an anchor program emitted using the events of the provided
+/// [idl], see [EventDecoder::decode_event_data].
+///
+/// - [idl] the IDL of the program that emitted the event
+/// - [data] the raw event data including the discriminator
+/// - [opts] specifying how specific data types should be deserialized
+pub fn decode_event_data(
+    idl: &Idl,
+    data: &[u8],
+    opts: &JsonSerializationOpts,
+) -> ChainparserResult<Option<(String, String)>> {
+    let events = idl.events.as_deref().unwrap_or_default();
+    if find_matching_idl_event(events, data).is_none() {
+        return Ok(None);
+    }
+    EventDecoder::new(idl, opts)?.decode_event_data(data)
+}
+
+/// Decodes the events that an anchor program emitted into the [logs] of a transaction using the
+/// events of the provided [idl], see [EventDecoder::decode_event_logs].
+///
+/// - [idl] the IDL of the program that emitted the events
+/// - [logs] the log messages of the transaction
+/// - [opts] specifying how specific data types should be deserialized
+pub fn decode_event_logs<S: AsRef<str>>(
+    idl: &Idl,
+    logs: &[S],
+    opts: &JsonSerializationOpts,
+) -> ChainparserResult<Vec<(String, String)>> {
+    EventDecoder::new(idl, opts)?.decode_event_logs(logs)
+}
+
+/// Decodes the events that an anchor program emits using the events of its IDL.
+/// The deserializers for the types of the IDL are created once and reused for all events that
+/// are decoded with the same instance.
+pub struct EventDecoder<'opts> {
+    de_provider: DeserializeProvider,
+    events: Vec<IdlEvent>,
+    type_de_map: JsonTypeDefinitionDeserializerMap<'opts>,
+    opts: OptsRef<'opts>,
+}
+
+impl<'opts> EventDecoder<'opts> {
+    /// Creates an [EventDecoder] for the events of the provided [idl].
+    ///
+    /// - [idl] the IDL of the program that emits the events
+    /// - [opts] specifying how specific data types should be deserialized
+    pub fn new(
+        idl: &Idl,
+        opts: impl Into<OptsRef<'opts>>,
+    ) -> ChainparserResult<Self> {
+        let opts = opts.into();
+        let de_provider = DeserializeProvider::try_from(idl)?;
+        let type_de_map = Arc::new(Mutex::new(HashMap::new()));
+        for type_definition in &idl.types {
+            let instance = JsonIdlTypeDefinitionDeserializer::new(
+                type_definition,
+                type_de_map.clone(),
+                opts.clone(),
+            );
+            type_de_map
+                .lock()
+                .unwrap()
+                .insert(instance.name.clone(), instance);
+        }
+        Ok(Self {
+            de_provider,
+            events: idl.events.clone().unwrap_or_default(),
+            type_de_map,
+            opts,
+        })
+    }
+
+    /// Decodes raw event [data].
+    /// The event is resolved by matching its discriminator against the first 8 bytes of the
+    /// [data] and its fields are deserialized from the remaining bytes.
+    ///
+    /// Returns the name of the matched event and its fields as JSON or `None` if the [data]
+    /// doesn't start with the discriminator of any event of the IDL.
+    ///
+    /// - [data] the raw event data including the discriminator
+    pub fn decode_event_data(
+        &self,
+        data: &[u8],
+    ) -> ChainparserResult<Option<(String, String)>> {
+        find_matching_idl_event(&self.events, data)
+            .map(|idl_event| self.decode(idl_event, data))
+            .transpose()
+    }
+
+    /// Decodes the events that were emitted into the [logs] of a transaction.
+    /// Events are logged as `Program data: <base64>`, all other log lines are ignored as are
+    /// payloads that don't start with the discriminator of any event of the IDL.
+    /// Fails if a payload isn't valid base64.
+    ///
+    /// Returns the name of each decoded event along with its fields as JSON in the order in
+    /// which they were logged.
+    ///
+    /// - [logs] the log messages of the transaction
+    pub fn decode_event_logs<S: AsRef<str>>(
+        &self,
+        logs: &[S],
+    ) -> ChainparserResult<Vec<(String, String)>> {
+        let mut events = Vec::new();
+        for log in logs {
+            let Some(payload) =
+                log.as_ref().strip_prefix(PROGRAM_DATA_LOG_PREFIX)
+            else {
+                continue;
+            };
+            let payload = payload.trim();
+            let data = BASE64_STANDARD.decode(payload).map_err(|err| {
+                ChainparserError::EventDataBase64DecodeError(
+                    payload.to_string(),
+                    err,
+                )
+            })?;
+            if let Some(event) = self.decode_event_data(&data)? {
+                events.push(event);
+            }
+        }
+        Ok(events)
+    }
+
+    fn decode(
+        &self,
+        idl_event: &IdlEvent,
+        data: &[u8],
+    ) -> ChainparserResult<(String, String)> {
+        let event_de = JsonIdlEventDeserializer::new(
+            idl_event,
+            self.type_de_map.clone(),
+            self.opts.clone(),
+        );
+        let mut fields = String::new();
+        let buf = &mut &data[8..];
+        match &self.de_provider {
+            DeserializeProvider::Borsh(de) => {
+                event_de.deserialize(de, &mut fields, buf)
+            }
+            DeserializeProvider::Spl(de) => {
+                event_de.deserialize(de, &mut fields, buf)
+            }
+        }?;
+        Ok((idl_event.name.to_string(), fields))
+    }
+}
+
+// The [type_de_map] can hold circular references and thus leaks memory if not cleared.
+impl Drop for EventDecoder<'_> {
+    fn drop(&mut self) {
+        self.type_de_map.lock().unwrap().clear();
+    }
+}
+
+fn find_matching_idl_event<'a>(
+    events: &'a [IdlEvent],
+    data: &[u8],
+) -> Option<&'a IdlEvent> {
+    let discriminator: DiscriminatorBytes = data.get(..8)?.try_into().ok()?;
+    events
+        .iter()
+        .find(|event| event_discriminator(&event.name) == discriminator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EVENTS_IDL_JSON: &str = r#"{
+  "version": "0.1.0",
+  "name": "events",
+  "instructions": [],
+  "events": [
+    {
+      "name": "Deposited",
+      "fields": [
+        { "name": "amount", "type": "u64", "index": false },
+        { "name": "memo", "type": "string", "index": false }
+      ]
+    },
+    {
+      "name": "Withdrawn",
+      "fields": [{ "name": "amount", "type": "u64", "index": false }]
+    }
+  ]
+}"#;
+
+    fn event_data(name: &str, fields: &[u8]) -> Vec<u8> {
+        [event_discriminator(name).as_slice(), fields].concat()
+    }
+
+    #[test]
+    fn decode_anchor_event_data() {
+        let idl: Idl = serde_json::from_str(EVENTS_IDL_JSON).unwrap();
+        let opts = JsonSerializationOpts::default();
+        let data = event_data(
+            "Deposited",
+            &[5, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, b'h', b'i'],
+        );
+        let event = decode_event_data(&idl, &data, &opts).unwrap();
+        assert_eq!(
+            event,
+            Some((
+                "Deposited".to_string(),
+                r#"{"amount":5,"memo":"hi"}"#.to_string()
+            ))
+        );
+        assert_eq!(decode_event_data(&idl, &[1, 2, 3], &opts).unwrap(), None);
+    }
+
+    #[test]
+    fn decode_anchor_event_logs() {
+        let idl: Idl = serde_json::from_str(EVENTS_IDL_JSON).unwrap();
+        let opts = JsonSerializationOpts::default();
+        let withdrawn = event_data("Withdrawn", &7u64.to_le_bytes());
+        let unknown = event_data("Unknown", &7u64.to_le_bytes());
+        let logs = [
+            "Program Vau1t11111111111111111111111111111111111111 invoke [1]"
+                .to_string(),
+            "Program log: Instruction: Withdraw".to_string(),
+            format!("Program data: {}", BASE64_STANDARD.encode(&withdrawn)),
+            format!("Program data: {}", BASE64_STANDARD.encode(&unknown)),
+            "Program Vau1t11111111111111111111111111111111111111 success"
+                .to_string(),
+        ];
+        let events = decode_event_logs(&idl, &logs, &opts).unwrap();
+        assert_eq!(
+            events,
+            vec![("Withdrawn".to_string(), r#"{"amount":7}"#.to_string())]
+        );
+    }
+
+    #[test]
+    fn decode_truncated_event_logs() {
+        let idl: Idl = serde_json::from_str(EVENTS_IDL_JSON).unwrap();
+        let opts = JsonSerializationOpts::default();
+        let truncated = event_data("Withdrawn", &[7, 0]);
+        let logs = [format!(
+            "Program data: {}",
+            BASE64_STANDARD.encode(truncated)
+        )];
+        let res = decode_event_logs(&idl, &logs, &opts);
+        assert!(matches!(
+            res,
+            Err(ChainparserError::EventDeserializeError(ref name, _)) if name == "Withdrawn"
+        ));
+    }
+
+    #[test]
+    fn decode_corrupt_event_logs() {
+        let idl: Idl = serde_json::from_str(EVENTS_IDL_JSON).unwrap();
+        let opts = JsonSerializationOpts::default();
+        let decoder = EventDecoder::new(&idl, &opts).unwrap();
+        let withdrawn = event_data("Withdrawn", &7u64.to_le_bytes());
+        let logs = [
+            format!("Program data: {}", BASE64_STANDARD.encode(&withdrawn)),
+            "Program data: not base64!".to_string(),
+        ];
+        let res = decoder.decode_event_logs(&logs);
+        assert!(matches!(
+            res,
+            Err(ChainparserError::EventDataBase64DecodeError(ref payload, _))
+                if payload == "not base64!"
+        ));
+
+        // The decoder can be reused after a failure
+        let events = decoder.decode_event_logs(&logs[..1]).unwrap();
+        assert_eq!(
+            events,
+            vec![("Withdrawn".to_string(), r#"{"amount":7}"#.to_string())]
+        );
+    }
+}
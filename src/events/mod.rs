@@ -0,0 +1,3 @@
+mod event_decoder;
+
+pub use event_decoder::{decode_event_data, decode_event_logs, EventDecoder};
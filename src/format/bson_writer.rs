@@ -0,0 +1,199 @@
+use bson::{spec::BinarySubtype, Binary, Bson, Decimal128, Document};
+
+use super::ChainparserVisitor;
+use crate::{
+    errors::ChainparserResult, json::JsonSerializationOpts, pubkey::Pubkey,
+};
+
+/// Containers that are currently being built.
+enum Frame {
+    /// A struct or map along with the key of the value that is written next.
+    Document(Document, Option<String>),
+    Array(Vec<Bson>),
+    /// The key of a map entry which BSON requires to be a string.
+    MapKey(Option<Bson>),
+}
+
+/// Writes the values emitted to a [super::ChainparserVisitor] into a BSON [Document], i.e. to
+/// store accounts in MongoDB.
+///
+/// Integers are stored as `int32` or `int64` depending on their size and `u64` values exceeding
+/// `int64` as `decimal128`, while 128-bit integers are stored as strings.
+/// Public keys are stored as base58 strings or as generic binary if
+/// [JsonSerializationOpts::pubkey_as_base58] is disabled and `bytes` as well as `u8` arrays as
+/// generic binary.
+/// Map keys that aren't strings are converted to their extended JSON representation.
+pub struct BsonWriter<'opts> {
+    frames: Vec<Frame>,
+    root: Option<Bson>,
+    opts: &'opts JsonSerializationOpts,
+}
+
+impl<'opts> BsonWriter<'opts> {
+    pub fn new(opts: &'opts JsonSerializationOpts) -> Self {
+        Self {
+            frames: Vec::new(),
+            root: None,
+            opts,
+        }
+    }
+
+    /// Returns the value that was written.
+    pub fn into_bson(self) -> Option<Bson> {
+        self.root
+    }
+
+    fn value(&mut self, value: Bson) -> ChainparserResult<()> {
+        match self.frames.last_mut() {
+            Some(Frame::Document(doc, key)) => {
+                doc.insert(key.take().unwrap_or_default(), value);
+            }
+            Some(Frame::Array(items)) => items.push(value),
+            Some(Frame::MapKey(key)) => *key = Some(value),
+            None => self.root = Some(value),
+        }
+        Ok(())
+    }
+
+    fn end_frame(&mut self) -> ChainparserResult<()> {
+        match self.frames.pop() {
+            Some(Frame::Document(doc, _)) => self.value(Bson::Document(doc)),
+            Some(Frame::Array(items)) => self.value(Bson::Array(items)),
+            Some(Frame::MapKey(_)) | None => Ok(()),
+        }
+    }
+}
+
+impl ChainparserVisitor for BsonWriter<'_> {
+    fn begin_struct(&mut self, _len: usize) -> ChainparserResult<()> {
+        self.frames.push(Frame::Document(Document::new(), None));
+        Ok(())
+    }
+
+    fn field_name(&mut self, name: &str) -> ChainparserResult<()> {
+        if let Some(Frame::Document(_, key)) = self.frames.last_mut() {
+            *key = Some(name.to_string());
+        }
+        Ok(())
+    }
+
+    fn end_struct(&mut self) -> ChainparserResult<()> {
+        self.end_frame()
+    }
+
+    fn begin_seq(&mut self, len: usize) -> ChainparserResult<()> {
+        self.frames.push(Frame::Array(Vec::with_capacity(len)));
+        Ok(())
+    }
+
+    fn end_seq(&mut self) -> ChainparserResult<()> {
+        self.end_frame()
+    }
+
+    fn begin_map(&mut self, _len: usize) -> ChainparserResult<()> {
+        self.frames.push(Frame::Document(Document::new(), None));
+        Ok(())
+    }
+
+    fn begin_map_key(&mut self) -> ChainparserResult<()> {
+        self.frames.push(Frame::MapKey(None));
+        Ok(())
+    }
+
+    fn end_map_key(&mut self) -> ChainparserResult<()> {
+        let key = match self.frames.pop() {
+            Some(Frame::MapKey(Some(Bson::String(key)))) => key,
+            Some(Frame::MapKey(Some(key))) => {
+                key.into_relaxed_extjson().to_string()
+            }
+            _ => String::new(),
+        };
+        self.field_name(&key)
+    }
+
+    fn end_map(&mut self) -> ChainparserResult<()> {
+        self.end_frame()
+    }
+
+    fn null_value(&mut self) -> ChainparserResult<()> {
+        self.value(Bson::Null)
+    }
+
+    fn bool_value(&mut self, b: bool) -> ChainparserResult<()> {
+        self.value(Bson::Boolean(b))
+    }
+
+    fn u32_value(&mut self, n: u32) -> ChainparserResult<()> {
+        match i32::try_from(n) {
+            Ok(n) => self.value(Bson::Int32(n)),
+            Err(_) => self.value(Bson::Int64(n.into())),
+        }
+    }
+
+    fn u64_value(&mut self, n: u64) -> ChainparserResult<()> {
+        match i64::try_from(n) {
+            Ok(n) => self.value(Bson::Int64(n)),
+            Err(_) => self.value(Bson::Decimal128(decimal128(n))),
+        }
+    }
+
+    fn u128_value(&mut self, n: u128) -> ChainparserResult<()> {
+        self.value(Bson::String(n.to_string()))
+    }
+
+    fn i32_value(&mut self, n: i32) -> ChainparserResult<()> {
+        self.value(Bson::Int32(n))
+    }
+
+    fn i64_value(&mut self, n: i64) -> ChainparserResult<()> {
+        self.value(Bson::Int64(n))
+    }
+
+    fn i128_value(&mut self, n: i128) -> ChainparserResult<()> {
+        self.value(Bson::String(n.to_string()))
+    }
+
+    fn f64_value(&mut self, n: f64) -> ChainparserResult<()> {
+        self.value(Bson::Double(n))
+    }
+
+    fn str_value(&mut self, s: &str) -> ChainparserResult<()> {
+        self.value(Bson::String(s.to_string()))
+    }
+
+    fn pubkey_value(&mut self, pubkey: &Pubkey) -> ChainparserResult<()> {
+        if self.opts.pubkey_as_base58 {
+            self.str_value(&pubkey.to_string())
+        } else {
+            self.bytes_value(&pubkey.to_bytes())
+        }
+    }
+
+    fn bytes_value(&mut self, bytes: &[u8]) -> ChainparserResult<()> {
+        self.value(Bson::Binary(Binary {
+            subtype: BinarySubtype::Generic,
+            bytes: bytes.to_vec(),
+        }))
+    }
+}
+
+/// Encodes the integer [n] as a `decimal128` with an exponent of `0`.
+/// The coefficient occupies the low 113 bits which holds any `u64`, followed by the exponent
+/// which is biased by 6176.
+fn decimal128(n: u64) -> Decimal128 {
+    const EXPONENT_BIAS: u128 = 6176;
+    let bits = (EXPONENT_BIAS << 113) | u128::from(n);
+    Decimal128::from_bytes(bits.to_le_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decimal128_of_large_u64() {
+        let decimal = decimal128(u64::MAX);
+        assert_eq!(decimal.to_string(), u64::MAX.to_string());
+        assert_eq!(decimal128(0).to_string(), "0");
+    }
+}
@@ -0,0 +1,258 @@
+use super::ChainparserVisitor;
+use crate::{
+    errors::ChainparserResult,
+    json::{
+        write_bool, write_bytes, write_float, write_int, write_int_hex,
+        write_int_quoted, write_quoted, JsonSerializationOpts, JsonWrite,
+    },
+    pubkey::Pubkey,
+};
+
+/// Containers that are currently being written along with whether an entry was written to them
+/// yet or how many elements remain to be written.
+enum Frame {
+    Struct { first: bool },
+    Seq { remaining: usize },
+    Map { first: bool },
+}
+
+/// Writes the values that the deserializers of the [crate::json] module emit as JSON.
+/// This is how they render JSON for the given [JsonSerializationOpts], i.e. via
+/// [crate::json::JsonAccountsDeserializer::deserialize_account_data].
+pub struct JsonFormatWriter<'a, W: JsonWrite> {
+    f: &'a mut W,
+    opts: &'a JsonSerializationOpts,
+    frames: Vec<Frame>,
+    /// Map keys are captured so they can be written as JSON strings once complete.
+    keys: Vec<String>,
+}
+
+impl<'a, W: JsonWrite> JsonFormatWriter<'a, W> {
+    pub fn new(f: &'a mut W, opts: &'a JsonSerializationOpts) -> Self {
+        Self {
+            f,
+            opts,
+            frames: Vec::new(),
+            keys: Vec::new(),
+        }
+    }
+
+    /// The writer to write to, which is the current map key while one is written.
+    fn out(&mut self) -> &mut dyn std::fmt::Write {
+        match self.keys.last_mut() {
+            Some(key) => key,
+            None => self.f,
+        }
+    }
+
+    /// Separates the value from the next element of the sequence it is part of.
+    /// Separators are written right after an element so that a flush passes it on along with
+    /// its separator.
+    fn end_value(&mut self) -> ChainparserResult<()> {
+        if let Some(Frame::Seq { remaining }) = self.frames.last_mut() {
+            *remaining = remaining.saturating_sub(1);
+            if *remaining > 0 {
+                self.out().write_str(", ")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn end_frame(&mut self, close: char) -> ChainparserResult<()> {
+        self.frames.pop();
+        self.out().write_char(close)?;
+        self.end_value()
+    }
+}
+
+impl<W: JsonWrite> ChainparserVisitor for JsonFormatWriter<'_, W> {
+    const STREAMING: bool = W::STREAMING;
+
+    fn begin_struct(&mut self, _len: usize) -> ChainparserResult<()> {
+        self.frames.push(Frame::Struct { first: true });
+        self.out().write_char('{')?;
+        Ok(())
+    }
+
+    fn field_name(&mut self, name: &str) -> ChainparserResult<()> {
+        if let Some(Frame::Struct { first }) = self.frames.last_mut() {
+            if !std::mem::take(first) {
+                self.out().write_char(',')?;
+            }
+        }
+        let out = &mut self.out();
+        write_quoted(out, name)?;
+        out.write_char(':')?;
+        Ok(())
+    }
+
+    fn end_struct(&mut self) -> ChainparserResult<()> {
+        self.end_frame('}')
+    }
+
+    fn begin_seq(&mut self, len: usize) -> ChainparserResult<()> {
+        self.frames.push(Frame::Seq { remaining: len });
+        self.out().write_char('[')?;
+        Ok(())
+    }
+
+    fn end_seq(&mut self) -> ChainparserResult<()> {
+        self.end_frame(']')
+    }
+
+    /// Flushes the underlying writer unless a map key is being captured.
+    fn flush(&mut self) -> ChainparserResult<()> {
+        if self.keys.is_empty() {
+            self.f.flush()?;
+        }
+        Ok(())
+    }
+
+    fn begin_map(&mut self, _len: usize) -> ChainparserResult<()> {
+        self.frames.push(Frame::Map { first: true });
+        self.out().write_char('{')?;
+        Ok(())
+    }
+
+    fn begin_map_key(&mut self) -> ChainparserResult<()> {
+        if let Some(Frame::Map { first }) = self.frames.last_mut() {
+            if !std::mem::take(first) {
+                self.out().write_str(", ")?;
+            }
+        }
+        self.keys.push(String::new());
+        Ok(())
+    }
+
+    /// JSON requires keys to be strings, thus keys that don't render as one, i.e. a number or
+    /// a struct, are written as an escaped string of their JSON.
+    fn end_map_key(&mut self) -> ChainparserResult<()> {
+        let key = self.keys.pop().unwrap_or_default();
+        let out = &mut self.out();
+        if key.starts_with('"') {
+            out.write_str(&key)?;
+        } else {
+            write_quoted(out, &key)?;
+        }
+        out.write_str(": ")?;
+        Ok(())
+    }
+
+    fn end_map(&mut self) -> ChainparserResult<()> {
+        self.end_frame('}')
+    }
+
+    fn null_value(&mut self) -> ChainparserResult<()> {
+        self.out().write_str("null")?;
+        self.end_value()
+    }
+
+    fn bool_value(&mut self, b: bool) -> ChainparserResult<()> {
+        write_bool(&mut self.out(), b)?;
+        self.end_value()
+    }
+
+    fn u32_value(&mut self, n: u32) -> ChainparserResult<()> {
+        write_int(&mut self.out(), n)?;
+        self.end_value()
+    }
+
+    fn u64_value(&mut self, n: u64) -> ChainparserResult<()> {
+        if self.opts.n64_as_string {
+            write_int_quoted(&mut self.out(), n)?;
+        } else {
+            write_int(&mut self.out(), n)?;
+        }
+        self.end_value()
+    }
+
+    fn u128_value(&mut self, n: u128) -> ChainparserResult<()> {
+        if self.opts.n128_as_hex {
+            write_int_hex(&mut self.out(), false, n)?;
+        } else if self.opts.n128_as_string {
+            write_int_quoted(&mut self.out(), n)?;
+        } else {
+            write_int(&mut self.out(), n)?;
+        }
+        self.end_value()
+    }
+
+    fn i32_value(&mut self, n: i32) -> ChainparserResult<()> {
+        write_int(&mut self.out(), n)?;
+        self.end_value()
+    }
+
+    fn i64_value(&mut self, n: i64) -> ChainparserResult<()> {
+        if self.opts.n64_as_string {
+            write_int_quoted(&mut self.out(), n)?;
+        } else {
+            write_int(&mut self.out(), n)?;
+        }
+        self.end_value()
+    }
+
+    fn i128_value(&mut self, n: i128) -> ChainparserResult<()> {
+        if self.opts.n128_as_hex {
+            write_int_hex(&mut self.out(), n < 0, n.unsigned_abs())?;
+        } else if self.opts.n128_as_string {
+            write_int_quoted(&mut self.out(), n)?;
+        } else {
+            write_int(&mut self.out(), n)?;
+        }
+        self.end_value()
+    }
+
+    fn f32_value(&mut self, n: f32) -> ChainparserResult<()> {
+        let non_finite = self.opts.non_finite;
+        write_float(&mut self.out(), n, non_finite)?;
+        self.end_value()
+    }
+
+    fn f64_value(&mut self, n: f64) -> ChainparserResult<()> {
+        let non_finite = self.opts.non_finite;
+        write_float(&mut self.out(), n, non_finite)?;
+        self.end_value()
+    }
+
+    fn str_value(&mut self, s: &str) -> ChainparserResult<()> {
+        write_quoted(&mut self.out(), s)?;
+        self.end_value()
+    }
+
+    fn pubkey_value(&mut self, pubkey: &Pubkey) -> ChainparserResult<()> {
+        if self.opts.pubkey_as_base58 {
+            write_quoted(&mut self.out(), &pubkey.to_string())?;
+        } else {
+            write!(self.out(), "{:?}", pubkey.to_bytes())?;
+        }
+        self.end_value()
+    }
+
+    fn bytes_value(&mut self, bytes: &[u8]) -> ChainparserResult<()> {
+        let bytes_as = self.opts.bytes_as;
+        write_bytes(&mut self.out(), bytes, bytes_as)?;
+        self.end_value()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_escaped_field_names_and_separated_elements() {
+        let opts = JsonSerializationOpts::default();
+        let mut json = String::new();
+        let mut w = JsonFormatWriter::new(&mut json, &opts);
+        w.begin_struct(2).unwrap();
+        w.field_name("amount").unwrap();
+        w.u8_value(1).unwrap();
+        w.field_name("a\"b\\c").unwrap();
+        w.begin_seq(2).unwrap();
+        w.bool_value(true).unwrap();
+        w.null_value().unwrap();
+        w.end_seq().unwrap();
+        w.end_struct().unwrap();
+        assert_eq!(json, r#"{"amount":1,"a\"b\\c":[true, null]}"#);
+    }
+}
@@ -0,0 +1,255 @@
+use base64::{prelude::BASE64_STANDARD, Engine as _};
+use serde_json::{Map, Number, Value};
+
+use super::ChainparserVisitor;
+use crate::{
+    errors::ChainparserResult,
+    json::{BytesRepr, JsonSerializationOpts, NonFiniteRepr},
+    pubkey::Pubkey,
+};
+
+/// Containers that are currently being built.
+enum Frame {
+    /// A struct or map along with the key of the value that is written next.
+    Object(Map<String, Value>, Option<String>),
+    Array(Vec<Value>),
+    /// The key of a map entry which JSON requires to be a string.
+    MapKey(Option<Value>),
+}
+
+/// Builds a [serde_json::Value] from the values emitted to a [ChainparserVisitor], holding the
+/// same JSON that the [super::JsonFormatWriter] writes for the given [JsonSerializationOpts].
+///
+/// Since a [Value] cannot hold NaN or infinite numbers they are stored as the strings that
+/// [NonFiniteRepr::Literal] writes, i.e. `"NaN"` or `"inf"`.
+/// Integers exceeding `u64` or `i64` are stored as floats unless they are rendered as strings.
+pub struct JsonValueWriter<'opts> {
+    frames: Vec<Frame>,
+    root: Option<Value>,
+    opts: &'opts JsonSerializationOpts,
+}
+
+impl<'opts> JsonValueWriter<'opts> {
+    pub fn new(opts: &'opts JsonSerializationOpts) -> Self {
+        Self {
+            frames: Vec::new(),
+            root: None,
+            opts,
+        }
+    }
+
+    /// Returns the value that was written.
+    pub fn into_value(self) -> Option<Value> {
+        self.root
+    }
+
+    fn value(&mut self, value: Value) -> ChainparserResult<()> {
+        match self.frames.last_mut() {
+            Some(Frame::Object(map, key)) => {
+                map.insert(key.take().unwrap_or_default(), value);
+            }
+            Some(Frame::Array(items)) => items.push(value),
+            Some(Frame::MapKey(key)) => *key = Some(value),
+            None => self.root = Some(value),
+        }
+        Ok(())
+    }
+
+    fn end_frame(&mut self) -> ChainparserResult<()> {
+        match self.frames.pop() {
+            Some(Frame::Object(map, _)) => self.value(Value::Object(map)),
+            Some(Frame::Array(items)) => self.value(Value::Array(items)),
+            Some(Frame::MapKey(_)) | None => Ok(()),
+        }
+    }
+
+    fn float_value(
+        &mut self,
+        n: f64,
+        literal: String,
+    ) -> ChainparserResult<()> {
+        let value = match Number::from_f64(n) {
+            Some(n) => Value::Number(n),
+            None => match self.opts.non_finite {
+                NonFiniteRepr::Literal => Value::String(literal),
+                NonFiniteRepr::Null => Value::Null,
+                NonFiniteRepr::String if n.is_nan() => "NaN".into(),
+                NonFiniteRepr::String if n > 0.0 => "Infinity".into(),
+                NonFiniteRepr::String => "-Infinity".into(),
+            },
+        };
+        self.value(value)
+    }
+}
+
+impl ChainparserVisitor for JsonValueWriter<'_> {
+    fn begin_struct(&mut self, _len: usize) -> ChainparserResult<()> {
+        self.frames.push(Frame::Object(Map::new(), None));
+        Ok(())
+    }
+
+    fn field_name(&mut self, name: &str) -> ChainparserResult<()> {
+        if let Some(Frame::Object(_, key)) = self.frames.last_mut() {
+            *key = Some(name.to_string());
+        }
+        Ok(())
+    }
+
+    fn end_struct(&mut self) -> ChainparserResult<()> {
+        self.end_frame()
+    }
+
+    fn begin_seq(&mut self, len: usize) -> ChainparserResult<()> {
+        self.frames.push(Frame::Array(Vec::with_capacity(len)));
+        Ok(())
+    }
+
+    fn end_seq(&mut self) -> ChainparserResult<()> {
+        self.end_frame()
+    }
+
+    fn begin_map(&mut self, _len: usize) -> ChainparserResult<()> {
+        self.frames.push(Frame::Object(Map::new(), None));
+        Ok(())
+    }
+
+    fn begin_map_key(&mut self) -> ChainparserResult<()> {
+        self.frames.push(Frame::MapKey(None));
+        Ok(())
+    }
+
+    /// Keys that aren't strings, i.e. a number or a struct, are stored as a string of their
+    /// JSON, the same as the [super::JsonFormatWriter] writes them.
+    fn end_map_key(&mut self) -> ChainparserResult<()> {
+        let key = match self.frames.pop() {
+            Some(Frame::MapKey(Some(Value::String(key)))) => key,
+            Some(Frame::MapKey(Some(key))) => key.to_string(),
+            _ => String::new(),
+        };
+        self.field_name(&key)
+    }
+
+    fn end_map(&mut self) -> ChainparserResult<()> {
+        self.end_frame()
+    }
+
+    fn null_value(&mut self) -> ChainparserResult<()> {
+        self.value(Value::Null)
+    }
+
+    fn bool_value(&mut self, b: bool) -> ChainparserResult<()> {
+        self.value(Value::Bool(b))
+    }
+
+    fn u32_value(&mut self, n: u32) -> ChainparserResult<()> {
+        self.value(n.into())
+    }
+
+    fn u64_value(&mut self, n: u64) -> ChainparserResult<()> {
+        if self.opts.n64_as_string {
+            self.value(Value::String(n.to_string()))
+        } else {
+            self.value(n.into())
+        }
+    }
+
+    fn u128_value(&mut self, n: u128) -> ChainparserResult<()> {
+        if self.opts.n128_as_hex {
+            self.value(Value::String(format!("{n:#x}")))
+        } else if self.opts.n128_as_string {
+            self.value(Value::String(n.to_string()))
+        } else {
+            match u64::try_from(n) {
+                Ok(n) => self.value(n.into()),
+                Err(_) => self.value((n as f64).into()),
+            }
+        }
+    }
+
+    fn i32_value(&mut self, n: i32) -> ChainparserResult<()> {
+        self.value(n.into())
+    }
+
+    fn i64_value(&mut self, n: i64) -> ChainparserResult<()> {
+        if self.opts.n64_as_string {
+            self.value(Value::String(n.to_string()))
+        } else {
+            self.value(n.into())
+        }
+    }
+
+    fn i128_value(&mut self, n: i128) -> ChainparserResult<()> {
+        if self.opts.n128_as_hex {
+            let sign = if n < 0 { "-" } else { "" };
+            let magnitude = n.unsigned_abs();
+            self.value(Value::String(format!("{sign}{magnitude:#x}")))
+        } else if self.opts.n128_as_string {
+            self.value(Value::String(n.to_string()))
+        } else {
+            match i64::try_from(n) {
+                Ok(n) => self.value(n.into()),
+                Err(_) => self.value((n as f64).into()),
+            }
+        }
+    }
+
+    fn f32_value(&mut self, n: f32) -> ChainparserResult<()> {
+        self.float_value(n.into(), n.to_string())
+    }
+
+    fn f64_value(&mut self, n: f64) -> ChainparserResult<()> {
+        self.float_value(n, n.to_string())
+    }
+
+    fn str_value(&mut self, s: &str) -> ChainparserResult<()> {
+        self.value(Value::String(s.to_string()))
+    }
+
+    fn pubkey_value(&mut self, pubkey: &Pubkey) -> ChainparserResult<()> {
+        if self.opts.pubkey_as_base58 {
+            self.str_value(&pubkey.to_string())
+        } else {
+            self.value(pubkey.to_bytes().to_vec().into())
+        }
+    }
+
+    fn bytes_value(&mut self, bytes: &[u8]) -> ChainparserResult<()> {
+        let value = match self.opts.bytes_as {
+            BytesRepr::Array => bytes.to_vec().into(),
+            BytesRepr::Hex => Value::String(
+                bytes.iter().map(|b| format!("{b:02x}")).collect(),
+            ),
+            BytesRepr::Base64 => Value::String(BASE64_STANDARD.encode(bytes)),
+        };
+        self.value(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn store_non_finite_floats_per_opts() {
+        let write = |non_finite, n: f64| {
+            let opts = JsonSerializationOpts {
+                non_finite,
+                ..Default::default()
+            };
+            let mut w = JsonValueWriter::new(&opts);
+            w.f64_value(n).unwrap();
+            w.into_value().unwrap()
+        };
+        assert_eq!(write(NonFiniteRepr::Literal, 1.5), Value::from(1.5));
+        assert_eq!(write(NonFiniteRepr::Literal, f64::NAN), Value::from("NaN"));
+        assert_eq!(
+            write(NonFiniteRepr::Literal, f64::NEG_INFINITY),
+            Value::from("-inf")
+        );
+        assert_eq!(write(NonFiniteRepr::Null, f64::INFINITY), Value::Null);
+        assert_eq!(
+            write(NonFiniteRepr::String, f64::INFINITY),
+            Value::from("Infinity")
+        );
+    }
+}
@@ -0,0 +1,20 @@
+//! The [ChainparserVisitor] that the deserializers emit their values to. The
+//! [JsonFormatWriter] renders them as JSON, while custom visitors map them into a custom data
+//! model or write output formats other than JSON text like MessagePack via the `msgpack`
+//! feature and BSON via the `bson` feature.
+
+#[cfg(feature = "bson")]
+mod bson_writer;
+mod json_format_writer;
+mod json_value_writer;
+#[cfg(feature = "msgpack")]
+mod msgpack_writer;
+mod visitor;
+
+#[cfg(feature = "bson")]
+pub use bson_writer::BsonWriter;
+pub use json_format_writer::JsonFormatWriter;
+pub use json_value_writer::JsonValueWriter;
+#[cfg(feature = "msgpack")]
+pub use msgpack_writer::MsgpackWriter;
+pub use visitor::ChainparserVisitor;
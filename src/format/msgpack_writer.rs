@@ -0,0 +1,142 @@
+use rmp::encode;
+
+use super::ChainparserVisitor;
+use crate::{
+    errors::{ChainparserError, ChainparserResult},
+    json::JsonSerializationOpts,
+    pubkey::Pubkey,
+};
+
+/// Writes the values emitted to a [super::ChainparserVisitor] as MessagePack.
+///
+/// Structs are written as maps keyed by field name and enums use the same representation as
+/// for JSON.
+/// 128-bit integers exceed the integers MessagePack supports and are written as strings,
+/// `bytes` and `u8` arrays are written as binary and public keys as base58 strings or as 32 bytes of binary if
+/// [JsonSerializationOpts::pubkey_as_base58] is disabled.
+pub struct MsgpackWriter<'opts> {
+    buf: Vec<u8>,
+    opts: &'opts JsonSerializationOpts,
+}
+
+impl<'opts> MsgpackWriter<'opts> {
+    pub fn new(opts: &'opts JsonSerializationOpts) -> Self {
+        Self {
+            buf: Vec::new(),
+            opts,
+        }
+    }
+
+    /// Returns the MessagePack bytes written so far.
+    pub fn into_inner(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+impl ChainparserVisitor for MsgpackWriter<'_> {
+    fn begin_struct(&mut self, len: usize) -> ChainparserResult<()> {
+        encode::write_map_len(&mut self.buf, len as u32)?;
+        Ok(())
+    }
+
+    fn field_name(&mut self, name: &str) -> ChainparserResult<()> {
+        encode::write_str(&mut self.buf, name)?;
+        Ok(())
+    }
+
+    fn end_struct(&mut self) -> ChainparserResult<()> {
+        Ok(())
+    }
+
+    fn begin_seq(&mut self, len: usize) -> ChainparserResult<()> {
+        encode::write_array_len(&mut self.buf, len as u32)?;
+        Ok(())
+    }
+
+    fn end_seq(&mut self) -> ChainparserResult<()> {
+        Ok(())
+    }
+
+    fn begin_map(&mut self, len: usize) -> ChainparserResult<()> {
+        encode::write_map_len(&mut self.buf, len as u32)?;
+        Ok(())
+    }
+
+    fn begin_map_key(&mut self) -> ChainparserResult<()> {
+        Ok(())
+    }
+
+    fn end_map_key(&mut self) -> ChainparserResult<()> {
+        Ok(())
+    }
+
+    fn end_map(&mut self) -> ChainparserResult<()> {
+        Ok(())
+    }
+
+    fn null_value(&mut self) -> ChainparserResult<()> {
+        encode::write_nil(&mut self.buf)
+            .map_err(ChainparserError::WriterIoError)?;
+        Ok(())
+    }
+
+    fn bool_value(&mut self, b: bool) -> ChainparserResult<()> {
+        encode::write_bool(&mut self.buf, b)
+            .map_err(ChainparserError::WriterIoError)?;
+        Ok(())
+    }
+
+    fn u32_value(&mut self, n: u32) -> ChainparserResult<()> {
+        self.u64_value(n.into())
+    }
+
+    fn u64_value(&mut self, n: u64) -> ChainparserResult<()> {
+        encode::write_uint(&mut self.buf, n)?;
+        Ok(())
+    }
+
+    fn u128_value(&mut self, n: u128) -> ChainparserResult<()> {
+        self.str_value(&n.to_string())
+    }
+
+    fn i32_value(&mut self, n: i32) -> ChainparserResult<()> {
+        self.i64_value(n.into())
+    }
+
+    fn i64_value(&mut self, n: i64) -> ChainparserResult<()> {
+        encode::write_sint(&mut self.buf, n)?;
+        Ok(())
+    }
+
+    fn i128_value(&mut self, n: i128) -> ChainparserResult<()> {
+        self.str_value(&n.to_string())
+    }
+
+    fn f32_value(&mut self, n: f32) -> ChainparserResult<()> {
+        encode::write_f32(&mut self.buf, n)?;
+        Ok(())
+    }
+
+    fn f64_value(&mut self, n: f64) -> ChainparserResult<()> {
+        encode::write_f64(&mut self.buf, n)?;
+        Ok(())
+    }
+
+    fn str_value(&mut self, s: &str) -> ChainparserResult<()> {
+        encode::write_str(&mut self.buf, s)?;
+        Ok(())
+    }
+
+    fn pubkey_value(&mut self, pubkey: &Pubkey) -> ChainparserResult<()> {
+        if self.opts.pubkey_as_base58 {
+            self.str_value(&pubkey.to_string())
+        } else {
+            self.bytes_value(&pubkey.to_bytes())
+        }
+    }
+
+    fn bytes_value(&mut self, bytes: &[u8]) -> ChainparserResult<()> {
+        encode::write_bin(&mut self.buf, bytes)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+use crate::{errors::ChainparserResult, pubkey::Pubkey};
+
+/// Receives the events that the deserializers of the [crate::json] module emit while walking
+/// the IDL types of an account, which allows mapping accounts into any data model without
+/// building JSON first.
+/// Output formats like JSON via [super::JsonFormatWriter] are implemented as visitors as well.
+///
+/// Structs are visited as [ChainparserVisitor::begin_struct], followed by a
+/// [ChainparserVisitor::field_name] and its value for each field and a
+/// [ChainparserVisitor::end_struct].
+/// The number of entries of structs, sequences and maps is known up front, which allows formats
+/// that prefix containers with their length to write them without buffering.
+pub trait ChainparserVisitor {
+    fn begin_struct(&mut self, len: usize) -> ChainparserResult<()>;
+    /// Visits the [name] of the next field of the current struct.
+    fn field_name(&mut self, name: &str) -> ChainparserResult<()>;
+    fn end_struct(&mut self) -> ChainparserResult<()>;
+
+    fn begin_seq(&mut self, len: usize) -> ChainparserResult<()>;
+    fn end_seq(&mut self) -> ChainparserResult<()>;
+
+    /// Opts into [ChainparserVisitor::flush] being invoked after each element of a `Vec`.
+    const STREAMING: bool = false;
+
+    /// Invoked after each element of a `Vec` was visited if [ChainparserVisitor::STREAMING] is
+    /// set, i.e. so that writers which stream their output can pass on the elements of large
+    /// collections right away.
+    fn flush(&mut self) -> ChainparserResult<()> {
+        Ok(())
+    }
+
+    /// Starts a map with [len] entries each of which is visited as a key, enclosed in
+    /// [ChainparserVisitor::begin_map_key] and [ChainparserVisitor::end_map_key], followed by
+    /// its value.
+    fn begin_map(&mut self, len: usize) -> ChainparserResult<()>;
+    fn begin_map_key(&mut self) -> ChainparserResult<()>;
+    fn end_map_key(&mut self) -> ChainparserResult<()>;
+    fn end_map(&mut self) -> ChainparserResult<()>;
+
+    fn null_value(&mut self) -> ChainparserResult<()>;
+    fn bool_value(&mut self, b: bool) -> ChainparserResult<()>;
+
+    fn u8_value(&mut self, n: u8) -> ChainparserResult<()> {
+        self.u32_value(n.into())
+    }
+    fn u16_value(&mut self, n: u16) -> ChainparserResult<()> {
+        self.u32_value(n.into())
+    }
+    fn u32_value(&mut self, n: u32) -> ChainparserResult<()>;
+    fn u64_value(&mut self, n: u64) -> ChainparserResult<()>;
+    fn u128_value(&mut self, n: u128) -> ChainparserResult<()>;
+
+    fn i8_value(&mut self, n: i8) -> ChainparserResult<()> {
+        self.i32_value(n.into())
+    }
+    fn i16_value(&mut self, n: i16) -> ChainparserResult<()> {
+        self.i32_value(n.into())
+    }
+    fn i32_value(&mut self, n: i32) -> ChainparserResult<()>;
+    fn i64_value(&mut self, n: i64) -> ChainparserResult<()>;
+    fn i128_value(&mut self, n: i128) -> ChainparserResult<()>;
+
+    fn f32_value(&mut self, n: f32) -> ChainparserResult<()> {
+        self.f64_value(n.into())
+    }
+    fn f64_value(&mut self, n: f64) -> ChainparserResult<()>;
+
+    fn str_value(&mut self, s: &str) -> ChainparserResult<()>;
+    fn pubkey_value(&mut self, pubkey: &Pubkey) -> ChainparserResult<()>;
+
+    /// Visits a `bytes` value as well as `[u8; N]` arrays and `Vec<u8>`, by default as a sequence
+    /// of numbers.
+    fn bytes_value(&mut self, bytes: &[u8]) -> ChainparserResult<()> {
+        self.begin_seq(bytes.len())?;
+        for b in bytes {
+            self.u8_value(*b)?;
+        }
+        self.end_seq()
+    }
+}
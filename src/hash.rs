@@ -0,0 +1,10 @@
+use sha2::{Digest, Sha256};
+
+/// Hashes the concatenation of the [parts] with SHA-256.
+pub(crate) fn sha256(parts: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
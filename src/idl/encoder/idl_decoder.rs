@@ -1,10 +1,19 @@
-use std::io::Read;
+use std::{
+    collections::HashMap,
+    io::{self, Read},
+};
 
-use flate2::read::ZlibDecoder;
+use flate2::read::{GzDecoder, ZlibDecoder};
+use serde_json::Value;
 use solana_idl::Idl;
 
-use super::IDL_HEADER_SIZE;
-use crate::errors::{ChainparserError, ChainparserResult};
+use super::{
+    DEFAULT_MAX_IDL_DECOMPRESSED_SIZE, IDL_AUTHORITY_OFFSET, IDL_HEADER_SIZE,
+};
+use crate::{
+    errors::{ChainparserError, ChainparserResult},
+    pubkey::Pubkey,
+};
 
 /* Related anchor code:
 ```ts
@@ -40,40 +49,174 @@ export function vecU8(property?: string): Layout<Buffer> {
 
 /// Parses the provided JSON string into an [Idl] struct.
 /// It attempts to parse it directly as a classic IDL and if that fails it
-/// will parse as the new anchor IDL format and then convert to the
+/// will parse as the new anchor IDL format (anchor >= 0.30) and then convert to the
 /// classic.
-pub fn try_parse_idl_json(json: &str) -> ChainparserResult<Idl> {
+///
+/// This is what [crate::ChainparserDeserializer::add_idl_json] uses to parse IDLs.
+pub fn parse_idl_json(json: &str) -> ChainparserResult<Idl> {
     Ok(solana_idl::try_extract_classic_idl(json)?)
 }
 
+#[deprecated(since = "0.3.0", note = "use parse_idl_json instead")]
+pub fn try_parse_idl_json(json: &str) -> ChainparserResult<Idl> {
+    parse_idl_json(json)
+}
+
+/// Extracts the discriminators that the IDL [json] declares for its accounts keyed by account
+/// name, since the classic [Idl] doesn't include them.
+/// Accounts declare them either as `"discriminator": [1]` or in the same way that shank
+/// declares instruction discriminants, i.e. `"discriminant": { "type": "u8", "value": 1 }`.
+/// Accounts without a discriminator or with an invalid one are not included.
+pub fn declared_account_discriminators(json: &str) -> HashMap<String, Vec<u8>> {
+    let Ok(value) = serde_json::from_str::<Value>(json) else {
+        return HashMap::new();
+    };
+    let Some(accounts) = value.get("accounts").and_then(Value::as_array) else {
+        return HashMap::new();
+    };
+    accounts
+        .iter()
+        .filter_map(|account| {
+            let name = account.get("name")?.as_str()?;
+            let discriminator = match account.get("discriminator") {
+                Some(bytes) => bytes_value(bytes)?,
+                None => {
+                    let discriminant = account.get("discriminant")?;
+                    match discriminant.get("bytes") {
+                        Some(bytes) => bytes_value(bytes)?,
+                        None => vec![u8::try_from(
+                            discriminant.get("value")?.as_u64()?,
+                        )
+                        .ok()?],
+                    }
+                }
+            };
+            Some((name.to_string(), discriminator))
+        })
+        .collect()
+}
+
+fn bytes_value(value: &Value) -> Option<Vec<u8>> {
+    value
+        .as_array()?
+        .iter()
+        .map(|b| b.as_u64().and_then(|b| u8::try_from(b).ok()))
+        .collect()
+}
+
 /// Same as [decode_idl_data] except that it strips the prefix bytes before
 /// unzipping the packed JSON.
+/// The unzipped JSON may be at most [DEFAULT_MAX_IDL_DECOMPRESSED_SIZE] bytes.
 pub fn decode_idl_account_data(
     account_data: &[u8],
 ) -> ChainparserResult<(Idl, String)> {
-    decode_idl_data(&account_data[IDL_HEADER_SIZE..])
+    decode_idl_account_data_with_limit(
+        account_data,
+        DEFAULT_MAX_IDL_DECOMPRESSED_SIZE,
+    )
+}
+
+/// Same as [decode_idl_account_data] except that the unzipped JSON may be at most
+/// [max_size] bytes.
+pub fn decode_idl_account_data_with_limit(
+    account_data: &[u8],
+    max_size: usize,
+) -> ChainparserResult<(Idl, String)> {
+    decode_idl_data(strip_idl_header(account_data)?, max_size)
+}
+
+/// Reads the authority, i.e. the address that can modify the IDL, from the
+/// provided IDL account data.
+/// The authority is stored right after the 8 bytes of account discriminator.
+pub fn decode_idl_account_authority(
+    account_data: &[u8],
+) -> ChainparserResult<Pubkey> {
+    let end = IDL_AUTHORITY_OFFSET + 32;
+    if account_data.len() < end {
+        return Err(ChainparserError::IdlAccountDataTooShort(
+            account_data.len(),
+            end,
+        ));
+    }
+    let mut authority = [0u8; 32];
+    authority.copy_from_slice(&account_data[IDL_AUTHORITY_OFFSET..end]);
+    Ok(Pubkey::new_from_array(authority))
 }
 
 /// Unzips account data obtained from chain by first stripping the prefix
 /// bytes which aren't the zip data and then unpacking the containted string.
+/// The unzipped JSON may be at most [DEFAULT_MAX_IDL_DECOMPRESSED_SIZE] bytes.
 pub fn unzip_idl_account_json(bytes: &[u8]) -> ChainparserResult<String> {
-    unzip_bytes(&bytes[IDL_HEADER_SIZE..])
+    unzip_idl_account_json_with_limit(bytes, DEFAULT_MAX_IDL_DECOMPRESSED_SIZE)
+}
+
+/// Same as [unzip_idl_account_json] except that the unzipped JSON may be at
+/// most [max_size] bytes.
+pub fn unzip_idl_account_json_with_limit(
+    bytes: &[u8],
+    max_size: usize,
+) -> ChainparserResult<String> {
+    unzip_bytes(strip_idl_header(bytes)?, max_size)
+}
+
+/// Strips the account discriminator, authority and data length prefix from IDL account data.
+fn strip_idl_header(account_data: &[u8]) -> ChainparserResult<&[u8]> {
+    if account_data.len() < IDL_HEADER_SIZE {
+        return Err(ChainparserError::IdlAccountDataTooShort(
+            account_data.len(),
+            IDL_HEADER_SIZE,
+        ));
+    }
+    Ok(&account_data[IDL_HEADER_SIZE..])
 }
 
 /// Decodes IDL data by first unzipping the provided data and then parsing
 /// the contained JSON.
-fn decode_idl_data(data: &[u8]) -> ChainparserResult<(Idl, String)> {
-    let json = unzip_bytes(data)?;
+fn decode_idl_data(
+    data: &[u8],
+    max_size: usize,
+) -> ChainparserResult<(Idl, String)> {
+    let json = unzip_bytes(data, max_size)?;
     let idl: Idl = solana_idl::try_extract_classic_idl(&json)?;
     Ok((idl, json))
 }
 
+/// Magic bytes that gzip data starts with, see RFC 1952.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
 /// Unzips the provided [bytes] into a string.
-fn unzip_bytes(bytes: &[u8]) -> ChainparserResult<String> {
-    let mut zlib = ZlibDecoder::new(bytes);
-    let mut write = String::new();
-    zlib.read_to_string(&mut write).map_err(|err| {
-        ChainparserError::IdlContainerShouldContainZlibData(err.to_string())
-    })?;
-    Ok(write)
+/// Anchor stores zlib data, but some tooling wraps the IDL with gzip instead which is detected
+/// via its magic bytes.
+/// Since IDL account data is untrusted we stop inflating once more than [max_size] bytes were
+/// produced in order to guard against zip bombs.
+fn unzip_bytes(bytes: &[u8], max_size: usize) -> ChainparserResult<String> {
+    if bytes.starts_with(&GZIP_MAGIC) {
+        inflate(GzDecoder::new(bytes), max_size)
+    } else {
+        inflate(ZlibDecoder::new(bytes), max_size)
+    }
+}
+
+/// Inflates the data of the [decoder] into a string.
+/// Failures are told apart by the kind of the error and how much was inflated before, i.e.
+/// truncated data may succeed when fetched again, while data that isn't compressed won't.
+fn inflate(decoder: impl Read, max_size: usize) -> ChainparserResult<String> {
+    let mut write = Vec::new();
+    decoder
+        .take(max_size as u64 + 1)
+        .read_to_end(&mut write)
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::UnexpectedEof => {
+                ChainparserError::IdlTruncated(write.len())
+            }
+            _ if write.is_empty() => {
+                ChainparserError::IdlNotZlib(err.to_string())
+            }
+            _ => ChainparserError::IdlCorrupt(write.len(), err.to_string()),
+        })?;
+    if write.len() > max_size {
+        return Err(ChainparserError::IdlDecompressionTooLarge(max_size));
+    }
+    String::from_utf8(write)
+        .map_err(|err| ChainparserError::IdlInvalidUtf8(err.to_string()))
 }
@@ -1,10 +1,10 @@
 use std::io::Write;
 
 use flate2::write::ZlibEncoder;
+pub use flate2::Compression;
 use solana_idl::Idl;
-use solana_sdk::pubkey::Pubkey;
 
-use crate::errors::ChainparserResult;
+use crate::{errors::ChainparserResult, pubkey::Pubkey};
 
 /*
 * Structure of an Anchor IDL account:
@@ -53,43 +53,77 @@ const DISCRIMINATOR: [u8; 8] = [
     0x3a, 0x90, 0x7b, 0x9e,
 ];
 
+/// Encodes the [idl] into the data of an Anchor IDL account owned by the [program_id] using the
+/// default zlib compression level.
 pub fn encode_idl_account(
     program_id: &Pubkey,
     idl: &Idl,
+) -> ChainparserResult<Vec<u8>> {
+    encode_idl_account_with_compression(program_id, idl, Compression::default())
+}
+
+/// Same as [encode_idl_account] except that the zlib compression [level] can be selected.
+pub fn encode_idl_account_with_compression(
+    program_id: &Pubkey,
+    idl: &Idl,
+    level: Compression,
 ) -> ChainparserResult<Vec<u8>> {
     let json = serde_json::to_vec(idl)?;
 
     let pubkey_vec = program_id.to_bytes().to_vec();
     let data_len_bytes = (json.len() as u32).to_le_bytes().to_vec();
-    let zipped = zip_bytes(&json)?;
+    let zipped = zip_bytes(&json, level)?;
 
     let full_vec =
         [DISCRIMINATOR.to_vec(), pubkey_vec, data_len_bytes, zipped].concat();
     Ok(full_vec)
 }
 
+/// Encodes the [idl] into zlib compressed JSON using the default compression level.
 pub fn encode_idl(idl: &Idl) -> ChainparserResult<Vec<u8>> {
+    encode_idl_with_compression(idl, Compression::default())
+}
+
+/// Same as [encode_idl] except that the zlib compression [level] can be selected.
+pub fn encode_idl_with_compression(
+    idl: &Idl,
+    level: Compression,
+) -> ChainparserResult<Vec<u8>> {
     let json = serde_json::to_vec(idl)?;
-    zip_bytes(&json)
+    zip_bytes(&json, level)
 }
 
+/// Encodes the [idl_json] into the data of an Anchor IDL account owned by the [program_id]
+/// using the default zlib compression level.
 pub fn encode_idl_account_json(
     program_id: &Pubkey,
     idl_json: &str,
+) -> ChainparserResult<Vec<u8>> {
+    encode_idl_account_json_with_compression(
+        program_id,
+        idl_json,
+        Compression::default(),
+    )
+}
+
+/// Same as [encode_idl_account_json] except that the zlib compression [level] can be selected.
+pub fn encode_idl_account_json_with_compression(
+    program_id: &Pubkey,
+    idl_json: &str,
+    level: Compression,
 ) -> ChainparserResult<Vec<u8>> {
     let json_bytes = idl_json.as_bytes();
     let pubkey_vec = program_id.to_bytes().to_vec();
     let data_len_bytes = (json_bytes.len() as u32).to_le_bytes().to_vec();
-    let zipped = zip_bytes(idl_json.as_bytes())?;
+    let zipped = zip_bytes(idl_json.as_bytes(), level)?;
 
     let full_vec =
         [DISCRIMINATOR.to_vec(), pubkey_vec, data_len_bytes, zipped].concat();
     Ok(full_vec)
 }
 
-fn zip_bytes(bytes: &[u8]) -> ChainparserResult<Vec<u8>> {
-    let mut encoder =
-        ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+fn zip_bytes(bytes: &[u8], level: Compression) -> ChainparserResult<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), level);
     encoder.write_all(bytes)?;
     Ok(encoder.finish()?)
 }
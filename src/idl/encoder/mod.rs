@@ -7,13 +7,19 @@ pub use idl_encoder::*;
 // anchor:cli/src/lib.rs
 pub const IDL_HEADER_SIZE: usize = 44;
 
+// The authority pubkey follows the 8 byte account discriminator
+pub const IDL_AUTHORITY_OFFSET: usize = 8;
+
+// Upper bound for the size of an unzipped IDL to guard against zip bombs
+pub const DEFAULT_MAX_IDL_DECOMPRESSED_SIZE: usize = 10 * 1024 * 1024;
+
 #[cfg(test)]
 mod tests {
     use base64::{engine::general_purpose, Engine as _};
     use solana_idl::Idl;
-    use solana_sdk::pubkey::Pubkey;
 
     use super::*;
+    use crate::{errors::ChainparserError, pubkey::Pubkey};
 
     pub fn base64_decode(data: &str) -> Vec<u8> {
         general_purpose::STANDARD.decode(data).unwrap()
@@ -58,4 +64,165 @@ mod tests {
         assert_eq!(decoded_idl, idl);
         assert_eq!(decoded_json, BASIC_IDL_JSON);
     }
+
+    #[test]
+    fn roundtrip_minimal_idl_at_every_compression_level() {
+        const BASIC_IDL_JSON: &str =
+            "{\"version\":\"0.1.0\",\"name\":\"foo\",\"instructions\":[]}";
+
+        let some_pubkey = Pubkey::new_unique();
+        let idl: Idl = serde_json::from_str(BASIC_IDL_JSON).unwrap();
+        for level in 0..=9 {
+            let encoded = encode_idl_account_with_compression(
+                &some_pubkey,
+                &idl,
+                Compression::new(level),
+            )
+            .unwrap();
+            let (decoded_idl, decoded_json) =
+                decode_idl_account_data(&encoded).unwrap();
+            assert_eq!(decoded_idl, idl, "level {level}");
+            assert_eq!(decoded_json, BASIC_IDL_JSON, "level {level}");
+
+            let encoded = encode_idl_account_json_with_compression(
+                &some_pubkey,
+                BASIC_IDL_JSON,
+                Compression::new(level),
+            )
+            .unwrap();
+            assert_eq!(
+                unzip_idl_account_json(&encoded).unwrap(),
+                BASIC_IDL_JSON,
+                "level {level}"
+            );
+        }
+    }
+
+    #[test]
+    fn unzip_idl_exceeding_decompression_limit() {
+        // Compresses extremely well and thus inflates way beyond the limit
+        let json = format!("\"{}\"", "a".repeat(100_000));
+        let encoded =
+            encode_idl_account_json(&Pubkey::new_unique(), &json).unwrap();
+        assert!(encoded.len() < 1_000);
+
+        let res = unzip_idl_account_json_with_limit(&encoded, 1_000);
+        assert!(matches!(
+            res,
+            Err(ChainparserError::IdlDecompressionTooLarge(1_000))
+        ));
+        let res = decode_idl_account_data_with_limit(&encoded, 1_000);
+        assert!(matches!(
+            res,
+            Err(ChainparserError::IdlDecompressionTooLarge(1_000))
+        ));
+
+        let unzipped =
+            unzip_idl_account_json_with_limit(&encoded, json.len()).unwrap();
+        assert_eq!(unzipped, json);
+    }
+
+    #[test]
+    fn decode_idl_account_wrapped_in_zlib_or_gzip() {
+        use std::io::Write;
+
+        use flate2::write::{GzEncoder, ZlibEncoder};
+
+        const BASIC_IDL_JSON: &str =
+            "{\"version\":\"0.1.0\",\"name\":\"foo\",\"instructions\":[]}";
+        let header = encode_idl_account_json(&Pubkey::new_unique(), "")
+            .unwrap()[..IDL_HEADER_SIZE]
+            .to_vec();
+
+        let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+        zlib.write_all(BASIC_IDL_JSON.as_bytes()).unwrap();
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+        gzip.write_all(BASIC_IDL_JSON.as_bytes()).unwrap();
+
+        for zipped in [zlib.finish().unwrap(), gzip.finish().unwrap()] {
+            let encoded = [header.clone(), zipped].concat();
+            let (idl, json) = decode_idl_account_data(&encoded).unwrap();
+            assert_eq!(idl.name, "foo");
+            assert_eq!(json, BASIC_IDL_JSON);
+        }
+    }
+
+    #[test]
+    fn unzip_invalid_idl_data() {
+        let some_pubkey = Pubkey::new_unique();
+        let json = (0..1_000).map(|i| i.to_string()).collect::<String>();
+        let encoded = encode_idl_account_json(&some_pubkey, &json).unwrap();
+        let header = &encoded[..IDL_HEADER_SIZE];
+
+        assert!(matches!(
+            unzip_idl_account_json(&encoded[..encoded.len() - 10]),
+            Err(ChainparserError::IdlTruncated(_))
+        ));
+        assert!(matches!(
+            unzip_idl_account_json(&[header, b"not zipped"].concat()),
+            Err(ChainparserError::IdlNotZlib(_))
+        ));
+
+        let mut gzip =
+            flate2::write::GzEncoder::new(Vec::new(), Compression::default());
+        std::io::Write::write_all(&mut gzip, json.as_bytes()).unwrap();
+        let gzipped = gzip.finish().unwrap();
+        for len in [4, gzipped.len() - 4, gzipped.len() / 2] {
+            assert!(matches!(
+                unzip_idl_account_json(&[header, &gzipped[..len]].concat()),
+                Err(ChainparserError::IdlTruncated(_))
+            ));
+        }
+        let mut corrupt = gzipped.clone();
+        *corrupt.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            unzip_idl_account_json(&[header, &corrupt].concat()),
+            Err(ChainparserError::IdlCorrupt(..))
+        ));
+
+        let mut zlib =
+            flate2::write::ZlibEncoder::new(Vec::new(), Compression::default());
+        std::io::Write::write_all(&mut zlib, &[0xff, 0xfe]).unwrap();
+        let zipped = zlib.finish().unwrap();
+        assert!(matches!(
+            unzip_idl_account_json(&[header, &zipped].concat()),
+            Err(ChainparserError::IdlInvalidUtf8(_))
+        ));
+    }
+
+    #[test]
+    fn decode_authority_of_idl_account() {
+        let some_pubkey = Pubkey::new_unique();
+        let encoded = encode_idl_account_json(
+            &some_pubkey,
+            "{\"version\":\"0.1.0\",\"name\":\"foo\",\"instructions\":[]}",
+        )
+        .unwrap();
+        let authority = decode_idl_account_authority(&encoded).unwrap();
+        assert_eq!(authority, some_pubkey);
+
+        assert!(matches!(
+            decode_idl_account_authority(&encoded[..20]),
+            Err(ChainparserError::IdlAccountDataTooShort(20, 40))
+        ));
+    }
+
+    #[test]
+    fn declared_account_discriminators_of_idl() {
+        let json = r#"{
+          "version": "0.1.0",
+          "name": "tagged",
+          "instructions": [],
+          "accounts": [
+            { "name": "Bytes", "discriminator": [1, 2], "type": { "kind": "struct", "fields": [] } },
+            { "name": "Value", "discriminant": { "type": "u8", "value": 3 }, "type": { "kind": "struct", "fields": [] } },
+            { "name": "Invalid", "discriminator": [256], "type": { "kind": "struct", "fields": [] } },
+            { "name": "None", "type": { "kind": "struct", "fields": [] } }
+          ]
+        }"#;
+        let declared = declared_account_discriminators(json);
+        assert_eq!(declared.len(), 2);
+        assert_eq!(declared["Bytes"], vec![1, 2]);
+        assert_eq!(declared["Value"], vec![3]);
+    }
 }
@@ -1,13 +1,13 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, ops::Range};
 
 use solana_idl::{IdlType, IdlTypeDefinitionTy};
-use solana_sdk::pubkey::Pubkey;
 
-use super::IdlProvider;
-use crate::errors::ChainparserResult;
+use super::{IdlProvider, IDL_PROVIDERS};
+use crate::{errors::ChainparserResult, pubkey::Pubkey};
 
 const ANCHOR_SEED: &str = "anchor:idl";
 const SHANK_SEED: &str = "shank:idl";
+const CODAMA_SEED: &str = "codama:idl";
 
 /// Resolves the address of the account where the program [IDL] is stored.
 ///
@@ -21,6 +21,7 @@ pub fn try_idl_address(
     let seed = match provider {
         IdlProvider::Anchor => ANCHOR_SEED,
         IdlProvider::Shank => SHANK_SEED,
+        IdlProvider::Codama => CODAMA_SEED,
     };
     let key = Pubkey::create_with_seed(&base, seed, program_id)?;
     Ok(key)
@@ -37,12 +38,9 @@ pub fn get_idl_addresses(
 }
 
 pub fn is_idl_addess(program_id: &Pubkey, address: &Pubkey) -> bool {
-    let (anchor, shank) = get_idl_addresses(program_id);
-    let is_anchor_idl = matches!(anchor, Some(anchor) if anchor == *address);
-    if is_anchor_idl {
-        return true;
-    }
-    matches!(shank, Some(shank) if shank == *address)
+    IDL_PROVIDERS.iter().any(|provider| {
+        matches!(try_idl_address(provider, program_id), Ok(key) if key == *address)
+    })
 }
 
 pub(crate) fn idl_type_bytes(
@@ -109,6 +107,80 @@ pub(crate) fn idl_def_bytes(
     }
 }
 
+/// Resolves the size and alignment of the [ty] when laid out per `repr(C)` as is the case for
+/// anchor zero-copy accounts, see [idl_type_bytes] for the borsh layout.
+/// NOTE: unlike on x86_64 `u128` and `i128` are 8 byte aligned on the SBF target.
+pub(crate) fn repr_c_type_layout(
+    ty: &IdlType,
+    type_map: Option<&HashMap<String, &IdlTypeDefinitionTy>>,
+) -> Option<(usize, usize)> {
+    use IdlType::*;
+    match ty {
+        U8 | I8 | Bool => Some((1, 1)),
+        U16 | I16 => Some((2, 2)),
+        U32 | I32 | F32 => Some((4, 4)),
+        U64 | I64 | F64 => Some((8, 8)),
+        U128 | I128 => Some((16, 8)),
+        PublicKey => Some((32, 1)),
+        Array(inner, len) => repr_c_type_layout(inner, type_map)
+            .map(|(size, align)| (size * len, align)),
+        Defined(s) => {
+            let ty = type_map.and_then(|map| map.get(s))?;
+            repr_c_def_layout(ty, type_map, 1)
+        }
+        // Types of variable size or without a fixed representation cannot be zero-copy
+        _ => None,
+    }
+}
+
+/// Resolves the size and alignment of the type defined by [ty] when laid out per
+/// `repr(C, align(N))` where [align] is `N` or `1` for a plain `repr(C)`.
+/// Enums are assumed to be `repr(u8)` and are only supported if none of their variants have
+/// fields.
+pub(crate) fn repr_c_def_layout(
+    ty: &IdlTypeDefinitionTy,
+    type_map: Option<&HashMap<String, &IdlTypeDefinitionTy>>,
+    align: usize,
+) -> Option<(usize, usize)> {
+    match ty {
+        IdlTypeDefinitionTy::Struct { fields } => {
+            let (offsets, fields_align) = repr_c_field_offsets(
+                fields.iter().map(|field| &field.ty),
+                type_map,
+            )?;
+            let align = fields_align.max(align);
+            let end = offsets.last().map_or(0, |range| range.end);
+            Some((end.next_multiple_of(align), align))
+        }
+        IdlTypeDefinitionTy::Enum { variants }
+            if variants.iter().all(|variant| variant.fields.is_none()) =>
+        {
+            Some((1, 1))
+        }
+        IdlTypeDefinitionTy::Enum { .. } => None,
+    }
+}
+
+/// Resolves the byte range of each of the [tys] of struct fields when laid out per `repr(C)`,
+/// inserting padding in front of each field to align it, along with the largest alignment of
+/// them.
+pub(crate) fn repr_c_field_offsets<'a>(
+    tys: impl Iterator<Item = &'a IdlType>,
+    type_map: Option<&HashMap<String, &IdlTypeDefinitionTy>>,
+) -> Option<(Vec<Range<usize>>, usize)> {
+    let mut offset = 0usize;
+    let mut struct_align = 1;
+    let mut offsets = Vec::new();
+    for ty in tys {
+        let (size, align) = repr_c_type_layout(ty, type_map)?;
+        offset = offset.next_multiple_of(align);
+        offsets.push(offset..offset + size);
+        offset += size;
+        struct_align = struct_align.max(align);
+    }
+    Some((offsets, struct_align))
+}
+
 #[cfg(test)]
 mod test {
     use std::str::FromStr;
@@ -128,6 +200,8 @@ mod test {
             try_idl_address(&IdlProvider::Anchor, &program_id).unwrap();
         let shank_idl_address =
             try_idl_address(&IdlProvider::Shank, &program_id).unwrap();
+        let codama_idl_address =
+            try_idl_address(&IdlProvider::Codama, &program_id).unwrap();
 
         assert_eq!(
             anchor_idl_address.to_string(),
@@ -137,6 +211,10 @@ mod test {
             shank_idl_address.to_string(),
             "AEUhdmwzSea7oYDWhAiSBArqq6tBLFNNZZ448wfbaV3Z"
         );
+        assert_eq!(
+            codama_idl_address.to_string(),
+            "FRUDPn7r889cYZz4PvnjdzozNYqNH1af9J1RxXWbQw4E"
+        );
     }
 
     #[test]
@@ -166,6 +244,110 @@ mod test {
             &program_id,
             &str_to_pubkey("AEUhdmwzSea7oYDWhAiSBArqq6tBLFNNZZ448wfbaV3Z")
         ));
+        assert!(is_idl_addess(
+            &program_id,
+            &str_to_pubkey("FRUDPn7r889cYZz4PvnjdzozNYqNH1af9J1RxXWbQw4E")
+        ));
         assert!(!is_idl_addess(&program_id, &Pubkey::default()));
     }
+
+    fn point_ty() -> IdlTypeDefinitionTy {
+        serde_json::from_str(
+            r#"{
+              "kind": "struct",
+              "fields": [
+                { "name": "x", "type": "i64" },
+                { "name": "y", "type": "i64" }
+              ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn idl_type_bytes_array_of_defined() {
+        use IdlType::*;
+        let point = point_ty();
+        let type_map =
+            std::collections::HashMap::from([("Point".to_string(), &point)]);
+        let defined = || Box::new(Defined("Point".to_string()));
+
+        assert_eq!(
+            idl_type_bytes(&Array(defined(), 4), Some(&type_map)),
+            Some(64)
+        );
+        assert_eq!(
+            idl_type_bytes(
+                &Array(Box::new(Array(defined(), 2)), 3),
+                Some(&type_map)
+            ),
+            Some(96)
+        );
+        // Without the type map the defined type cannot be resolved
+        assert_eq!(idl_type_bytes(&Array(defined(), 4), None), None);
+    }
+
+    #[test]
+    fn repr_c_layout_pads_fields() {
+        use IdlType::*;
+        let point = point_ty();
+        let type_map =
+            std::collections::HashMap::from([("Point".to_string(), &point)]);
+
+        let (offsets, align) = repr_c_field_offsets(
+            [Bool, U64, U16, U128, Defined("Point".to_string()), U8].iter(),
+            Some(&type_map),
+        )
+        .unwrap();
+        assert_eq!(offsets, [0..1, 8..16, 16..18, 24..40, 40..56, 56..57]);
+        assert_eq!(align, 8);
+
+        let unaligned: IdlTypeDefinitionTy = serde_json::from_str(
+            r#"{
+              "kind": "struct",
+              "fields": [
+                { "name": "a", "type": "u32" },
+                { "name": "b", "type": "u8" }
+              ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(repr_c_def_layout(&unaligned, None, 1), Some((8, 4)));
+        assert_eq!(repr_c_def_layout(&unaligned, None, 16), Some((16, 16)));
+        assert_eq!(
+            repr_c_type_layout(&Array(Box::new(U16), 3), None),
+            Some((6, 2))
+        );
+        assert_eq!(repr_c_type_layout(&Vec(Box::new(U8)), None), None);
+    }
+
+    #[test]
+    fn idl_type_bytes_array_of_coption() {
+        use IdlType::*;
+        let point = point_ty();
+        let type_map =
+            std::collections::HashMap::from([("Point".to_string(), &point)]);
+
+        assert_eq!(
+            idl_type_bytes(&Array(Box::new(COption(Box::new(U64))), 3), None),
+            Some(36)
+        );
+        assert_eq!(
+            idl_type_bytes(
+                &Array(
+                    Box::new(COption(Box::new(Defined("Point".to_string())))),
+                    2
+                ),
+                Some(&type_map)
+            ),
+            Some(40)
+        );
+        assert_eq!(
+            idl_type_bytes(
+                &Array(Box::new(COption(Box::new(String))), 2),
+                None
+            ),
+            None
+        );
+    }
 }
@@ -0,0 +1,85 @@
+use solana_idl::{Idl, IdlErrorCode};
+
+/// Precedes the error code in the log line of a failed instruction, i.e.
+/// `Program <id> failed: custom program error: 0x1771`.
+const CUSTOM_PROGRAM_ERROR: &str = "custom program error: ";
+
+/// Precedes the error code in the log line that anchor emits for an `AnchorError`, i.e.
+/// `Program log: AnchorError occurred. Error Code: Unauthorized. Error Number: 6001. ...`.
+const ANCHOR_ERROR_NUMBER: &str = "Error Number: ";
+
+/// Extracts the custom program error code from the [log_line] of a failed transaction.
+/// The code is parsed as hex if prefixed with `0x` and as decimal otherwise since both forms
+/// appear depending on the Solana version.
+///
+/// Returns `None` if the [log_line] doesn't include a custom program error.
+pub fn error_code_from_log(log_line: &str) -> Option<u32> {
+    let (_, rest) = log_line
+        .split_once(CUSTOM_PROGRAM_ERROR)
+        .or_else(|| log_line.split_once(ANCHOR_ERROR_NUMBER))?;
+    let code = rest
+        .split(|c: char| c.is_whitespace() || c == '.')
+        .next()
+        .unwrap_or_default();
+    match code.strip_prefix("0x").or_else(|| code.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => code.parse().ok(),
+    }
+}
+
+/// Finds the error with the given [code] declared in the [idl].
+pub fn find_idl_error(idl: &Idl, code: u32) -> Option<&IdlErrorCode> {
+    idl.errors.as_ref()?.iter().find(|err| err.code == code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_code_from_log_lines() {
+        assert_eq!(
+            error_code_from_log(
+                "Program Vau1t11111111111111111111111111111111111111 failed: custom program error: 0x1771"
+            ),
+            Some(6001)
+        );
+        assert_eq!(
+            error_code_from_log(
+                "Program Vau1t11111111111111111111111111111111111111 failed: custom program error: 6001"
+            ),
+            Some(6001)
+        );
+        assert_eq!(
+            error_code_from_log(
+                "Program log: AnchorError occurred. Error Code: Unauthorized. Error Number: 6001. Error Message: Not allowed."
+            ),
+            Some(6001)
+        );
+        assert_eq!(
+            error_code_from_log("Program log: Instruction: Withdraw"),
+            None
+        );
+        assert_eq!(error_code_from_log("custom program error: 0xnothex"), None);
+    }
+
+    #[test]
+    fn find_idl_error_by_code() {
+        let idl: Idl = serde_json::from_str(
+            r#"{
+              "version": "0.1.0",
+              "name": "errors",
+              "instructions": [],
+              "errors": [
+                { "code": 6000, "name": "Overflow" },
+                { "code": 6001, "name": "Unauthorized", "msg": "Not allowed" }
+              ]
+            }"#,
+        )
+        .unwrap();
+        let err = find_idl_error(&idl, 6001).unwrap();
+        assert_eq!(err.name, "Unauthorized");
+        assert_eq!(err.msg.as_deref(), Some("Not allowed"));
+        assert!(find_idl_error(&idl, 6002).is_none());
+    }
+}
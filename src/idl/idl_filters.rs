@@ -0,0 +1,70 @@
+use solana_idl::Idl;
+
+use super::IdlProvider;
+use crate::discriminator::account_discriminator;
+
+/// Resolves the `memcmp` filter that limits the accounts returned by the `getProgramAccounts`
+/// RPC method to the ones of the given type.
+///
+/// - [idl] the IDL containing the account definition
+/// - [provider] the provider used to create the IDL
+/// - [account_name] the name of the account type
+///
+/// Returns the offset at which to compare along with the base58 encoded discriminator, i.e.
+/// `{ "memcmp": { "offset": 0, "bytes": "<discriminator>" } }`.
+///
+/// Returns `None` if the account is not defined in the IDL or if its data isn't prefixed with
+/// a discriminator as is the case for shank and codama accounts. Those can be filtered by
+/// their size via a `dataSize` filter if they have a fixed size, see [super::account_size].
+pub fn memcmp_filter(
+    idl: &Idl,
+    provider: &IdlProvider,
+    account_name: &str,
+) -> Option<(usize, String)> {
+    if !idl.accounts.iter().any(|acc| acc.name == account_name) {
+        return None;
+    }
+    match provider {
+        IdlProvider::Anchor => {
+            let discriminator = account_discriminator(account_name);
+            Some((0, bs58::encode(discriminator).into_string()))
+        }
+        IdlProvider::Shank | IdlProvider::Codama => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memcmp_filter_of_anchor_accounts() {
+        let idl: Idl = serde_json::from_str(
+            r#"{
+              "version": "0.1.0",
+              "name": "filters",
+              "instructions": [],
+              "accounts": [{
+                "name": "Vault",
+                "type": {
+                  "kind": "struct",
+                  "fields": [{ "name": "amount", "type": "u64" }]
+                }
+              }]
+            }"#,
+        )
+        .expect("valid IDL");
+
+        let (offset, bytes) =
+            memcmp_filter(&idl, &IdlProvider::Anchor, "Vault").unwrap();
+        assert_eq!(offset, 0);
+        assert_eq!(
+            bs58::decode(bytes).into_vec().unwrap(),
+            account_discriminator("Vault")
+        );
+
+        assert_eq!(memcmp_filter(&idl, &IdlProvider::Anchor, "Unknown"), None);
+        assert_eq!(memcmp_filter(&idl, &IdlProvider::Shank, "Vault"), None);
+        assert_eq!(memcmp_filter(&idl, &IdlProvider::Codama, "Vault"), None);
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+
+use solana_idl::{Idl, IdlType, IdlTypeDefinition, IdlTypeDefinitionTy};
+
+use crate::errors::{ChainparserError, ChainparserResult};
+
+/// Maps `(account name, field path)` to the type the field should be decoded with.
+pub type FieldTypeOverrides = HashMap<(String, String), IdlType>;
+
+/// Replaces the types of the fields in [overrides] inside the [idl], i.e. to fix IDLs that
+/// declare a field as `u32` while the program actually stores a `u64`.
+///
+/// The field path is the name of a field of the account, or a dotted path into fields of
+/// defined struct types, i.e. `config.fee`.
+/// Note that overriding a nested field changes the defined type, thus it applies to every
+/// account that includes that type.
+pub fn apply_field_type_overrides(
+    idl: &mut Idl,
+    overrides: &FieldTypeOverrides,
+) -> ChainparserResult<()> {
+    for ((account_name, field_path), ty) in overrides {
+        let not_found = || {
+            ChainparserError::CannotFindFieldToOverride(
+                account_name.to_string(),
+                field_path.to_string(),
+            )
+        };
+        let mut segments = field_path.split('.');
+        let mut field_name = segments.next().ok_or_else(not_found)?;
+        let mut def_name = account_name.to_string();
+        for segment in segments {
+            let field_ty = find_field_ty(idl, &def_name, field_name)
+                .ok_or_else(not_found)?;
+            let IdlType::Defined(nested) = field_ty else {
+                return Err(not_found());
+            };
+            def_name = nested.to_string();
+            field_name = segment;
+        }
+        *find_field_ty(idl, &def_name, field_name).ok_or_else(not_found)? =
+            ty.clone();
+    }
+    Ok(())
+}
+
+fn find_field_ty<'a>(
+    idl: &'a mut Idl,
+    def_name: &str,
+    field_name: &str,
+) -> Option<&'a mut IdlType> {
+    let def = idl
+        .accounts
+        .iter_mut()
+        .chain(idl.types.iter_mut())
+        .find(|def: &&mut IdlTypeDefinition| def.name == def_name)?;
+    match &mut def.ty {
+        IdlTypeDefinitionTy::Struct { fields } => fields
+            .iter_mut()
+            .find(|field| field.name == field_name)
+            .map(|field| &mut field.ty),
+        IdlTypeDefinitionTy::Enum { .. } => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn idl() -> Idl {
+        serde_json::from_str(
+            r#"{
+              "version": "0.1.0",
+              "name": "overrides",
+              "instructions": [],
+              "accounts": [{
+                "name": "Pool",
+                "type": {
+                  "kind": "struct",
+                  "fields": [
+                    { "name": "amount", "type": "u32" },
+                    { "name": "config", "type": { "defined": "Config" } }
+                  ]
+                }
+              }],
+              "types": [{
+                "name": "Config",
+                "type": {
+                  "kind": "struct",
+                  "fields": [{ "name": "fee", "type": "u16" }]
+                }
+              }]
+            }"#,
+        )
+        .expect("valid IDL")
+    }
+
+    fn field_ty<'a>(idl: &'a mut Idl, def: &str, field: &str) -> &'a IdlType {
+        find_field_ty(idl, def, field).unwrap()
+    }
+
+    #[test]
+    fn override_account_and_nested_fields() {
+        let mut idl = idl();
+        let overrides = FieldTypeOverrides::from([
+            (("Pool".to_string(), "amount".to_string()), IdlType::U64),
+            (("Pool".to_string(), "config.fee".to_string()), IdlType::U32),
+        ]);
+        apply_field_type_overrides(&mut idl, &overrides).unwrap();
+        assert_eq!(field_ty(&mut idl, "Pool", "amount"), &IdlType::U64);
+        assert_eq!(field_ty(&mut idl, "Config", "fee"), &IdlType::U32);
+    }
+
+    #[test]
+    fn override_unknown_field() {
+        for path in ["unknown", "amount.fee", "config.unknown"] {
+            let overrides = FieldTypeOverrides::from([(
+                ("Pool".to_string(), path.to_string()),
+                IdlType::U64,
+            )]);
+            assert!(matches!(
+                apply_field_type_overrides(&mut idl(), &overrides),
+                Err(ChainparserError::CannotFindFieldToOverride(_, _))
+            ));
+        }
+    }
+}
@@ -3,12 +3,14 @@ use std::{fs, str::FromStr};
 
 use solana_sdk::{
     account::{Account, AccountSharedData},
-    pubkey::Pubkey,
     stake_history::Epoch,
 };
 
 use super::{encode_idl_account_json, try_idl_address, IdlProvider};
-use crate::errors::{ChainparserError, ChainparserResult};
+use crate::{
+    errors::{ChainparserError, ChainparserResult},
+    pubkey::Pubkey,
+};
 
 /// Given the full path to an IDL JSON file, returns the [Pubkey] of the IDL
 /// account and an [AccountSharedData] that can be loaded into the validator at
@@ -73,7 +75,7 @@ fn account_shared_data_for_idl(
     let account = Account {
         lamports,
         data,
-        owner: program_pubkey,
+        owner: program_pubkey.into(),
         executable,
         rent_epoch,
     };
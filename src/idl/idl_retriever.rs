@@ -1,9 +1,10 @@
 use log::trace;
 use solana_idl::Idl;
-use solana_sdk::pubkey::Pubkey;
 
 use super::{decode_idl_account_data, try_idl_address, IdlProvider};
-use crate::{errors::ChainparserResult, traits::AccountProvider};
+use crate::{
+    errors::ChainparserResult, pubkey::Pubkey, traits::AccountProvider,
+};
 
 pub fn try_find_idl_for_program<T: AccountProvider>(
     account_provider: &T,
@@ -0,0 +1,572 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
+
+use solana_idl::{EnumFields, Idl, IdlField, IdlType, IdlTypeDefinitionTy};
+
+use super::{idl_def_bytes, idl_type_bytes, repr_c_field_offsets, IdlProvider};
+use crate::errors::{ChainparserError, ChainparserResult};
+
+/// The rent parameters of the Solana runtime, see `solana_sdk::rent::Rent::default`.
+const LAMPORTS_PER_BYTE_YEAR: u64 = 3_480;
+const EXEMPTION_THRESHOLD: f64 = 2.0;
+const ACCOUNT_STORAGE_OVERHEAD: u64 = 128;
+
+type TypeMap<'a> = HashMap<String, &'a IdlTypeDefinitionTy>;
+
+/// Maps the names of the types and accounts of the [idl] to their definitions.
+/// Accounts are added last so they win if a type shares their name.
+fn type_map(idl: &Idl) -> TypeMap<'_> {
+    idl.types
+        .iter()
+        .chain(idl.accounts.iter())
+        .map(|def| (def.name.clone(), &def.ty))
+        .collect()
+}
+
+/// Size of the discriminator that anchor prefixes account data with.
+const ANCHOR_DISCRIMINATOR_SIZE: usize = 8;
+
+/// Resolves the number of bytes an account of the given type takes up on chain, i.e. in order
+/// to size PDAs or estimate rent.
+///
+/// - [idl] the IDL containing the account definition and the types it references
+/// - [provider] the provider used to create the IDL, for anchor IDLs the size includes the 8
+///   byte discriminator
+/// - [account_name] the name of the account type
+///
+/// Returns `None` if the account is not defined in the IDL or if it contains variable sized
+/// fields, use [account_size_bounds] for those instead.
+pub fn account_size(
+    idl: &Idl,
+    provider: &IdlProvider,
+    account_name: &str,
+) -> Option<usize> {
+    let account = idl.accounts.iter().find(|acc| acc.name == account_name)?;
+    let type_map = type_map(idl);
+
+    let size = idl_def_bytes(&account.ty, Some(&type_map))?;
+    match provider {
+        IdlProvider::Anchor => Some(ANCHOR_DISCRIMINATOR_SIZE + size),
+        IdlProvider::Shank | IdlProvider::Codama => Some(size),
+    }
+}
+
+/// Resolves the minimum lamports an account of the given type needs to hold in order to be
+/// rent exempt, using the default rent of the Solana runtime which charges per byte of account
+/// data plus a fixed overhead per account.
+///
+/// - [idl] the IDL containing the account definition and the types it references
+/// - [provider] the provider used to create the IDL, for anchor IDLs the 8 byte discriminator
+///   is included
+/// - [account_name] the name of the account type
+///
+/// Returns `None` if the account is not defined in the IDL or if it contains variable sized
+/// fields since the minimum depends on the actual data then, see [account_size].
+pub fn rent_exempt_lamports(
+    idl: &Idl,
+    provider: &IdlProvider,
+    account_name: &str,
+) -> Option<u64> {
+    let size = account_size(idl, provider, account_name)?;
+    let bytes = ACCOUNT_STORAGE_OVERHEAD + size as u64;
+    Some(((bytes * LAMPORTS_PER_BYTE_YEAR) as f64 * EXEMPTION_THRESHOLD) as u64)
+}
+
+/// Resolves the byte range of each field of an account whose fields are all fixed size, i.e.
+/// zero-copy accounts, so that callers can read them in place without deserializing the
+/// account.
+///
+/// - [idl] the IDL containing the account definition and the types it references
+/// - [account_name] the name of the account type
+///
+/// The ranges are relative to the start of the account struct, thus for anchor accounts they
+/// need to be shifted by the 8 byte discriminator to index into the account data.
+/// Fields are assumed to be laid out without padding as is the case for `Pod` accounts, use
+/// [field_offsets_repr_c] for accounts laid out per `repr(C)`.
+///
+/// Returns `None` if the account is not defined in the IDL or if any of its fields is variable
+/// sized.
+pub fn field_offsets(
+    idl: &Idl,
+    account_name: &str,
+) -> Option<Vec<(String, Range<usize>)>> {
+    let account = idl.accounts.iter().find(|acc| acc.name == account_name)?;
+    let IdlTypeDefinitionTy::Struct { fields } = &account.ty else {
+        return None;
+    };
+    let type_map = type_map(idl);
+
+    let mut offset = 0;
+    let mut offsets = Vec::with_capacity(fields.len());
+    for field in fields {
+        let size = idl_type_bytes(&field.ty, Some(&type_map))?;
+        offsets.push((field.name.clone(), offset..offset + size));
+        offset += size;
+    }
+    Some(offsets)
+}
+
+/// Same as [field_offsets], but for accounts laid out per `repr(C)` as is the case for anchor
+/// zero-copy accounts, inserting padding in front of fields to align them.
+/// The offsets are the same for accounts declared with `repr(C, align(N))` since that only
+/// pads the end of the account.
+pub fn field_offsets_repr_c(
+    idl: &Idl,
+    account_name: &str,
+) -> Option<Vec<(String, Range<usize>)>> {
+    let account = idl.accounts.iter().find(|acc| acc.name == account_name)?;
+    let IdlTypeDefinitionTy::Struct { fields } = &account.ty else {
+        return None;
+    };
+    let type_map = type_map(idl);
+
+    let (offsets, _) = repr_c_field_offsets(
+        fields.iter().map(|field| &field.ty),
+        Some(&type_map),
+    )?;
+    Some(
+        fields
+            .iter()
+            .map(|field| field.name.clone())
+            .zip(offsets)
+            .collect(),
+    )
+}
+
+/// Resolves the minimum and maximum number of bytes an account of the given type takes up
+/// when serialized.
+///
+/// - [idl] the IDL containing the account definition and the types it references
+/// - [account_name] the name of the account type
+///
+/// The minimum assumes all variable sized fields are empty, i.e. vecs, strings and maps only
+/// take up their 4 byte length prefix and options are `None`.
+/// The maximum is `None` if the account has variable sized fields without a cap. Caps are
+/// declared on the field via a `max_len(N)` attribute.
+pub fn account_size_bounds(
+    idl: &Idl,
+    account_name: &str,
+) -> ChainparserResult<(usize, Option<usize>)> {
+    let account = idl
+        .accounts
+        .iter()
+        .find(|acc| acc.name == account_name)
+        .ok_or_else(|| {
+            ChainparserError::UnknownAccount(account_name.to_string())
+        })?;
+
+    let calc = SizeCalculator {
+        type_map: type_map(idl),
+    };
+    let mut visiting = HashSet::new();
+    visiting.insert(account_name);
+    Ok((
+        calc.def_min_size(&account.ty),
+        calc.def_max_size(&account.ty, &mut visiting),
+    ))
+}
+
+struct SizeCalculator<'a> {
+    type_map: TypeMap<'a>,
+}
+
+impl<'a> SizeCalculator<'a> {
+    fn def_min_size(&self, ty: &IdlTypeDefinitionTy) -> usize {
+        match ty {
+            IdlTypeDefinitionTy::Struct { fields } => {
+                fields.iter().map(|field| self.min_size(&field.ty)).sum()
+            }
+            IdlTypeDefinitionTy::Enum { variants } => {
+                let smallest_variant = variants
+                    .iter()
+                    .map(|variant| match &variant.fields {
+                        Some(EnumFields::Named(fields)) => fields
+                            .iter()
+                            .map(|field| self.min_size(&field.ty))
+                            .sum(),
+                        Some(EnumFields::Tuple(tys)) => {
+                            tys.iter().map(|ty| self.min_size(ty)).sum()
+                        }
+                        None => 0,
+                    })
+                    .min()
+                    .unwrap_or(0);
+                1 + smallest_variant
+            }
+        }
+    }
+
+    fn min_size(&self, ty: &IdlType) -> usize {
+        use IdlType::*;
+        match ty {
+            Bool | U8 | I8 => 1,
+            U16 | I16 => 2,
+            U32 | I32 | F32 => 4,
+            U64 | I64 | F64 => 8,
+            U128 | I128 => 16,
+            PublicKey => 32,
+            Bytes
+            | String
+            | Vec(_)
+            | HashMap(_, _)
+            | BTreeMap(_, _)
+            | HashSet(_)
+            | BTreeSet(_) => 4,
+            Option(_) => 1,
+            COption(inner) => 4 + self.min_size(inner),
+            Array(inner, len) => self.min_size(inner) * len,
+            Tuple(tys) => tys.iter().map(|ty| self.min_size(ty)).sum(),
+            Defined(name) => self
+                .type_map
+                .get(name.as_str())
+                .map(|ty| self.def_min_size(ty))
+                .unwrap_or(0),
+        }
+    }
+
+    fn def_max_size(
+        &self,
+        ty: &'a IdlTypeDefinitionTy,
+        visiting: &mut HashSet<&'a str>,
+    ) -> Option<usize> {
+        match ty {
+            IdlTypeDefinitionTy::Struct { fields } => {
+                self.fields_max_size(fields, visiting)
+            }
+            IdlTypeDefinitionTy::Enum { variants } => {
+                let mut largest_variant = 0;
+                for variant in variants {
+                    let size = match &variant.fields {
+                        Some(EnumFields::Named(fields)) => {
+                            self.fields_max_size(fields, visiting)?
+                        }
+                        Some(EnumFields::Tuple(tys)) => {
+                            let mut size = 0;
+                            for ty in tys {
+                                size += self.max_size(ty, None, visiting)?;
+                            }
+                            size
+                        }
+                        None => 0,
+                    };
+                    largest_variant = largest_variant.max(size);
+                }
+                Some(1 + largest_variant)
+            }
+        }
+    }
+
+    fn fields_max_size(
+        &self,
+        fields: &'a [IdlField],
+        visiting: &mut HashSet<&'a str>,
+    ) -> Option<usize> {
+        let mut size = 0;
+        for field in fields {
+            let cap = field
+                .attrs
+                .as_ref()
+                .and_then(|attrs| attrs.iter().find_map(|a| max_len_attr(a)));
+            size += self.max_size(&field.ty, cap, visiting)?;
+        }
+        Some(size)
+    }
+
+    /// Resolves the max size of the given type.
+    /// The [cap] limits the number of elements of a variable sized type and only applies to
+    /// the outermost type of a field.
+    fn max_size(
+        &self,
+        ty: &'a IdlType,
+        cap: Option<usize>,
+        visiting: &mut HashSet<&'a str>,
+    ) -> Option<usize> {
+        use IdlType::*;
+        match ty {
+            Bytes | String => cap.map(|cap| 4 + cap),
+            Vec(inner) | HashSet(inner) | BTreeSet(inner) => {
+                let cap = cap?;
+                Some(4 + cap * self.max_size(inner, None, visiting)?)
+            }
+            HashMap(key, val) | BTreeMap(key, val) => {
+                let cap = cap?;
+                let entry = self.max_size(key, None, visiting)?
+                    + self.max_size(val, None, visiting)?;
+                Some(4 + cap * entry)
+            }
+            Option(inner) => Some(1 + self.max_size(inner, cap, visiting)?),
+            COption(inner) => Some(4 + self.max_size(inner, cap, visiting)?),
+            Array(inner, len) => {
+                Some(self.max_size(inner, None, visiting)? * len)
+            }
+            Tuple(tys) => {
+                let mut size = 0;
+                for ty in tys {
+                    size += self.max_size(ty, None, visiting)?;
+                }
+                Some(size)
+            }
+            Defined(name) => {
+                let def = *self.type_map.get(name.as_str())?;
+                // Recursive types are unbounded
+                if !visiting.insert(name.as_str()) {
+                    return None;
+                }
+                let size = self.def_max_size(def, visiting);
+                visiting.remove(name.as_str());
+                size
+            }
+            _ => Some(self.min_size(ty)),
+        }
+    }
+}
+
+fn max_len_attr(attr: &str) -> Option<usize> {
+    attr.trim()
+        .strip_prefix("max_len(")
+        .and_then(|s| s.strip_suffix(')'))
+        .and_then(|s| s.trim().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn idl(accounts: &str, types: &str) -> Idl {
+        let json = format!(
+            r#"{{
+              "version": "0.1.0",
+              "name": "sizes",
+              "instructions": [],
+              "accounts": {accounts},
+              "types": {types}
+            }}"#
+        );
+        serde_json::from_str(&json).expect("valid IDL")
+    }
+
+    #[test]
+    fn size_bounds_fixed_account() {
+        let idl = idl(
+            r#"[{
+              "name": "Fixed",
+              "type": {
+                "kind": "struct",
+                "fields": [
+                  { "name": "authority", "type": "publicKey" },
+                  { "name": "amount", "type": "u64" },
+                  { "name": "kind", "type": { "defined": "Kind" } }
+                ]
+              }
+            }]"#,
+            r#"[{
+              "name": "Kind",
+              "type": {
+                "kind": "enum",
+                "variants": [{ "name": "One" }, { "name": "Two" }]
+              }
+            }]"#,
+        );
+        assert_eq!(
+            account_size_bounds(&idl, "Fixed").unwrap(),
+            (32 + 8 + 1, Some(32 + 8 + 1))
+        );
+    }
+
+    #[test]
+    fn size_bounds_account_with_vec() {
+        let idl = idl(
+            r#"[{
+              "name": "Holder",
+              "type": {
+                "kind": "struct",
+                "fields": [
+                  { "name": "authority", "type": "publicKey" },
+                  { "name": "amounts", "type": { "vec": "u64" } }
+                ]
+              }
+            }]"#,
+            "[]",
+        );
+        assert_eq!(
+            account_size_bounds(&idl, "Holder").unwrap(),
+            (32 + 4, None)
+        );
+    }
+
+    #[test]
+    fn size_bounds_account_with_capped_fields() {
+        let idl = idl(
+            r#"[{
+              "name": "Capped",
+              "type": {
+                "kind": "struct",
+                "fields": [
+                  { "name": "bump", "type": "u8" },
+                  {
+                    "name": "amounts",
+                    "type": { "vec": "u64" },
+                    "attrs": ["max_len(10)"]
+                  },
+                  {
+                    "name": "label",
+                    "type": { "option": "string" },
+                    "attrs": ["max_len(32)"]
+                  }
+                ]
+              }
+            }]"#,
+            "[]",
+        );
+        assert_eq!(
+            account_size_bounds(&idl, "Capped").unwrap(),
+            (1 + 4 + 1, Some(1 + (4 + 10 * 8) + (1 + 4 + 32)))
+        );
+    }
+
+    #[test]
+    fn size_of_fixed_account() {
+        let idl = idl(
+            r#"[{
+              "name": "Fixed",
+              "type": {
+                "kind": "struct",
+                "fields": [
+                  { "name": "authority", "type": "publicKey" },
+                  { "name": "point", "type": { "defined": "Point" } },
+                  { "name": "flags", "type": { "array": ["bool", 3] } }
+                ]
+              }
+            },
+            {
+              "name": "Variable",
+              "type": {
+                "kind": "struct",
+                "fields": [{ "name": "name", "type": "string" }]
+              }
+            }]"#,
+            r#"[{
+              "name": "Point",
+              "type": {
+                "kind": "struct",
+                "fields": [
+                  { "name": "x", "type": "i64" },
+                  { "name": "y", "type": "i64" }
+                ]
+              }
+            }]"#,
+        );
+        let fixed_size = 32 + 16 + 3;
+        assert_eq!(
+            account_size(&idl, &IdlProvider::Anchor, "Fixed"),
+            Some(8 + fixed_size)
+        );
+        assert_eq!(
+            account_size(&idl, &IdlProvider::Shank, "Fixed"),
+            Some(fixed_size)
+        );
+        assert_eq!(account_size(&idl, &IdlProvider::Anchor, "Variable"), None);
+        assert_eq!(account_size(&idl, &IdlProvider::Anchor, "Unknown"), None);
+    }
+
+    #[test]
+    fn field_offsets_of_fixed_account() {
+        let idl = idl(
+            r#"[{
+              "name": "Fixed",
+              "type": {
+                "kind": "struct",
+                "fields": [
+                  { "name": "authority", "type": "publicKey" },
+                  { "name": "point", "type": { "defined": "Point" } },
+                  { "name": "flags", "type": { "array": ["bool", 3] } },
+                  { "name": "bump", "type": "u8" }
+                ]
+              }
+            },
+            {
+              "name": "Variable",
+              "type": {
+                "kind": "struct",
+                "fields": [
+                  { "name": "bump", "type": "u8" },
+                  { "name": "name", "type": "string" }
+                ]
+              }
+            }]"#,
+            r#"[{
+              "name": "Point",
+              "type": {
+                "kind": "struct",
+                "fields": [
+                  { "name": "x", "type": "i64" },
+                  { "name": "y", "type": "i64" }
+                ]
+              }
+            }]"#,
+        );
+        assert_eq!(
+            field_offsets(&idl, "Fixed"),
+            Some(vec![
+                ("authority".to_string(), 0..32),
+                ("point".to_string(), 32..48),
+                ("flags".to_string(), 48..51),
+                ("bump".to_string(), 51..52),
+            ])
+        );
+        assert_eq!(field_offsets(&idl, "Variable"), None);
+        assert_eq!(field_offsets(&idl, "Unknown"), None);
+    }
+
+    #[test]
+    fn size_bounds_unknown_account() {
+        let idl = idl("[]", "[]");
+        assert!(matches!(
+            account_size_bounds(&idl, "Unknown"),
+            Err(ChainparserError::UnknownAccount(_))
+        ));
+    }
+
+    #[test]
+    fn rent_exempt_lamports_of_fixed_account() {
+        let idl = idl(
+            r#"[{
+              "name": "Vault",
+              "type": {
+                "kind": "struct",
+                "fields": [
+                  { "name": "authority", "type": "publicKey" },
+                  { "name": "amount", "type": "u64" }
+                ]
+              }
+            },
+            {
+              "name": "Named",
+              "type": {
+                "kind": "struct",
+                "fields": [{ "name": "name", "type": "string" }]
+              }
+            }]"#,
+            "[]",
+        );
+        // (128 bytes overhead + data) * 3480 lamports per byte-year * 2 years
+        assert_eq!(
+            rent_exempt_lamports(&idl, &IdlProvider::Anchor, "Vault"),
+            Some((128 + 8 + 32 + 8) * 3480 * 2)
+        );
+        assert_eq!(
+            rent_exempt_lamports(&idl, &IdlProvider::Shank, "Vault"),
+            Some((128 + 32 + 8) * 3480 * 2)
+        );
+        assert_eq!(
+            rent_exempt_lamports(&idl, &IdlProvider::Anchor, "Named"),
+            None
+        );
+        assert_eq!(
+            rent_exempt_lamports(&idl, &IdlProvider::Anchor, "Unknown"),
+            None
+        );
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::{HashMap, HashSet};
+
+use solana_idl::{EnumFields, Idl, IdlType, IdlTypeDefinitionTy};
+
+use crate::errors::{ChainparserError, ChainparserResult};
+
+type TypeMap<'a> = HashMap<&'a str, &'a IdlTypeDefinitionTy>;
+
+/// Finds the accounts and types of the [idl] that reference themselves, directly or via other
+/// types, without an indirection that allows the nesting to end, as well as the ones embedding
+/// such a type.
+///
+/// An `Option`, `Vec`, set or map can be empty and an enum can pick another variant, thus they
+/// break a cycle, while struct fields, tuples and non-empty arrays don't.
+/// Such types can never be deserialized since their data would have to be infinitely long.
+///
+/// Returns the names of the offending types sorted alphabetically.
+pub fn find_cyclic_types(idl: &Idl) -> Vec<String> {
+    // Types are added last so they win if an account shares their name
+    let type_map = idl
+        .accounts
+        .iter()
+        .chain(idl.types.iter())
+        .map(|def| (def.name.as_str(), &def.ty))
+        .collect::<TypeMap>();
+
+    // Resolves which types can be built from finite data until no more are found, the
+    // remaining ones only exist inside a cycle.
+    let mut finite = HashSet::new();
+    loop {
+        let found = type_map
+            .iter()
+            .filter(|(name, ty)| {
+                !finite.contains(*name) && is_finite_def(ty, &type_map, &finite)
+            })
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>();
+        if found.is_empty() {
+            break;
+        }
+        finite.extend(found);
+    }
+
+    let mut cyclic = type_map
+        .keys()
+        .filter(|name| !finite.contains(*name))
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>();
+    cyclic.sort();
+    cyclic
+}
+
+/// Fails with [ChainparserError::CyclicTypeDefinitions] if the [idl] includes types that
+/// reference themselves without an indirection, see [find_cyclic_types].
+pub fn verify_acyclic_types(idl: &Idl) -> ChainparserResult<()> {
+    let cyclic = find_cyclic_types(idl);
+    if cyclic.is_empty() {
+        Ok(())
+    } else {
+        Err(ChainparserError::CyclicTypeDefinitions(
+            idl.name.clone(),
+            cyclic,
+        ))
+    }
+}
+
+fn is_finite_def(
+    ty: &IdlTypeDefinitionTy,
+    type_map: &TypeMap,
+    finite: &HashSet<&str>,
+) -> bool {
+    match ty {
+        IdlTypeDefinitionTy::Struct { fields } => fields
+            .iter()
+            .all(|f| is_finite_type(&f.ty, type_map, finite)),
+        IdlTypeDefinitionTy::Enum { variants } => {
+            variants.is_empty()
+                || variants.iter().any(|variant| match &variant.fields {
+                    Some(EnumFields::Named(fields)) => fields
+                        .iter()
+                        .all(|f| is_finite_type(&f.ty, type_map, finite)),
+                    Some(EnumFields::Tuple(types)) => types
+                        .iter()
+                        .all(|ty| is_finite_type(ty, type_map, finite)),
+                    None => true,
+                })
+        }
+    }
+}
+
+fn is_finite_type(
+    ty: &IdlType,
+    type_map: &TypeMap,
+    finite: &HashSet<&str>,
+) -> bool {
+    use IdlType::*;
+    match ty {
+        // Types missing from the IDL are reported once they are deserialized
+        Defined(name) => {
+            !type_map.contains_key(name.as_str())
+                || finite.contains(name.as_str())
+        }
+        Array(_, 0) => true,
+        Array(inner, _) => is_finite_type(inner, type_map, finite),
+        Tuple(inners) => {
+            inners.iter().all(|ty| is_finite_type(ty, type_map, finite))
+        }
+        // Option, COption, Vec, sets and maps can be empty
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn idl(types: &str) -> Idl {
+        let json = format!(
+            r#"{{
+              "version": "0.1.0",
+              "name": "cycles",
+              "instructions": [],
+              "types": {types}
+            }}"#
+        );
+        serde_json::from_str(&json).expect("valid IDL")
+    }
+
+    #[test]
+    fn find_cycles_without_indirection() {
+        let idl = idl(r#"[
+              { "name": "Node", "type": { "kind": "struct", "fields": [
+                { "name": "next", "type": { "option": { "defined": "Node" } } }
+              ] } },
+              { "name": "Tree", "type": { "kind": "struct", "fields": [
+                { "name": "children", "type": { "vec": { "defined": "Tree" } } }
+              ] } },
+              { "name": "Expr", "type": { "kind": "enum", "variants": [
+                { "name": "Lit", "fields": ["u64"] },
+                { "name": "Neg", "fields": [{ "defined": "Expr" }] }
+              ] } },
+              { "name": "Ping", "type": { "kind": "struct", "fields": [
+                { "name": "pong", "type": { "array": [{ "defined": "Pong" }, 2] } }
+              ] } },
+              { "name": "Pong", "type": { "kind": "struct", "fields": [
+                { "name": "ping", "type": { "defined": "Ping" } }
+              ] } },
+              { "name": "Holder", "type": { "kind": "struct", "fields": [
+                { "name": "ping", "type": { "defined": "Ping" } }
+              ] } }
+            ]"#);
+        assert_eq!(find_cyclic_types(&idl), vec!["Holder", "Ping", "Pong"]);
+        assert!(matches!(
+            verify_acyclic_types(&idl),
+            Err(ChainparserError::CyclicTypeDefinitions(name, types))
+                if name == "cycles" && types.len() == 3
+        ));
+    }
+}
@@ -1,14 +1,28 @@
 mod encoder;
 mod idl_address;
+mod idl_errors;
+mod idl_filters;
+mod idl_overrides;
+#[cfg(feature = "solana-sdk")]
 mod idl_provider;
 mod idl_retriever;
+mod idl_size;
+mod idl_type_cycles;
+mod spl_token;
 
 use std::fmt;
 
 pub use encoder::*;
 pub use idl_address::*;
+pub use idl_errors::*;
+pub use idl_filters::*;
+pub use idl_overrides::*;
+#[cfg(feature = "solana-sdk")]
 pub use idl_provider::*;
 pub use idl_retriever::*;
+pub use idl_size::*;
+pub use idl_type_cycles::*;
+pub use spl_token::*;
 
 /// The provider responsible for generating the IDL.
 /// Some providers like [Anchor] also prefix the account data in a specific way, i.e. by adding a
@@ -17,10 +31,14 @@ pub use idl_retriever::*;
 pub enum IdlProvider {
     Anchor,
     Shank,
+    /// IDLs generated via Codama (formerly Kinobi).
+    /// Codama programs don't prefix account data with an anchor discriminator, thus their
+    /// accounts are resolved via the match discriminator the same way as shank accounts.
+    Codama,
 }
 
-pub const IDL_PROVIDERS: &[IdlProvider; 2] =
-    &[IdlProvider::Anchor, IdlProvider::Shank];
+pub const IDL_PROVIDERS: &[IdlProvider; 3] =
+    &[IdlProvider::Anchor, IdlProvider::Shank, IdlProvider::Codama];
 
 impl TryFrom<&str> for IdlProvider {
     type Error = ();
@@ -28,6 +46,7 @@ impl TryFrom<&str> for IdlProvider {
         match s {
             "anchor" => Ok(Self::Anchor),
             "shank" => Ok(Self::Shank),
+            "codama" => Ok(Self::Codama),
             _ => Err(()),
         }
     }
@@ -38,6 +57,7 @@ impl fmt::Display for IdlProvider {
         match self {
             Self::Anchor => write!(f, "anchor"),
             Self::Shank => write!(f, "shank"),
+            Self::Codama => write!(f, "codama"),
         }
     }
 }
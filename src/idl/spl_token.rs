@@ -0,0 +1,322 @@
+use std::collections::HashMap;
+
+use solana_idl::{
+    Idl, IdlEnumVariant, IdlField, IdlType, IdlTypeDefinition,
+    IdlTypeDefinitionTy,
+};
+
+/// The program id of the SPL token program.
+pub const SPL_TOKEN_PROGRAM_ID: &str =
+    "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+
+/// Size of an SPL token `Mint` account.
+pub const SPL_TOKEN_MINT_SIZE: usize = 82;
+
+/// Size of an SPL token `Account` account.
+pub const SPL_TOKEN_ACCOUNT_SIZE: usize = 165;
+
+/// The program id of the Token-2022 program.
+pub const SPL_TOKEN_2022_PROGRAM_ID: &str =
+    "TokenzQdBNbLqP5VEhdkAS6EeFy8R3rA2fRdMDQoZzUDn";
+
+/// Offset of the account type of Token-2022 accounts with extensions.
+/// Mints are padded to the size of token accounts so that both can be told apart by it.
+pub const SPL_TOKEN_2022_ACCOUNT_TYPE_OFFSET: usize = SPL_TOKEN_ACCOUNT_SIZE;
+
+/// Account type of Token-2022 mints with extensions.
+pub const SPL_TOKEN_2022_MINT_ACCOUNT_TYPE: u8 = 1;
+
+/// Account type of Token-2022 token accounts with extensions.
+pub const SPL_TOKEN_2022_ACCOUNT_ACCOUNT_TYPE: u8 = 2;
+
+/// Provides an IDL describing the `Mint` and `Account` accounts of the SPL token program so
+/// they can be decoded without supplying an IDL.
+///
+/// The accounts are serialized via the spl serializer, i.e. `COption`s take up the same space
+/// whether they are set or not, and are resolved by matching their shape like shank accounts.
+pub fn spl_token_idl() -> Idl {
+    let mut idl: Idl = serde_json::from_str(
+        r#"{
+          "version": "3.5.0",
+          "name": "spl_token",
+          "instructions": [],
+          "metadata": { "serializer": "spl" }
+        }"#,
+    )
+    .expect("valid SPL token IDL");
+    idl.metadata.as_mut().expect("IDL has metadata").address =
+        Some(SPL_TOKEN_PROGRAM_ID.to_string());
+
+    let pubkey = || IdlType::PublicKey;
+    let coption = |ty: IdlType| IdlType::COption(Box::new(ty));
+    idl.accounts = vec![
+        struct_definition(
+            "Mint",
+            vec![
+                field("mintAuthority", coption(pubkey())),
+                field("supply", IdlType::U64),
+                field("decimals", IdlType::U8),
+                field("isInitialized", IdlType::Bool),
+                field("freezeAuthority", coption(pubkey())),
+            ],
+        ),
+        struct_definition(
+            "Account",
+            vec![
+                field("mint", pubkey()),
+                field("owner", pubkey()),
+                field("amount", IdlType::U64),
+                field("delegate", coption(pubkey())),
+                field("state", IdlType::Defined("AccountState".to_string())),
+                field("isNative", coption(IdlType::U64)),
+                field("delegatedAmount", IdlType::U64),
+                field("closeAuthority", coption(pubkey())),
+            ],
+        ),
+    ];
+    idl.types = vec![IdlTypeDefinition {
+        name: "AccountState".to_string(),
+        ty: IdlTypeDefinitionTy::Enum {
+            variants: ["Uninitialized", "Initialized", "Frozen"]
+                .into_iter()
+                .map(|name| IdlEnumVariant {
+                    name: name.to_string(),
+                    fields: None,
+                })
+                .collect(),
+        },
+    }];
+    idl
+}
+
+/// Provides an IDL describing the `Mint` and `Account` accounts of the Token-2022 program.
+/// The base accounts share the layout of the SPL token program, the extensions that follow them
+/// are described via [spl_token_2022_extensions].
+pub fn spl_token_2022_idl() -> Idl {
+    let mut idl = spl_token_idl();
+    idl.name = "spl_token_2022".to_string();
+    idl.version = "1.0.0".to_string();
+    idl.metadata.as_mut().expect("IDL has metadata").address =
+        Some(SPL_TOKEN_2022_PROGRAM_ID.to_string());
+    idl.types.push(struct_definition(
+        "TransferFee",
+        vec![
+            field("epoch", IdlType::U64),
+            field("maximumFee", IdlType::U64),
+            field("transferFeeBasisPoints", IdlType::U16),
+        ],
+    ));
+    idl
+}
+
+/// Provides the layouts of well-known Token-2022 extensions keyed by their extension type.
+/// Optional pubkeys are stored as all zeros when unset, thus they are decoded as the default
+/// pubkey in that case.
+///
+/// The types they reference are included in [spl_token_2022_idl].
+pub fn spl_token_2022_extensions() -> HashMap<u16, IdlTypeDefinition> {
+    let pubkey = || IdlType::PublicKey;
+    let string = || IdlType::String;
+    HashMap::from([
+        (
+            1,
+            struct_definition(
+                "TransferFeeConfig",
+                vec![
+                    field("transferFeeConfigAuthority", pubkey()),
+                    field("withdrawWithheldAuthority", pubkey()),
+                    field("withheldAmount", IdlType::U64),
+                    field(
+                        "olderTransferFee",
+                        IdlType::Defined("TransferFee".to_string()),
+                    ),
+                    field(
+                        "newerTransferFee",
+                        IdlType::Defined("TransferFee".to_string()),
+                    ),
+                ],
+            ),
+        ),
+        (
+            2,
+            struct_definition(
+                "TransferFeeAmount",
+                vec![field("withheldAmount", IdlType::U64)],
+            ),
+        ),
+        (
+            3,
+            struct_definition(
+                "MintCloseAuthority",
+                vec![field("closeAuthority", pubkey())],
+            ),
+        ),
+        (
+            6,
+            struct_definition(
+                "DefaultAccountState",
+                vec![field(
+                    "state",
+                    IdlType::Defined("AccountState".to_string()),
+                )],
+            ),
+        ),
+        (7, struct_definition("ImmutableOwner", vec![])),
+        (
+            8,
+            struct_definition(
+                "MemoTransfer",
+                vec![field("requireIncomingTransferMemos", IdlType::Bool)],
+            ),
+        ),
+        (9, struct_definition("NonTransferable", vec![])),
+        (
+            10,
+            struct_definition(
+                "InterestBearingConfig",
+                vec![
+                    field("rateAuthority", pubkey()),
+                    field("initializationTimestamp", IdlType::I64),
+                    field("preUpdateAverageRate", IdlType::I16),
+                    field("lastUpdateTimestamp", IdlType::I64),
+                    field("currentRate", IdlType::I16),
+                ],
+            ),
+        ),
+        (
+            11,
+            struct_definition(
+                "CpiGuard",
+                vec![field("lockCpi", IdlType::Bool)],
+            ),
+        ),
+        (
+            12,
+            struct_definition(
+                "PermanentDelegate",
+                vec![field("delegate", pubkey())],
+            ),
+        ),
+        (13, struct_definition("NonTransferableAccount", vec![])),
+        (
+            14,
+            struct_definition(
+                "TransferHook",
+                vec![
+                    field("authority", pubkey()),
+                    field("programId", pubkey()),
+                ],
+            ),
+        ),
+        (
+            15,
+            struct_definition(
+                "TransferHookAccount",
+                vec![field("transferring", IdlType::Bool)],
+            ),
+        ),
+        (
+            18,
+            struct_definition(
+                "MetadataPointer",
+                vec![
+                    field("authority", pubkey()),
+                    field("metadataAddress", pubkey()),
+                ],
+            ),
+        ),
+        (
+            19,
+            struct_definition(
+                "TokenMetadata",
+                vec![
+                    field("updateAuthority", pubkey()),
+                    field("mint", pubkey()),
+                    field("name", string()),
+                    field("symbol", string()),
+                    field("uri", string()),
+                    field(
+                        "additionalMetadata",
+                        IdlType::Vec(Box::new(IdlType::Tuple(vec![
+                            string(),
+                            string(),
+                        ]))),
+                    ),
+                ],
+            ),
+        ),
+        (
+            20,
+            struct_definition(
+                "GroupPointer",
+                vec![
+                    field("authority", pubkey()),
+                    field("groupAddress", pubkey()),
+                ],
+            ),
+        ),
+        (
+            22,
+            struct_definition(
+                "GroupMemberPointer",
+                vec![
+                    field("authority", pubkey()),
+                    field("memberAddress", pubkey()),
+                ],
+            ),
+        ),
+    ])
+}
+
+fn struct_definition(name: &str, fields: Vec<IdlField>) -> IdlTypeDefinition {
+    IdlTypeDefinition {
+        name: name.to_string(),
+        ty: IdlTypeDefinitionTy::Struct { fields },
+    }
+}
+
+fn field(name: &str, ty: IdlType) -> IdlField {
+    IdlField {
+        name: name.to_string(),
+        ty,
+        attrs: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::idl::{account_size, idl_def_bytes, IdlProvider};
+
+    #[test]
+    fn spl_token_account_sizes() {
+        let idl = spl_token_idl();
+        assert_eq!(
+            account_size(&idl, &IdlProvider::Shank, "Mint"),
+            Some(SPL_TOKEN_MINT_SIZE)
+        );
+        assert_eq!(
+            account_size(&idl, &IdlProvider::Shank, "Account"),
+            Some(SPL_TOKEN_ACCOUNT_SIZE)
+        );
+    }
+
+    #[test]
+    fn spl_token_2022_extension_sizes() {
+        let idl = spl_token_2022_idl();
+        let type_map = idl
+            .types
+            .iter()
+            .map(|def| (def.name.clone(), &def.ty))
+            .collect::<HashMap<_, _>>();
+        let extensions = spl_token_2022_extensions();
+        for (ty, size) in [(1, 108), (2, 8), (3, 32), (6, 1), (7, 0), (10, 52)]
+        {
+            assert_eq!(
+                idl_def_bytes(&extensions[&ty].ty, Some(&type_map)),
+                Some(size),
+                "extension {ty}"
+            );
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use heck::ToSnakeCase;
 use solana_idl::IdlInstruction;
-use solana_sdk::hash;
+
+use crate::{hash::sha256, idl::IdlProvider};
 
 // Namespace for calculating instruction sighash signatures for any instruction
 // not affecting program state.
@@ -21,6 +22,23 @@ pub fn discriminator_from_ix(ix: &IdlInstruction) -> Vec<u8> {
         })
 }
 
+/// Same as [discriminator_from_ix], but for shank and codama IDLs whose instructions don't
+/// declare a discriminant the index [idx] of the instruction is used as discriminator.
+pub fn provider_discriminator_from_ix(
+    ix: &IdlInstruction,
+    idx: usize,
+    provider: &IdlProvider,
+) -> Vec<u8> {
+    match provider {
+        IdlProvider::Shank | IdlProvider::Codama
+            if ix.discriminant.is_none() =>
+        {
+            vec![idx as u8]
+        }
+        _ => discriminator_from_ix(ix),
+    }
+}
+
 /// Replicates the mechanism that anchor used in order to derive a discriminator
 /// from the name of an instruction.
 fn anchor_sighash(namespace: &str, ix_name: &str) -> [u8; 8] {
@@ -32,7 +50,7 @@ fn anchor_sighash(namespace: &str, ix_name: &str) -> [u8; 8] {
     let preimage = format!("{namespace}:{ix_name}");
 
     let mut sighash = [0u8; 8];
-    sighash.copy_from_slice(&hash::hash(preimage.as_bytes()).to_bytes()[..8]);
+    sighash.copy_from_slice(&sha256(&[preimage.as_bytes()])[..8]);
     sighash
 }
 
@@ -0,0 +1,233 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use solana_idl::{Idl, IdlInstruction};
+
+use super::instruction_mapper::{
+    find_best_matching_idl_ix, DiscriminatorMatch,
+};
+use crate::{
+    deserializer::DeserializeProvider,
+    errors::{ChainparserError, ChainparserResult},
+    idl::IdlProvider,
+    json::{
+        JsonIdlInstructionDeserializer, JsonIdlTypeDefinitionDeserializer,
+        JsonSerializationOpts, JsonTypeDefinitionDeserializerMap, OptsRef,
+    },
+};
+
+/// Decodes raw instruction [data] using the provided [idl] without requiring the program id or
+/// accounts of the instruction, see [InstructionDecoder::decode_instruction_data].
+///
+/// - [idl] the IDL of the program that the instruction was sent to
+/// - [provider] the provider used to create the IDL, for shank and codama IDLs whose
+///   instructions don't declare a discriminant the index of the instruction is used as
+///   discriminator
+/// - [data] the raw instruction data including the discriminator
+/// - [opts] specifying how specific data types should be deserialized
+pub fn decode_instruction_data(
+    idl: &Idl,
+    provider: IdlProvider,
+    data: &[u8],
+    opts: &JsonSerializationOpts,
+) -> ChainparserResult<(String, String)> {
+    InstructionDecoder::new(idl, provider, opts)?.decode_instruction_data(data)
+}
+
+/// Decodes the instructions sent to a program using the instructions of its IDL.
+/// The deserializers for the types of the IDL are created once and reused for all instructions
+/// that are decoded with the same instance.
+pub struct InstructionDecoder<'opts> {
+    de_provider: DeserializeProvider,
+    provider: IdlProvider,
+    instructions: Vec<IdlInstruction>,
+    type_de_map: JsonTypeDefinitionDeserializerMap<'opts>,
+    opts: OptsRef<'opts>,
+}
+
+impl<'opts> InstructionDecoder<'opts> {
+    /// Creates an [InstructionDecoder] for the instructions of the provided [idl].
+    ///
+    /// - [idl] the IDL of the program that the instructions are sent to
+    /// - [provider] the provider used to create the IDL, for shank and codama IDLs whose
+    ///   instructions don't declare a discriminant the index of the instruction is used as
+    ///   discriminator
+    /// - [opts] specifying how specific data types should be deserialized
+    pub fn new(
+        idl: &Idl,
+        provider: IdlProvider,
+        opts: impl Into<OptsRef<'opts>>,
+    ) -> ChainparserResult<Self> {
+        let opts = opts.into();
+        let de_provider = DeserializeProvider::try_from(idl)?;
+        let type_de_map = Arc::new(Mutex::new(HashMap::new()));
+        for type_definition in &idl.types {
+            let instance = JsonIdlTypeDefinitionDeserializer::new(
+                type_definition,
+                type_de_map.clone(),
+                opts.clone(),
+            );
+            type_de_map
+                .lock()
+                .unwrap()
+                .insert(instance.name.clone(), instance);
+        }
+        Ok(Self {
+            de_provider,
+            provider,
+            instructions: idl.instructions.clone(),
+            type_de_map,
+            opts,
+        })
+    }
+
+    /// Decodes raw instruction [data].
+    /// The instruction is resolved by matching its discriminator against the start of the
+    /// [data] and its args are deserialized from the remaining bytes.
+    ///
+    /// Returns the name of the matched instruction and its args as JSON.
+    ///
+    /// - [data] the raw instruction data including the discriminator
+    pub fn decode_instruction_data(
+        &self,
+        data: &[u8],
+    ) -> ChainparserResult<(String, String)> {
+        let (idl_ix, disc) = find_best_matching_idl_ix(
+            &self.instructions,
+            data,
+            &self.provider,
+            DiscriminatorMatch::Exact,
+        )
+        .ok_or_else(|| {
+            ChainparserError::CannotFindInstructionForData(data.to_vec())
+        })?;
+
+        self.decode_instruction_args(idl_ix, &data[disc.len()..])
+            .map(|args| (idl_ix.name.to_string(), args))
+    }
+
+    /// The provider used to create the IDL that this decoder was created for.
+    pub fn provider(&self) -> &IdlProvider {
+        &self.provider
+    }
+
+    /// Deserializes the args of the [idl_ix] instruction from the [data] that follows its
+    /// discriminator and returns them as JSON.
+    pub(super) fn decode_instruction_args(
+        &self,
+        idl_ix: &IdlInstruction,
+        data: &[u8],
+    ) -> ChainparserResult<String> {
+        let ix_de = JsonIdlInstructionDeserializer::new(
+            idl_ix,
+            self.type_de_map.clone(),
+            self.opts.clone(),
+        );
+        let mut args = String::new();
+        let buf = &mut &data[..];
+        match &self.de_provider {
+            DeserializeProvider::Borsh(de) => {
+                ix_de.deserialize(de, &mut args, buf)
+            }
+            DeserializeProvider::Spl(de) => {
+                ix_de.deserialize(de, &mut args, buf)
+            }
+        }?;
+        Ok(args)
+    }
+}
+
+// The [type_de_map] can hold circular references and thus leaks memory if not cleared.
+impl Drop for InstructionDecoder<'_> {
+    fn drop(&mut self) {
+        self.type_de_map.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COUNTER_IDL_JSON: &str = r#"{
+  "version": "0.1.0",
+  "name": "counter",
+  "instructions": [
+    {
+      "name": "initialize",
+      "accounts": [],
+      "args": []
+    },
+    {
+      "name": "increment",
+      "accounts": [],
+      "args": [
+        { "name": "amount", "type": "u64" },
+        { "name": "label", "type": "string" }
+      ]
+    }
+  ]
+}"#;
+
+    #[test]
+    fn decode_anchor_instruction_data() {
+        let idl: Idl = serde_json::from_str(COUNTER_IDL_JSON).unwrap();
+        let opts = JsonSerializationOpts::default();
+        // discriminator for 'increment' followed by amount: 5 and label: "hi"
+        let data = [
+            11, 18, 104, 9, 104, 174, 59, 33, //
+            5, 0, 0, 0, 0, 0, 0, 0, //
+            2, 0, 0, 0, b'h', b'i',
+        ];
+        let (name, args) =
+            decode_instruction_data(&idl, IdlProvider::Anchor, &data, &opts)
+                .unwrap();
+        assert_eq!(name, "increment");
+        assert_eq!(args, r#"{"amount":5,"label":"hi"}"#);
+    }
+
+    #[test]
+    fn decode_shank_instruction_data_by_index() {
+        let idl: Idl = serde_json::from_str(COUNTER_IDL_JSON).unwrap();
+        let opts = JsonSerializationOpts::default();
+        let data = [1, 7, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        let (name, args) =
+            decode_instruction_data(&idl, IdlProvider::Shank, &data, &opts)
+                .unwrap();
+        assert_eq!(name, "increment");
+        assert_eq!(args, r#"{"amount":7,"label":""}"#);
+    }
+
+    #[test]
+    fn decode_instruction_data_with_opts() {
+        let idl: Idl = serde_json::from_str(COUNTER_IDL_JSON).unwrap();
+        let opts = JsonSerializationOpts {
+            skip_fields: ["label".to_string()].into(),
+            ..Default::default()
+        };
+        let decoder =
+            InstructionDecoder::new(&idl, IdlProvider::Shank, &opts).unwrap();
+        for amount in [7u8, 8] {
+            let data = [1, amount, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+            let (name, args) = decoder.decode_instruction_data(&data).unwrap();
+            assert_eq!(name, "increment");
+            assert_eq!(args, format!(r#"{{"amount":{amount}}}"#));
+        }
+    }
+
+    #[test]
+    fn decode_unknown_instruction_data() {
+        let idl: Idl = serde_json::from_str(COUNTER_IDL_JSON).unwrap();
+        let res = decode_instruction_data(
+            &idl,
+            IdlProvider::Anchor,
+            &[1, 2, 3, 4, 5, 6, 7, 8],
+            &JsonSerializationOpts::default(),
+        );
+        assert!(matches!(
+            res,
+            Err(ChainparserError::CannotFindInstructionForData(_))
+        ));
+    }
+}
@@ -0,0 +1,130 @@
+use solana_idl::{IdlInstruction, IdlTypeDefinition};
+
+use super::discriminator::discriminator_from_ix;
+use crate::{
+    errors::{ChainparserError, ChainparserResult},
+    json::JsonIdlTypeSerializer,
+    serializer::BorshSerializer,
+};
+
+/// Builds the data of an instruction from a JSON object holding the values of its args keyed
+/// by arg name.
+/// The data is prefixed with the instruction discriminator followed by the borsh serialized
+/// args.
+///
+/// - [idl_instruction] the IDL instruction to build the data for
+/// - [args_json] the JSON object of arg values, i.e. `{ "amount": 1, "memo": null }`
+pub fn encode_instruction_data(
+    idl_instruction: &IdlInstruction,
+    args_json: &str,
+) -> ChainparserResult<Vec<u8>> {
+    encode_instruction_data_with_types(idl_instruction, &[], args_json)
+}
+
+/// Same as [encode_instruction_data], but resolves args of defined types via the provided
+/// [types], i.e. the types of the IDL that declares the instruction.
+pub fn encode_instruction_data_with_types(
+    idl_instruction: &IdlInstruction,
+    types: &[IdlTypeDefinition],
+    args_json: &str,
+) -> ChainparserResult<Vec<u8>> {
+    let args = serde_json::from_str(args_json)?;
+    let mut buf = discriminator_from_ix(idl_instruction);
+    JsonIdlTypeSerializer::new(types)
+        .serialize_fields(
+            &BorshSerializer,
+            &idl_instruction.args,
+            &args,
+            &mut buf,
+        )
+        .map_err(|e| {
+            ChainparserError::InstructionSerializeError(
+                idl_instruction.name.to_string(),
+                Box::new(e),
+            )
+        })?;
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pubkey::Pubkey;
+
+    fn transfer_ix() -> IdlInstruction {
+        serde_json::from_str(
+            r#"{
+              "name": "transfer",
+              "accounts": [],
+              "args": [
+                { "name": "amount", "type": "u64" },
+                { "name": "recipient", "type": "publicKey" },
+                { "name": "memo", "type": { "option": "string" } },
+                { "name": "splits", "type": { "vec": "u16" } }
+              ]
+            }"#,
+        )
+        .expect("valid instruction")
+    }
+
+    #[test]
+    fn encode_transfer_ix() {
+        let ix = transfer_ix();
+        let recipient = Pubkey::new_unique();
+
+        let data = encode_instruction_data(
+            &ix,
+            &format!(
+                r#"{{
+                  "amount": 1000,
+                  "recipient": "{recipient}",
+                  "memo": "hi",
+                  "splits": [1, 2]
+                }}"#
+            ),
+        )
+        .unwrap();
+        let expected = [
+            discriminator_from_ix(&ix),
+            1000u64.to_le_bytes().to_vec(),
+            recipient.to_bytes().to_vec(),
+            vec![1, 2, 0, 0, 0],
+            b"hi".to_vec(),
+            vec![2, 0, 0, 0, 1, 0, 2, 0],
+        ]
+        .concat();
+        assert_eq!(data, expected);
+
+        let data = encode_instruction_data(
+            &ix,
+            &format!(
+                r#"{{
+                  "amount": "1000",
+                  "recipient": "{recipient}",
+                  "memo": null,
+                  "splits": []
+                }}"#
+            ),
+        )
+        .unwrap();
+        let expected = [
+            discriminator_from_ix(&ix),
+            1000u64.to_le_bytes().to_vec(),
+            recipient.to_bytes().to_vec(),
+            vec![0],
+            vec![0, 0, 0, 0],
+        ]
+        .concat();
+        assert_eq!(data, expected);
+    }
+
+    #[test]
+    fn encode_ix_with_invalid_args() {
+        let ix = transfer_ix();
+        let res = encode_instruction_data(&ix, r#"{ "amount": 1 }"#);
+        assert!(matches!(
+            res,
+            Err(ChainparserError::InstructionSerializeError(_, _))
+        ));
+    }
+}
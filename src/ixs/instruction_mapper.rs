@@ -3,9 +3,15 @@ use log::*;
 use std::{collections::HashMap, str::FromStr};
 
 use solana_idl::{Idl, IdlInstruction};
-use solana_sdk::pubkey::Pubkey;
 
-use super::{discriminator::discriminator_from_ix, ParseableInstruction};
+use super::{
+    discriminator::provider_discriminator_from_ix,
+    instruction_decoder::InstructionDecoder, ParseableInstruction,
+};
+use crate::{
+    errors::ChainparserResult, idl::IdlProvider, json::JsonSerializationOpts,
+    pubkey::Pubkey,
+};
 
 #[rustfmt::skip]
 lazy_static! {
@@ -46,100 +52,492 @@ pub fn map_instruction(
     InstructionMapper::map_accounts(instruction, idl)
 }
 
+/// Same as [map_instruction] but derives the instruction discriminators per the [provider] of
+/// the IDL, resolves the IDL instruction via the provided [discriminator_match] mode and
+/// deserializes its args per the provided [opts].
+pub fn map_instruction_with(
+    instruction: &impl ParseableInstruction,
+    idl: Option<&Idl>,
+    provider: IdlProvider,
+    discriminator_match: DiscriminatorMatch,
+    opts: &JsonSerializationOpts,
+) -> InstructionMapResult {
+    InstructionMapper::map_accounts_with(
+        instruction,
+        idl,
+        provider,
+        discriminator_match,
+        opts,
+    )
+}
+
+/// Same as [map_instruction_with] but reuses the provided [decoder] which was created for the
+/// [idl] to deserialize the args, see [InstructionMapper::map_accounts_with_decoder].
+pub fn map_instruction_with_decoder(
+    instruction: &impl ParseableInstruction,
+    idl: &Idl,
+    decoder: &InstructionDecoder,
+    discriminator_match: DiscriminatorMatch,
+) -> InstructionMapResult {
+    InstructionMapper::map_accounts_with_decoder(
+        instruction,
+        idl,
+        decoder,
+        discriminator_match,
+    )
+}
+
+/// Determines how the discriminator of an IDL instruction has to match the start of the
+/// instruction data in order for that IDL instruction to be selected.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DiscriminatorMatch {
+    /// Selects the instruction whose discriminator matches the most leading bytes of the data
+    /// even if it doesn't fully match.
+    #[default]
+    Prefix,
+    /// Only selects an instruction whose entire discriminator matches the leading bytes of the
+    /// data, preferring the longest discriminator if multiple match.
+    Exact,
+}
+
 pub struct InstructionMapper {
     idl_instruction: IdlInstruction,
+    discriminator: Vec<u8>,
 }
 
 pub struct InstructionMapResult {
     pub accounts: HashMap<Pubkey, String>,
+    /// The accounts of the instruction in the order in which they were provided, including
+    /// accounts that are passed more than once, along with their label if one was found.
+    pub ordered_accounts: Vec<(Pubkey, Option<String>)>,
     pub instruction_name: Option<String>,
     pub program_name: Option<String>,
+    /// The args of the instruction as JSON if its discriminator fully matched the start of the
+    /// instruction data or the error that occurred while deserializing the remaining data.
+    pub args_json: Option<ChainparserResult<String>>,
 }
 
 impl InstructionMapper {
     /// First determines which IDL to use via the [program_id] of the instruction.
     /// Then it finds the best matching IDL instruction for provided instruction and
     /// creates an entry for each account pubkey providing its name.
+    /// If the discriminator of that IDL instruction fully matches it also decodes its args.
     pub fn map_accounts(
         instruction: &impl ParseableInstruction,
         idl: Option<&Idl>,
     ) -> InstructionMapResult {
-        let mapper = idl
-            .as_ref()
-            .and_then(|idl| Self::determine_accounts_mapper(instruction, idl));
+        Self::map_accounts_with(
+            instruction,
+            idl,
+            IdlProvider::Anchor,
+            DiscriminatorMatch::default(),
+            &JsonSerializationOpts::default(),
+        )
+    }
+
+    /// Same as [InstructionMapper::map_accounts] but derives the instruction discriminators
+    /// per the [provider] of the IDL, finds the IDL instruction via the provided
+    /// [discriminator_match] mode and deserializes its args per the provided [opts].
+    ///
+    /// The deserializers for the types of the [idl] are created for each call, use
+    /// [InstructionMapper::map_accounts_with_decoder] when mapping many instructions.
+    pub fn map_accounts_with(
+        instruction: &impl ParseableInstruction,
+        idl: Option<&Idl>,
+        provider: IdlProvider,
+        discriminator_match: DiscriminatorMatch,
+        opts: &JsonSerializationOpts,
+    ) -> InstructionMapResult {
+        Self::map_accounts_impl(
+            instruction,
+            idl,
+            &provider,
+            discriminator_match,
+            |idl, idl_ix, data| {
+                InstructionDecoder::new(idl, provider.clone(), opts)?
+                    .decode_instruction_args(idl_ix, data)
+            },
+        )
+    }
+
+    /// Same as [InstructionMapper::map_accounts_with] but deserializes the args with the
+    /// provided [decoder] which has to be created for the [idl].
+    /// This way the deserializers for the types of the [idl] are reused for all instructions.
+    pub fn map_accounts_with_decoder(
+        instruction: &impl ParseableInstruction,
+        idl: &Idl,
+        decoder: &InstructionDecoder,
+        discriminator_match: DiscriminatorMatch,
+    ) -> InstructionMapResult {
+        Self::map_accounts_impl(
+            instruction,
+            Some(idl),
+            decoder.provider(),
+            discriminator_match,
+            |_, idl_ix, data| decoder.decode_instruction_args(idl_ix, data),
+        )
+    }
+
+    fn map_accounts_impl(
+        instruction: &impl ParseableInstruction,
+        idl: Option<&Idl>,
+        provider: &IdlProvider,
+        discriminator_match: DiscriminatorMatch,
+        decode_args: impl FnOnce(
+            &Idl,
+            &IdlInstruction,
+            &[u8],
+        ) -> ChainparserResult<String>,
+    ) -> InstructionMapResult {
+        let mapper = idl.as_ref().and_then(|idl| {
+            Self::determine_accounts_mapper(
+                instruction,
+                idl,
+                provider,
+                discriminator_match,
+            )
+        });
         let program_name = idl.as_ref().map(|idl| idl.name.to_string());
         let program_id = instruction.program_id();
 
         let mut accounts = HashMap::new();
+        let mut ordered_accounts = Vec::new();
         let mut instruction_name = None::<String>;
         let ix_accounts = instruction.accounts();
         for (idx, pubkey) in ix_accounts.into_iter().enumerate() {
+            let mut label = None::<String>;
             if let Some(name) = BUILTIN_PROGRAMS.get(&pubkey) {
-                accounts.insert(pubkey, name.to_string());
-                continue;
-            }
-            if let Some(program_name) = program_name.as_ref() {
-                if &pubkey == program_id {
-                    accounts.insert(pubkey, program_name.to_string());
-                    continue;
-                }
-            }
-            if let Some(mapper) = &mapper {
-                let name = mapper
+                label.replace(name.to_string());
+            } else if program_name.is_some() && &pubkey == program_id {
+                label.clone_from(&program_name);
+            } else if let Some(mapper) = &mapper {
+                label = mapper
                     .idl_instruction
                     .accounts
                     .get(idx)
                     .map(|x| x.name().to_string());
-                if let Some(name) = name {
-                    accounts.insert(pubkey, name);
-                }
                 instruction_name
                     .replace(mapper.idl_instruction.name.to_string());
             }
+            if let Some(label) = &label {
+                accounts.insert(pubkey, label.to_string());
+            }
+            ordered_accounts.push((pubkey, label));
         }
+        let args_json = idl.zip(mapper.as_ref()).and_then(|(idl, mapper)| {
+            let data = instruction
+                .data()
+                .strip_prefix(mapper.discriminator.as_slice())?;
+            Some(decode_args(idl, &mapper.idl_instruction, data))
+        });
         let program_name = idl.map(|x| x.name.to_string()).or_else(|| {
             BUILTIN_PROGRAMS.get(program_id).map(|x| x.to_string())
         });
 
         InstructionMapResult {
             accounts,
+            ordered_accounts,
             instruction_name,
             program_name,
+            args_json,
         }
     }
 
     fn determine_accounts_mapper(
         instruction: &impl ParseableInstruction,
         idl: &Idl,
+        provider: &IdlProvider,
+        discriminator_match: DiscriminatorMatch,
     ) -> Option<InstructionMapper> {
-        find_best_matching_idl_ix(&idl.instructions, instruction)
-            .map(|idl_instruction| InstructionMapper { idl_instruction })
+        find_best_matching_idl_ix(
+            &idl.instructions,
+            instruction.data(),
+            provider,
+            discriminator_match,
+        )
+        .map(|(idl_instruction, discriminator)| InstructionMapper {
+            idl_instruction: idl_instruction.clone(),
+            discriminator,
+        })
     }
 }
 
-fn find_best_matching_idl_ix(
-    ix_idls: &[IdlInstruction],
-    ix: &impl ParseableInstruction,
-) -> Option<IdlInstruction> {
+/// Finds the instruction whose discriminator best matches the start of the [data] per the
+/// [discriminator_match] mode, the discriminators are derived per [provider_discriminator_from_ix].
+/// If multiple instructions match equally well the first one wins, thus with
+/// [DiscriminatorMatch::Exact] the one with the longest discriminator is selected.
+/// Returns the instruction and its discriminator.
+pub(super) fn find_best_matching_idl_ix<'a>(
+    ix_idls: &'a [IdlInstruction],
+    data: &[u8],
+    provider: &IdlProvider,
+    discriminator_match: DiscriminatorMatch,
+) -> Option<(&'a IdlInstruction, Vec<u8>)> {
     let mut best_match = None;
     let mut best_match_score = 0;
-    for idl_ix in ix_idls {
-        let disc = discriminator_from_ix(idl_ix);
+    for (idx, idl_ix) in ix_idls.iter().enumerate() {
+        let disc = provider_discriminator_from_ix(idl_ix, idx, provider);
         trace!("Discriminator for '{}': {:?}", idl_ix.name, disc);
-        if disc.len() > ix.data().len() {
+        if disc.len() > data.len() {
             continue;
         }
         let mut score = 0;
-        for (a, b) in disc.iter().zip(ix.data()) {
+        for (a, b) in disc.iter().zip(data) {
             if a != b {
                 break;
             }
+            // Discriminators are matched as a contiguous prefix, bytes that happen to match
+            // after the first mismatch don't count.
             score += 1;
         }
+        if discriminator_match == DiscriminatorMatch::Exact
+            && score < disc.len()
+        {
+            continue;
+        }
         if score > best_match_score {
-            best_match = Some(idl_ix);
+            best_match = Some((idl_ix, disc));
             best_match_score = score;
         }
     }
-    best_match.cloned()
+    best_match
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ixs::discriminator::discriminator_from_ix;
+
+    const TRANSFER_IDL_JSON: &str = r#"{
+  "version": "0.1.0",
+  "name": "transfer",
+  "instructions": [
+    {
+      "name": "transfer",
+      "accounts": [
+        { "name": "from", "isMut": true, "isSigner": true },
+        { "name": "to", "isMut": true, "isSigner": false },
+        { "name": "systemProgram", "isMut": false, "isSigner": false }
+      ],
+      "args": [{ "name": "amount", "type": "u64" }]
+    }
+  ]
+}"#;
+
+    struct Instruction {
+        program_id: Pubkey,
+        accounts: Vec<Pubkey>,
+        data: Vec<u8>,
+    }
+
+    impl ParseableInstruction for Instruction {
+        fn program_id(&self) -> &Pubkey {
+            &self.program_id
+        }
+        fn accounts(&self) -> Vec<Pubkey> {
+            self.accounts.clone()
+        }
+        fn data(&self) -> &[u8] {
+            &self.data
+        }
+    }
+
+    #[test]
+    fn map_instruction_with_ordered_accounts_and_args() {
+        let idl: Idl = serde_json::from_str(TRANSFER_IDL_JSON).unwrap();
+        let payer = Pubkey::new_unique();
+        let system_program = Pubkey::default();
+        let data = [
+            discriminator_from_ix(&idl.instructions[0]),
+            9u64.to_le_bytes().to_vec(),
+        ]
+        .concat();
+        let ix = Instruction {
+            program_id: Pubkey::new_unique(),
+            accounts: vec![payer, payer, system_program],
+            data,
+        };
+
+        let result = map_instruction(&ix, Some(&idl));
+        assert_eq!(result.instruction_name.as_deref(), Some("transfer"));
+        assert_eq!(
+            result.ordered_accounts,
+            vec![
+                (payer, Some("from".to_string())),
+                (payer, Some("to".to_string())),
+                (system_program, Some("System Program".to_string())),
+            ]
+        );
+        assert_eq!(result.accounts.len(), 2);
+        assert_eq!(
+            result.args_json.unwrap().unwrap(),
+            r#"{"amount":9}"#.to_string()
+        );
+
+        let result = map_instruction(&ix, None);
+        assert_eq!(
+            result.ordered_accounts,
+            vec![
+                (payer, None),
+                (payer, None),
+                (system_program, Some("System Program".to_string())),
+            ]
+        );
+        assert!(result.args_json.is_none());
+    }
+
+    #[test]
+    fn map_shank_instruction_with_args_error() {
+        let idl: Idl = serde_json::from_str(TRANSFER_IDL_JSON).unwrap();
+        let payer = Pubkey::new_unique();
+        let ix = |data: Vec<u8>| Instruction {
+            program_id: Pubkey::new_unique(),
+            accounts: vec![payer],
+            data,
+        };
+        let map = |ix: &Instruction| {
+            map_instruction_with(
+                ix,
+                Some(&idl),
+                IdlProvider::Shank,
+                DiscriminatorMatch::Exact,
+                &JsonSerializationOpts::default(),
+            )
+        };
+
+        let result = map(&ix([vec![0], 9u64.to_le_bytes().to_vec()].concat()));
+        assert_eq!(result.instruction_name.as_deref(), Some("transfer"));
+        assert_eq!(
+            result.args_json.unwrap().unwrap(),
+            r#"{"amount":9}"#.to_string()
+        );
+
+        // The args are truncated
+        let result = map(&ix(vec![0, 9, 0]));
+        assert_eq!(result.instruction_name.as_deref(), Some("transfer"));
+        assert!(matches!(result.args_json, Some(Err(_))));
+
+        // Reusing one decoder for multiple instructions
+        let opts = JsonSerializationOpts::default();
+        let decoder =
+            InstructionDecoder::new(&idl, IdlProvider::Shank, &opts).unwrap();
+        for amount in [1u64, 2] {
+            let result = map_instruction_with_decoder(
+                &ix([vec![0], amount.to_le_bytes().to_vec()].concat()),
+                &idl,
+                &decoder,
+                DiscriminatorMatch::Exact,
+            );
+            assert_eq!(
+                result.args_json.unwrap().unwrap(),
+                format!(r#"{{"amount":{amount}}}"#)
+            );
+        }
+    }
+
+    const SHARED_PREFIX_IDL_JSON: &str = r#"{
+  "version": "0.1.0",
+  "name": "shared",
+  "instructions": [
+    {
+      "name": "open",
+      "accounts": [{ "name": "vault", "isMut": true, "isSigner": false }],
+      "args": [],
+      "discriminant": { "type": "u8", "value": 0, "bytes": [1, 2, 3, 4] }
+    },
+    {
+      "name": "close",
+      "accounts": [{ "name": "receiver", "isMut": true, "isSigner": false }],
+      "args": [],
+      "discriminant": { "type": "u8", "value": 0, "bytes": [1, 2, 9, 9] }
+    }
+  ]
+}"#;
+
+    #[test]
+    fn map_instruction_with_exact_discriminator_match() {
+        let idl: Idl = serde_json::from_str(SHARED_PREFIX_IDL_JSON).unwrap();
+        let vault = Pubkey::new_unique();
+        let ix = |data: Vec<u8>| Instruction {
+            program_id: Pubkey::new_unique(),
+            accounts: vec![vault],
+            data,
+        };
+
+        // Only shares a prefix with 'open'
+        let partial = ix(vec![1, 2, 3, 7]);
+        let result = map_instruction(&partial, Some(&idl));
+        assert_eq!(result.instruction_name.as_deref(), Some("open"));
+        let result = map_instruction_with(
+            &partial,
+            Some(&idl),
+            IdlProvider::Anchor,
+            DiscriminatorMatch::Exact,
+            &JsonSerializationOpts::default(),
+        );
+        assert_eq!(result.instruction_name, None);
+        assert_eq!(result.ordered_accounts, vec![(vault, None)]);
+
+        let exact = ix(vec![1, 2, 9, 9, 0]);
+        let result = map_instruction_with(
+            &exact,
+            Some(&idl),
+            IdlProvider::Anchor,
+            DiscriminatorMatch::Exact,
+            &JsonSerializationOpts::default(),
+        );
+        assert_eq!(result.instruction_name.as_deref(), Some("close"));
+        assert_eq!(
+            result.ordered_accounts,
+            vec![(vault, Some("receiver".to_string()))]
+        );
+    }
+
+    #[test]
+    fn best_matching_ix_scores_contiguous_prefix_only() {
+        let idl: Idl = serde_json::from_str(
+            &SHARED_PREFIX_IDL_JSON
+                .replace("[1, 2, 3, 4]", "[1, 2, 0, 0]")
+                .replace("[1, 2, 9, 9]", "[1, 9, 9, 9]"),
+        )
+        .unwrap();
+        let best_match = |data: Vec<u8>| {
+            find_best_matching_idl_ix(
+                &idl.instructions,
+                &data,
+                &IdlProvider::Anchor,
+                DiscriminatorMatch::Prefix,
+            )
+            .map(|(x, _)| x.name.clone())
+        };
+
+        // 'close' matches more bytes overall, but 'open' matches the longer prefix
+        assert_eq!(best_match(vec![1, 2, 9, 9]).as_deref(), Some("open"));
+        assert_eq!(best_match(vec![1, 9, 0, 0]).as_deref(), Some("close"));
+        // on equal prefixes the first instruction wins
+        assert_eq!(best_match(vec![1, 7, 7, 7]).as_deref(), Some("open"));
+        assert_eq!(best_match(vec![2, 2, 0, 0]), None);
+        // discriminators longer than the data are never selected
+        assert_eq!(best_match(vec![1, 2, 0]), None);
+    }
+
+    #[test]
+    fn best_matching_ix_by_shank_index() {
+        let idl: Idl = serde_json::from_str(TRANSFER_IDL_JSON).unwrap();
+        let best_match = |provider: IdlProvider| {
+            find_best_matching_idl_ix(
+                &idl.instructions,
+                &[0, 9, 0, 0, 0, 0, 0, 0, 0],
+                &provider,
+                DiscriminatorMatch::Exact,
+            )
+            .map(|(x, disc)| (x.name.clone(), disc))
+        };
+
+        assert_eq!(
+            best_match(IdlProvider::Shank),
+            Some(("transfer".into(), vec![0]))
+        );
+        assert_eq!(best_match(IdlProvider::Anchor), None);
+    }
 }
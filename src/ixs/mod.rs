@@ -1,6 +1,8 @@
-use solana_sdk::pubkey::Pubkey;
+use crate::pubkey::Pubkey;
 
 mod discriminator;
+mod instruction_decoder;
+mod instruction_encoder;
 mod instruction_mapper;
 
 pub trait ParseableInstruction {
@@ -9,6 +11,12 @@ pub trait ParseableInstruction {
     fn data(&self) -> &[u8];
 }
 
+pub use instruction_decoder::{decode_instruction_data, InstructionDecoder};
+pub use instruction_encoder::{
+    encode_instruction_data, encode_instruction_data_with_types,
+};
 pub use instruction_mapper::{
-    map_instruction, InstructionMapResult, InstructionMapper, BUILTIN_PROGRAMS,
+    map_instruction, map_instruction_with, map_instruction_with_decoder,
+    DiscriminatorMatch, InstructionMapResult, InstructionMapper,
+    BUILTIN_PROGRAMS,
 };
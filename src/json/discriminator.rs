@@ -1,18 +1,24 @@
-use std::{collections::HashMap, fmt::Write};
+use std::collections::HashMap;
 
+use log::warn;
 use solana_idl::{Idl, IdlTypeDefinition, IdlTypeDefinitionTy};
 
 use crate::{
     deserializer::DeserializeProvider,
     discriminator::{
-        account_discriminator, match_discriminator::MatchDiscriminators,
-        DiscriminatorBytes,
+        match_discriminator::{
+            AmbiguousMatchPolicy, MatchConfidence, MatchDiscriminators,
+        },
+        AnchorDiscriminatorStrategy, DiscriminatorBytes,
+        DiscriminatorCollision, DiscriminatorStrategy,
+        ANCHOR_ACCOUNT_NAMESPACE,
     },
     errors::{ChainparserError, ChainparserResult},
+    format::ChainparserVisitor,
     idl::IdlProvider,
     json::{
-        JsonIdlTypeDefinitionDeserializer, JsonSerializationOpts,
-        JsonTypeDefinitionDeserializerMap,
+        FieldInfo, JsonIdlTypeDefinitionDeserializer, JsonSerializationOpts,
+        JsonTypeDefinitionDeserializerMap, OptsRef,
     },
 };
 
@@ -21,19 +27,30 @@ use crate::{
 // -----------------
 
 /// This is the common way of resolving the account type for account data.
-/// It expects the first 8 bytes of data to hold the account discriminator as is the case for
-/// anchor accounts.
-/// This is what is used for Anchor accounts.
+/// It expects the first bytes of data to hold the account discriminator as is the case for
+/// anchor accounts which are prefixed with 8 bytes.
+/// This is what is used for Anchor accounts as well as for IDLs that declare the discriminator
+/// of each account, i.e. shank programs that prefix accounts with a 1 byte account kind tag.
 pub struct PrefixDiscriminator<'opts> {
     /// Allows looking up a account names by discriminator.
-    account_names: HashMap<DiscriminatorBytes, String>,
+    account_names: HashMap<Vec<u8>, String>,
+
+    /// The discriminator of each account keyed by account name.
+    discriminators: HashMap<String, Vec<u8>>,
 
     /// The deserializers for accounts of this program keyed by the discriminator of each account
     /// type.
-    deserializers:
-        HashMap<DiscriminatorBytes, JsonIdlTypeDefinitionDeserializer<'opts>>,
+    deserializers: HashMap<Vec<u8>, JsonIdlTypeDefinitionDeserializer<'opts>>,
 
     de_provider: DeserializeProvider,
+
+    /// Accounts whose discriminator clashed with the one of an account defined before them.
+    collisions: Vec<DiscriminatorCollision>,
+
+    /// The number of bytes of the discriminator that prefixes the account data.
+    width: usize,
+
+    opts: OptsRef<'opts>,
 }
 
 impl<'opts> PrefixDiscriminator<'opts> {
@@ -41,88 +58,325 @@ impl<'opts> PrefixDiscriminator<'opts> {
         de_provider: DeserializeProvider,
         accounts: &[IdlTypeDefinition],
         type_map: JsonTypeDefinitionDeserializerMap<'opts>,
-        opts: &'opts JsonSerializationOpts,
+        opts: impl Into<OptsRef<'opts>>,
+    ) -> Self {
+        Self::with_namespace(
+            de_provider,
+            accounts,
+            type_map,
+            opts,
+            ANCHOR_ACCOUNT_NAMESPACE,
+        )
+    }
+
+    /// Same as [PrefixDiscriminator::new], but derives the account discriminators with the
+    /// provided [namespace] instead of the anchor one.
+    pub fn with_namespace(
+        de_provider: DeserializeProvider,
+        accounts: &[IdlTypeDefinition],
+        type_map: JsonTypeDefinitionDeserializerMap<'opts>,
+        opts: impl Into<OptsRef<'opts>>,
+        namespace: &str,
+    ) -> Self {
+        Self::with_strategy(
+            de_provider,
+            accounts,
+            type_map,
+            opts,
+            &AnchorDiscriminatorStrategy::with_namespace(namespace),
+        )
+    }
+
+    /// Same as [PrefixDiscriminator::new], but derives the account discriminators via the
+    /// provided [strategy] instead of the anchor one.
+    pub fn with_strategy(
+        de_provider: DeserializeProvider,
+        accounts: &[IdlTypeDefinition],
+        type_map: JsonTypeDefinitionDeserializerMap<'opts>,
+        opts: impl Into<OptsRef<'opts>>,
+        strategy: &impl DiscriminatorStrategy,
+    ) -> Self {
+        let opts = opts.into();
+        let discriminators = accounts
+            .iter()
+            .map(|account| strategy.discriminator(&account.name).to_vec());
+        Self::from_discriminators(
+            de_provider,
+            accounts,
+            discriminators,
+            std::mem::size_of::<DiscriminatorBytes>(),
+            type_map,
+            opts,
+        )
+    }
+
+    /// Same as [PrefixDiscriminator::new], but uses the discriminators the IDL declares for
+    /// each account, keyed by account name in [declared], instead of deriving them.
+    /// Returns `None` unless all [accounts] declare a discriminator and all of them have the
+    /// same width since account data could not be resolved otherwise.
+    pub fn with_declared_discriminators(
+        de_provider: DeserializeProvider,
+        accounts: &[IdlTypeDefinition],
+        declared: &HashMap<String, Vec<u8>>,
+        type_map: JsonTypeDefinitionDeserializerMap<'opts>,
+        opts: impl Into<OptsRef<'opts>>,
+    ) -> Option<Self> {
+        let opts = opts.into();
+        let discriminators = accounts
+            .iter()
+            .map(|account| declared.get(&account.name).cloned())
+            .collect::<Option<Vec<_>>>()?;
+        let width = discriminators.first()?.len();
+        if width == 0 || discriminators.iter().any(|d| d.len() != width) {
+            return None;
+        }
+        Some(Self::from_discriminators(
+            de_provider,
+            accounts,
+            discriminators.into_iter(),
+            width,
+            type_map,
+            opts,
+        ))
+    }
+
+    fn from_discriminators(
+        de_provider: DeserializeProvider,
+        accounts: &[IdlTypeDefinition],
+        discriminators: impl Iterator<Item = Vec<u8>>,
+        width: usize,
+        type_map: JsonTypeDefinitionDeserializerMap<'opts>,
+        opts: impl Into<OptsRef<'opts>>,
     ) -> Self {
-        let mut by_name = HashMap::<String, DiscriminatorBytes>::new();
-        let mut deserializers = HashMap::<
-            DiscriminatorBytes,
-            JsonIdlTypeDefinitionDeserializer<'opts>,
-        >::new();
+        let opts = opts.into();
+        let mut account_names = HashMap::<Vec<u8>, String>::new();
+        let mut discriminators_by_name = HashMap::<String, Vec<u8>>::new();
+        let mut deserializers =
+            HashMap::<Vec<u8>, JsonIdlTypeDefinitionDeserializer<'opts>>::new();
+        let mut collisions = Vec::new();
+
+        for (account_definition, discriminator) in
+            accounts.iter().zip(discriminators)
+        {
+            discriminators_by_name
+                .entry(account_definition.name.clone())
+                .or_insert_with(|| discriminator.clone());
+
+            // The first account with a given discriminator wins, later ones would silently
+            // shadow it otherwise
+            if let Some(first) = account_names.get(&discriminator) {
+                warn!(
+                    "Account '{}' has the same discriminator {:?} as account '{}' and will be ignored",
+                    account_definition.name, discriminator, first
+                );
+                collisions.push(DiscriminatorCollision {
+                    discriminator,
+                    first: first.clone(),
+                    second: account_definition.name.clone(),
+                });
+                continue;
+            }
 
-        for account_definition in accounts {
             let type_deserializer =
                 JsonIdlTypeDefinitionDeserializer::<'opts>::new(
                     account_definition,
                     type_map.clone(),
-                    opts,
+                    opts.clone(),
                 );
 
-            // NOTE: for now we assume that one account doesn't reference another
-            //       thus we don't include it in the lookup map for nested types
-            //       Similarly for instruction args once we support them
-            let discriminator = account_discriminator(&account_definition.name);
-            deserializers.insert(discriminator, type_deserializer);
-            by_name.insert(account_definition.name.clone(), discriminator);
+            // NOTE: accounts embedded in other accounts are resolved via the [type_map] which
+            //       includes all accounts, these deserializers are only looked up by discriminator
+            deserializers.insert(discriminator.clone(), type_deserializer);
+            account_names
+                .insert(discriminator, account_definition.name.clone());
         }
 
-        let account_names = by_name
-            .iter()
-            .map(|(name, discriminator)| (*discriminator, name.clone()))
-            .collect();
-
         Self {
             de_provider,
             account_names,
+            discriminators: discriminators_by_name,
             deserializers,
+            collisions,
+            width,
+            opts,
         }
     }
 
+    /// The number of bytes of the discriminator that prefixes the account data.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
     /// Deserializes
-    pub fn deserialize_account_data<W: Write>(
+    pub fn deserialize_account_data<V: ChainparserVisitor>(
         &self,
         account_data: &mut &[u8],
-        f: &mut W,
+        visitor: &mut V,
     ) -> ChainparserResult<()> {
-        if account_data.len() < 8 {
+        if account_data.len() < self.width {
             return Err(
                 ChainparserError::AccountDataTooShortForDiscriminatorBytes(
                     account_data.len(),
-                    8,
+                    self.width,
                 ),
             );
         }
-        let discriminator = &account_data[..8];
+        let discriminator = &account_data[..self.width];
         let deserializer =
             self.deserializers.get(discriminator).ok_or_else(|| {
                 ChainparserError::UnknownDiscriminatedAccount(format!(
-                    "disciminator: {discriminator:?}"
+                    "discriminator: {discriminator:?}"
                 ))
             })?;
 
-        let data = &mut &account_data[8..];
-        deserialize(&self.de_provider, deserializer, f, data)
+        let data = &mut &account_data[self.width..];
+        let discriminator = self.opts.include_discriminator.then(|| {
+            discriminator.iter().map(|b| format!("{b:02x}")).collect()
+        });
+        let envelope = envelope(&self.opts, &deserializer.name, &discriminator);
+        deserialize(&self.de_provider, deserializer, visitor, data, &envelope)?;
+        // Advance past the discriminator and the account so callers can tell how many bytes
+        // were consumed
+        *account_data = data;
+        Ok(())
     }
 
-    pub fn deserialize_account_data_by_name<W: Write>(
+    pub fn deserialize_account_data_by_name<V: ChainparserVisitor>(
         &self,
         account_data: &mut &[u8],
         account_name: &str,
-        f: &mut W,
+        visitor: &mut V,
     ) -> ChainparserResult<()> {
-        let discriminator = account_discriminator(account_name);
+        let (discriminator, deserializer) = self.by_name(account_name)?;
+
+        let discriminator = self.opts.include_discriminator.then(|| {
+            discriminator.iter().map(|b| format!("{b:02x}")).collect()
+        });
+        let envelope = envelope(&self.opts, &deserializer.name, &discriminator);
+        deserialize(
+            &self.de_provider,
+            deserializer,
+            visitor,
+            account_data,
+            &envelope,
+        )
+    }
+
+    /// Describes the fields of the account with the given [account_name] deserialized from the
+    /// [account_data] which is expected to be prefixed with the discriminator.
+    /// The offsets of the fields include the discriminator.
+    pub fn deserialize_account_fields_by_name(
+        &self,
+        account_data: &[u8],
+        account_name: &str,
+    ) -> ChainparserResult<Vec<FieldInfo>> {
+        let (_, deserializer) = self.by_name(account_name)?;
+        let data = account_data.get(self.width..).ok_or(
+            ChainparserError::AccountDataTooShortForDiscriminatorBytes(
+                account_data.len(),
+                self.width,
+            ),
+        )?;
+        field_infos(&self.de_provider, deserializer, data, self.width)
+    }
+
+    /// Deserializes only the [selected] fields of the account whose discriminator prefixes the
+    /// [account_data].
+    pub fn deserialize_selected_fields(
+        &self,
+        account_data: &[u8],
+        selected: &[&str],
+    ) -> ChainparserResult<serde_json::Map<String, serde_json::Value>> {
+        if account_data.len() < self.width {
+            return Err(
+                ChainparserError::AccountDataTooShortForDiscriminatorBytes(
+                    account_data.len(),
+                    self.width,
+                ),
+            );
+        }
+        let discriminator = &account_data[..self.width];
         let deserializer =
-            self.deserializers.get(&discriminator).ok_or_else(|| {
-                ChainparserError::UnknownAccount(account_name.to_string())
+            self.deserializers.get(discriminator).ok_or_else(|| {
+                ChainparserError::UnknownDiscriminatedAccount(format!(
+                    "discriminator: {discriminator:?}"
+                ))
             })?;
+        selected_fields(
+            &self.de_provider,
+            deserializer,
+            &account_data[self.width..],
+            selected,
+        )
+    }
+
+    pub fn account_name(&self, discriminator: &[u8]) -> Option<&str> {
+        self.account_names.get(discriminator).map(|s| s.as_str())
+    }
 
-        deserialize(&self.de_provider, deserializer, f, account_data)
+    /// Accounts whose discriminator clashed with the one of another account of the IDL.
+    pub fn collisions(&self) -> &[DiscriminatorCollision] {
+        &self.collisions
     }
 
-    pub fn account_name(
+    pub fn deserializer_mut(
+        &mut self,
+        account_name: &str,
+    ) -> Option<&mut JsonIdlTypeDefinitionDeserializer<'opts>> {
+        let discriminator = self.discriminators.get(account_name)?;
+        self.deserializers.get_mut(discriminator)
+    }
+
+    /// Resolves the deserializer of the account whose discriminator prefixes the
+    /// [account_data] and advances it past the discriminator.
+    pub fn resolve_account(
         &self,
-        discriminator: &DiscriminatorBytes,
-    ) -> Option<&str> {
-        self.account_names.get(discriminator).map(|s| s.as_str())
+        account_data: &mut &[u8],
+    ) -> ChainparserResult<(
+        &DeserializeProvider,
+        &JsonIdlTypeDefinitionDeserializer<'opts>,
+    )> {
+        if account_data.len() < self.width {
+            return Err(
+                ChainparserError::AccountDataTooShortForDiscriminatorBytes(
+                    account_data.len(),
+                    self.width,
+                ),
+            );
+        }
+        let (discriminator, data) = account_data.split_at(self.width);
+        let deserializer =
+            self.deserializers.get(discriminator).ok_or_else(|| {
+                ChainparserError::UnknownDiscriminatedAccount(format!(
+                    "discriminator: {discriminator:?}"
+                ))
+            })?;
+        *account_data = data;
+        Ok((&self.de_provider, deserializer))
+    }
+
+    /// The discriminator of the account with the given [account_name].
+    pub fn discriminator(&self, account_name: &str) -> Option<&[u8]> {
+        self.discriminators.get(account_name).map(|d| d.as_slice())
+    }
+
+    /// Resolves the discriminator and deserializer of the account with the given
+    /// [account_name].
+    fn by_name(
+        &self,
+        account_name: &str,
+    ) -> ChainparserResult<(&[u8], &JsonIdlTypeDefinitionDeserializer<'opts>)>
+    {
+        self.discriminators
+            .get(account_name)
+            .and_then(|discriminator| {
+                self.deserializers
+                    .get(discriminator)
+                    .map(|de| (discriminator.as_slice(), de))
+            })
+            .ok_or_else(|| {
+                ChainparserError::UnknownAccount(account_name.to_string())
+            })
     }
 }
 
@@ -144,6 +398,11 @@ pub struct MatchDiscriminator<'opts> {
         HashMap<String, JsonIdlTypeDefinitionDeserializer<'opts>>,
 
     de_provider: DeserializeProvider,
+
+    /// How to proceed when multiple accounts match the account data equally well.
+    pub ambiguous_match_policy: AmbiguousMatchPolicy,
+
+    opts: OptsRef<'opts>,
 }
 
 impl<'opts> MatchDiscriminator<'opts> {
@@ -152,8 +411,9 @@ impl<'opts> MatchDiscriminator<'opts> {
         accounts: &[IdlTypeDefinition],
         type_map: &HashMap<String, &IdlTypeDefinitionTy>,
         type_de_map: JsonTypeDefinitionDeserializerMap<'opts>,
-        opts: &'opts JsonSerializationOpts,
+        opts: impl Into<OptsRef<'opts>>,
     ) -> Self {
+        let opts = opts.into();
         let discriminators = MatchDiscriminators::from((accounts, type_map));
         let mut deserializer_by_name =
             HashMap::<String, JsonIdlTypeDefinitionDeserializer<'opts>>::new();
@@ -162,7 +422,7 @@ impl<'opts> MatchDiscriminator<'opts> {
             let deserializer = JsonIdlTypeDefinitionDeserializer::<'opts>::new(
                 &disc.account,
                 type_de_map.clone(),
-                opts,
+                opts.clone(),
             );
             deserializer_by_name
                 .insert(disc.account_name().to_string(), deserializer);
@@ -171,13 +431,15 @@ impl<'opts> MatchDiscriminator<'opts> {
             de_provider,
             discriminators,
             deserializer_by_name,
+            ambiguous_match_policy: AmbiguousMatchPolicy::default(),
+            opts,
         }
     }
 
-    pub fn deserialize_account_data<W: Write>(
+    pub fn deserialize_account_data<V: ChainparserVisitor>(
         &self,
         account_data: &mut &[u8],
-        f: &mut W,
+        visitor: &mut V,
     ) -> ChainparserResult<()> {
         if account_data.is_empty() {
             return Err(
@@ -186,32 +448,168 @@ impl<'opts> MatchDiscriminator<'opts> {
                 ),
             );
         }
-        match self.discriminators.find_match_name(account_data) {
-            Some(name) => {
-                self.deserialize_account_data_by_name(account_data, name, f)
-            }
+        match self
+            .discriminators
+            .try_find_match_name(account_data, self.ambiguous_match_policy)?
+        {
+            Some(name) => self.deserialize_account_data_by_name(
+                account_data,
+                name,
+                visitor,
+            ),
             None => Err(ChainparserError::CannotFindDeserializerForAccount),
         }
     }
 
-    pub fn deserialize_account_data_by_name<W: Write>(
+    pub fn deserialize_account_data_by_name<V: ChainparserVisitor>(
         &self,
         account_data: &mut &[u8],
         account_name: &str,
-        f: &mut W,
+        visitor: &mut V,
     ) -> ChainparserResult<()> {
         match self.deserializer_by_name.get(account_name) {
             Some(deserializer) => {
-                deserialize(&self.de_provider, deserializer, f, account_data)
+                // Matched accounts have no discriminator bytes to include
+                let envelope = envelope(&self.opts, &deserializer.name, &None);
+                deserialize(
+                    &self.de_provider,
+                    deserializer,
+                    visitor,
+                    account_data,
+                    &envelope,
+                )
+            }
+            None => {
+                Err(ChainparserError::UnknownAccount(account_name.to_string()))
+            }
+        }
+    }
+
+    /// Describes the fields of the account with the given [account_name] deserialized from the
+    /// [account_data].
+    pub fn deserialize_account_fields_by_name(
+        &self,
+        account_data: &[u8],
+        account_name: &str,
+    ) -> ChainparserResult<Vec<FieldInfo>> {
+        match self.deserializer_by_name.get(account_name) {
+            Some(deserializer) => {
+                field_infos(&self.de_provider, deserializer, account_data, 0)
+            }
+            None => {
+                Err(ChainparserError::UnknownAccount(account_name.to_string()))
             }
+        }
+    }
+
+    /// Deserializes only the [selected] fields of the account matching the [account_data].
+    pub fn deserialize_selected_fields(
+        &self,
+        account_data: &[u8],
+        selected: &[&str],
+    ) -> ChainparserResult<serde_json::Map<String, serde_json::Value>> {
+        let account_name = self
+            .discriminators
+            .try_find_match_name(account_data, self.ambiguous_match_policy)?
+            .ok_or(ChainparserError::CannotFindDeserializerForAccount)?;
+        match self.deserializer_by_name.get(account_name) {
+            Some(deserializer) => selected_fields(
+                &self.de_provider,
+                deserializer,
+                account_data,
+                selected,
+            ),
             None => {
                 Err(ChainparserError::UnknownAccount(account_name.to_string()))
             }
         }
     }
 
+    /// Resolves the account name for the provided account data.
+    /// Returns `None` if the match is ambiguous and the [AmbiguousMatchPolicy] requires it not to
+    /// be.
     pub fn account_name(&self, account_data: &[u8]) -> Option<&str> {
-        self.discriminators.find_match_name(account_data)
+        self.discriminators
+            .try_find_match_name(account_data, self.ambiguous_match_policy)
+            .ok()
+            .flatten()
+    }
+
+    /// Same as [Self::account_name], but also returns how confident the match is.
+    pub fn account_name_with_confidence(
+        &self,
+        account_data: &[u8],
+    ) -> Option<(&str, MatchConfidence)> {
+        self.discriminators
+            .try_find_match_with_confidence(
+                account_data,
+                self.ambiguous_match_policy,
+            )
+            .ok()
+            .flatten()
+    }
+
+    /// Requires the byte at [offset] of the data of the account with the given [account_name]
+    /// to be [value] whenever the data extends that far.
+    /// Returns `false` if no such account is known.
+    pub fn add_tag_byte_matcher(
+        &mut self,
+        account_name: &str,
+        offset: usize,
+        value: u8,
+    ) -> bool {
+        self.discriminators
+            .add_tag_byte_matcher(account_name, offset, value)
+    }
+
+    /// Matches the [account] per its `repr(C, align(N))` layout, see
+    /// [MatchDiscriminators::set_repr_c].
+    pub fn set_repr_c(
+        &mut self,
+        account: &IdlTypeDefinition,
+        align: usize,
+        type_map: &HashMap<String, &IdlTypeDefinitionTy>,
+    ) {
+        self.discriminators.set_repr_c(account, align, type_map);
+    }
+
+    /// Configures whether accounts whose leading pubkey is on the curve are preferred over
+    /// equally well matching accounts.
+    pub fn set_pubkey_on_curve_tie_breaker(&mut self, enabled: bool) {
+        self.discriminators.set_pubkey_on_curve_tie_breaker(enabled);
+    }
+
+    /// Describes how the account for the [account_data] is resolved, i.e. to debug why an
+    /// account matched or didn't.
+    pub fn explain(&self, account_data: &[u8]) -> String {
+        self.discriminators
+            .explain(account_data, self.ambiguous_match_policy)
+    }
+
+    pub fn deserializer_mut(
+        &mut self,
+        account_name: &str,
+    ) -> Option<&mut JsonIdlTypeDefinitionDeserializer<'opts>> {
+        self.deserializer_by_name.get_mut(account_name)
+    }
+
+    /// Resolves the deserializer of the account matching the [account_data].
+    pub fn resolve_account(
+        &self,
+        account_data: &[u8],
+    ) -> ChainparserResult<(
+        &DeserializeProvider,
+        &JsonIdlTypeDefinitionDeserializer<'opts>,
+    )> {
+        let account_name = self
+            .discriminators
+            .try_find_match_name(account_data, self.ambiguous_match_policy)?
+            .ok_or(ChainparserError::CannotFindDeserializerForAccount)?;
+        let deserializer =
+            self.deserializer_by_name.get(account_name).ok_or_else(|| {
+                ChainparserError::UnknownAccount(account_name.to_string())
+            })?;
+        Ok((&self.de_provider, deserializer))
     }
 }
 
@@ -224,14 +622,116 @@ pub enum JsonAccountsDiscriminator<'opts> {
 }
 
 impl<'opts> JsonAccountsDiscriminator<'opts> {
+    /// Accounts whose discriminator clashed with the one of another account of the IDL.
+    /// Only applies to accounts resolved via a [PrefixDiscriminator].
+    pub fn collisions(&self) -> &[DiscriminatorCollision] {
+        match self {
+            Self::PrefixDiscriminator(disc) => disc.collisions(),
+            Self::MatchDiscriminator(_) => &[],
+        }
+    }
+
+    /// Describes the fields of the account with the given [account_name] deserialized from the
+    /// [account_data].
+    pub fn deserialize_account_fields_by_name(
+        &self,
+        account_data: &[u8],
+        account_name: &str,
+    ) -> ChainparserResult<Vec<FieldInfo>> {
+        match self {
+            Self::PrefixDiscriminator(disc) => disc
+                .deserialize_account_fields_by_name(account_data, account_name),
+            Self::MatchDiscriminator(disc) => disc
+                .deserialize_account_fields_by_name(account_data, account_name),
+        }
+    }
+
+    /// Deserializes only the [selected] fields of the account resolved from the
+    /// [account_data].
+    pub fn deserialize_selected_fields(
+        &self,
+        account_data: &[u8],
+        selected: &[&str],
+    ) -> ChainparserResult<serde_json::Map<String, serde_json::Value>> {
+        match self {
+            Self::PrefixDiscriminator(disc) => {
+                disc.deserialize_selected_fields(account_data, selected)
+            }
+            Self::MatchDiscriminator(disc) => {
+                disc.deserialize_selected_fields(account_data, selected)
+            }
+        }
+    }
+
+    /// Describes how the account for the [account_data] is resolved.
+    /// Returns `None` for accounts resolved via a [PrefixDiscriminator] since they are
+    /// identified by their discriminator bytes.
+    pub fn explain(&self, account_data: &[u8]) -> Option<String> {
+        match self {
+            Self::PrefixDiscriminator(_) => None,
+            Self::MatchDiscriminator(disc) => Some(disc.explain(account_data)),
+        }
+    }
+
+    /// Resolves the deserializer of the account from the [account_data] along with the
+    /// [DeserializeProvider] to use for it.
+    /// The [account_data] is advanced past the discriminator if it is prefixed with one.
+    pub fn resolve_account(
+        &self,
+        account_data: &mut &[u8],
+    ) -> ChainparserResult<(
+        &DeserializeProvider,
+        &JsonIdlTypeDefinitionDeserializer<'opts>,
+    )> {
+        match self {
+            Self::PrefixDiscriminator(disc) => {
+                disc.resolve_account(account_data)
+            }
+            Self::MatchDiscriminator(disc) => {
+                disc.resolve_account(account_data)
+            }
+        }
+    }
+
+    /// Provides mutable access to the deserializer of the account with the given [account_name].
+    pub fn deserializer_mut(
+        &mut self,
+        account_name: &str,
+    ) -> Option<&mut JsonIdlTypeDefinitionDeserializer<'opts>> {
+        match self {
+            Self::PrefixDiscriminator(disc) => {
+                disc.deserializer_mut(account_name)
+            }
+            Self::MatchDiscriminator(disc) => {
+                disc.deserializer_mut(account_name)
+            }
+        }
+    }
+
+    /// Picks how accounts are resolved from their data.
+    /// If the IDL declares a discriminator for each of its accounts, keyed by account name in
+    /// [declared], account data is expected to be prefixed with it regardless of the
+    /// [provider]. Otherwise anchor accounts are resolved via their derived discriminator and
+    /// shank as well as codama accounts by matching their shape.
     pub fn new(
         de_provider: DeserializeProvider,
         provider: IdlProvider,
         idl: &Idl,
+        declared: &HashMap<String, Vec<u8>>,
         type_map: &HashMap<String, &IdlTypeDefinitionTy>,
         type_de_map: JsonTypeDefinitionDeserializerMap<'opts>,
-        opts: &'opts JsonSerializationOpts,
+        opts: impl Into<OptsRef<'opts>>,
     ) -> Self {
+        let opts = opts.into();
+        if let Some(disc) = PrefixDiscriminator::with_declared_discriminators(
+            de_provider.clone(),
+            &idl.accounts,
+            declared,
+            type_de_map.clone(),
+            opts.clone(),
+        ) {
+            return Self::PrefixDiscriminator(disc);
+        }
         match provider {
             IdlProvider::Anchor => {
                 Self::PrefixDiscriminator(PrefixDiscriminator::new(
@@ -241,13 +741,17 @@ impl<'opts> JsonAccountsDiscriminator<'opts> {
                     opts,
                 ))
             }
-            _ => Self::MatchDiscriminator(MatchDiscriminator::new(
-                de_provider,
-                &idl.accounts,
-                type_map,
-                type_de_map,
-                opts,
-            )),
+            // Without declared discriminators we cannot tell how shank and codama accounts
+            // are prefixed, thus we need to resolve accounts by matching their shape
+            IdlProvider::Shank | IdlProvider::Codama => {
+                Self::MatchDiscriminator(MatchDiscriminator::new(
+                    de_provider,
+                    &idl.accounts,
+                    type_map,
+                    type_de_map,
+                    opts,
+                ))
+            }
         }
     }
 }
@@ -255,14 +759,75 @@ impl<'opts> JsonAccountsDiscriminator<'opts> {
 // -----------------
 // Helpers
 // -----------------
+/// Deserializes the account via the provided [deserializer].
 fn deserialize(
     de_provider: &DeserializeProvider,
     deserializer: &JsonIdlTypeDefinitionDeserializer,
-    f: &mut impl Write,
+    visitor: &mut impl ChainparserVisitor,
     data: &mut &[u8],
+    envelope: &[(&str, &str)],
 ) -> ChainparserResult<()> {
     match de_provider {
-        DeserializeProvider::Borsh(de) => deserializer.deserialize(de, f, data),
-        DeserializeProvider::Spl(de) => deserializer.deserialize(de, f, data),
+        DeserializeProvider::Borsh(de) => {
+            deserializer.deserialize_enveloped(de, visitor, data, envelope)
+        }
+        DeserializeProvider::Spl(de) => {
+            deserializer.deserialize_enveloped(de, visitor, data, envelope)
+        }
+    }
+}
+
+/// Collects the entries the [opts] require to be added in front of the account fields.
+fn envelope<'a>(
+    opts: &'a JsonSerializationOpts,
+    account_name: &'a str,
+    discriminator: &'a Option<String>,
+) -> Vec<(&'a str, &'a str)> {
+    let mut envelope = Vec::new();
+    if opts.include_account_name {
+        envelope.push((opts.account_name_key.as_str(), account_name));
+    }
+    if let Some(discriminator) = discriminator {
+        envelope
+            .push((opts.discriminator_key.as_str(), discriminator.as_str()));
+    }
+    envelope
+}
+
+/// Describes the fields of the account via the provided [deserializer], see
+/// [JsonIdlTypeDefinitionDeserializer::deserialize_field_infos].
+fn field_infos(
+    de_provider: &DeserializeProvider,
+    deserializer: &JsonIdlTypeDefinitionDeserializer,
+    data: &[u8],
+    base_offset: usize,
+) -> ChainparserResult<Vec<FieldInfo>> {
+    let data = &mut &data[..];
+    match de_provider {
+        DeserializeProvider::Borsh(de) => {
+            deserializer.deserialize_field_infos(de, data, base_offset)
+        }
+        DeserializeProvider::Spl(de) => {
+            deserializer.deserialize_field_infos(de, data, base_offset)
+        }
+    }
+}
+
+/// Deserializes only the [selected] fields of the account via the provided [deserializer], see
+/// [JsonIdlTypeDefinitionDeserializer::deserialize_selected_fields].
+fn selected_fields(
+    de_provider: &DeserializeProvider,
+    deserializer: &JsonIdlTypeDefinitionDeserializer,
+    data: &[u8],
+    selected: &[&str],
+) -> ChainparserResult<serde_json::Map<String, serde_json::Value>> {
+    let data = &mut &data[..];
+    match de_provider {
+        DeserializeProvider::Borsh(de) => {
+            deserializer.deserialize_selected_fields(de, data, selected)
+        }
+        DeserializeProvider::Spl(de) => {
+            deserializer.deserialize_selected_fields(de, data, selected)
+        }
     }
 }
@@ -1,20 +1,37 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt::Write,
-    sync::{Arc, Mutex},
+    ops::Range,
+    sync::{Arc, Mutex, OnceLock},
 };
 
-use solana_idl::{Idl, IdlTypeDefinitionTy};
+use log::warn;
+use solana_idl::{Idl, IdlConst, IdlTypeDefinition, IdlTypeDefinitionTy};
 
 use super::{
     discriminator::JsonAccountsDiscriminator, JsonTypeDefinitionDeserializerMap,
 };
 use crate::{
     deserializer::DeserializeProvider,
-    discriminator::discriminator_from_data,
-    errors::ChainparserResult,
-    idl::IdlProvider,
-    json::{JsonIdlTypeDefinitionDeserializer, JsonSerializationOpts},
+    discriminator::{
+        match_discriminator::{AmbiguousMatchPolicy, MatchConfidence},
+        AnchorDiscriminatorStrategy, DiscriminatorCollision,
+        DiscriminatorStrategy,
+    },
+    errors::{ChainparserError, ChainparserResult},
+    events::EventDecoder,
+    format::{ChainparserVisitor, JsonFormatWriter},
+    idl::{
+        declared_account_discriminators, field_offsets, field_offsets_repr_c,
+        parse_idl_json, verify_acyclic_types, IdlProvider,
+    },
+    json::{
+        rewrite_definition, FieldInfo, FmtJsonWriter,
+        JsonIdlTypeDefinitionDeserializer, JsonIdlTypeSerializer,
+        JsonTlvDeserializer, JsonWrite, LimitedJsonWriter, OptsRef,
+        PrefixDiscriminator, TypeRegistry, UnknownPolicy,
+    },
+    serializer::SerializeProvider,
 };
 
 /// Setup to  deserialize accounts for a given program. The accounts are expected to have been
@@ -27,28 +44,48 @@ pub struct JsonAccountsDeserializer<'opts> {
     pub discriminator: JsonAccountsDiscriminator<'opts>,
 
     /// The [JsonSerializationOpts] specifying how specific data types should be deserialized.
-    pub serialization_opts: &'opts JsonSerializationOpts,
+    pub serialization_opts: OptsRef<'opts>,
 
     /// Map of [JsonIdlTypeDefinitionDeserializer] for each type defined in the IDL.
     pub type_de_map: JsonTypeDefinitionDeserializerMap<'opts>,
+
+    /// The [Idl] this deserializer was derived from.
+    pub idl: Idl,
+
+    /// The keys under which the types of the IDL are stored inside the [type_de_map] by type
+    /// name if it is shared with other IDLs via a [TypeRegistry].
+    type_keys: Option<HashMap<String, String>>,
+
+    /// Accounts that are laid out per `repr(C)`, see [Self::set_type_repr_c].
+    repr_c_accounts: HashSet<String>,
+
+    /// Decodes the events of the [idl], created on first use, see [Self::event_decoder].
+    event_decoder: OnceLock<EventDecoder<'opts>>,
 }
 
 impl<'opts> JsonAccountsDeserializer<'opts> {
     /// Tries to create an [AccounbtDeserializer] by parsing the [Idl].
-    /// Fails if the IDL could not be parsed.
+    /// Fails if the IDL could not be parsed or includes types that reference themselves without
+    /// an indirection, see [verify_acyclic_types], as well as if the [serialization_opts]
+    /// conflict, see [JsonSerializationOpts::validate].
     ///
-    /// - [json} the IDL definition in JSON format
+    /// - [json} the IDL definition in classic or new anchor JSON format, see [parse_idl_json]
     /// - [provider] the provider used to create the IDL
     /// - [serialization_opts] specifying how specific data types should be deserialized.
     pub fn try_from_idl(
         json: &str,
         provider: IdlProvider,
-        serialization_opts: &'opts JsonSerializationOpts,
+        serialization_opts: impl Into<OptsRef<'opts>>,
     ) -> ChainparserResult<Self> {
-        let idl: Idl = serde_json::from_str(json)?;
+        let serialization_opts = serialization_opts.into();
+        serialization_opts.validate()?;
+        let idl = parse_idl_json(json)?;
+        verify_acyclic_types(&idl)?;
+        let declared = declared_account_discriminators(json);
         let de_resolver = DeserializeProvider::try_from(&idl)?;
-        Ok(Self::from_idl(
+        Ok(Self::from_idl_with_account_discriminators(
             &idl,
+            &declared,
             de_resolver,
             provider,
             serialization_opts,
@@ -56,7 +93,9 @@ impl<'opts> JsonAccountsDeserializer<'opts> {
     }
 
     /// Creates an [AccounbtDeserializer] from the provided [Idl]
-    /// Fails if the IDL could not be parsed.
+    /// Logs a warning if the IDL includes types that reference themselves without an
+    /// indirection, see [find_cyclic_types], since accounts containing them cannot be
+    /// deserialized.
     ///
     /// - [idl} the IDL definition
     /// - [de_provider] to be used to deserialize each account, i.e. Borsh
@@ -66,17 +105,42 @@ impl<'opts> JsonAccountsDeserializer<'opts> {
         idl: &Idl,
         de_provider: DeserializeProvider,
         provider: IdlProvider,
-        serialization_opts: &'opts JsonSerializationOpts,
+        serialization_opts: impl Into<OptsRef<'opts>>,
+    ) -> Self {
+        let serialization_opts = serialization_opts.into();
+        Self::from_idl_with_account_discriminators(
+            idl,
+            &HashMap::new(),
+            de_provider,
+            provider,
+            serialization_opts,
+        )
+    }
+
+    /// Same as [Self::from_idl], but resolves accounts via the discriminators that the IDL
+    /// declares for them, see [declared_account_discriminators].
+    /// If not all accounts declare one, they are resolved the same way as via
+    /// [Self::from_idl].
+    pub fn from_idl_with_account_discriminators(
+        idl: &Idl,
+        declared: &HashMap<String, Vec<u8>>,
+        de_provider: DeserializeProvider,
+        provider: IdlProvider,
+        serialization_opts: impl Into<OptsRef<'opts>>,
     ) -> Self {
+        let serialization_opts = serialization_opts.into();
+        warn_about_cyclic_types(idl);
         let type_de_map = Arc::new(Mutex::new(HashMap::new()));
         let mut type_map = HashMap::<String, &IdlTypeDefinitionTy>::new();
 
-        for type_definition in &idl.types {
+        // Accounts are included since programs may embed one account inside another.
+        // Types are added last so they win if an account shares their name.
+        for type_definition in idl.accounts.iter().chain(idl.types.iter()) {
             type_map.insert(type_definition.name.clone(), &type_definition.ty);
             let instance = JsonIdlTypeDefinitionDeserializer::new(
                 type_definition,
                 type_de_map.clone(),
-                serialization_opts,
+                serialization_opts.clone(),
             );
             type_de_map
                 .lock()
@@ -88,35 +152,212 @@ impl<'opts> JsonAccountsDeserializer<'opts> {
             de_provider,
             provider,
             idl,
+            declared,
             &type_map,
             type_de_map.clone(),
+            serialization_opts.clone(),
+        );
+
+        Self {
             serialization_opts,
+            discriminator,
+            type_de_map,
+            idl: idl.clone(),
+            type_keys: None,
+            repr_c_accounts: HashSet::new(),
+            event_decoder: OnceLock::new(),
+        }
+    }
+
+    /// Same as [Self::try_from_idl], but stores the deserializers of the IDL types inside the
+    /// [registry] in order to share them with other IDLs, see [TypeRegistry].
+    pub fn try_from_idl_with_type_registry(
+        json: &str,
+        provider: IdlProvider,
+        serialization_opts: impl Into<OptsRef<'opts>>,
+        registry: &mut TypeRegistry<'opts>,
+    ) -> ChainparserResult<Self> {
+        let serialization_opts = serialization_opts.into();
+        serialization_opts.validate()?;
+        let idl = parse_idl_json(json)?;
+        verify_acyclic_types(&idl)?;
+        let declared = declared_account_discriminators(json);
+        let de_resolver = DeserializeProvider::try_from(&idl)?;
+        Ok(Self::from_idl_with_type_registry(
+            &idl,
+            &declared,
+            de_resolver,
+            provider,
+            serialization_opts,
+            registry,
+        ))
+    }
+
+    /// Same as [Self::from_idl_with_account_discriminators], but stores the deserializers of the
+    /// IDL types inside the [registry] in order to share them with other IDLs, see
+    /// [TypeRegistry].
+    ///
+    /// NOTE: shared types cannot be configured via [Self::set_type_de_provider] nor
+    /// [Self::set_type_repr_c] since that would affect the other IDLs as well.
+    pub fn from_idl_with_type_registry(
+        idl: &Idl,
+        declared: &HashMap<String, Vec<u8>>,
+        de_provider: DeserializeProvider,
+        provider: IdlProvider,
+        serialization_opts: impl Into<OptsRef<'opts>>,
+        registry: &mut TypeRegistry<'opts>,
+    ) -> Self {
+        let serialization_opts = serialization_opts.into();
+        warn_about_cyclic_types(idl);
+        let definitions = idl
+            .accounts
+            .iter()
+            .chain(idl.types.iter())
+            .cloned()
+            .collect::<Vec<_>>();
+        let type_keys = registry.intern(&definitions);
+
+        // The account deserializers and matchers refer to types by their key in the registry
+        let mut shared_idl = idl.clone();
+        shared_idl.accounts = rewrite_definitions(&idl.accounts, &type_keys);
+        shared_idl.types = rewrite_definitions(&idl.types, &type_keys);
+        let mut type_map = HashMap::<String, &IdlTypeDefinitionTy>::new();
+        for type_definition in
+            shared_idl.accounts.iter().chain(shared_idl.types.iter())
+        {
+            type_map.insert(
+                type_keys[&type_definition.name].clone(),
+                &type_definition.ty,
+            );
+        }
+
+        let type_de_map = registry.type_de_map();
+        let discriminator = JsonAccountsDiscriminator::new(
+            de_provider,
+            provider,
+            &shared_idl,
+            declared,
+            &type_map,
+            type_de_map.clone(),
+            serialization_opts.clone(),
         );
 
         Self {
             serialization_opts,
             discriminator,
             type_de_map,
+            idl: idl.clone(),
+            type_keys: Some(type_keys),
+            repr_c_accounts: HashSet::new(),
+            event_decoder: OnceLock::new(),
         }
     }
 
     /// Deserializes an account from the provided data.
+    /// Data that doesn't match any account is handled per
+    /// [JsonSerializationOpts::unknown_account].
+    /// Fails with [ChainparserError::OutputTooLarge] once the JSON exceeds
+    /// [JsonSerializationOpts::max_output_bytes].
     pub fn deserialize_account_data<W: Write>(
         &self,
         account_data: &mut &[u8],
         f: &mut W,
+    ) -> ChainparserResult<()> {
+        self.deserialize_account_data_to_json_writer(
+            account_data,
+            &mut FmtJsonWriter(f),
+        )
+    }
+
+    /// Same as [Self::deserialize_account_data], but writes to a [JsonWrite] writer which is
+    /// flushed after each element of a `Vec` if it is [JsonWrite::STREAMING], i.e. to stream
+    /// large accounts.
+    pub fn deserialize_account_data_to_json_writer<W: JsonWrite>(
+        &self,
+        account_data: &mut &[u8],
+        f: &mut W,
+    ) -> ChainparserResult<()> {
+        match self.serialization_opts.max_output_bytes {
+            Some(max) => {
+                let mut f = LimitedJsonWriter::new(f, max);
+                let res = self.write_account_data(account_data, &mut f);
+                limit_output_error(res, &f, max)
+            }
+            None => self.write_account_data(account_data, f),
+        }
+    }
+
+    fn write_account_data<W: JsonWrite>(
+        &self,
+        account_data: &mut &[u8],
+        f: &mut W,
+    ) -> ChainparserResult<()> {
+        let mut w = JsonFormatWriter::new(f, &self.serialization_opts);
+        self.deserialize_account_data_with_visitor(account_data, &mut w)
+    }
+
+    /// Deserializes an account from the provided data and emits it to the [visitor], i.e. to
+    /// render it in a format other than JSON.
+    /// Accounts that cannot be resolved are handled per [JsonSerializationOpts::unknown_account].
+    pub fn deserialize_account_data_with_visitor<V: ChainparserVisitor>(
+        &self,
+        account_data: &mut &[u8],
+        visitor: &mut V,
     ) -> ChainparserResult<()> {
         use JsonAccountsDiscriminator::*;
-        match &self.discriminator {
+        let res = match &self.discriminator {
             PrefixDiscriminator(disc) => {
-                disc.deserialize_account_data(account_data, f)
+                disc.deserialize_account_data(account_data, visitor)
             }
             MatchDiscriminator(disc) => {
-                disc.deserialize_account_data(account_data, f)
+                disc.deserialize_account_data(account_data, visitor)
             }
+        };
+        match res {
+            Err(
+                e @ (ChainparserError::CannotFindDeserializerForAccount
+                | ChainparserError::UnknownDiscriminatedAccount(_)
+                | ChainparserError::AccountDataTooShortForDiscriminatorBytes(
+                    ..,
+                )),
+            ) => match self.serialization_opts.unknown_account {
+                UnknownPolicy::Error => Err(e),
+                UnknownPolicy::HexDump => {
+                    let data: String = account_data
+                        .iter()
+                        .map(|b| format!("{b:02x}"))
+                        .collect();
+                    visitor.begin_struct(2)?;
+                    visitor.field_name("_unknown")?;
+                    visitor.bool_value(true)?;
+                    visitor.field_name("data")?;
+                    visitor.str_value(&data)?;
+                    visitor.end_struct()?;
+                    *account_data = &[];
+                    Ok(())
+                }
+                UnknownPolicy::Skip => {
+                    visitor.null_value()?;
+                    *account_data = &[];
+                    Ok(())
+                }
+            },
+            res => res,
         }
     }
 
+    /// Same as [Self::deserialize_account_data], but returns the JSON along with the number of
+    /// bytes of the [account_data] that were consumed, including the discriminator.
+    pub fn deserialize_account_data_counted(
+        &self,
+        account_data: &[u8],
+    ) -> ChainparserResult<(String, usize)> {
+        let mut remaining = account_data;
+        let mut json = String::new();
+        self.deserialize_account_data(&mut remaining, &mut json)?;
+        Ok((json, account_data.len() - remaining.len()))
+    }
+
     /// Deserializes an account from the provided data.
     ///
     /// This method expects account data to **not** be prefixed with 8 bytes of discriminator data.
@@ -127,42 +368,525 @@ impl<'opts> JsonAccountsDeserializer<'opts> {
         account_data: &mut &[u8],
         account_name: &str,
         f: &mut W,
+    ) -> ChainparserResult<()> {
+        let mut f = FmtJsonWriter(f);
+        match self.serialization_opts.max_output_bytes {
+            Some(max) => {
+                let mut f = LimitedJsonWriter::new(&mut f, max);
+                let res = self.write_account_data_by_name(
+                    account_data,
+                    account_name,
+                    &mut f,
+                );
+                limit_output_error(res, &f, max)
+            }
+            None => self.write_account_data_by_name(
+                account_data,
+                account_name,
+                &mut f,
+            ),
+        }
+    }
+
+    fn write_account_data_by_name<W: JsonWrite>(
+        &self,
+        account_data: &mut &[u8],
+        account_name: &str,
+        f: &mut W,
     ) -> ChainparserResult<()> {
         use JsonAccountsDiscriminator::*;
+        let mut w = JsonFormatWriter::new(f, &self.serialization_opts);
         match &self.discriminator {
             PrefixDiscriminator(disc) => disc.deserialize_account_data_by_name(
                 account_data,
                 account_name,
-                f,
+                &mut w,
             ),
             MatchDiscriminator(disc) => disc.deserialize_account_data_by_name(
                 account_data,
                 account_name,
-                f,
+                &mut w,
+            ),
+        }
+    }
+
+    /// Deserializes the account with the given [account_name] from the [account_data] and
+    /// describes each of its fields via a [FieldInfo], i.e. to render them in an account viewer.
+    /// For accounts prefixed with a discriminator the [account_data] needs to include it and
+    /// the offsets of the fields account for it.
+    pub fn deserialize_account_fields(
+        &self,
+        account_name: &str,
+        account_data: &[u8],
+    ) -> ChainparserResult<Vec<FieldInfo>> {
+        self.discriminator
+            .deserialize_account_fields_by_name(account_data, account_name)
+    }
+
+    /// Deserializes the account resolved from the [account_data] but only includes the
+    /// [selected] top level fields in the returned object.
+    /// For accounts prefixed with a discriminator the [account_data] needs to include it.
+    pub fn deserialize_selected_fields(
+        &self,
+        account_data: &[u8],
+        selected: &[&str],
+    ) -> ChainparserResult<serde_json::Map<String, serde_json::Value>> {
+        self.discriminator
+            .deserialize_selected_fields(account_data, selected)
+    }
+
+    /// Serializes the [value] of an account with the given [account_name] back into account
+    /// data, prefixing it with the account discriminator for anchor accounts.
+    /// Bytes and scaled decimals are expected to be rendered per the [serialization_opts] of
+    /// this deserializer, see [JsonIdlTypeSerializer::with_opts].
+    pub fn serialize_account_data(
+        &self,
+        account_name: &str,
+        value: &serde_json::Value,
+    ) -> ChainparserResult<Vec<u8>> {
+        if !self.idl.accounts.iter().any(|acc| acc.name == account_name) {
+            return Err(ChainparserError::UnknownAccount(
+                account_name.to_string(),
+            ));
+        }
+        let mut buf = match &self.discriminator {
+            JsonAccountsDiscriminator::PrefixDiscriminator(disc) => disc
+                .discriminator(account_name)
+                .map(|d| d.to_vec())
+                .unwrap_or_default(),
+            JsonAccountsDiscriminator::MatchDiscriminator(_) => Vec::new(),
+        };
+        let type_ser = JsonIdlTypeSerializer::from_idl(&self.idl)
+            .with_opts(&self.serialization_opts);
+        match SerializeProvider::try_from(&self.idl)? {
+            SerializeProvider::Borsh(ser) => {
+                type_ser.serialize_defined(&ser, account_name, value, &mut buf)
+            }
+            SerializeProvider::Spl(ser) => {
+                type_ser.serialize_defined(&ser, account_name, value, &mut buf)
+            }
+        }?;
+        Ok(buf)
+    }
+
+    /// Configures the type with the given [type_name] to be deserialized via the provided
+    /// [de_provider] whenever it is referenced by an account or another type.
+    /// This supports accounts that nest types which were serialized differently than the
+    /// account itself.
+    pub fn set_type_de_provider(
+        &self,
+        type_name: &str,
+        de_provider: DeserializeProvider,
+    ) -> ChainparserResult<()> {
+        if self.type_keys.is_some() {
+            return Err(ChainparserError::SharedTypeCannotBeModified(
+                type_name.to_string(),
+            ));
+        }
+        let mut type_de_map = self.type_de_map.lock().unwrap();
+        let type_de = type_de_map.remove(type_name).ok_or_else(|| {
+            ChainparserError::CannotFindDefinedType(type_name.to_string())
+        })?;
+        type_de_map.insert(
+            type_name.to_string(),
+            type_de.with_de_provider(de_provider),
+        );
+        Ok(())
+    }
+
+    /// Makes the accounts and types of another program's [idl] available to the accounts and
+    /// types of this IDL, i.e. for programs that share a CPI interface.
+    /// They are referenced via `{ "defined": "<namespace>::<name>" }` where the namespace is
+    /// usually the name of the other IDL, see
+    /// [crate::ChainparserDeserializer::import_idl_types].
+    ///
+    /// NOTE: types cannot be imported into a type map that is shared via a [TypeRegistry].
+    pub fn import_types(
+        &self,
+        namespace: &str,
+        idl: &Idl,
+    ) -> ChainparserResult<()> {
+        if self.type_keys.is_some() {
+            return Err(ChainparserError::SharedTypeCannotBeModified(format!(
+                "{namespace}::*"
+            )));
+        }
+        let definitions = idl.accounts.iter().chain(idl.types.iter());
+        let keys = definitions
+            .clone()
+            .map(|def| (def.name.clone(), format!("{namespace}::{}", def.name)))
+            .collect::<HashMap<_, _>>();
+
+        // The imported types refer to each other via their namespaced names as well
+        let instances = definitions
+            .map(|def| {
+                let mut definition = rewrite_definition(def, &keys);
+                definition.name = keys[&def.name].clone();
+                JsonIdlTypeDefinitionDeserializer::new(
+                    &definition,
+                    self.type_de_map.clone(),
+                    self.serialization_opts.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut type_de_map = self.type_de_map.lock().unwrap();
+        for instance in instances {
+            type_de_map.insert(instance.name.clone(), instance);
+        }
+        Ok(())
+    }
+
+    /// Configures the account or type with the given [type_name] to be deserialized per the
+    /// `repr(C, align(N))` layout as is the case for zero-copy accounts.
+    /// Pass an [align] of `1` for types declared with plain `repr(C)`.
+    pub fn set_type_repr_c(
+        &mut self,
+        type_name: &str,
+        align: usize,
+    ) -> ChainparserResult<()> {
+        let mut found = false;
+        if let Some(account_de) = self.discriminator.deserializer_mut(type_name)
+        {
+            *account_de = account_de.clone().with_repr_c(align);
+            self.match_account_repr_c(type_name, align);
+            self.repr_c_accounts.insert(type_name.to_string());
+            found = true;
+        }
+        if self.type_keys.is_some() {
+            return if found {
+                Ok(())
+            } else {
+                Err(ChainparserError::SharedTypeCannotBeModified(
+                    type_name.to_string(),
+                ))
+            };
+        }
+
+        let mut type_de_map = self.type_de_map.lock().unwrap();
+        if let Some(type_de) = type_de_map.remove(type_name) {
+            type_de_map
+                .insert(type_name.to_string(), type_de.with_repr_c(align));
+            found = true;
+        }
+
+        if found {
+            Ok(())
+        } else {
+            Err(ChainparserError::CannotFindDefinedType(
+                type_name.to_string(),
+            ))
+        }
+    }
+
+    /// Matches the account with the given [account_name] per its `repr(C)` layout if accounts
+    /// are resolved by matching their shape.
+    fn match_account_repr_c(&mut self, account_name: &str, align: usize) {
+        let accounts = self.accounts();
+        let types = match &self.type_keys {
+            Some(keys) => rewrite_definitions(&self.idl.types, keys),
+            None => self.idl.types.clone(),
+        };
+        let type_key = |name: &str| match &self.type_keys {
+            Some(keys) => keys[name].clone(),
+            None => name.to_string(),
+        };
+        let type_map = accounts
+            .iter()
+            .chain(types.iter())
+            .map(|def| (type_key(&def.name), &def.ty))
+            .collect::<HashMap<_, _>>();
+
+        if let (
+            JsonAccountsDiscriminator::MatchDiscriminator(disc),
+            Some(account),
+        ) = (
+            &mut self.discriminator,
+            accounts.iter().find(|acc| acc.name == account_name),
+        ) {
+            disc.set_repr_c(account, align, &type_map);
+        }
+    }
+
+    /// Configures the account with the given [account_name] to be followed by a region of TLV
+    /// entries whose layouts are provided via [entries] keyed by their type discriminator.
+    pub fn set_account_tlv_entries(
+        &mut self,
+        account_name: &str,
+        entries: &HashMap<u16, IdlTypeDefinition>,
+    ) -> ChainparserResult<()> {
+        self.set_account_tlv_entries_at(account_name, 0, entries)
+    }
+
+    /// Same as [JsonAccountsDeserializer::set_account_tlv_entries], but skips [offset] bytes
+    /// between the end of the account struct and the first TLV entry.
+    pub fn set_account_tlv_entries_at(
+        &mut self,
+        account_name: &str,
+        offset: usize,
+        entries: &HashMap<u16, IdlTypeDefinition>,
+    ) -> ChainparserResult<()> {
+        let entries = match &self.type_keys {
+            Some(keys) => entries
+                .iter()
+                .map(|(ty, def)| (*ty, rewrite_definition(def, keys)))
+                .collect(),
+            None => entries.clone(),
+        };
+        let tlv = JsonTlvDeserializer::new(
+            &entries,
+            self.type_de_map.clone(),
+            self.serialization_opts.clone(),
+        )
+        .with_offset(offset);
+        let account_de = self
+            .discriminator
+            .deserializer_mut(account_name)
+            .ok_or_else(|| {
+                ChainparserError::UnknownAccount(account_name.to_string())
+            })?;
+        *account_de = account_de.clone().with_tlv(tlv);
+        Ok(())
+    }
+
+    /// Configures how to proceed when multiple accounts match the account data equally well.
+    /// Only applies to accounts that are resolved by matching their shape, i.e. shank accounts.
+    pub fn set_ambiguous_match_policy(&mut self, policy: AmbiguousMatchPolicy) {
+        if let JsonAccountsDiscriminator::MatchDiscriminator(disc) =
+            &mut self.discriminator
+        {
+            disc.ambiguous_match_policy = policy;
+        }
+    }
+
+    /// Configures whether accounts whose leading pubkey field holds a key on the ed25519 curve
+    /// are preferred when multiple accounts match the account data equally well.
+    /// Only applies to accounts that are resolved by matching their shape.
+    pub fn set_pubkey_on_curve_tie_breaker(&mut self, enabled: bool) {
+        if let JsonAccountsDiscriminator::MatchDiscriminator(disc) =
+            &mut self.discriminator
+        {
+            disc.set_pubkey_on_curve_tie_breaker(enabled);
+        }
+    }
+
+    /// Requires the byte at [offset] of the data of the account with the given [account_name]
+    /// to be [value] whenever the data extends that far, i.e. to tell apart Token-2022 accounts
+    /// with extensions by their account type.
+    /// Only applies to accounts that are resolved by matching their shape.
+    pub fn set_account_tag_byte(
+        &mut self,
+        account_name: &str,
+        offset: usize,
+        value: u8,
+    ) -> ChainparserResult<()> {
+        let found = match &mut self.discriminator {
+            JsonAccountsDiscriminator::MatchDiscriminator(disc) => {
+                disc.add_tag_byte_matcher(account_name, offset, value)
+            }
+            JsonAccountsDiscriminator::PrefixDiscriminator(_) => false,
+        };
+        if found {
+            Ok(())
+        } else {
+            Err(ChainparserError::UnknownAccount(account_name.to_string()))
+        }
+    }
+
+    /// Resolves accounts via discriminators derived with the provided [namespace] instead of
+    /// the anchor `account` namespace, i.e. for frameworks that hash `"state:<name>"`.
+    /// This applies to IDLs of any provider since their account data is then expected to be
+    /// prefixed with those discriminators.
+    ///
+    /// NOTE: this recreates the account deserializers, thus call it before configuring them.
+    pub fn set_account_discriminator_namespace(
+        &mut self,
+        namespace: &str,
+    ) -> ChainparserResult<()> {
+        self.set_account_discriminator_strategy(
+            &AnchorDiscriminatorStrategy::with_namespace(namespace),
+        )
+    }
+
+    /// Resolves accounts via discriminators derived with the provided [strategy], i.e. for
+    /// frameworks that hash account names differently than anchor.
+    /// This applies to IDLs of any provider since their account data is then expected to be
+    /// prefixed with those discriminators.
+    ///
+    /// NOTE: this recreates the account deserializers, thus call it before configuring them.
+    pub fn set_account_discriminator_strategy(
+        &mut self,
+        strategy: &impl DiscriminatorStrategy,
+    ) -> ChainparserResult<()> {
+        let de_provider = DeserializeProvider::try_from(&self.idl)?;
+        self.repr_c_accounts.clear();
+        self.discriminator = JsonAccountsDiscriminator::PrefixDiscriminator(
+            PrefixDiscriminator::with_strategy(
+                de_provider,
+                &self.accounts(),
+                self.type_de_map.clone(),
+                self.serialization_opts.clone(),
+                strategy,
             ),
+        );
+        Ok(())
+    }
+
+    /// The accounts of the IDL with references to types rewritten to their keys inside the
+    /// [TypeRegistry] if one is used.
+    fn accounts(&self) -> Vec<IdlTypeDefinition> {
+        match &self.type_keys {
+            Some(keys) => rewrite_definitions(&self.idl.accounts, keys),
+            None => self.idl.accounts.clone(),
         }
     }
 
+    /// Accounts of the IDL whose discriminators collide, i.e. due to duplicate account names.
+    /// For each collision only the account defined first can be deserialized.
+    pub fn discriminator_collisions(&self) -> &[DiscriminatorCollision] {
+        self.discriminator.collisions()
+    }
+
+    /// Finds the constant with the given [name] declared in the IDL.
+    pub fn idl_constant(&self, name: &str) -> Option<&IdlConst> {
+        self.idl.constants.iter().find(|c| c.name == name)
+    }
+
+    /// Resolves the byte range of each field of the fixed size account with the given
+    /// [account_name] inside its account data, see [field_offsets].
+    /// Accounts configured via [Self::set_type_repr_c] are resolved per their `repr(C)` layout,
+    /// see [field_offsets_repr_c].
+    /// For accounts prefixed with a discriminator the ranges are shifted past it.
+    pub fn field_offsets(
+        &self,
+        account_name: &str,
+    ) -> Option<Vec<(String, Range<usize>)>> {
+        let prefix = match &self.discriminator {
+            JsonAccountsDiscriminator::PrefixDiscriminator(disc) => {
+                disc.width()
+            }
+            JsonAccountsDiscriminator::MatchDiscriminator(_) => 0,
+        };
+        let offsets = if self.repr_c_accounts.contains(account_name) {
+            field_offsets_repr_c(&self.idl, account_name)?
+        } else {
+            field_offsets(&self.idl, account_name)?
+        };
+        Some(
+            offsets
+                .into_iter()
+                .map(|(name, range)| {
+                    (name, range.start + prefix..range.end + prefix)
+                })
+                .collect(),
+        )
+    }
+
+    /// Returns the [EventDecoder] for the events of the IDL which is created the first time it
+    /// is requested and reused afterwards.
+    pub fn event_decoder(&self) -> ChainparserResult<&EventDecoder<'opts>> {
+        if let Some(decoder) = self.event_decoder.get() {
+            return Ok(decoder);
+        }
+        let decoder =
+            EventDecoder::new(&self.idl, self.serialization_opts.clone())?;
+        Ok(self.event_decoder.get_or_init(|| decoder))
+    }
+
+    /// Describes how the account for the [account_data] is matched, see
+    /// [MatchDiscriminators::explain].
+    /// Returns `None` for accounts that are resolved via their discriminator bytes.
+    pub fn explain_account_match(&self, account_data: &[u8]) -> Option<String> {
+        self.discriminator.explain(account_data)
+    }
+
     /// Resolves the account name for the provided account data.
     pub fn account_name(&self, account_data: &[u8]) -> Option<&str> {
         use JsonAccountsDiscriminator::*;
         match &self.discriminator {
             PrefixDiscriminator(disc) => {
-                if account_data.len() < 8 {
-                    return None;
-                }
-                let discriminator =
-                    discriminator_from_data(&account_data[0..8]);
-                disc.account_name(&discriminator)
+                disc.account_name(account_data.get(..disc.width())?)
             }
             MatchDiscriminator(disc) => disc.account_name(account_data),
         }
     }
+
+    /// Resolves the account name for the provided account data along with how confident the
+    /// match is for accounts that are resolved by matching their shape, see [MatchConfidence].
+    /// Accounts resolved via their discriminator bytes have no confidence since they are
+    /// identified definitively.
+    pub fn account_name_with_confidence(
+        &self,
+        account_data: &[u8],
+    ) -> Option<(&str, Option<MatchConfidence>)> {
+        use JsonAccountsDiscriminator::*;
+        match &self.discriminator {
+            PrefixDiscriminator(disc) => disc
+                .account_name(account_data.get(..disc.width())?)
+                .map(|name| (name, None)),
+            MatchDiscriminator(disc) => disc
+                .account_name_with_confidence(account_data)
+                .map(|(name, confidence)| (name, Some(confidence))),
+        }
+    }
+
+    /// Resolves the account name for the provided account data along with the hex encoded
+    /// discriminator for accounts that are prefixed with one, see
+    /// [crate::ChainparserDeserializer::peek_account_name].
+    pub fn peek_account_name(
+        &self,
+        account_data: &[u8],
+    ) -> Option<(&str, Option<String>)> {
+        use JsonAccountsDiscriminator::*;
+        match &self.discriminator {
+            PrefixDiscriminator(disc) => {
+                let discriminator = account_data.get(..disc.width())?;
+                let name = disc.account_name(discriminator)?;
+                let hex =
+                    discriminator.iter().map(|b| format!("{b:02x}")).collect();
+                Some((name, Some(hex)))
+            }
+            MatchDiscriminator(disc) => {
+                disc.account_name(account_data).map(|name| (name, None))
+            }
+        }
+    }
+}
+
+/// Replaces the error of a deserialization that failed since its output exceeded the [max]
+/// size of the writer [f], since it surfaces as a plain [std::fmt::Error] otherwise.
+fn limit_output_error<W: JsonWrite>(
+    res: ChainparserResult<()>,
+    f: &LimitedJsonWriter<W>,
+    max: usize,
+) -> ChainparserResult<()> {
+    match res {
+        Err(_) if f.exceeded() => Err(ChainparserError::OutputTooLarge(max)),
+        res => res,
+    }
+}
+
+fn warn_about_cyclic_types(idl: &Idl) {
+    if let Err(err) = verify_acyclic_types(idl) {
+        warn!("{err}");
+    }
+}
+
+fn rewrite_definitions(
+    definitions: &[IdlTypeDefinition],
+    keys: &HashMap<String, String>,
+) -> Vec<IdlTypeDefinition> {
+    definitions
+        .iter()
+        .map(|def| rewrite_definition(def, keys))
+        .collect()
 }
 
 // The [type_de_map] can hold circular references and thus leaks memory if not cleared.
+// A map shared via a [TypeRegistry] is cleared by the registry instead.
 impl Drop for JsonAccountsDeserializer<'_> {
     fn drop(&mut self) {
-        self.type_de_map.lock().unwrap().clear();
+        if self.type_keys.is_none() {
+            self.type_de_map.lock().unwrap().clear();
+        }
     }
 }
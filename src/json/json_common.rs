@@ -1,34 +1,796 @@
-use std::fmt::Write;
+use std::collections::HashMap;
 
-use super::json_idl_field_de::JsonIdlFieldDeserializer;
-use crate::{deserializer::ChainparserDeserialize, errors::ChainparserResult};
+use base64::{prelude::BASE64_STANDARD, Engine as _};
+use solana_idl::IdlType;
 
-pub fn deserialize_fields_to_object<W: Write>(
+use super::{
+    json_idl_field_de::JsonIdlFieldDeserializer, BytesRepr, FieldInfo,
+    FieldKind, JsonTypeDefinitionDeserializerMap, NonFiniteRepr, TypeMapKind,
+};
+use crate::{
+    deserializer::ChainparserDeserialize,
+    errors::{ChainparserError, ChainparserResult},
+    format::{ChainparserVisitor, JsonValueWriter},
+    pubkey::Pubkey,
+};
+
+/// Deserializes the [fields] as a struct.
+pub fn deserialize_fields_to_object<V: ChainparserVisitor, K: TypeMapKind>(
     de: &impl ChainparserDeserialize,
-    f: &mut W,
+    visitor: &mut V,
+    buf: &mut &[u8],
+    fields: &[JsonIdlFieldDeserializer<'_, K>],
+) -> ChainparserResult<()> {
+    visitor.begin_struct(emitted_fields(fields))?;
+    deserialize_fields(de, visitor, buf, fields)?;
+    visitor.end_struct()
+}
+
+/// Deserializes the [fields] as the entries of a struct without beginning or ending it.
+pub fn deserialize_fields<V: ChainparserVisitor, K: TypeMapKind>(
+    de: &impl ChainparserDeserialize,
+    visitor: &mut V,
     buf: &mut &[u8],
-    fields: &[JsonIdlFieldDeserializer<'_>],
+    fields: &[JsonIdlFieldDeserializer<'_, K>],
 ) -> ChainparserResult<()> {
-    f.write_char('{')?;
+    let mut field_values = FieldValues::new(fields);
+    for field_de in fields {
+        field_values.deserialize_entry(
+            de,
+            visitor,
+            buf,
+            field_de,
+            !field_de.skip,
+        )?;
+    }
+    Ok(())
+}
+
+/// The number of the [fields] that are included in the output, see
+/// [JsonIdlFieldDeserializer::skip].
+pub fn emitted_fields<K: TypeMapKind>(
+    fields: &[JsonIdlFieldDeserializer<'_, K>],
+) -> usize {
+    fields.iter().filter(|field| !field.skip).count()
+}
+
+/// Same as [deserialize_fields_to_object], but only includes the fields whose name is part of
+/// [selected] while the remaining fields are consumed from the [buf] without being emitted.
+/// Fields following the last selected field are not consumed at all.
+/// When [repr_c_aligns] holds the alignment of each field and of the struct the padding that
+/// aligns them is skipped.
+pub fn deserialize_selected_fields<V: ChainparserVisitor, K: TypeMapKind>(
+    de: &impl ChainparserDeserialize,
+    visitor: &mut V,
+    buf: &mut &[u8],
+    fields: &[JsonIdlFieldDeserializer<'_, K>],
+    selected: &[&str],
+    repr_c_aligns: Option<(&[usize], usize)>,
+) -> ChainparserResult<()> {
+    let is_selected = |field: &JsonIdlFieldDeserializer<'_, K>| {
+        selected.contains(&field.name.as_str()) && !field.skip
+    };
+    let Some(last) = fields.iter().rposition(is_selected) else {
+        visitor.begin_struct(0)?;
+        return visitor.end_struct();
+    };
+
+    let start_len = buf.len();
+    let mut field_values = FieldValues::new(fields);
+    visitor.begin_struct(fields.iter().filter(|f| is_selected(f)).count())?;
+    for (i, field_de) in fields[..=last].iter().enumerate() {
+        if let Some((field_aligns, _)) = repr_c_aligns {
+            skip_padding(buf, start_len - buf.len(), field_aligns[i])?;
+        }
+        field_values.deserialize_entry(
+            de,
+            visitor,
+            buf,
+            field_de,
+            is_selected(field_de),
+        )?;
+    }
+    visitor.end_struct()
+}
+
+/// Ignores all values visited, used to consume fields that aren't included in the output, see
+/// [JsonIdlFieldDeserializer::skip], as well as to find the bytes that a value takes up.
+pub(crate) struct DiscardVisitor;
+
+impl ChainparserVisitor for DiscardVisitor {
+    fn begin_struct(&mut self, _len: usize) -> ChainparserResult<()> {
+        Ok(())
+    }
+    fn field_name(&mut self, _name: &str) -> ChainparserResult<()> {
+        Ok(())
+    }
+    fn end_struct(&mut self) -> ChainparserResult<()> {
+        Ok(())
+    }
+    fn begin_seq(&mut self, _len: usize) -> ChainparserResult<()> {
+        Ok(())
+    }
+    fn end_seq(&mut self) -> ChainparserResult<()> {
+        Ok(())
+    }
+    fn begin_map(&mut self, _len: usize) -> ChainparserResult<()> {
+        Ok(())
+    }
+    fn begin_map_key(&mut self) -> ChainparserResult<()> {
+        Ok(())
+    }
+    fn end_map_key(&mut self) -> ChainparserResult<()> {
+        Ok(())
+    }
+    fn end_map(&mut self) -> ChainparserResult<()> {
+        Ok(())
+    }
+    fn null_value(&mut self) -> ChainparserResult<()> {
+        Ok(())
+    }
+    fn bool_value(&mut self, _b: bool) -> ChainparserResult<()> {
+        Ok(())
+    }
+    fn u32_value(&mut self, _n: u32) -> ChainparserResult<()> {
+        Ok(())
+    }
+    fn u64_value(&mut self, _n: u64) -> ChainparserResult<()> {
+        Ok(())
+    }
+    fn u128_value(&mut self, _n: u128) -> ChainparserResult<()> {
+        Ok(())
+    }
+    fn i32_value(&mut self, _n: i32) -> ChainparserResult<()> {
+        Ok(())
+    }
+    fn i64_value(&mut self, _n: i64) -> ChainparserResult<()> {
+        Ok(())
+    }
+    fn i128_value(&mut self, _n: i128) -> ChainparserResult<()> {
+        Ok(())
+    }
+    fn f64_value(&mut self, _n: f64) -> ChainparserResult<()> {
+        Ok(())
+    }
+    fn str_value(&mut self, _s: &str) -> ChainparserResult<()> {
+        Ok(())
+    }
+    fn pubkey_value(&mut self, _pubkey: &Pubkey) -> ChainparserResult<()> {
+        Ok(())
+    }
+    fn bytes_value(&mut self, _bytes: &[u8]) -> ChainparserResult<()> {
+        Ok(())
+    }
+}
+
+/// A value visited by the [BufferVisitor].
+enum VisitEvent {
+    BeginStruct(usize),
+    FieldName(String),
+    EndStruct,
+    BeginSeq(usize),
+    EndSeq,
+    BeginMap(usize),
+    BeginMapKey,
+    EndMapKey,
+    EndMap,
+    Null,
+    Bool(bool),
+    U32(u32),
+    U64(u64),
+    U128(u128),
+    I32(i32),
+    I64(i64),
+    I128(i128),
+    F32(f32),
+    F64(f64),
+    Str(String),
+    Pubkey(Pubkey),
+    Bytes(Vec<u8>),
+}
+
+/// Buffers the values visited so that they can be [BufferVisitor::replay]ed to another visitor
+/// once it is known that a value deserializes or dropped otherwise.
+#[derive(Default)]
+pub(crate) struct BufferVisitor {
+    events: Vec<VisitEvent>,
+}
+
+impl BufferVisitor {
+    /// Visits the buffered values with the [visitor] in the order they were visited.
+    pub(crate) fn replay(
+        self,
+        visitor: &mut impl ChainparserVisitor,
+    ) -> ChainparserResult<()> {
+        use VisitEvent::*;
+        for event in self.events {
+            match event {
+                BeginStruct(len) => visitor.begin_struct(len),
+                FieldName(name) => visitor.field_name(&name),
+                EndStruct => visitor.end_struct(),
+                BeginSeq(len) => visitor.begin_seq(len),
+                EndSeq => visitor.end_seq(),
+                BeginMap(len) => visitor.begin_map(len),
+                BeginMapKey => visitor.begin_map_key(),
+                EndMapKey => visitor.end_map_key(),
+                EndMap => visitor.end_map(),
+                Null => visitor.null_value(),
+                Bool(b) => visitor.bool_value(b),
+                U32(n) => visitor.u32_value(n),
+                U64(n) => visitor.u64_value(n),
+                U128(n) => visitor.u128_value(n),
+                I32(n) => visitor.i32_value(n),
+                I64(n) => visitor.i64_value(n),
+                I128(n) => visitor.i128_value(n),
+                F32(n) => visitor.f32_value(n),
+                F64(n) => visitor.f64_value(n),
+                Str(s) => visitor.str_value(&s),
+                Pubkey(pubkey) => visitor.pubkey_value(&pubkey),
+                Bytes(bytes) => visitor.bytes_value(&bytes),
+            }?;
+        }
+        Ok(())
+    }
 
+    fn push(&mut self, event: VisitEvent) -> ChainparserResult<()> {
+        self.events.push(event);
+        Ok(())
+    }
+}
+
+impl ChainparserVisitor for BufferVisitor {
+    fn begin_struct(&mut self, len: usize) -> ChainparserResult<()> {
+        self.push(VisitEvent::BeginStruct(len))
+    }
+    fn field_name(&mut self, name: &str) -> ChainparserResult<()> {
+        self.push(VisitEvent::FieldName(name.to_string()))
+    }
+    fn end_struct(&mut self) -> ChainparserResult<()> {
+        self.push(VisitEvent::EndStruct)
+    }
+    fn begin_seq(&mut self, len: usize) -> ChainparserResult<()> {
+        self.push(VisitEvent::BeginSeq(len))
+    }
+    fn end_seq(&mut self) -> ChainparserResult<()> {
+        self.push(VisitEvent::EndSeq)
+    }
+    fn begin_map(&mut self, len: usize) -> ChainparserResult<()> {
+        self.push(VisitEvent::BeginMap(len))
+    }
+    fn begin_map_key(&mut self) -> ChainparserResult<()> {
+        self.push(VisitEvent::BeginMapKey)
+    }
+    fn end_map_key(&mut self) -> ChainparserResult<()> {
+        self.push(VisitEvent::EndMapKey)
+    }
+    fn end_map(&mut self) -> ChainparserResult<()> {
+        self.push(VisitEvent::EndMap)
+    }
+    fn null_value(&mut self) -> ChainparserResult<()> {
+        self.push(VisitEvent::Null)
+    }
+    fn bool_value(&mut self, b: bool) -> ChainparserResult<()> {
+        self.push(VisitEvent::Bool(b))
+    }
+    fn u32_value(&mut self, n: u32) -> ChainparserResult<()> {
+        self.push(VisitEvent::U32(n))
+    }
+    fn u64_value(&mut self, n: u64) -> ChainparserResult<()> {
+        self.push(VisitEvent::U64(n))
+    }
+    fn u128_value(&mut self, n: u128) -> ChainparserResult<()> {
+        self.push(VisitEvent::U128(n))
+    }
+    fn i32_value(&mut self, n: i32) -> ChainparserResult<()> {
+        self.push(VisitEvent::I32(n))
+    }
+    fn i64_value(&mut self, n: i64) -> ChainparserResult<()> {
+        self.push(VisitEvent::I64(n))
+    }
+    fn i128_value(&mut self, n: i128) -> ChainparserResult<()> {
+        self.push(VisitEvent::I128(n))
+    }
+    fn f32_value(&mut self, n: f32) -> ChainparserResult<()> {
+        self.push(VisitEvent::F32(n))
+    }
+    fn f64_value(&mut self, n: f64) -> ChainparserResult<()> {
+        self.push(VisitEvent::F64(n))
+    }
+    fn str_value(&mut self, s: &str) -> ChainparserResult<()> {
+        self.push(VisitEvent::Str(s.to_string()))
+    }
+    fn pubkey_value(&mut self, pubkey: &Pubkey) -> ChainparserResult<()> {
+        self.push(VisitEvent::Pubkey(*pubkey))
+    }
+    fn bytes_value(&mut self, bytes: &[u8]) -> ChainparserResult<()> {
+        self.push(VisitEvent::Bytes(bytes.to_vec()))
+    }
+}
+
+/// Deserializes the [fields] one by one and describes each of them via a [FieldInfo].
+/// When [repr_c_aligns] holds the alignment of each field and of the struct the padding that
+/// aligns them is skipped.
+/// The [base_offset] is the offset of the struct inside the account data.
+pub fn deserialize_field_infos<K: TypeMapKind>(
+    de: &impl ChainparserDeserialize,
+    buf: &mut &[u8],
+    fields: &[JsonIdlFieldDeserializer<'_, K>],
+    repr_c_aligns: Option<(&[usize], usize)>,
+    type_map: &JsonTypeDefinitionDeserializerMap<'_, K>,
+    base_offset: usize,
+) -> ChainparserResult<Vec<FieldInfo>> {
+    let start_len = buf.len();
+    let mut field_values = FieldValues::new(fields);
+    let mut infos = Vec::with_capacity(fields.len());
     for (i, field_de) in fields.iter().enumerate() {
-        field_de.deserialize(de, f, buf)?;
-        if (i + 1) < fields.len() {
-            f.write_char(',')?;
+        if let Some((field_aligns, _)) = repr_c_aligns {
+            skip_padding(buf, start_len - buf.len(), field_aligns[i])?;
         }
+        let offset = start_len - buf.len();
+
+        // The name the field emits ahead of its value is ignored outside of a struct
+        let mut w = JsonValueWriter::new(&field_de.ty_deserealizer.opts);
+        field_values.deserialize_field(de, &mut w, buf, field_de)?;
+        let value = w.into_value().unwrap_or_default();
+
+        let kind = K::with_map(type_map, |type_map| {
+            FieldKind::of_field(field_de, type_map)
+        });
+        infos.push(FieldInfo {
+            name: field_de.name.clone(),
+            ty: field_de.ty.clone(),
+            value,
+            offset: base_offset + offset,
+            len: start_len - buf.len() - offset,
+            kind,
+        });
     }
+    if let Some((_, struct_align)) = repr_c_aligns {
+        skip_padding(buf, start_len - buf.len(), struct_align)?;
+    }
+    Ok(infos)
+}
 
-    f.write_char('}')?;
+/// Tracks the values of fields that other fields of the same struct refer to, i.e. the head
+/// and count of a ring buffer.
+struct FieldValues<'a> {
+    referenced: Vec<&'a str>,
+    values: HashMap<String, u64>,
+}
+
+impl<'a> FieldValues<'a> {
+    fn new<K: TypeMapKind>(
+        fields: &'a [JsonIdlFieldDeserializer<'_, K>],
+    ) -> Self {
+        let referenced = fields
+            .iter()
+            .filter_map(|field| field.ring_buffer.as_ref())
+            .flat_map(|ring| {
+                std::iter::once(ring.head.as_str()).chain(ring.count.as_deref())
+            })
+            .collect();
+        Self {
+            referenced,
+            values: HashMap::new(),
+        }
+    }
+
+    fn deserialize_field<V: ChainparserVisitor, K: TypeMapKind>(
+        &mut self,
+        de: &impl ChainparserDeserialize,
+        visitor: &mut V,
+        buf: &mut &[u8],
+        field_de: &JsonIdlFieldDeserializer<'_, K>,
+    ) -> ChainparserResult<()> {
+        if self.referenced.contains(&field_de.name.as_str()) {
+            if let Some(value) = field_de.peek_unsigned(de, buf) {
+                self.values.insert(field_de.name.clone(), value);
+            }
+        }
+        match &field_de.ring_buffer {
+            Some(ring_buffer) => field_de.deserialize_ring_buffer(
+                de,
+                ring_buffer,
+                &self.values,
+                visitor,
+                buf,
+            ),
+            None => field_de.deserialize_with_visitor(de, visitor, buf),
+        }
+    }
+
+    /// Deserializes the field as a struct entry.
+    /// When the field isn't to be [emit]ted its bytes are consumed without emitting anything.
+    fn deserialize_entry<V: ChainparserVisitor, K: TypeMapKind>(
+        &mut self,
+        de: &impl ChainparserDeserialize,
+        visitor: &mut V,
+        buf: &mut &[u8],
+        field_de: &JsonIdlFieldDeserializer<'_, K>,
+        emit: bool,
+    ) -> ChainparserResult<()> {
+        if !emit {
+            self.deserialize_field(de, &mut DiscardVisitor, buf, field_de)
+        } else if field_de.ty_deserealizer.opts.best_effort {
+            self.deserialize_field_best_effort(de, visitor, buf, field_de)
+        } else {
+            self.deserialize_field(de, visitor, buf, field_de)
+        }
+    }
+
+    /// Same as [Self::deserialize_field], but emits `null` for a field with a fixed size that
+    /// fails to deserialize and continues past its bytes.
+    /// Fields without a fixed size or exceeding the [buf] still fail since the offset of the
+    /// fields following them is unknown.
+    fn deserialize_field_best_effort<V: ChainparserVisitor, K: TypeMapKind>(
+        &mut self,
+        de: &impl ChainparserDeserialize,
+        visitor: &mut V,
+        buf: &mut &[u8],
+        field_de: &JsonIdlFieldDeserializer<'_, K>,
+    ) -> ChainparserResult<()> {
+        let size = field_de.fixed_size().filter(|size| *size <= buf.len());
+        let Some(size) = size else {
+            return self.deserialize_field(de, visitor, buf, field_de);
+        };
+        // Values that were emitted cannot be taken back, thus the field is buffered and only
+        // passed on once it is known to deserialize
+        let mut scratch = BufferVisitor::default();
+        let mut rest = *buf;
+        match self.deserialize_field(de, &mut scratch, &mut rest, field_de) {
+            Ok(()) => {
+                *buf = rest;
+                scratch.replay(visitor)
+            }
+            Err(_) => {
+                *buf = &buf[size..];
+                visitor.field_name(&field_de.name)?;
+                visitor.null_value()
+            }
+        }
+    }
+}
+
+/// Resolves the number of bytes that any value of the [ty] takes up, looking up defined types
+/// in the [type_map] up to a nesting of [max_depth].
+/// Returns `None` if the size depends on the value, i.e. for strings, vecs and options.
+pub fn fixed_type_size<K: TypeMapKind>(
+    ty: &IdlType,
+    type_map: &JsonTypeDefinitionDeserializerMap<'_, K>,
+    max_depth: usize,
+) -> Option<usize> {
+    use IdlType::*;
+    match ty {
+        Bool | U8 | I8 => Some(1),
+        U16 | I16 => Some(2),
+        U32 | I32 | F32 => Some(4),
+        U64 | I64 | F64 => Some(8),
+        U128 | I128 => Some(16),
+        PublicKey => Some(32),
+        Array(inner, len) => {
+            fixed_type_size::<K>(inner, type_map, max_depth)?.checked_mul(*len)
+        }
+        Tuple(inners) => inners
+            .iter()
+            .map(|inner| fixed_type_size::<K>(inner, type_map, max_depth))
+            .sum(),
+        COption(inner) => fixed_type_size::<K>(inner, type_map, max_depth)
+            .map(|size| size + 4),
+        Defined(name) if max_depth > 0 => {
+            let definition = K::get(type_map, name)?;
+            if definition.align.is_some() || definition.tlv.is_some() {
+                return None;
+            }
+            match (&definition.fields, &definition.variants) {
+                (Some(fields), _) => fields
+                    .iter()
+                    .map(|field| {
+                        fixed_type_size::<K>(&field.ty, type_map, max_depth - 1)
+                    })
+                    .sum(),
+                // Variants with fields may differ in size
+                (None, Some(variants))
+                    if variants.iter().all(|variant| {
+                        variant.named_fields.is_none()
+                            && variant.tuple_types.is_none()
+                    }) =>
+                {
+                    Some(1)
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Deserializes the fields of a `repr(C)` struct without beginning or ending it, skipping the
+/// padding that aligns each field to its [field_aligns] entry and the trailing padding which
+/// aligns the struct to [struct_align].
+pub fn deserialize_repr_c_fields<V: ChainparserVisitor, K: TypeMapKind>(
+    de: &impl ChainparserDeserialize,
+    visitor: &mut V,
+    buf: &mut &[u8],
+    fields: &[JsonIdlFieldDeserializer<'_, K>],
+    field_aligns: &[usize],
+    struct_align: usize,
+) -> ChainparserResult<()> {
+    let start_len = buf.len();
+
+    let mut field_values = FieldValues::new(fields);
+    for (field_de, align) in fields.iter().zip(field_aligns) {
+        skip_padding(buf, start_len - buf.len(), *align)?;
+        field_values.deserialize_entry(
+            de,
+            visitor,
+            buf,
+            field_de,
+            !field_de.skip,
+        )?;
+    }
+
+    skip_padding(buf, start_len - buf.len(), struct_align)?;
 
     Ok(())
 }
 
+/// Advances the [buf] past the padding needed to align the [offset] to [align].
+fn skip_padding(
+    buf: &mut &[u8],
+    offset: usize,
+    align: usize,
+) -> ChainparserResult<()> {
+    let padding = (align - offset % align) % align;
+    if buf.len() < padding {
+        return Err(ChainparserError::InsufficientDataForPadding(
+            padding,
+            buf.len(),
+        ));
+    }
+    *buf = &buf[padding..];
+    Ok(())
+}
+
+#[inline(always)]
+pub fn write_bool<W: std::fmt::Write>(
+    f: &mut W,
+    b: bool,
+) -> Result<(), std::fmt::Error> {
+    f.write_str(if b { "true" } else { "false" })
+}
+
+/// Writes the float [n] rendering NaN and infinite values per [non_finite].
+#[inline(always)]
+pub fn write_float<
+    W: std::fmt::Write,
+    F: Copy + std::fmt::Display + Into<f64>,
+>(
+    f: &mut W,
+    n: F,
+    non_finite: NonFiniteRepr,
+) -> Result<(), std::fmt::Error> {
+    let n64: f64 = n.into();
+    if n64.is_finite() {
+        return write!(f, "{n}");
+    }
+    match non_finite {
+        NonFiniteRepr::Literal => write!(f, "{n}"),
+        NonFiniteRepr::Null => f.write_str("null"),
+        NonFiniteRepr::String if n64.is_nan() => f.write_str("\"NaN\""),
+        NonFiniteRepr::String if n64 > 0.0 => f.write_str("\"Infinity\""),
+        NonFiniteRepr::String => f.write_str("\"-Infinity\""),
+    }
+}
+
+/// Writes the integer [n] without allocating an intermediate [String].
 #[inline(always)]
-pub fn write_quoted<W: Write>(
+pub fn write_int<W: std::fmt::Write>(
+    f: &mut W,
+    n: impl itoa::Integer,
+) -> Result<(), std::fmt::Error> {
+    f.write_str(itoa::Buffer::new().format(n))
+}
+
+/// Writes the [bytes] per the [repr], see [BytesRepr].
+pub fn write_bytes<W: std::fmt::Write>(
+    f: &mut W,
+    bytes: &[u8],
+    repr: BytesRepr,
+) -> Result<(), std::fmt::Error> {
+    match repr {
+        BytesRepr::Array => {
+            f.write_char('[')?;
+            for (i, b) in bytes.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+                write_int(f, *b)?;
+            }
+            f.write_char(']')
+        }
+        BytesRepr::Hex => {
+            f.write_char('"')?;
+            for b in bytes {
+                write!(f, "{b:02x}")?;
+            }
+            f.write_char('"')
+        }
+        BytesRepr::Base64 => {
+            f.write_char('"')?;
+            f.write_str(&BASE64_STANDARD.encode(bytes))?;
+            f.write_char('"')
+        }
+    }
+}
+
+/// Writes the integer [n] as a JSON string without allocating an intermediate [String].
+#[inline(always)]
+pub fn write_int_quoted<W: std::fmt::Write>(
+    f: &mut W,
+    n: impl itoa::Integer,
+) -> Result<(), std::fmt::Error> {
+    write_quoted(f, itoa::Buffer::new().format(n))
+}
+
+/// Renders the integer [magnitude] as a decimal with [scale] digits after the decimal point,
+/// i.e. `1500000` with a scale of `6` becomes `1.500000`.
+pub fn scaled_decimal(negative: bool, magnitude: u128, scale: u32) -> String {
+    let scale = scale as usize;
+    let digits = format!("{magnitude:0>width$}", width = scale + 1);
+    let (int, frac) = digits.split_at(digits.len() - scale);
+    let sign = if negative { "-" } else { "" };
+    if frac.is_empty() {
+        format!("{sign}{int}")
+    } else {
+        format!("{sign}{int}.{frac}")
+    }
+}
+
+/// Writes the integer [magnitude] as a `0x` prefixed hex string, i.e. `"0x1f"` or `"-0x1f"`.
+pub fn write_int_hex<W: std::fmt::Write>(
+    f: &mut W,
+    negative: bool,
+    magnitude: u128,
+) -> Result<(), std::fmt::Error> {
+    let sign = if negative { "-" } else { "" };
+    f.write_str(&format!("\"{sign}{magnitude:#x}\""))
+}
+
+/// Writes [s] as a JSON string, escaping quotes, backslashes and control characters.
+#[inline(always)]
+pub fn write_quoted<W: std::fmt::Write>(
     f: &mut W,
     s: &str,
 ) -> Result<(), std::fmt::Error> {
     f.write_str("\"")?;
-    f.write_str(s)?;
+    write_escaped(f, s)?;
     f.write_str("\"")
 }
+
+/// Writes the contents of a JSON string for [s] without the enclosing quotes.
+/// Runs of characters that need no escaping are written in a single call.
+pub fn write_escaped<W: std::fmt::Write>(
+    f: &mut W,
+    s: &str,
+) -> Result<(), std::fmt::Error> {
+    // All escaped characters are ASCII, thus the byte indexes always fall on char boundaries
+    let mut start = 0;
+    for (i, byte) in s.bytes().enumerate() {
+        let escaped = match byte {
+            b'"' => "\\\"",
+            b'\\' => "\\\\",
+            b'\n' => "\\n",
+            b'\r' => "\\r",
+            b'\t' => "\\t",
+            0x08 => "\\b",
+            0x0c => "\\f",
+            0x00..=0x1f => "",
+            _ => continue,
+        };
+        f.write_str(&s[start..i])?;
+        if escaped.is_empty() {
+            write!(f, "\\u{byte:04x}")?;
+        } else {
+            f.write_str(escaped)?;
+        }
+        start = i + 1;
+    }
+    f.write_str(&s[start..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{format::JsonFormatWriter, json::JsonSerializationOpts};
+
+    #[test]
+    fn write_non_finite_floats() {
+        let write_all = |non_finite| {
+            let mut json = String::new();
+            for n in [1.5, f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+                write_float(&mut json, n, non_finite).unwrap();
+                json.push(',');
+            }
+            write_float(&mut json, f32::NAN, non_finite).unwrap();
+            json
+        };
+        assert_eq!(write_all(NonFiniteRepr::Literal), "1.5,NaN,inf,-inf,NaN");
+        assert_eq!(write_all(NonFiniteRepr::Null), "1.5,null,null,null,null");
+        assert_eq!(
+            write_all(NonFiniteRepr::String),
+            r#"1.5,"NaN","Infinity","-Infinity","NaN""#
+        );
+    }
+
+    #[test]
+    fn replay_buffered_values() {
+        let mut buffer = BufferVisitor::default();
+        buffer.begin_struct(2).unwrap();
+        buffer.field_name("ratio").unwrap();
+        buffer.f32_value(1.5).unwrap();
+        buffer.field_name("data").unwrap();
+        buffer.bytes_value(&[1, 2]).unwrap();
+        buffer.end_struct().unwrap();
+
+        let opts = JsonSerializationOpts::default();
+        let mut json = String::new();
+        buffer
+            .replay(&mut JsonFormatWriter::new(&mut json, &opts))
+            .unwrap();
+        assert_eq!(json, r#"{"ratio":1.5,"data":[1, 2]}"#);
+    }
+
+    #[test]
+    fn write_quoted_escapes_special_characters() {
+        let mut json = String::new();
+        write_quoted(&mut json, "say \"hi\"\n\tback\\slash\r\u{1}\u{8}\u{c} ✓")
+            .unwrap();
+        assert_eq!(json, r#""say \"hi\"\n\tback\\slash\r\u0001\b\f ✓""#);
+
+        let s = "quote \" and\nnewline\u{1f}";
+        let mut json = String::new();
+        write_quoted(&mut json, s).unwrap();
+        assert_eq!(serde_json::from_str::<String>(&json).unwrap(), s);
+    }
+
+    #[test]
+    fn render_scaled_decimals() {
+        for (negative, magnitude, scale, expected) in [
+            (false, 1_500_000, 6, "1.500000"),
+            (false, 42, 6, "0.000042"),
+            (true, 1_234, 2, "-12.34"),
+            (false, 7, 0, "7"),
+            (
+                false,
+                u128::MAX,
+                38,
+                "3.40282366920938463463374607431768211455",
+            ),
+        ] {
+            assert_eq!(scaled_decimal(negative, magnitude, scale), expected);
+        }
+    }
+
+    #[test]
+    fn write_hex_ints() {
+        for (negative, magnitude, expected) in [
+            (false, 0, r#""0x0""#),
+            (false, 31, r#""0x1f""#),
+            (true, 31, r#""-0x1f""#),
+            (false, u128::MAX, r#""0xffffffffffffffffffffffffffffffff""#),
+        ] {
+            let mut json = String::new();
+            write_int_hex(&mut json, negative, magnitude).unwrap();
+            assert_eq!(json, expected);
+        }
+    }
+
+    #[test]
+    fn write_ints() {
+        let mut json = String::new();
+        write_int(&mut json, u128::MAX).unwrap();
+        json.push(',');
+        write_int_quoted(&mut json, i64::MIN).unwrap();
+        assert_eq!(json, format!("{},\"{}\"", u128::MAX, i64::MIN),);
+    }
+}
@@ -0,0 +1,78 @@
+use solana_idl::IdlType;
+
+use super::{
+    json_idl_field_de::JsonIdlFieldDeserializer,
+    JsonTypeDefinitionDeserializers, TypeMapKind,
+};
+
+/// Describes a field of a deserialized account, i.e. to render it in an account viewer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldInfo {
+    /// The name of the field as declared in the IDL.
+    pub name: String,
+    /// The type of the field as declared in the IDL.
+    pub ty: IdlType,
+    /// The deserialized value of the field.
+    pub value: serde_json::Value,
+    /// The offset of the field inside the account data.
+    pub offset: usize,
+    /// The number of bytes the field takes up inside the account data.
+    pub len: usize,
+    /// The kind of the field's type.
+    pub kind: FieldKind,
+}
+
+/// The kind of a field's type with defined types resolved to either a struct or an enum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    Bool,
+    Integer,
+    Float,
+    String,
+    Bytes,
+    PublicKey,
+    Option,
+    COption,
+    Array,
+    Vec,
+    Tuple,
+    Map,
+    Set,
+    Struct,
+    Enum,
+}
+
+impl FieldKind {
+    /// Resolves the kind of the [field], looking up defined types in the [type_map].
+    /// Integers that hold the discriminant of an enum are considered an enum.
+    pub(crate) fn of_field<K: TypeMapKind>(
+        field: &JsonIdlFieldDeserializer<'_, K>,
+        type_map: &JsonTypeDefinitionDeserializers<'_, K>,
+    ) -> Self {
+        if field.enum_ty.is_some() {
+            return Self::Enum;
+        }
+        use IdlType::*;
+        match &field.ty {
+            Bool => Self::Bool,
+            U8 | U16 | U32 | U64 | U128 | I8 | I16 | I32 | I64 | I128 => {
+                Self::Integer
+            }
+            F32 | F64 => Self::Float,
+            String => Self::String,
+            Bytes => Self::Bytes,
+            PublicKey => Self::PublicKey,
+            Option(_) => Self::Option,
+            COption(_) => Self::COption,
+            Array(_, _) => Self::Array,
+            Vec(_) => Self::Vec,
+            Tuple(_) => Self::Tuple,
+            HashMap(_, _) | BTreeMap(_, _) => Self::Map,
+            HashSet(_) | BTreeSet(_) => Self::Set,
+            Defined(name) => match type_map.get(name) {
+                Some(type_de) if type_de.variants.is_some() => Self::Enum,
+                _ => Self::Struct,
+            },
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+/// Flattens the nested [value] into a map of field paths to scalar values, i.e.
+/// `{"composite":{"uno":{"value":1}},"ns":[1]}` becomes
+/// `{"composite.uno.value": 1, "ns[0]": 1}`.
+///
+/// Object keys are joined with dots and array items are indexed via `[i]`, matching the
+/// convention of common JSON flatten libraries.
+/// Empty objects and arrays are kept as is since they hold no values to flatten.
+pub fn flatten_json_value(value: &Value) -> BTreeMap<String, Value> {
+    let mut flat = BTreeMap::new();
+    flatten_into(&mut flat, String::new(), value);
+    flat
+}
+
+fn flatten_into(
+    flat: &mut BTreeMap<String, Value>,
+    path: String,
+    value: &Value,
+) {
+    match value {
+        Value::Object(entries) if !entries.is_empty() => {
+            for (key, value) in entries {
+                let path = if path.is_empty() {
+                    key.to_string()
+                } else {
+                    format!("{path}.{key}")
+                };
+                flatten_into(flat, path, value);
+            }
+        }
+        Value::Array(items) if !items.is_empty() => {
+            for (i, value) in items.iter().enumerate() {
+                flatten_into(flat, format!("{path}[{i}]"), value);
+            }
+        }
+        _ => {
+            flat.insert(path, value.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn flatten_nested_value() {
+        let value = json!({
+            "composite": { "uno": { "value": 1 }, "dos": null },
+            "ns": [1, 2],
+            "points": [{ "x": 1 }, { "x": 2 }],
+            "empty": [],
+            "label": "foo"
+        });
+        let flat = flatten_json_value(&value);
+        assert_eq!(
+            flat,
+            BTreeMap::from([
+                ("composite.dos".to_string(), Value::Null),
+                ("composite.uno.value".to_string(), json!(1)),
+                ("empty".to_string(), json!([])),
+                ("label".to_string(), json!("foo")),
+                ("ns[0]".to_string(), json!(1)),
+                ("ns[1]".to_string(), json!(2)),
+                ("points[0].x".to_string(), json!(1)),
+                ("points[1].x".to_string(), json!(2)),
+            ])
+        );
+    }
+
+    #[test]
+    fn flatten_nested_arrays() {
+        let flat = flatten_json_value(&json!({ "grid": [[1], [2, 3]] }));
+        assert_eq!(
+            flat.keys().collect::<Vec<_>>(),
+            vec!["grid[0][0]", "grid[1][0]", "grid[1][1]"]
+        );
+    }
+}
@@ -1,17 +1,16 @@
-use std::fmt::Write;
-
 use solana_idl::{EnumFields, IdlEnumVariant, IdlType};
 
 use super::{
-    json_common::{deserialize_fields_to_object, write_quoted},
+    json_common::deserialize_fields_to_object,
     json_idl_field_de::JsonIdlFieldDeserializer,
     json_idl_type_de::JsonIdlTypeDeserializer,
-    JsonTypeDefinitionDeserializerMap,
+    JsonTypeDefinitionDeserializerMap, SharedTypeMap, TypeMapKind,
 };
 use crate::{
     deserializer::ChainparserDeserialize,
     errors::{ChainparserError, ChainparserResult},
-    json::json_serialization_opts::JsonSerializationOpts,
+    format::ChainparserVisitor,
+    json::json_serialization_opts::OptsRef,
 };
 
 /// Deserializes an enum variant.
@@ -19,19 +18,34 @@ use crate::{
 /// treated like a named type with fields.
 /// However it handles scalar variants as well as unnamed field variants in a specific way.
 #[derive(Clone)]
-pub struct JsonIdlEnumVariantDeserializer<'opts> {
+pub struct JsonIdlEnumVariantDeserializer<'opts, K: TypeMapKind = SharedTypeMap>
+{
     pub name: String,
-    pub named_fields: Option<Vec<JsonIdlFieldDeserializer<'opts>>>,
-    pub tuple_types: Option<(JsonIdlTypeDeserializer<'opts>, IdlType)>,
-    pub type_map: JsonTypeDefinitionDeserializerMap<'opts>,
+    pub named_fields: Option<Vec<JsonIdlFieldDeserializer<'opts, K>>>,
+    pub tuple_types: Option<(JsonIdlTypeDeserializer<'opts, K>, IdlType)>,
+    pub type_map: JsonTypeDefinitionDeserializerMap<'opts, K>,
 }
 
 impl<'opts> JsonIdlEnumVariantDeserializer<'opts> {
     pub fn new(
         variant: &IdlEnumVariant,
         type_map: JsonTypeDefinitionDeserializerMap<'opts>,
-        opts: &'opts JsonSerializationOpts,
+        opts: impl Into<OptsRef<'opts>>,
+    ) -> Self {
+        let opts = opts.into();
+        Self::with_type_map(variant, type_map, opts)
+    }
+}
+
+impl<'opts, K: TypeMapKind> JsonIdlEnumVariantDeserializer<'opts, K> {
+    /// Creates a deserializer that resolves defined types from a [type_map] of any
+    /// [TypeMapKind].
+    pub fn with_type_map(
+        variant: &IdlEnumVariant,
+        type_map: JsonTypeDefinitionDeserializerMap<'opts, K>,
+        opts: impl Into<OptsRef<'opts>>,
     ) -> Self {
+        let opts = opts.into();
         let name = variant.name.clone();
         use EnumFields::*;
         match &variant.fields {
@@ -39,7 +53,11 @@ impl<'opts> JsonIdlEnumVariantDeserializer<'opts> {
                 let named_fields = fields
                     .iter()
                     .map(|f| {
-                        JsonIdlFieldDeserializer::new(f, type_map.clone(), opts)
+                        JsonIdlFieldDeserializer::with_type_map(
+                            f,
+                            type_map.clone(),
+                            opts.clone(),
+                        )
                     })
                     .collect();
                 Self {
@@ -50,8 +68,10 @@ impl<'opts> JsonIdlEnumVariantDeserializer<'opts> {
                 }
             }
             Some(Tuple(types)) => {
-                let tuple_ty_de =
-                    JsonIdlTypeDeserializer::new(type_map.clone(), opts);
+                let tuple_ty_de = JsonIdlTypeDeserializer::with_type_map(
+                    type_map.clone(),
+                    opts,
+                );
                 Self {
                     name,
                     named_fields: None,
@@ -70,63 +90,41 @@ impl<'opts> JsonIdlEnumVariantDeserializer<'opts> {
             },
         }
     }
-    /// Deserializes the enum variant into JSON that has the same format that [serde_json] uses.
-    /// This means that non-scalar variants field values are wrapped in an object whose key is the
-    /// variant name.
+    /// Deserializes the enum variant and emits it in the same shape that [serde_json] uses.
+    /// This means that non-scalar variants field values are wrapped in a struct whose single
+    /// field is named after the variant.
     /// Scalar variants are just a string of the variant name.
-    pub fn deserialize<W: Write>(
+    pub fn deserialize_with_visitor<V: ChainparserVisitor>(
         &self,
         de: &impl ChainparserDeserialize,
-        f: &mut W,
+        visitor: &mut V,
         buf: &mut &[u8],
     ) -> ChainparserResult<()> {
         if let Some(named_fields) = &self.named_fields {
-            f.write_char('{')?;
-            {
-                self.write_key(f)?;
-                deserialize_fields_to_object(de, f, buf, named_fields)
-                    .map_err(|e| {
-                        ChainparserError::EnumVariantDeserializeError(
-                            self.name.to_string(),
-                            Box::new(e),
-                        )
-                    })?;
-            }
-            f.write_char('}')?;
+            visitor.begin_struct(1)?;
+            visitor.field_name(&self.name)?;
+            deserialize_fields_to_object(de, visitor, buf, named_fields)
+                .map_err(|e| {
+                    ChainparserError::EnumVariantDeserializeError(
+                        self.name.to_string(),
+                        Box::new(e),
+                    )
+                })?;
+            visitor.end_struct()
         } else if let Some((tuple_ty_de, ty)) = &self.tuple_types {
-            f.write_char('{')?;
-            {
-                self.write_key(f)?;
-                self.deserialize_tuple_fields(de, f, buf, tuple_ty_de, ty)
-                    .map_err(|e| {
-                        ChainparserError::EnumVariantDeserializeError(
-                            self.name.to_string(),
-                            Box::new(e),
-                        )
-                    })?;
-            }
-            f.write_char('}')?;
+            visitor.begin_struct(1)?;
+            visitor.field_name(&self.name)?;
+            tuple_ty_de
+                .deserialize_with_visitor(de, ty, visitor, buf)
+                .map_err(|e| {
+                    ChainparserError::EnumVariantDeserializeError(
+                        self.name.to_string(),
+                        Box::new(e),
+                    )
+                })?;
+            visitor.end_struct()
         } else {
-            write_quoted(f, &self.name)?;
+            visitor.str_value(&self.name)
         }
-        Ok(())
-    }
-
-    fn deserialize_tuple_fields<W: Write>(
-        &self,
-        de: &impl ChainparserDeserialize,
-        f: &mut W,
-        buf: &mut &[u8],
-        tuple_el_de: &JsonIdlTypeDeserializer<'opts>,
-        ty: &IdlType,
-    ) -> ChainparserResult<()> {
-        tuple_el_de.deserialize(de, ty, f, buf)
-    }
-
-    fn write_key<W: Write>(&self, f: &mut W) -> ChainparserResult<()> {
-        f.write_char('"')?;
-        f.write_str(&self.name)?;
-        f.write_str("\":")?;
-        Ok(())
     }
 }
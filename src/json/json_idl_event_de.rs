@@ -0,0 +1,83 @@
+use solana_idl::{IdlEvent, IdlField};
+
+use super::{
+    json_common::deserialize_fields_to_object,
+    json_idl_field_de::JsonIdlFieldDeserializer,
+    JsonTypeDefinitionDeserializerMap, JsonWrite,
+};
+use crate::{
+    deserializer::ChainparserDeserialize,
+    errors::{ChainparserError, ChainparserResult},
+    format::{ChainparserVisitor, JsonFormatWriter},
+    json::json_serialization_opts::OptsRef,
+};
+
+/// Deserializes the fields of an event into a JSON object keyed by field name.
+#[derive(Clone)]
+pub struct JsonIdlEventDeserializer<'opts> {
+    pub name: String,
+    pub fields: Vec<JsonIdlFieldDeserializer<'opts>>,
+    pub type_map: JsonTypeDefinitionDeserializerMap<'opts>,
+    pub opts: OptsRef<'opts>,
+}
+
+impl<'opts> JsonIdlEventDeserializer<'opts> {
+    pub fn new(
+        event: &IdlEvent,
+        type_map: JsonTypeDefinitionDeserializerMap<'opts>,
+        opts: impl Into<OptsRef<'opts>>,
+    ) -> Self {
+        let opts = opts.into();
+        let fields = event
+            .fields
+            .iter()
+            .map(|field| {
+                let field = IdlField {
+                    name: field.name.clone(),
+                    ty: field.ty.clone(),
+                    attrs: None,
+                };
+                JsonIdlFieldDeserializer::new(
+                    &field,
+                    type_map.clone(),
+                    opts.clone(),
+                )
+            })
+            .collect();
+        Self {
+            name: event.name.clone(),
+            fields,
+            type_map,
+            opts,
+        }
+    }
+
+    /// Deserializes the event fields from the provided [buf] which is expected to **not**
+    /// include the event discriminator.
+    pub fn deserialize<W: JsonWrite>(
+        &self,
+        de: &impl ChainparserDeserialize,
+        f: &mut W,
+        buf: &mut &[u8],
+    ) -> ChainparserResult<()> {
+        let mut w = JsonFormatWriter::new(f, &self.opts);
+        self.deserialize_with_visitor(de, &mut w, buf)
+    }
+
+    /// Same as [Self::deserialize], but emits the event fields to the [visitor].
+    pub fn deserialize_with_visitor<V: ChainparserVisitor>(
+        &self,
+        de: &impl ChainparserDeserialize,
+        visitor: &mut V,
+        buf: &mut &[u8],
+    ) -> ChainparserResult<()> {
+        deserialize_fields_to_object(de, visitor, buf, &self.fields).map_err(
+            |e| {
+                ChainparserError::EventDeserializeError(
+                    self.name.to_string(),
+                    Box::new(e),
+                )
+            },
+        )
+    }
+}
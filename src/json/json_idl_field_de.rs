@@ -1,57 +1,293 @@
-use std::fmt::Write;
+use std::collections::HashMap;
 
 use solana_idl::{IdlField, IdlType};
 
 use super::{
+    json_common::{fixed_type_size, DiscardVisitor},
     json_idl_type_de::JsonIdlTypeDeserializer,
-    JsonTypeDefinitionDeserializerMap,
+    JsonTypeDefinitionDeserializerMap, JsonWrite, SharedTypeMap, TypeMapKind,
 };
 use crate::{
     deserializer::ChainparserDeserialize,
     errors::{ChainparserError, ChainparserResult},
-    json::json_serialization_opts::JsonSerializationOpts,
+    format::{ChainparserVisitor, JsonFormatWriter},
+    json::json_serialization_opts::OptsRef,
 };
 
 #[derive(Clone)]
-pub struct JsonIdlFieldDeserializer<'opts> {
+pub struct JsonIdlFieldDeserializer<'opts, K: TypeMapKind = SharedTypeMap> {
     pub name: String,
     pub ty: IdlType,
-    pub ty_deserealizer: JsonIdlTypeDeserializer<'opts>,
-    pub type_map: JsonTypeDefinitionDeserializerMap<'opts>,
+    pub ty_deserealizer: JsonIdlTypeDeserializer<'opts, K>,
+    pub type_map: JsonTypeDefinitionDeserializerMap<'opts, K>,
+    /// Set when the field is an integer holding the discriminant of the enum with this name, as
+    /// specified via the `enum(TypeName)` attribute.
+    pub enum_ty: Option<String>,
+    /// Set when the field is an array used as a ring buffer, as specified via the
+    /// `ring_buffer(head = field, count = field)` attribute.
+    pub ring_buffer: Option<RingBuffer>,
+    /// Set when the field is included in [JsonSerializationOpts::skip_fields], its bytes are
+    /// consumed but it is omitted from the output.
+    pub skip: bool,
+}
+
+/// Links an array field to the fields of the same struct that hold the index of its oldest
+/// entry ([head]) and optionally the number of active entries ([count]).
+/// Both fields need to be unsigned integers declared before the array.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RingBuffer {
+    pub head: String,
+    pub count: Option<String>,
 }
 
 impl<'opts> JsonIdlFieldDeserializer<'opts> {
     pub fn new(
         field: &IdlField,
         type_map: JsonTypeDefinitionDeserializerMap<'opts>,
-        opts: &'opts JsonSerializationOpts,
+        opts: impl Into<OptsRef<'opts>>,
+    ) -> Self {
+        let opts = opts.into();
+        Self::with_type_map(field, type_map, opts)
+    }
+}
+
+impl<'opts, K: TypeMapKind> JsonIdlFieldDeserializer<'opts, K> {
+    /// Creates a deserializer that resolves defined types from a [type_map] of any
+    /// [TypeMapKind].
+    pub fn with_type_map(
+        field: &IdlField,
+        type_map: JsonTypeDefinitionDeserializerMap<'opts, K>,
+        opts: impl Into<OptsRef<'opts>>,
     ) -> Self {
-        let ty_deserealizer =
-            JsonIdlTypeDeserializer::new(type_map.clone(), opts);
+        let opts = opts.into();
+        let ty_deserealizer = JsonIdlTypeDeserializer::with_type_map(
+            type_map.clone(),
+            opts.clone(),
+        );
+        let enum_ty = field_enum_ty(field);
+        let ring_buffer = if matches!(field.ty, IdlType::Array(_, _)) {
+            field.attrs.as_ref().and_then(|attrs| {
+                attrs.iter().find_map(|a| ring_buffer_attr(a))
+            })
+        } else {
+            None
+        };
         Self {
             name: field.name.clone(),
             ty: field.ty.clone(),
             ty_deserealizer,
             type_map,
+            enum_ty,
+            ring_buffer,
+            skip: opts.skip_fields.contains(&field.name),
         }
     }
 
-    pub fn deserialize<W: Write>(
+    /// Deserializes the ring buffer array of this field and emits its active entries ordered
+    /// from oldest to newest.
+    /// The values of the head and count fields are looked up in [field_values] which holds the
+    /// values of fields of the same struct that were deserialized before.
+    pub fn deserialize_ring_buffer<V: ChainparserVisitor>(
         &self,
         de: &impl ChainparserDeserialize,
-        f: &mut W,
+        ring_buffer: &RingBuffer,
+        field_values: &HashMap<String, u64>,
+        visitor: &mut V,
         buf: &mut &[u8],
     ) -> ChainparserResult<()> {
-        f.write_char('"')?;
-        f.write_str(&self.name)?;
-        f.write_str("\":")?;
-        self.ty_deserealizer
-            .deserialize(de, &self.ty, f, buf)
-            .map_err(|e| {
-                ChainparserError::FieldDeserializeError(
+        let IdlType::Array(inner, len) = &self.ty else {
+            unreachable!("ring_buffer is only set for arrays")
+        };
+        let field_value = |name: &str| {
+            field_values.get(name).copied().ok_or_else(|| {
+                ChainparserError::RingBufferFieldNotFound(
                     self.name.to_string(),
-                    Box::new(e),
+                    name.to_string(),
                 )
             })
+        };
+        let head = field_value(&ring_buffer.head)?;
+        let head = usize::try_from(head).map_err(|_| {
+            ChainparserError::RingBufferHeadTooLarge(
+                self.name.to_string(),
+                head,
+            )
+        })?;
+        // Only up to [len] entries are emitted, thus larger counts are clamped
+        let count = match &ring_buffer.count {
+            Some(count) => {
+                usize::try_from(field_value(count)?).unwrap_or(usize::MAX)
+            }
+            None => *len,
+        };
+
+        // The entries are emitted out of order, thus the bytes of each are found first
+        let mut entries = Vec::with_capacity(*len);
+        for _ in 0..*len {
+            let start = *buf;
+            self.ty_deserealizer
+                .deserialize_with_visitor(de, inner, &mut DiscardVisitor, buf)
+                .map_err(|e| {
+                    ChainparserError::FieldDeserializeError(
+                        self.name.to_string(),
+                        Box::new(e),
+                    )
+                })?;
+            entries.push(&start[..start.len() - buf.len()]);
+        }
+
+        visitor.field_name(&self.name)?;
+        let count = if *len > 0 { count.min(*len) } else { 0 };
+        visitor.begin_seq(count)?;
+        for i in 0..count {
+            let entry = &mut &entries[(head % len + i) % len][..];
+            self.ty_deserealizer
+                .deserialize_with_visitor(de, inner, visitor, entry)?;
+        }
+        visitor.end_seq()
+    }
+
+    /// The number of bytes this field takes up if it is the same for all values, see
+    /// [fixed_type_size].
+    pub fn fixed_size(&self) -> Option<usize> {
+        fixed_type_size::<K>(
+            &self.ty,
+            &self.type_map,
+            self.ty_deserealizer.opts.max_depth,
+        )
+    }
+
+    /// Reads the value of this field without consuming the [buf] if it is an unsigned integer.
+    pub fn peek_unsigned(
+        &self,
+        de: &impl ChainparserDeserialize,
+        buf: &[u8],
+    ) -> Option<u64> {
+        let buf = &mut &buf[..];
+        match self.ty {
+            IdlType::U8 => de.u8(buf).ok().map(u64::from),
+            IdlType::U16 => de.u16(buf).ok().map(u64::from),
+            IdlType::U32 => de.u32(buf).ok().map(u64::from),
+            IdlType::U64 => de.u64(buf).ok(),
+            _ => None,
+        }
+    }
+
+    /// Deserializes the field and writes it as a JSON object entry, i.e. `"name":value`.
+    pub fn deserialize<W: JsonWrite>(
+        &self,
+        de: &impl ChainparserDeserialize,
+        f: &mut W,
+        buf: &mut &[u8],
+    ) -> ChainparserResult<()> {
+        let opts = &self.ty_deserealizer.opts;
+        self.deserialize_with_visitor(
+            de,
+            &mut JsonFormatWriter::new(f, opts),
+            buf,
+        )
+    }
+
+    /// Deserializes the field and emits its name followed by its value to the [visitor].
+    pub fn deserialize_with_visitor<V: ChainparserVisitor>(
+        &self,
+        de: &impl ChainparserDeserialize,
+        visitor: &mut V,
+        buf: &mut &[u8],
+    ) -> ChainparserResult<()> {
+        visitor.field_name(&self.name)?;
+        match &self.enum_ty {
+            Some(enum_ty) => {
+                self.deserialize_enum_value(de, enum_ty, visitor, buf)
+            }
+            None => self
+                .ty_deserealizer
+                .deserialize_with_visitor(de, &self.ty, visitor, buf),
+        }
+        .map_err(|e| {
+            ChainparserError::FieldDeserializeError(
+                self.name.to_string(),
+                Box::new(e),
+            )
+        })
+    }
+
+    /// Deserializes the integer value of the field and emits the name of the matching variant of
+    /// the [enum_ty] enum.
+    fn deserialize_enum_value<V: ChainparserVisitor>(
+        &self,
+        de: &impl ChainparserDeserialize,
+        enum_ty: &str,
+        visitor: &mut V,
+        buf: &mut &[u8],
+    ) -> ChainparserResult<()> {
+        let value = match self.ty {
+            IdlType::U8 => de.u8(buf)? as u64,
+            IdlType::U16 => de.u16(buf)? as u64,
+            IdlType::U32 => de.u32(buf)? as u64,
+            IdlType::U64 => de.u64(buf)?,
+            _ => unreachable!("enum_ty is only set for unsigned integers"),
+        };
+        let type_de = K::get(&self.type_map, enum_ty);
+        let variant = type_de
+            .as_ref()
+            .ok_or_else(|| {
+                ChainparserError::CannotFindDefinedType(enum_ty.to_string())
+            })?
+            .variants
+            .as_ref()
+            .and_then(|variants| variants.get(value as usize))
+            .ok_or_else(|| {
+                ChainparserError::InvalidEnumVariantValue(
+                    enum_ty.to_string(),
+                    value,
+                )
+            })?;
+        visitor.str_value(&variant.name)
+    }
+}
+
+/// Resolves the enum type whose variant an unsigned integer [field] holds per its
+/// `enum(TypeName)` attribute.
+pub(super) fn field_enum_ty(field: &IdlField) -> Option<String> {
+    if !is_unsigned_int(&field.ty) {
+        return None;
+    }
+    field
+        .attrs
+        .as_ref()
+        .and_then(|attrs| attrs.iter().find_map(|a| enum_attr(a)))
+}
+
+fn is_unsigned_int(ty: &IdlType) -> bool {
+    matches!(ty, IdlType::U8 | IdlType::U16 | IdlType::U32 | IdlType::U64)
+}
+
+/// Extracts `TypeName` from an `enum(TypeName)` field attribute.
+fn enum_attr(attr: &str) -> Option<String> {
+    attr.trim()
+        .strip_prefix("enum(")
+        .and_then(|s| s.strip_suffix(')'))
+        .map(|s| s.trim().to_string())
+}
+
+/// Extracts the fields from a `ring_buffer(head = field, count = field)` field attribute.
+/// The `count` is optional.
+fn ring_buffer_attr(attr: &str) -> Option<RingBuffer> {
+    let args = attr
+        .trim()
+        .strip_prefix("ring_buffer(")
+        .and_then(|s| s.strip_suffix(')'))?;
+    let mut head = None;
+    let mut count = None;
+    for arg in args.split(',') {
+        let (key, value) = arg.split_once('=')?;
+        let value = Some(value.trim().to_string());
+        match key.trim() {
+            "head" => head = value,
+            "count" => count = value,
+            _ => return None,
+        }
     }
+    Some(RingBuffer { head: head?, count })
 }
@@ -0,0 +1,78 @@
+use solana_idl::IdlInstruction;
+
+use super::{
+    json_common::deserialize_fields_to_object,
+    json_idl_field_de::JsonIdlFieldDeserializer,
+    JsonTypeDefinitionDeserializerMap, JsonWrite,
+};
+use crate::{
+    deserializer::ChainparserDeserialize,
+    errors::{ChainparserError, ChainparserResult},
+    format::{ChainparserVisitor, JsonFormatWriter},
+    json::json_serialization_opts::OptsRef,
+};
+
+/// Deserializes the args of an instruction into a JSON object keyed by arg name.
+#[derive(Clone)]
+pub struct JsonIdlInstructionDeserializer<'opts> {
+    pub name: String,
+    pub args: Vec<JsonIdlFieldDeserializer<'opts>>,
+    pub type_map: JsonTypeDefinitionDeserializerMap<'opts>,
+    pub opts: OptsRef<'opts>,
+}
+
+impl<'opts> JsonIdlInstructionDeserializer<'opts> {
+    pub fn new(
+        instruction: &IdlInstruction,
+        type_map: JsonTypeDefinitionDeserializerMap<'opts>,
+        opts: impl Into<OptsRef<'opts>>,
+    ) -> Self {
+        let opts = opts.into();
+        let args = instruction
+            .args
+            .iter()
+            .map(|arg| {
+                JsonIdlFieldDeserializer::new(
+                    arg,
+                    type_map.clone(),
+                    opts.clone(),
+                )
+            })
+            .collect();
+        Self {
+            name: instruction.name.clone(),
+            args,
+            type_map,
+            opts,
+        }
+    }
+
+    /// Deserializes the instruction args from the provided [buf] which is expected to **not**
+    /// include the instruction discriminator.
+    pub fn deserialize<W: JsonWrite>(
+        &self,
+        de: &impl ChainparserDeserialize,
+        f: &mut W,
+        buf: &mut &[u8],
+    ) -> ChainparserResult<()> {
+        let mut w = JsonFormatWriter::new(f, &self.opts);
+        self.deserialize_with_visitor(de, &mut w, buf)
+    }
+
+    /// Same as [Self::deserialize], but emits the instruction args to the [visitor].
+    pub fn deserialize_with_visitor<V: ChainparserVisitor>(
+        &self,
+        de: &impl ChainparserDeserialize,
+        visitor: &mut V,
+        buf: &mut &[u8],
+    ) -> ChainparserResult<()> {
+        deserialize_fields_to_object(de, visitor, buf, &self.args).map_err(
+            |e| {
+                ChainparserError::InstructionDeserializeError(
+                    self.name.to_string(),
+                    Box::new(e),
+                )
+            },
+        )
+    }
+}
@@ -1,223 +1,425 @@
-use std::fmt::Write;
+use std::cell::Cell;
 
 use solana_idl::IdlType;
 
-use super::{json_common::write_quoted, JsonTypeDefinitionDeserializerMap};
+use super::{
+    json_common::{scaled_decimal, write_quoted},
+    JsonIdlFieldDeserializer, JsonTypeDefinitionDeserializerMap, JsonWrite,
+    SharedTypeMap, TypeMapKind,
+};
 use crate::{
     deserializer::ChainparserDeserialize,
     errors::{ChainparserError, ChainparserResult},
-    json::json_serialization_opts::JsonSerializationOpts,
+    format::{ChainparserVisitor, JsonFormatWriter},
+    json::json_serialization_opts::OptsRef,
 };
 
+thread_local! {
+    /// The number of defined types currently being deserialized on this thread.
+    static DEFINED_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Tracks the nesting of defined types while deserializing them since each of them is resolved
+/// via a separate deserializer taken from the type map.
+/// This prevents self-referential types from overflowing the stack.
+struct DepthGuard;
+
+impl DepthGuard {
+    fn enter(max_depth: usize) -> ChainparserResult<Self> {
+        DEFINED_DEPTH.with(|depth| {
+            if depth.get() >= max_depth {
+                return Err(ChainparserError::MaxDepthExceeded(max_depth));
+            }
+            depth.set(depth.get() + 1);
+            Ok(Self)
+        })
+    }
+}
+
+impl Drop for DepthGuard {
+    fn drop(&mut self) {
+        DEFINED_DEPTH.with(|depth| depth.set(depth.get() - 1));
+    }
+}
+
 #[derive(Clone)]
-pub struct JsonIdlTypeDeserializer<'opts> {
-    pub type_map: JsonTypeDefinitionDeserializerMap<'opts>,
-    pub opts: &'opts JsonSerializationOpts,
+pub struct JsonIdlTypeDeserializer<'opts, K: TypeMapKind = SharedTypeMap> {
+    pub type_map: JsonTypeDefinitionDeserializerMap<'opts, K>,
+    pub opts: OptsRef<'opts>,
 }
 
 impl<'opts> JsonIdlTypeDeserializer<'opts> {
     pub fn new(
         type_map: JsonTypeDefinitionDeserializerMap<'opts>,
-        opts: &'opts JsonSerializationOpts,
+        opts: impl Into<OptsRef<'opts>>,
+    ) -> Self {
+        let opts = opts.into();
+        Self::with_type_map(type_map, opts)
+    }
+}
+
+impl<'opts, K: TypeMapKind> JsonIdlTypeDeserializer<'opts, K> {
+    /// Creates a deserializer that resolves defined types from a [type_map] of any
+    /// [TypeMapKind].
+    pub fn with_type_map(
+        type_map: JsonTypeDefinitionDeserializerMap<'opts, K>,
+        opts: impl Into<OptsRef<'opts>>,
     ) -> Self {
+        let opts = opts.into();
         Self { type_map, opts }
     }
 
-    pub fn deserialize<W: Write>(
+    /// Deserializes a value of the given [ty] and writes it as JSON, see [JsonFormatWriter].
+    pub fn deserialize<W: JsonWrite>(
         &self,
         de: &impl ChainparserDeserialize,
         ty: &IdlType,
         f: &mut W,
         buf: &mut &[u8],
+    ) -> ChainparserResult<()> {
+        let mut w = JsonFormatWriter::new(f, &self.opts);
+        self.deserialize_with_visitor(de, ty, &mut w, buf)
+    }
+
+    /// Deserializes a value of the given [ty] and emits it to the [visitor].
+    pub fn deserialize_with_visitor<V: ChainparserVisitor>(
+        &self,
+        de: &impl ChainparserDeserialize,
+        ty: &IdlType,
+        visitor: &mut V,
+        buf: &mut &[u8],
     ) -> ChainparserResult<()> {
         use IdlType::{
             Bool, F32, F64, I128, I16, I32, I64, I8, U128, U16, U32, U64, U8,
         };
         match ty {
-            U8 => f.write_str(&de.u8(buf)?.to_string()),
-            U16 => f.write_str(&de.u16(buf)?.to_string()),
-            U32 => f.write_str(&de.u32(buf)?.to_string()),
-            U64 if self.opts.n64_as_string => {
-                write_quoted(f, &de.u64(buf)?.to_string())
-            }
-            U64 => f.write_str(&de.u64(buf)?.to_string()),
+            U8 => visitor.u8_value(de.u8(buf)?),
+            U16 => visitor.u16_value(de.u16(buf)?),
+            U32 => visitor.u32_value(de.u32(buf)?),
+            U64 => visitor.u64_value(de.u64(buf)?),
+            U128 => visitor.u128_value(de.u128(buf)?),
 
-            U128 if self.opts.n128_as_string => {
-                write_quoted(f, &de.u128(buf)?.to_string())
-            }
-            U128 => f.write_str(&de.u128(buf)?.to_string()),
+            I8 => visitor.i8_value(de.i8(buf)?),
+            I16 => visitor.i16_value(de.i16(buf)?),
+            I32 => visitor.i32_value(de.i32(buf)?),
+            I64 => visitor.i64_value(de.i64(buf)?),
+            I128 => visitor.i128_value(de.i128(buf)?),
 
-            I8 => f.write_str(&de.i8(buf)?.to_string()),
-            I16 => f.write_str(&de.i16(buf)?.to_string()),
-            I32 => f.write_str(&de.i32(buf)?.to_string()),
+            F32 => visitor.f32_value(de.f32(buf)?),
+            F64 => visitor.f64_value(de.f64(buf)?),
 
-            I64 if self.opts.n64_as_string => {
-                write_quoted(f, &de.i64(buf)?.to_string())
+            Bool if self.opts.lenient_bool => {
+                visitor.bool_value(de.u8(buf)? != 0)
             }
-            I64 => f.write_str(&de.i64(buf)?.to_string()),
+            Bool => visitor.bool_value(de.bool(buf)?),
 
-            I128 if self.opts.n128_as_string => {
-                write_quoted(f, &de.i128(buf)?.to_string())
+            // Strings are length prefixed like bytes, thus invalid UTF-8 can be replaced
+            IdlType::String if self.opts.lossy_strings => {
+                visitor.str_value(&String::from_utf8_lossy(&de.bytes(buf)?))
             }
-            I128 => f.write_str(&de.i128(buf)?.to_string()),
-
-            F32 => f.write_str(&de.f32(buf)?.to_string()),
-            F64 => f.write_str(&de.f64(buf)?.to_string()),
-
-            Bool => f.write_str(&de.bool(buf)?.to_string()),
-
-            IdlType::String => write_quoted(f, &de.string(buf)?),
+            IdlType::String => visitor.str_value(&de.string(buf)?),
 
             // Composites
             IdlType::Tuple(inners) => {
-                let len = inners.len();
-                f.write_char('[')?;
-                for (i, inner) in inners.iter().enumerate() {
-                    self.deserialize(de, inner, f, buf)?;
-                    if i < len - 1 {
-                        f.write_str(", ")?;
-                    }
+                visitor.begin_seq(inners.len())?;
+                for inner in inners {
+                    self.deserialize_with_visitor(de, inner, visitor, buf)?;
                 }
-                f.write_char(']')
+                visitor.end_seq()
+            }
+            // Hashes and seeds are read at once and rendered like bytes
+            IdlType::Array(inner, len) if **inner == U8 => {
+                visitor.bytes_value(de.u8_array(buf, *len)?)
+            }
+            IdlType::Vec(inner) if **inner == U8 => {
+                visitor.bytes_value(&de.bytes(buf)?)
             }
             IdlType::Array(inner, len) => {
-                f.write_char('[')?;
+                visitor.begin_seq(*len)?;
                 for i in 0..*len {
-                    self.deserialize(de, inner, f, buf).map_err(|e| {
-                        ChainparserError::CompositeDeserializeError(
-                            format!("Array[{i}] size({len})"),
-                            Box::new(e),
-                        )
-                    })?;
-                    if i < len - 1 {
-                        f.write_str(", ")?;
-                    }
+                    self.deserialize_with_visitor(de, inner, visitor, buf)
+                        .map_err(|e| {
+                            ChainparserError::CompositeDeserializeError(
+                                format!("Array[{i}] size({len})"),
+                                Box::new(e),
+                            )
+                        })?;
                 }
-                f.write_char(']')
+                visitor.end_seq()
             }
             IdlType::Vec(inner) => {
-                let len = de.u32(buf)?;
-                f.write_char('[')?;
+                let len = de.collection_len(
+                    buf,
+                    K::min_type_bytes(&self.type_map, inner),
+                )?;
+                visitor.begin_seq(len)?;
                 for i in 0..len {
-                    self.deserialize(de, inner, f, buf).map_err(|e| {
-                        ChainparserError::CompositeDeserializeError(
-                            format!("Vec[{i}] size({len})"),
-                            Box::new(e),
-                        )
-                    })?;
-                    if i < len - 1 {
-                        f.write_str(", ")?;
+                    self.deserialize_with_visitor(de, inner, visitor, buf)
+                        .map_err(|e| {
+                            ChainparserError::CompositeDeserializeError(
+                                format!("Vec[{i}] size({len})"),
+                                Box::new(e),
+                            )
+                        })?;
+                    // Pass each element on right away, i.e. to stream large vecs
+                    if V::STREAMING {
+                        visitor.flush()?;
                     }
                 }
-                f.write_char(']')
+                visitor.end_seq()
+            }
+            IdlType::HashMap(inner1, inner2)
+            | IdlType::BTreeMap(inner1, inner2)
+                if self.opts.sort_map_keys =>
+            {
+                let min_entry_bytes = K::min_type_bytes(&self.type_map, inner1)
+                    .saturating_add(K::min_type_bytes(&self.type_map, inner2));
+                let len = de.collection_len(buf, min_entry_bytes)?;
+                let mut entries = Vec::with_capacity(len.min(buf.len()));
+                for i in 0..len {
+                    let (key, key_bytes) =
+                        self.render_sortable(de, inner1, buf).map_err(|e| {
+                            ChainparserError::CompositeDeserializeError(
+                                format!("Key HashMap[{i}] size({len})"),
+                                Box::new(e),
+                            )
+                        })?;
+                    let (val, val_bytes) =
+                        self.render_sortable(de, inner2, buf).map_err(|e| {
+                            ChainparserError::CompositeDeserializeError(
+                                format!("Val HashMap[{i}] size({len})"),
+                                Box::new(e),
+                            )
+                        })?;
+                    entries.push((
+                        (json_map_key(key), val),
+                        key_bytes,
+                        val_bytes,
+                    ));
+                }
+                entries.sort_by(|(a, ..), (b, ..)| a.cmp(b));
+
+                visitor.begin_map(len)?;
+                for (_, key_bytes, val_bytes) in entries {
+                    visitor.begin_map_key()?;
+                    self.deserialize_with_visitor(
+                        de,
+                        inner1,
+                        visitor,
+                        &mut &key_bytes[..],
+                    )?;
+                    visitor.end_map_key()?;
+                    self.deserialize_with_visitor(
+                        de,
+                        inner2,
+                        visitor,
+                        &mut &val_bytes[..],
+                    )?;
+                }
+                visitor.end_map()
             }
             IdlType::HashMap(inner1, inner2)
             | IdlType::BTreeMap(inner1, inner2) => {
-                let len = de.u32(buf)?;
-                f.write_char('{')?;
+                let min_entry_bytes = K::min_type_bytes(&self.type_map, inner1)
+                    .saturating_add(K::min_type_bytes(&self.type_map, inner2));
+                let len = de.collection_len(buf, min_entry_bytes)?;
+                visitor.begin_map(len)?;
                 for i in 0..len {
-                    f.write_char('"')?;
-                    self.deserialize(de, inner1, f, buf).map_err(|e| {
-                        ChainparserError::CompositeDeserializeError(
-                            format!("Key HashMap[{i}] size({len})"),
-                            Box::new(e),
-                        )
-                    })?;
-                    f.write_str("\": ")?;
-                    self.deserialize(de, inner2, f, buf).map_err(|e| {
-                        ChainparserError::CompositeDeserializeError(
-                            format!("Val HashMap[{i}] size({len})"),
-                            Box::new(e),
-                        )
-                    })?;
-                    if i < len - 1 {
-                        f.write_str(", ")?;
-                    }
+                    visitor.begin_map_key()?;
+                    self.deserialize_with_visitor(de, inner1, visitor, buf)
+                        .map_err(|e| {
+                            ChainparserError::CompositeDeserializeError(
+                                format!("Key HashMap[{i}] size({len})"),
+                                Box::new(e),
+                            )
+                        })?;
+                    visitor.end_map_key()?;
+                    self.deserialize_with_visitor(de, inner2, visitor, buf)
+                        .map_err(|e| {
+                            ChainparserError::CompositeDeserializeError(
+                                format!("Val HashMap[{i}] size({len})"),
+                                Box::new(e),
+                            )
+                        })?;
                 }
-                f.write_char('}')
+                visitor.end_map()
+            }
+            IdlType::HashSet(inner) | IdlType::BTreeSet(inner)
+                if self.opts.sort_map_keys =>
+            {
+                let len = de.collection_len(
+                    buf,
+                    K::min_type_bytes(&self.type_map, inner),
+                )?;
+                let mut items = Vec::with_capacity(len.min(buf.len()));
+                for i in 0..len {
+                    let item =
+                        self.render_sortable(de, inner, buf).map_err(|e| {
+                            ChainparserError::CompositeDeserializeError(
+                                format!("HashSet[{i}] size({len})"),
+                                Box::new(e),
+                            )
+                        })?;
+                    items.push(item);
+                }
+                items.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+                visitor.begin_seq(len)?;
+                for (_, item_bytes) in items {
+                    self.deserialize_with_visitor(
+                        de,
+                        inner,
+                        visitor,
+                        &mut &item_bytes[..],
+                    )?;
+                }
+                visitor.end_seq()
             }
             IdlType::HashSet(inner) | IdlType::BTreeSet(inner) => {
-                let len = de.u32(buf)?;
-                f.write_char('[')?;
+                let len = de.collection_len(
+                    buf,
+                    K::min_type_bytes(&self.type_map, inner),
+                )?;
+                visitor.begin_seq(len)?;
                 for i in 0..len {
-                    self.deserialize(de, inner, f, buf).map_err(|e| {
-                        ChainparserError::CompositeDeserializeError(
-                            format!("HashSet[{i}] size({len})"),
-                            Box::new(e),
-                        )
-                    })?;
-                    if i < len - 1 {
-                        f.write_str(", ")?;
-                    }
+                    self.deserialize_with_visitor(de, inner, visitor, buf)
+                        .map_err(|e| {
+                            ChainparserError::CompositeDeserializeError(
+                                format!("HashSet[{i}] size({len})"),
+                                Box::new(e),
+                            )
+                        })?;
                 }
-                f.write_char(']')
+                visitor.end_seq()
             }
             IdlType::Option(inner) => {
                 if de.option(buf)? {
-                    self.deserialize(de, inner, f, buf).map_err(|e| {
-                        ChainparserError::CompositeDeserializeError(
-                            "Option".to_string(),
-                            Box::new(e),
-                        )
-                    })?;
+                    self.deserialize_with_visitor(de, inner, visitor, buf)
+                        .map_err(|e| {
+                            ChainparserError::CompositeDeserializeError(
+                                "Option".to_string(),
+                                Box::new(e),
+                            )
+                        })
                 } else {
-                    f.write_str("null")?;
+                    visitor.null_value()
                 }
-                Ok(())
             }
             IdlType::COption(inner) => {
                 if de.coption(buf, inner)? {
-                    self.deserialize(de, inner, f, buf).map_err(|e| {
-                        ChainparserError::CompositeDeserializeError(
-                            "Option".to_string(),
-                            Box::new(e),
-                        )
-                    })?;
-                } else {
-                    f.write_str("null")?;
-                }
-                Ok(())
-            }
-            IdlType::Bytes => {
-                // Bytes is the same as a u8 array, thus stringify to an array of numbers
-                // in order to be able to later JSON.parse it back into a bytes array.
-                f.write_char('[')?;
-                let bytes = de
-                    .bytes(buf)?
-                    .into_iter()
-                    .map(|b| b.to_string())
-                    .collect::<Vec<String>>()
-                    .join(", ");
-                f.write_str(&bytes)?;
-                f.write_char(']')
-            }
-            IdlType::PublicKey => {
-                let pubkey = de.pubkey(buf)?;
-                if self.opts.pubkey_as_base58 {
-                    write_quoted(f, &pubkey.to_string())?;
+                    self.deserialize_with_visitor(de, inner, visitor, buf)
+                        .map_err(|e| {
+                            ChainparserError::CompositeDeserializeError(
+                                "Option".to_string(),
+                                Box::new(e),
+                            )
+                        })
                 } else {
-                    write!(f, "{:?}", pubkey.to_bytes())?;
+                    visitor.null_value()
                 }
-                Ok(())
             }
+            // Bytes is the same as a u8 array, thus by default it is rendered as an array of
+            // numbers in order to be able to later JSON.parse it back into a bytes array.
+            IdlType::Bytes => visitor.bytes_value(&de.bytes(buf)?),
+            IdlType::PublicKey => visitor.pubkey_value(&de.pubkey(buf)?),
             IdlType::Defined(name) => {
-                let ty = { self.type_map.lock().unwrap().get(name).cloned() };
+                let _guard = DepthGuard::enter(self.opts.max_depth)?;
+                let ty = K::get(&self.type_map, name);
+                if let Some(scale) = self.opts.scaled_decimals.get(name) {
+                    let deser = ty.ok_or_else(|| {
+                        ChainparserError::CannotFindDefinedType(
+                            name.to_string(),
+                        )
+                    })?;
+                    return self.deserialize_scaled_decimal(
+                        de,
+                        name,
+                        deser.fields.as_deref(),
+                        *scale,
+                        visitor,
+                        buf,
+                    );
+                }
                 match ty {
-                    Some(deser) => {
-                        deser.deserialize(de, f, buf).map_err(|e| {
+                    Some(deser) => deser
+                        .deserialize_with_visitor(de, visitor, buf)
+                        .map_err(|e| {
                             ChainparserError::CompositeDeserializeError(
                                 format!("Defined('{name}')"),
                                 Box::new(e),
                             )
-                        })?;
-                        Ok(())
-                    }
+                        }),
                     None => Err(ChainparserError::CannotFindDefinedType(
                         name.to_string(),
-                    ))?,
+                    )),
                 }
             }
-        }?;
-        Ok(())
+        }
+    }
+
+    /// Emits the single integer field of the defined type [name] as a decimal string with
+    /// [scale] digits after the decimal point, see [JsonSerializationOpts::scaled_decimals].
+    fn deserialize_scaled_decimal<V: ChainparserVisitor>(
+        &self,
+        de: &impl ChainparserDeserialize,
+        name: &str,
+        fields: Option<&[JsonIdlFieldDeserializer<'opts, K>]>,
+        scale: u32,
+        visitor: &mut V,
+        buf: &mut &[u8],
+    ) -> ChainparserResult<()> {
+        let invalid =
+            || ChainparserError::InvalidScaledDecimalType(name.to_string());
+        let field = match fields {
+            Some([field]) => field,
+            _ => return Err(invalid()),
+        };
+        let (negative, magnitude) = match field.ty {
+            IdlType::U8 => (false, de.u8(buf)? as u128),
+            IdlType::U16 => (false, de.u16(buf)? as u128),
+            IdlType::U32 => (false, de.u32(buf)? as u128),
+            IdlType::U64 => (false, de.u64(buf)? as u128),
+            IdlType::U128 => (false, de.u128(buf)?),
+            IdlType::I8 => signed(de.i8(buf)?.into()),
+            IdlType::I16 => signed(de.i16(buf)?.into()),
+            IdlType::I32 => signed(de.i32(buf)?.into()),
+            IdlType::I64 => signed(de.i64(buf)?.into()),
+            IdlType::I128 => signed(de.i128(buf)?),
+            _ => return Err(invalid()),
+        };
+        visitor.str_value(&scaled_decimal(negative, magnitude, scale))
     }
+
+    /// Renders a value of the [ty] as JSON in order to sort it by its JSON representation, see
+    /// [JsonSerializationOpts::sort_map_keys].
+    /// Returns it along with the bytes it was deserialized from so that it can be emitted once
+    /// sorted.
+    fn render_sortable<'b>(
+        &self,
+        de: &impl ChainparserDeserialize,
+        ty: &IdlType,
+        buf: &mut &'b [u8],
+    ) -> ChainparserResult<(String, &'b [u8])> {
+        let start = *buf;
+        let mut json = String::new();
+        self.deserialize(de, ty, &mut json, buf)?;
+        Ok((json, &start[..start.len() - buf.len()]))
+    }
+}
+
+/// Renders the [json] of a map key as the string that JSON requires keys to be, the same way
+/// that [JsonFormatWriter] writes them.
+fn json_map_key(json: String) -> String {
+    if json.starts_with('"') {
+        json
+    } else {
+        let mut key = String::new();
+        write_quoted(&mut key, &json).expect("writing to a String never fails");
+        key
+    }
+}
+
+/// Splits [n] into its sign and magnitude.
+fn signed(n: i128) -> (bool, u128) {
+    (n < 0, n.unsigned_abs())
 }
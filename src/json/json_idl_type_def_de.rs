@@ -1,40 +1,74 @@
-use std::fmt::Write;
-
 use borsh::BorshDeserialize;
-use solana_idl::{IdlTypeDefinition, IdlTypeDefinitionTy};
+use solana_idl::{IdlType, IdlTypeDefinition, IdlTypeDefinitionTy};
 
 use super::{
-    json_common::deserialize_fields_to_object,
+    json_common::{
+        deserialize_field_infos, deserialize_fields, deserialize_repr_c_fields,
+        deserialize_selected_fields, emitted_fields,
+    },
     json_idl_enum_variant_de::JsonIdlEnumVariantDeserializer,
     json_idl_field_de::JsonIdlFieldDeserializer,
-    JsonTypeDefinitionDeserializerMap,
+    FieldInfo, JsonTlvDeserializer, JsonTypeDefinitionDeserializerMap,
+    JsonTypeDefinitionDeserializers, JsonWrite, SharedTypeMap, TypeMapKind,
 };
 use crate::{
-    deserializer::ChainparserDeserialize,
+    deserializer::{ChainparserDeserialize, DeserializeProvider},
     errors::{ChainparserError, ChainparserResult},
-    json::json_serialization_opts::JsonSerializationOpts,
+    format::{ChainparserVisitor, JsonFormatWriter, JsonValueWriter},
+    json::json_serialization_opts::OptsRef,
 };
 
 #[derive(Clone)]
-pub struct JsonIdlTypeDefinitionDeserializer<'opts> {
+pub struct JsonIdlTypeDefinitionDeserializer<
+    'opts,
+    K: TypeMapKind = SharedTypeMap,
+> {
     pub name: String,
-    pub fields: Option<Vec<JsonIdlFieldDeserializer<'opts>>>,
-    pub variants: Option<Vec<JsonIdlEnumVariantDeserializer<'opts>>>,
-    pub type_map: JsonTypeDefinitionDeserializerMap<'opts>,
+    pub fields: Option<Vec<JsonIdlFieldDeserializer<'opts, K>>>,
+    pub variants: Option<Vec<JsonIdlEnumVariantDeserializer<'opts, K>>>,
+    pub type_map: JsonTypeDefinitionDeserializerMap<'opts, K>,
+    /// Overrides the deserializer of the account that includes this type, i.e. when an SPL
+    /// serialized type is nested inside a borsh account.
+    pub de_provider: Option<DeserializeProvider>,
+    /// Set when the type is laid out per `repr(C)`, i.e. for zero-copy accounts. Holds the
+    /// alignment declared via `repr(C, align(N))` or `1` if none was declared.
+    pub align: Option<usize>,
+    /// Set when the struct fields are followed by a region of TLV entries which is emitted as
+    /// an additional `tlv` field.
+    pub tlv: Option<JsonTlvDeserializer<'opts, K>>,
+    pub opts: OptsRef<'opts>,
 }
 
 impl<'opts> JsonIdlTypeDefinitionDeserializer<'opts> {
     pub fn new(
         definition: &IdlTypeDefinition,
         type_map: JsonTypeDefinitionDeserializerMap<'opts>,
-        opts: &'opts JsonSerializationOpts,
+        opts: impl Into<OptsRef<'opts>>,
+    ) -> Self {
+        let opts = opts.into();
+        Self::with_type_map(definition, type_map, opts)
+    }
+}
+
+impl<'opts, K: TypeMapKind> JsonIdlTypeDefinitionDeserializer<'opts, K> {
+    /// Creates a deserializer that resolves defined types from a [type_map] of any
+    /// [TypeMapKind], i.e. a [super::LocalTypeMap] when deserializing on a single thread.
+    pub fn with_type_map(
+        definition: &IdlTypeDefinition,
+        type_map: JsonTypeDefinitionDeserializerMap<'opts, K>,
+        opts: impl Into<OptsRef<'opts>>,
     ) -> Self {
+        let opts = opts.into();
         match &definition.ty {
             IdlTypeDefinitionTy::Struct { fields } => {
                 let fields = fields
                     .iter()
                     .map(|f| {
-                        JsonIdlFieldDeserializer::new(f, type_map.clone(), opts)
+                        JsonIdlFieldDeserializer::with_type_map(
+                            f,
+                            type_map.clone(),
+                            opts.clone(),
+                        )
                     })
                     .collect();
                 Self {
@@ -42,16 +76,20 @@ impl<'opts> JsonIdlTypeDefinitionDeserializer<'opts> {
                     fields: Some(fields),
                     variants: None,
                     type_map,
+                    de_provider: None,
+                    align: None,
+                    tlv: None,
+                    opts,
                 }
             }
             IdlTypeDefinitionTy::Enum { variants } => {
                 let variants = variants
                     .iter()
                     .map(|v| {
-                        JsonIdlEnumVariantDeserializer::new(
+                        JsonIdlEnumVariantDeserializer::with_type_map(
                             v,
                             type_map.clone(),
-                            opts,
+                            opts.clone(),
                         )
                     })
                     .collect();
@@ -60,25 +98,100 @@ impl<'opts> JsonIdlTypeDefinitionDeserializer<'opts> {
                     fields: None,
                     variants: Some(variants),
                     type_map,
+                    de_provider: None,
+                    align: None,
+                    tlv: None,
+                    opts,
                 }
             }
         }
     }
 
-    pub fn deserialize<W: Write>(
+    /// Configures the [DeserializeProvider] to use for this type instead of the one that is
+    /// passed to [Self::deserialize].
+    pub fn with_de_provider(
+        mut self,
+        de_provider: DeserializeProvider,
+    ) -> Self {
+        self.de_provider = Some(de_provider);
+        self
+    }
+
+    /// Configures this type to be deserialized per the `repr(C, align(N))` layout, inserting
+    /// padding in front of fields to align them and after the last field to pad the struct to
+    /// its alignment.
+    /// Pass an [align] of `1` for types declared with plain `repr(C)`.
+    pub fn with_repr_c(mut self, align: usize) -> Self {
+        self.align = Some(align.max(1));
+        self
+    }
+
+    /// Configures this struct to be followed by a region of TLV entries which are deserialized
+    /// via the provided [tlv] deserializer.
+    pub fn with_tlv(mut self, tlv: JsonTlvDeserializer<'opts, K>) -> Self {
+        self.tlv = Some(tlv);
+        self
+    }
+
+    /// Deserializes this type and writes it as JSON, see [JsonFormatWriter].
+    pub fn deserialize<W: JsonWrite>(
         &self,
         de: &impl ChainparserDeserialize,
         f: &mut W,
         buf: &mut &[u8],
+    ) -> ChainparserResult<()> {
+        let mut w = JsonFormatWriter::new(f, &self.opts);
+        self.deserialize_with_visitor(de, &mut w, buf)
+    }
+
+    /// Deserializes this type and emits it to the [visitor].
+    pub fn deserialize_with_visitor<V: ChainparserVisitor>(
+        &self,
+        de: &impl ChainparserDeserialize,
+        visitor: &mut V,
+        buf: &mut &[u8],
+    ) -> ChainparserResult<()> {
+        self.deserialize_enveloped(de, visitor, buf, &[])
+    }
+
+    /// Same as [Self::deserialize_with_visitor], but emits the [envelope] `(name, value)`
+    /// entries as string fields in front of the fields of the struct, i.e. to include the
+    /// account name.
+    /// The [envelope] is ignored for enums.
+    pub fn deserialize_enveloped<V: ChainparserVisitor>(
+        &self,
+        de: &impl ChainparserDeserialize,
+        visitor: &mut V,
+        buf: &mut &[u8],
+        envelope: &[(&str, &str)],
+    ) -> ChainparserResult<()> {
+        match &self.de_provider {
+            Some(DeserializeProvider::Borsh(de)) => {
+                self.deserialize_with(de, visitor, buf, envelope)
+            }
+            Some(DeserializeProvider::Spl(de)) => {
+                self.deserialize_with(de, visitor, buf, envelope)
+            }
+            None => self.deserialize_with(de, visitor, buf, envelope),
+        }
+    }
+
+    fn deserialize_with<V: ChainparserVisitor>(
+        &self,
+        de: &impl ChainparserDeserialize,
+        visitor: &mut V,
+        buf: &mut &[u8],
+        envelope: &[(&str, &str)],
     ) -> ChainparserResult<()> {
         if let Some(fields) = &self.fields {
             // Struct
-            deserialize_fields_to_object(de, f, buf, fields).map_err(|e| {
-                ChainparserError::StructDeserializeError(
-                    self.name.to_string(),
-                    Box::new(e),
-                )
-            })
+            self.deserialize_struct(de, visitor, buf, fields, envelope)
+                .map_err(|e| {
+                    ChainparserError::StructDeserializeError(
+                        self.name.to_string(),
+                        Box::new(e),
+                    )
+                })
         } else {
             // Enum
             let variants = self
@@ -90,7 +203,7 @@ impl<'opts> JsonIdlTypeDefinitionDeserializer<'opts> {
             // if shank/anchor ever supports that, we'll need to handle it here
             let discriminator = u8::deserialize(buf)?;
             match &variants.get(discriminator as usize) {
-                Some(deser) => deser.deserialize(de, f, buf),
+                Some(deser) => deser.deserialize_with_visitor(de, visitor, buf),
                 None => {
                     Err(ChainparserError::InvalidEnumVariantDiscriminator(
                         discriminator,
@@ -105,4 +218,196 @@ impl<'opts> JsonIdlTypeDefinitionDeserializer<'opts> {
             })
         }
     }
+
+    fn deserialize_struct<V: ChainparserVisitor>(
+        &self,
+        de: &impl ChainparserDeserialize,
+        visitor: &mut V,
+        buf: &mut &[u8],
+        fields: &[JsonIdlFieldDeserializer<'opts, K>],
+        envelope: &[(&str, &str)],
+    ) -> ChainparserResult<()> {
+        let len = envelope.len()
+            + emitted_fields(fields)
+            + usize::from(self.tlv.is_some());
+        visitor.begin_struct(len)?;
+        for (name, value) in envelope {
+            visitor.field_name(name)?;
+            visitor.str_value(value)?;
+        }
+        match self.repr_c_aligns(fields) {
+            Some((field_aligns, struct_align)) => deserialize_repr_c_fields(
+                de,
+                visitor,
+                buf,
+                fields,
+                &field_aligns,
+                struct_align,
+            )?,
+            None => deserialize_fields(de, visitor, buf, fields)?,
+        }
+        if let Some(tlv) = &self.tlv {
+            visitor.field_name("tlv")?;
+            tlv.deserialize_with_visitor(de, visitor, buf)?;
+        }
+        visitor.end_struct()
+    }
+
+    /// Deserializes the fields of this struct one by one and describes each of them via a
+    /// [FieldInfo], i.e. to render them in an account viewer.
+    /// The [base_offset] is the offset of the struct inside the account data and is added to the
+    /// offset of each field.
+    pub fn deserialize_field_infos(
+        &self,
+        de: &impl ChainparserDeserialize,
+        buf: &mut &[u8],
+        base_offset: usize,
+    ) -> ChainparserResult<Vec<FieldInfo>> {
+        match &self.de_provider {
+            Some(DeserializeProvider::Borsh(de)) => {
+                self.deserialize_field_infos_with(de, buf, base_offset)
+            }
+            Some(DeserializeProvider::Spl(de)) => {
+                self.deserialize_field_infos_with(de, buf, base_offset)
+            }
+            None => self.deserialize_field_infos_with(de, buf, base_offset),
+        }
+    }
+
+    fn deserialize_field_infos_with(
+        &self,
+        de: &impl ChainparserDeserialize,
+        buf: &mut &[u8],
+        base_offset: usize,
+    ) -> ChainparserResult<Vec<FieldInfo>> {
+        let fields = self.fields.as_ref().ok_or_else(|| {
+            ChainparserError::TypeHasNoFields(self.name.to_string())
+        })?;
+        let aligns = self.repr_c_aligns(fields);
+        deserialize_field_infos(
+            de,
+            buf,
+            fields,
+            aligns
+                .as_ref()
+                .map(|(field_aligns, align)| (field_aligns.as_slice(), *align)),
+            &self.type_map,
+            base_offset,
+        )
+        .map_err(|e| {
+            ChainparserError::StructDeserializeError(
+                self.name.to_string(),
+                Box::new(e),
+            )
+        })
+    }
+
+    /// Deserializes this struct but only includes the fields whose name is part of [selected]
+    /// in the returned object, i.e. to pick a few fields of a large account.
+    /// Fields that aren't selected are consumed without rendering them, fields following the
+    /// last selected field aren't consumed at all and unknown field names are ignored.
+    pub fn deserialize_selected_fields(
+        &self,
+        de: &impl ChainparserDeserialize,
+        buf: &mut &[u8],
+        selected: &[&str],
+    ) -> ChainparserResult<serde_json::Map<String, serde_json::Value>> {
+        match &self.de_provider {
+            Some(DeserializeProvider::Borsh(de)) => {
+                self.deserialize_selected_fields_with(de, buf, selected)
+            }
+            Some(DeserializeProvider::Spl(de)) => {
+                self.deserialize_selected_fields_with(de, buf, selected)
+            }
+            None => self.deserialize_selected_fields_with(de, buf, selected),
+        }
+    }
+
+    fn deserialize_selected_fields_with(
+        &self,
+        de: &impl ChainparserDeserialize,
+        buf: &mut &[u8],
+        selected: &[&str],
+    ) -> ChainparserResult<serde_json::Map<String, serde_json::Value>> {
+        let fields = self.fields.as_ref().ok_or_else(|| {
+            ChainparserError::TypeHasNoFields(self.name.to_string())
+        })?;
+        let aligns = self.repr_c_aligns(fields);
+
+        let mut w = JsonValueWriter::new(&self.opts);
+        deserialize_selected_fields(
+            de,
+            &mut w,
+            buf,
+            fields,
+            selected,
+            aligns
+                .as_ref()
+                .map(|(field_aligns, align)| (field_aligns.as_slice(), *align)),
+        )
+        .map_err(|e| {
+            ChainparserError::StructDeserializeError(
+                self.name.to_string(),
+                Box::new(e),
+            )
+        })?;
+        match w.into_value() {
+            Some(serde_json::Value::Object(fields)) => Ok(fields),
+            _ => Ok(serde_json::Map::new()),
+        }
+    }
+
+    /// Resolves the alignment of each of the [fields] along with the one of this struct if it is
+    /// laid out per `repr(C)`, see [Self::with_repr_c].
+    fn repr_c_aligns(
+        &self,
+        fields: &[JsonIdlFieldDeserializer<'opts, K>],
+    ) -> Option<(Vec<usize>, usize)> {
+        self.align?;
+        Some(K::with_map(&self.type_map, |type_map| {
+            let field_aligns = fields
+                .iter()
+                .map(|field| type_alignment(&field.ty, type_map))
+                .collect::<Vec<_>>();
+            (field_aligns, self.alignment(type_map))
+        }))
+    }
+
+    /// Resolves the alignment of this type, which is the largest alignment of its fields or
+    /// the one declared via `repr(C, align(N))` if that is larger.
+    /// Enums are assumed to be `repr(u8)`.
+    fn alignment(
+        &self,
+        type_map: &JsonTypeDefinitionDeserializers<'opts, K>,
+    ) -> usize {
+        let fields_align = self
+            .fields
+            .iter()
+            .flatten()
+            .map(|field| type_alignment(&field.ty, type_map))
+            .max()
+            .unwrap_or(1);
+        fields_align.max(self.align.unwrap_or(1))
+    }
+}
+
+/// Resolves the alignment of the given type when laid out per `repr(C)` on the SBF target
+/// that programs are compiled for.
+/// NOTE: unlike on x86_64 `u128` and `i128` are 8 byte aligned on SBF.
+fn type_alignment<K: TypeMapKind>(
+    ty: &IdlType,
+    type_map: &JsonTypeDefinitionDeserializers<'_, K>,
+) -> usize {
+    use IdlType::*;
+    match ty {
+        U16 | I16 => 2,
+        U32 | I32 | F32 => 4,
+        U64 | I64 | F64 | U128 | I128 => 8,
+        Array(inner, _) => type_alignment(inner, type_map),
+        Defined(name) => type_map
+            .get(name)
+            .map(|type_de| type_de.alignment(type_map))
+            .unwrap_or(1),
+        _ => 1,
+    }
 }
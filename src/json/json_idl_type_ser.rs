@@ -0,0 +1,445 @@
+use std::{collections::HashMap, str::FromStr};
+
+use base64::{prelude::BASE64_STANDARD, Engine as _};
+use serde_json::Value;
+use solana_idl::{
+    Idl, IdlField, IdlType, IdlTypeDefinition, IdlTypeDefinitionTy,
+};
+
+use crate::{
+    errors::{ChainparserError, ChainparserResult},
+    json::{BytesRepr, JsonSerializationOpts},
+    pubkey::Pubkey,
+    serializer::ChainparserSerialize,
+};
+
+/// Serializes JSON values back into bytes by walking the [IdlType] tree the same way as the
+/// [super::JsonIdlTypeDefinitionDeserializer] does when deserializing them.
+/// Thus it accepts the JSON that was produced when deserializing an account, i.e. with 64 bit
+/// numbers stringified or pubkeys as base58 strings or byte arrays.
+/// Bytes written as hex or base64 strings and scaled decimals are accepted once the opts that
+/// were used to deserialize them are provided via [JsonIdlTypeSerializer::with_opts].
+///
+/// NOTE: enums, maps and sets are not supported yet.
+/// NOTE: 128 bit numbers exceeding the 64 bit range lose precision when parsed from JSON
+/// numbers, thus they need to be stringified via
+/// [super::JsonSerializationOpts::n128_as_string] or
+/// [super::JsonSerializationOpts::n128_as_hex] in order to round trip.
+pub struct JsonIdlTypeSerializer {
+    type_map: HashMap<String, IdlTypeDefinitionTy>,
+    /// How strings provided for bytes are decoded, see [JsonSerializationOpts::bytes_as].
+    bytes_as: BytesRepr,
+    /// Defined types provided as decimal strings, see
+    /// [JsonSerializationOpts::scaled_decimals].
+    scaled_decimals: HashMap<String, u32>,
+}
+
+impl JsonIdlTypeSerializer {
+    /// Creates a serializer that resolves defined types via the provided [types].
+    pub fn new(types: &[IdlTypeDefinition]) -> Self {
+        let type_map = types
+            .iter()
+            .map(|def| (def.name.clone(), def.ty.clone()))
+            .collect();
+        Self {
+            type_map,
+            bytes_as: BytesRepr::Array,
+            scaled_decimals: HashMap::new(),
+        }
+    }
+
+    /// Accepts bytes and scaled decimals rendered per the provided [opts], i.e. the opts that
+    /// were used to deserialize the JSON.
+    pub fn with_opts(mut self, opts: &JsonSerializationOpts) -> Self {
+        self.bytes_as = opts.bytes_as;
+        self.scaled_decimals.clone_from(&opts.scaled_decimals);
+        self
+    }
+
+    /// Creates a serializer that resolves the types and accounts defined in the [idl].
+    pub fn from_idl(idl: &Idl) -> Self {
+        let defs = idl
+            .types
+            .iter()
+            .chain(idl.accounts.iter())
+            .cloned()
+            .collect::<Vec<_>>();
+        Self::new(&defs)
+    }
+
+    /// Serializes the [value] of the defined type with the given [type_name].
+    pub fn serialize_defined(
+        &self,
+        ser: &impl ChainparserSerialize,
+        type_name: &str,
+        value: &Value,
+        buf: &mut Vec<u8>,
+    ) -> ChainparserResult<()> {
+        let ty = self.type_map.get(type_name).ok_or_else(|| {
+            ChainparserError::CannotFindDefinedType(type_name.to_string())
+        })?;
+        if let Some(scale) = self.scaled_decimals.get(type_name) {
+            return self.serialize_scaled_decimal(
+                ser, type_name, ty, *scale, value, buf,
+            );
+        }
+        match ty {
+            IdlTypeDefinitionTy::Struct { fields } => {
+                self.serialize_fields(ser, fields, value, buf).map_err(|e| {
+                    ChainparserError::StructSerializeError(
+                        type_name.to_string(),
+                        Box::new(e),
+                    )
+                })
+            }
+            IdlTypeDefinitionTy::Enum { .. } => {
+                Err(ChainparserError::InvalidDataToSerialize(
+                    type_name.to_string(),
+                    "enums are not supported yet".to_string(),
+                ))
+            }
+        }
+    }
+
+    /// Serializes the [fields] in order, taking the value of each from the [value] object.
+    pub fn serialize_fields(
+        &self,
+        ser: &impl ChainparserSerialize,
+        fields: &[IdlField],
+        value: &Value,
+        buf: &mut Vec<u8>,
+    ) -> ChainparserResult<()> {
+        let object = value.as_object().ok_or_else(|| {
+            invalid("struct", format!("expected an object, got {value}"))
+        })?;
+        for field in fields {
+            let field_value = object.get(&field.name).ok_or_else(|| {
+                ChainparserError::FieldSerializeError(
+                    field.name.to_string(),
+                    Box::new(invalid("struct", "missing field".to_string())),
+                )
+            })?;
+            self.serialize(ser, &field.ty, field_value, buf)
+                .map_err(|e| {
+                    ChainparserError::FieldSerializeError(
+                        field.name.to_string(),
+                        Box::new(e),
+                    )
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Serializes the [value] of the given [ty].
+    pub fn serialize(
+        &self,
+        ser: &impl ChainparserSerialize,
+        ty: &IdlType,
+        value: &Value,
+        buf: &mut Vec<u8>,
+    ) -> ChainparserResult<()> {
+        use IdlType::*;
+        match ty {
+            U8 => ser.u8(buf, unsigned(value, "u8")?),
+            U16 => ser.u16(buf, unsigned(value, "u16")?),
+            U32 => ser.u32(buf, unsigned(value, "u32")?),
+            U64 => ser.u64(buf, unsigned(value, "u64")?),
+            U128 => ser.u128(buf, unsigned(value, "u128")?),
+
+            I8 => ser.i8(buf, signed(value, "i8")?),
+            I16 => ser.i16(buf, signed(value, "i16")?),
+            I32 => ser.i32(buf, signed(value, "i32")?),
+            I64 => ser.i64(buf, signed(value, "i64")?),
+            I128 => ser.i128(buf, signed(value, "i128")?),
+
+            F32 => ser.f32(buf, float(value, "f32")? as f32),
+            F64 => ser.f64(buf, float(value, "f64")?),
+
+            Bool => match value.as_bool() {
+                Some(b) => ser.bool(buf, b),
+                None => Err(invalid("bool", format!("got {value}"))),
+            },
+            String => match value.as_str() {
+                Some(s) => ser.string(buf, s),
+                None => Err(invalid("string", format!("got {value}"))),
+            },
+            Bytes => ser.bytes(buf, &self.bytes(value, "bytes")?),
+            PublicKey => ser.pubkey(buf, &pubkey(value)?),
+
+            // Composites
+            Tuple(inners) => {
+                let items = array(value, "tuple", Some(inners.len()))?;
+                for (inner, item) in inners.iter().zip(items) {
+                    self.serialize(ser, inner, item, buf)?;
+                }
+                Ok(())
+            }
+            // Hashes and seeds are rendered like bytes
+            Array(inner, len) if **inner == U8 => {
+                let bytes = self.bytes(value, "array")?;
+                if bytes.len() != *len {
+                    return Err(invalid(
+                        "array",
+                        format!("expected {len} bytes, got {}", bytes.len()),
+                    ));
+                }
+                bytes.into_iter().try_for_each(|b| ser.u8(buf, b))
+            }
+            Vec(inner) if **inner == U8 => {
+                let bytes = self.bytes(value, "vec")?;
+                ser.u32(buf, bytes.len() as u32)?;
+                bytes.into_iter().try_for_each(|b| ser.u8(buf, b))
+            }
+            Array(inner, len) => {
+                let items = array(value, "array", Some(*len))?;
+                for (i, item) in items.iter().enumerate() {
+                    self.serialize(ser, inner, item, buf).map_err(|e| {
+                        ChainparserError::CompositeSerializeError(
+                            format!("Array[{i}] size({len})"),
+                            Box::new(e),
+                        )
+                    })?;
+                }
+                Ok(())
+            }
+            Vec(inner) => {
+                let items = array(value, "vec", None)?;
+                ser.u32(buf, items.len() as u32)?;
+                for (i, item) in items.iter().enumerate() {
+                    self.serialize(ser, inner, item, buf).map_err(|e| {
+                        ChainparserError::CompositeSerializeError(
+                            format!("Vec[{i}] size({})", items.len()),
+                            Box::new(e),
+                        )
+                    })?;
+                }
+                Ok(())
+            }
+            Option(inner) => {
+                ser.option(buf, !value.is_null())?;
+                if value.is_null() {
+                    Ok(())
+                } else {
+                    self.serialize(ser, inner, value, buf)
+                }
+            }
+            COption(inner) => {
+                ser.coption(buf, !value.is_null(), inner)?;
+                if value.is_null() {
+                    Ok(())
+                } else {
+                    self.serialize(ser, inner, value, buf)
+                }
+            }
+            Defined(name) => self.serialize_defined(ser, name, value, buf),
+            HashMap(_, _) | BTreeMap(_, _) | HashSet(_) | BTreeSet(_) => {
+                Err(invalid(
+                    "map/set",
+                    "maps and sets are not supported yet".to_string(),
+                ))
+            }
+        }
+    }
+}
+
+impl JsonIdlTypeSerializer {
+    /// Bytes are provided as arrays of numbers or as strings encoded per [Self::bytes_as].
+    fn bytes(&self, value: &Value, ty: &str) -> ChainparserResult<Vec<u8>> {
+        let Value::String(s) = value else {
+            return byte_array(value, ty);
+        };
+        match self.bytes_as {
+            BytesRepr::Array => None,
+            BytesRepr::Hex => decode_hex(s),
+            BytesRepr::Base64 => BASE64_STANDARD.decode(s).ok(),
+        }
+        .ok_or_else(|| {
+            invalid(
+                ty,
+                format!("expected {:?} bytes, got {value}", self.bytes_as),
+            )
+        })
+    }
+
+    /// Serializes the decimal string [value] as the single integer field of the defined type
+    /// [name] that holds it scaled by [scale] digits, see
+    /// [JsonSerializationOpts::scaled_decimals].
+    fn serialize_scaled_decimal(
+        &self,
+        ser: &impl ChainparserSerialize,
+        name: &str,
+        ty: &IdlTypeDefinitionTy,
+        scale: u32,
+        value: &Value,
+        buf: &mut Vec<u8>,
+    ) -> ChainparserResult<()> {
+        let field = match ty {
+            IdlTypeDefinitionTy::Struct { fields } if fields.len() == 1 => {
+                &fields[0]
+            }
+            _ => {
+                return Err(ChainparserError::InvalidScaledDecimalType(
+                    name.to_string(),
+                ))
+            }
+        };
+        let n = value
+            .as_str()
+            .and_then(|s| unscale_decimal(s, scale))
+            .ok_or_else(|| {
+                invalid(name, format!("expected a decimal string, got {value}"))
+            })?;
+        self.serialize(ser, &field.ty, &Value::String(n), buf)
+    }
+}
+
+fn invalid(ty: &str, msg: String) -> ChainparserError {
+    ChainparserError::InvalidDataToSerialize(ty.to_string(), msg)
+}
+
+/// Numbers may be provided as JSON numbers or strings, i.e. when they were stringified due to
+/// [super::JsonSerializationOpts::n64_as_string], or as `0x` prefixed hex strings due to
+/// [super::JsonSerializationOpts::n128_as_hex].
+fn unsigned<T: TryFrom<u128>>(value: &Value, ty: &str) -> ChainparserResult<T> {
+    let n = match value {
+        Value::Number(n) => n.as_u64().map(u128::from),
+        Value::String(s) => match s.strip_prefix("0x") {
+            Some(hex) => u128::from_str_radix(hex, 16).ok(),
+            None => s.parse::<u128>().ok(),
+        },
+        _ => None,
+    };
+    n.and_then(|n| T::try_from(n).ok())
+        .ok_or_else(|| invalid(ty, format!("got {value}")))
+}
+
+fn signed<T: TryFrom<i128>>(value: &Value, ty: &str) -> ChainparserResult<T> {
+    let n = match value {
+        Value::Number(n) => n.as_i64().map(i128::from),
+        Value::String(s) => match s.strip_prefix("-0x") {
+            Some(hex) => i128::from_str_radix(&format!("-{hex}"), 16).ok(),
+            None => match s.strip_prefix("0x") {
+                Some(hex) => i128::from_str_radix(hex, 16).ok(),
+                None => s.parse::<i128>().ok(),
+            },
+        },
+        _ => None,
+    };
+    n.and_then(|n| T::try_from(n).ok())
+        .ok_or_else(|| invalid(ty, format!("got {value}")))
+}
+
+fn float(value: &Value, ty: &str) -> ChainparserResult<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse::<f64>().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| invalid(ty, format!("got {value}")))
+}
+
+fn array<'a>(
+    value: &'a Value,
+    ty: &str,
+    len: Option<usize>,
+) -> ChainparserResult<&'a Vec<Value>> {
+    let items = value.as_array().ok_or_else(|| {
+        invalid(ty, format!("expected an array, got {value}"))
+    })?;
+    match len {
+        Some(len) if items.len() != len => Err(invalid(
+            ty,
+            format!("expected {len} items, got {}", items.len()),
+        )),
+        _ => Ok(items),
+    }
+}
+
+fn byte_array(value: &Value, ty: &str) -> ChainparserResult<Vec<u8>> {
+    array(value, ty, None)?
+        .iter()
+        .map(|b| unsigned::<u8>(b, "u8"))
+        .collect()
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Converts a decimal string with at most [scale] digits after the decimal point into the
+/// integer string it was scaled from, i.e. `"1.5"` with a scale of `6` becomes `"1500000"`.
+fn unscale_decimal(s: &str, scale: u32) -> Option<String> {
+    let (sign, digits) = match s.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", s),
+    };
+    let (int, frac) = digits.split_once('.').unwrap_or((digits, ""));
+    let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if int.is_empty()
+        || frac.len() > scale as usize
+        || !all_digits(int)
+        || !all_digits(frac)
+    {
+        return None;
+    }
+    Some(format!(
+        "{sign}{int}{frac:0<width$}",
+        width = scale as usize
+    ))
+}
+
+/// Pubkeys are provided as base58 strings or byte arrays depending on
+/// [super::JsonSerializationOpts::pubkey_as_base58].
+fn pubkey(value: &Value) -> ChainparserResult<Pubkey> {
+    match value {
+        Value::String(s) => {
+            Pubkey::from_str(s).map_err(|e| invalid("pubkey", e.to_string()))
+        }
+        _ => {
+            let bytes = byte_array(value, "pubkey")?;
+            Pubkey::try_from(bytes.as_slice())
+                .map_err(|e| invalid("pubkey", format!("{value} ({e})")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_hex_numbers() {
+        let value = Value::String("0x10000000000000000".to_string());
+        assert_eq!(unsigned::<u128>(&value, "u128").unwrap(), 1 << 64);
+        assert_eq!(signed::<i128>(&value, "i128").unwrap(), 1 << 64);
+
+        let value = Value::String("-0x1f".to_string());
+        assert_eq!(signed::<i128>(&value, "i128").unwrap(), -31);
+        assert!(unsigned::<u128>(&value, "u128").is_err());
+    }
+
+    #[test]
+    fn unscale_decimals() {
+        assert_eq!(unscale_decimal("1.500000", 6).as_deref(), Some("1500000"));
+        assert_eq!(unscale_decimal("0.000042", 6).as_deref(), Some("0000042"));
+        assert_eq!(unscale_decimal("-1.5", 6).as_deref(), Some("-1500000"));
+        assert_eq!(unscale_decimal("42", 0).as_deref(), Some("42"));
+        assert_eq!(unscale_decimal("1.5000001", 6), None);
+        assert_eq!(unscale_decimal(".5", 6), None);
+        assert_eq!(unscale_decimal("1e6", 6), None);
+    }
+
+    #[test]
+    fn decode_hex_bytes() {
+        assert_eq!(decode_hex("01ff0a"), Some(vec![1, 255, 10]));
+        assert_eq!(decode_hex(""), Some(vec![]));
+        assert_eq!(decode_hex("0"), None);
+        assert_eq!(decode_hex("zz"), None);
+    }
+}
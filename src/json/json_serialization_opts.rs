@@ -1,7 +1,108 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Deref,
+    sync::Arc,
+};
+
+use crate::errors::{ChainparserError, ChainparserResult};
+
 pub struct JsonSerializationOpts {
     pub pubkey_as_base58: bool,
     pub n64_as_string: bool,
     pub n128_as_string: bool,
+    /// Writes `u128` and `i128` values as `0x` prefixed hex strings, i.e. `"0x1f"` or `"-0x1f"`,
+    /// which map cleanly to the bit fields of Q-format fixed-point numbers.
+    /// Mutually exclusive with [Self::n128_as_string], see [Self::validate].
+    pub n128_as_hex: bool,
+    /// The maximum number of nested defined types to deserialize before failing with
+    /// [crate::errors::ChainparserError::MaxDepthExceeded], i.e. for self-referential types.
+    pub max_depth: usize,
+    /// Decodes any nonzero bool byte as `true` instead of failing for bytes other than `0` and
+    /// `1`, i.e. for serializers that encode `true` as `0xff`.
+    pub lenient_bool: bool,
+    /// Replaces invalid UTF-8 sequences of strings with `U+FFFD` instead of failing, i.e. for
+    /// byte arrays that programs treat as strings.
+    pub lossy_strings: bool,
+    /// Writes `null` for struct fields that fail to deserialize and continues with the next
+    /// field instead of failing, i.e. for exploratory indexing.
+    /// Since fields are read sequentially this only applies to fields with a fixed size, i.e.
+    /// integers, bools, pubkeys, arrays and structs thereof. Fields without a fixed size, i.e.
+    /// strings, vecs or options, as well as fields exceeding the account data still fail.
+    pub best_effort: bool,
+    /// How NaN and infinite floats are rendered, see [NonFiniteRepr].
+    pub non_finite: NonFiniteRepr,
+    /// Adds the name of the account as the first entry of deserialized accounts keyed by
+    /// [Self::account_name_key], i.e. to tell apart accounts in a stream of different types.
+    pub include_account_name: bool,
+    /// Adds the hex encoded discriminator of accounts that are prefixed with one keyed by
+    /// [Self::discriminator_key].
+    pub include_discriminator: bool,
+    /// The key of the account name entry, change it if it clashes with an account field.
+    pub account_name_key: String,
+    /// The key of the discriminator entry, change it if it clashes with an account field.
+    pub discriminator_key: String,
+    /// Names of fields that are consumed from the account data but omitted from the output,
+    /// i.e. `padding` or `reserved` byte arrays. Applies to fields of nested structs as well.
+    pub skip_fields: HashSet<String>,
+    /// How `bytes` as well as `u8` arrays and vecs are rendered, see [BytesRepr].
+    pub bytes_as: BytesRepr,
+    /// Writes the entries of maps and sets sorted by their JSON representation instead of in
+    /// the order they were serialized, i.e. to diff deserialized accounts.
+    /// This buffers all entries of a map or set before writing it.
+    pub sort_map_keys: bool,
+    /// Maps names of defined types that wrap a single integer field, i.e. fixed-point amounts,
+    /// to the number of decimals implied by that integer.
+    /// Values of those types are written as decimal strings, i.e. with `{"Usd": 6}` a `Usd`
+    /// holding `1500000` is written as `"1.500000"`.
+    /// Serializing them back into bytes requires a [super::JsonIdlTypeSerializer] configured
+    /// with [super::JsonIdlTypeSerializer::with_opts].
+    pub scaled_decimals: HashMap<String, u32>,
+    /// How accounts that don't match any account of the IDL are handled, see [UnknownPolicy].
+    pub unknown_account: UnknownPolicy,
+    /// The maximum number of bytes of JSON to write per account before failing with
+    /// [crate::errors::ChainparserError::OutputTooLarge], i.e. to guard services that decode
+    /// untrusted account data against data that expands into huge JSON.
+    /// Only applies when deserializing to JSON.
+    pub max_output_bytes: Option<usize>,
+}
+
+/// Handling of account data that doesn't match any account of an IDL, i.e. to keep indexing
+/// programs whose IDL is incomplete.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnknownPolicy {
+    /// Fails with the error that the account could not be resolved.
+    #[default]
+    Error,
+    /// Writes the hex encoded account data as `{"_unknown":true,"data":"<hex>"}`.
+    HexDump,
+    /// Writes `null`.
+    Skip,
+}
+
+/// Representation of NaN and infinite floats which JSON has no literal for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NonFiniteRepr {
+    /// Writes `NaN`, `inf` and `-inf` as is, which only non-strict JSON parsers accept.
+    #[default]
+    Literal,
+    /// Writes `null`.
+    Null,
+    /// Writes the strings `"NaN"`, `"Infinity"` and `"-Infinity"`.
+    String,
+}
+
+/// Representation of `bytes`, `[u8; N]` and `Vec<u8>` values, i.e. hashes or seeds.
+/// Strings are decoded per the [BytesRepr] when serializing them back into bytes via a
+/// [super::JsonIdlTypeSerializer] configured with [super::JsonIdlTypeSerializer::with_opts].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BytesRepr {
+    /// Writes an array of numbers, i.e. `[1, 2, 3]`.
+    #[default]
+    Array,
+    /// Writes a lowercase hex string, i.e. `"010203"`.
+    Hex,
+    /// Writes a standard base64 string with padding, i.e. `"AQID"`.
+    Base64,
 }
 
 impl Default for JsonSerializationOpts {
@@ -10,6 +111,75 @@ impl Default for JsonSerializationOpts {
             pubkey_as_base58: true,
             n64_as_string: false,
             n128_as_string: false,
+            n128_as_hex: false,
+            max_depth: 128,
+            lenient_bool: false,
+            lossy_strings: false,
+            best_effort: false,
+            non_finite: NonFiniteRepr::Literal,
+            include_account_name: false,
+            include_discriminator: false,
+            account_name_key: "_account".to_string(),
+            discriminator_key: "_discriminator".to_string(),
+            skip_fields: HashSet::new(),
+            bytes_as: BytesRepr::Array,
+            sort_map_keys: false,
+            scaled_decimals: HashMap::new(),
+            unknown_account: UnknownPolicy::Error,
+            max_output_bytes: None,
+        }
+    }
+}
+
+impl JsonSerializationOpts {
+    /// Fails with [ChainparserError::ConflictingSerializationOpts] if options are combined
+    /// that contradict each other, i.e. [Self::n128_as_hex] and [Self::n128_as_string].
+    /// Adding an IDL validates the opts it is deserialized with.
+    pub fn validate(&self) -> ChainparserResult<()> {
+        if self.n128_as_hex && self.n128_as_string {
+            return Err(ChainparserError::ConflictingSerializationOpts(
+                "n128_as_hex".to_string(),
+                "n128_as_string".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Refers to the [JsonSerializationOpts] that the deserializers use, either borrowed from the
+/// caller or shared via an [Arc] so that deserializers owning their opts are `'static`, see
+/// [crate::ChainparserDeserializer::with_owned_opts].
+#[derive(Clone)]
+pub enum OptsRef<'opts> {
+    Borrowed(&'opts JsonSerializationOpts),
+    Shared(Arc<JsonSerializationOpts>),
+}
+
+impl Deref for OptsRef<'_> {
+    type Target = JsonSerializationOpts;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Borrowed(opts) => opts,
+            Self::Shared(opts) => opts,
         }
     }
 }
+
+impl<'opts> From<&'opts JsonSerializationOpts> for OptsRef<'opts> {
+    fn from(opts: &'opts JsonSerializationOpts) -> Self {
+        Self::Borrowed(opts)
+    }
+}
+
+impl From<Arc<JsonSerializationOpts>> for OptsRef<'static> {
+    fn from(opts: Arc<JsonSerializationOpts>) -> Self {
+        Self::Shared(opts)
+    }
+}
+
+impl From<JsonSerializationOpts> for OptsRef<'static> {
+    fn from(opts: JsonSerializationOpts) -> Self {
+        Self::Shared(Arc::new(opts))
+    }
+}
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+
+use solana_idl::IdlTypeDefinition;
+
+use super::{
+    JsonIdlTypeDefinitionDeserializer, JsonTypeDefinitionDeserializerMap,
+    SharedTypeMap, TypeMapKind,
+};
+use crate::{
+    deserializer::ChainparserDeserialize,
+    errors::{ChainparserError, ChainparserResult},
+    format::ChainparserVisitor,
+    json::json_serialization_opts::OptsRef,
+};
+
+/// Size of the header of each TLV entry, a `u16` type followed by a `u16` length.
+const TLV_HEADER_SIZE: usize = 4;
+
+/// Deserializes a region of Type-Length-Value entries as found in extensible accounts, i.e.
+/// Token-2022 accounts with extensions.
+///
+/// Each entry starts with a `u16` type discriminator followed by the `u16` length of its value.
+/// Entries whose type is known are deserialized via the matching type definition, unknown
+/// entries are emitted as hex along with their type.
+/// An entry of type `0` marks the uninitialized remainder of the region.
+#[derive(Clone)]
+pub struct JsonTlvDeserializer<'opts, K: TypeMapKind = SharedTypeMap> {
+    pub entries: HashMap<u16, JsonIdlTypeDefinitionDeserializer<'opts, K>>,
+
+    /// Number of bytes between the end of the struct and the first entry, i.e. the padding and
+    /// account type of Token-2022 accounts.
+    pub offset: usize,
+}
+
+impl<'opts> JsonTlvDeserializer<'opts> {
+    pub fn new(
+        entries: &HashMap<u16, IdlTypeDefinition>,
+        type_map: JsonTypeDefinitionDeserializerMap<'opts>,
+        opts: impl Into<OptsRef<'opts>>,
+    ) -> Self {
+        let opts = opts.into();
+        Self::with_type_map(entries, type_map, opts)
+    }
+}
+
+impl<'opts, K: TypeMapKind> JsonTlvDeserializer<'opts, K> {
+    /// Creates a deserializer that resolves defined types from a [type_map] of any
+    /// [TypeMapKind].
+    pub fn with_type_map(
+        entries: &HashMap<u16, IdlTypeDefinition>,
+        type_map: JsonTypeDefinitionDeserializerMap<'opts, K>,
+        opts: impl Into<OptsRef<'opts>>,
+    ) -> Self {
+        let opts = opts.into();
+        let entries = entries
+            .iter()
+            .map(|(ty, definition)| {
+                let de = JsonIdlTypeDefinitionDeserializer::with_type_map(
+                    definition,
+                    type_map.clone(),
+                    opts.clone(),
+                );
+                (*ty, de)
+            })
+            .collect();
+        Self { entries, offset: 0 }
+    }
+
+    /// Configures the number of bytes to skip before the first entry.
+    /// If the data ends before the entries start the region is considered empty.
+    pub fn with_offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Walks the TLV entries in [buf] and emits them as a sequence of structs holding the
+    /// `type` along with the `name` and `value` of known entries or the hex `data` of unknown
+    /// ones.
+    pub fn deserialize_with_visitor<V: ChainparserVisitor>(
+        &self,
+        de: &impl ChainparserDeserialize,
+        visitor: &mut V,
+        buf: &mut &[u8],
+    ) -> ChainparserResult<()> {
+        *buf = buf.get(self.offset..).unwrap_or_default();
+        let mut entries = Vec::new();
+        while let Some(entry) = next_tlv_entry(buf)? {
+            entries.push(entry);
+        }
+
+        visitor.begin_seq(entries.len())?;
+        for (ty, value) in entries {
+            match self.entries.get(&ty) {
+                Some(entry_de) => {
+                    visitor.begin_struct(3)?;
+                    visitor.field_name("type")?;
+                    visitor.u16_value(ty)?;
+                    visitor.field_name("name")?;
+                    visitor.str_value(&entry_de.name)?;
+                    visitor.field_name("value")?;
+                    entry_de.deserialize_with_visitor(
+                        de,
+                        visitor,
+                        &mut &value[..],
+                    )?;
+                }
+                None => {
+                    visitor.begin_struct(2)?;
+                    visitor.field_name("type")?;
+                    visitor.u16_value(ty)?;
+                    visitor.field_name("data")?;
+                    let data: String =
+                        value.iter().map(|b| format!("{b:02x}")).collect();
+                    visitor.str_value(&data)?;
+                }
+            }
+            visitor.end_struct()?;
+        }
+        visitor.end_seq()
+    }
+}
+
+/// Collects the `(type, value)` of all TLV entries in [buf] up to the first entry of type `0`
+/// which marks the uninitialized remainder of the region.
+pub fn tlv_entries(mut buf: &[u8]) -> ChainparserResult<Vec<(u16, &[u8])>> {
+    let mut entries = Vec::new();
+    while let Some(entry) = next_tlv_entry(&mut buf)? {
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Reads the next TLV entry from [buf] and advances it past that entry.
+/// Returns `None` once the region ends.
+fn next_tlv_entry<'a>(
+    buf: &mut &'a [u8],
+) -> ChainparserResult<Option<(u16, &'a [u8])>> {
+    if buf.len() < TLV_HEADER_SIZE {
+        return Ok(None);
+    }
+    let ty = u16::from_le_bytes([buf[0], buf[1]]);
+    let len = u16::from_le_bytes([buf[2], buf[3]]) as usize;
+    if ty == 0 {
+        return Ok(None);
+    }
+    let remaining = buf.len() - TLV_HEADER_SIZE;
+    if len > remaining {
+        return Err(ChainparserError::TlvEntryExceedsData(ty, len, remaining));
+    }
+    let value = &buf[TLV_HEADER_SIZE..TLV_HEADER_SIZE + len];
+    *buf = &buf[TLV_HEADER_SIZE + len..];
+    Ok(Some((ty, value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collect_tlv_entries() {
+        let buf = [
+            &[3u8, 0, 2, 0, 0xaa, 0xbb][..],
+            &[7, 0, 0, 0],
+            &[0, 0, 0, 0, 0xff],
+        ]
+        .concat();
+        assert_eq!(
+            tlv_entries(&buf).unwrap(),
+            vec![(3, &[0xaa, 0xbb][..]), (7, &[][..])]
+        );
+        assert!(matches!(
+            tlv_entries(&[3, 0, 9, 0, 0xaa]),
+            Err(ChainparserError::TlvEntryExceedsData(3, 9, 1))
+        ));
+    }
+}
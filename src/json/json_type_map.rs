@@ -0,0 +1,214 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    rc::Rc,
+    sync::{Arc, Mutex},
+};
+
+use solana_idl::IdlType;
+
+use super::JsonIdlTypeDefinitionDeserializer;
+
+/// How deep [TypeMapKind::min_type_bytes] resolves nested defined types.
+const MIN_BYTES_MAX_DEPTH: usize = 16;
+
+/// The deserializers of the defined types of an IDL keyed by type name.
+pub type JsonTypeDefinitionDeserializers<'opts, K> =
+    HashMap<String, JsonIdlTypeDefinitionDeserializer<'opts, K>>;
+
+/// Selects how the map of defined type deserializers is shared between the deserializers that
+/// resolve defined types from it.
+///
+/// - [SharedTypeMap] guards the map with an [Arc] and [Mutex] so deserializers can be sent
+///   across threads
+/// - [LocalTypeMap] uses an [Rc] and [RefCell] instead which avoids atomic reference counting
+///   and locking when deserializing on a single thread
+pub trait TypeMapKind: Clone + Sized + 'static {
+    type Map<'opts>: Clone;
+
+    /// Creates an empty type map.
+    fn new_map<'opts>() -> Self::Map<'opts>;
+
+    /// Provides mutable access to the deserializers inside the [map] for the duration of [f].
+    fn with_map<'opts, R>(
+        map: &Self::Map<'opts>,
+        f: impl FnOnce(&mut JsonTypeDefinitionDeserializers<'opts, Self>) -> R,
+    ) -> R;
+
+    /// Returns a clone of the deserializer of the type with the given [name].
+    fn get<'opts>(
+        map: &Self::Map<'opts>,
+        name: &str,
+    ) -> Option<JsonIdlTypeDefinitionDeserializer<'opts, Self>> {
+        Self::with_map(map, |map| map.get(name).cloned())
+    }
+
+    /// Adds the deserializer of the type with the given [name].
+    fn insert<'opts>(
+        map: &Self::Map<'opts>,
+        name: String,
+        de: JsonIdlTypeDefinitionDeserializer<'opts, Self>,
+    ) {
+        Self::with_map(map, |map| map.insert(name, de));
+    }
+
+    /// Returns a lower bound of the number of bytes that a value of the given [ty] takes up,
+    /// resolving defined types from the [map].
+    /// Enums count as their variant index only and types nested deeper than
+    /// [MIN_BYTES_MAX_DEPTH] as empty, thus it never exceeds the actual size.
+    fn min_type_bytes(map: &Self::Map<'_>, ty: &IdlType) -> usize {
+        Self::with_map(map, |map| min_type_bytes(map, ty, MIN_BYTES_MAX_DEPTH))
+    }
+}
+
+fn min_type_bytes<K: TypeMapKind>(
+    map: &JsonTypeDefinitionDeserializers<'_, K>,
+    ty: &IdlType,
+    depth: usize,
+) -> usize {
+    use IdlType::*;
+    match ty {
+        Bool | U8 | I8 => 1,
+        U16 | I16 => 2,
+        U32 | I32 | F32 => 4,
+        U64 | I64 | F64 => 8,
+        U128 | I128 => 16,
+        PublicKey => 32,
+        Bytes
+        | String
+        | Vec(_)
+        | HashMap(_, _)
+        | BTreeMap(_, _)
+        | HashSet(_)
+        | BTreeSet(_)
+        | COption(_) => 4,
+        Option(_) => 1,
+        Array(inner, len) => {
+            min_type_bytes(map, inner, depth).saturating_mul(*len)
+        }
+        Tuple(tys) => tys
+            .iter()
+            .map(|ty| min_type_bytes(map, ty, depth))
+            .fold(0, usize::saturating_add),
+        Defined(name) if depth > 0 => match map.get(name) {
+            Some(de) => match &de.fields {
+                Some(fields) => fields
+                    .iter()
+                    .map(|field| min_type_bytes(map, &field.ty, depth - 1))
+                    .fold(0, usize::saturating_add),
+                None => usize::from(de.variants.is_some()),
+            },
+            None => 0,
+        },
+        Defined(_) => 0,
+    }
+}
+
+/// Type map that can be shared across threads, see [TypeMapKind].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SharedTypeMap;
+
+impl TypeMapKind for SharedTypeMap {
+    type Map<'opts> = Arc<Mutex<JsonTypeDefinitionDeserializers<'opts, Self>>>;
+
+    fn new_map<'opts>() -> Self::Map<'opts> {
+        Arc::new(Mutex::new(HashMap::new()))
+    }
+
+    fn with_map<'opts, R>(
+        map: &Self::Map<'opts>,
+        f: impl FnOnce(&mut JsonTypeDefinitionDeserializers<'opts, Self>) -> R,
+    ) -> R {
+        f(&mut map.lock().unwrap())
+    }
+}
+
+/// Type map for single-threaded use which avoids the cost of locking, see [TypeMapKind].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LocalTypeMap;
+
+impl TypeMapKind for LocalTypeMap {
+    type Map<'opts> = Rc<RefCell<JsonTypeDefinitionDeserializers<'opts, Self>>>;
+
+    fn new_map<'opts>() -> Self::Map<'opts> {
+        Rc::new(RefCell::new(HashMap::new()))
+    }
+
+    fn with_map<'opts, R>(
+        map: &Self::Map<'opts>,
+        f: impl FnOnce(&mut JsonTypeDefinitionDeserializers<'opts, Self>) -> R,
+    ) -> R {
+        f(&mut map.borrow_mut())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_idl::{
+        IdlField, IdlType, IdlTypeDefinition, IdlTypeDefinitionTy,
+    };
+
+    use super::*;
+    use crate::{borsh::BorshDeserializer, json::JsonSerializationOpts};
+
+    fn definition(
+        name: &str,
+        fields: Vec<(&str, IdlType)>,
+    ) -> IdlTypeDefinition {
+        IdlTypeDefinition {
+            name: name.to_string(),
+            ty: IdlTypeDefinitionTy::Struct {
+                fields: fields
+                    .into_iter()
+                    .map(|(name, ty)| IdlField {
+                        name: name.to_string(),
+                        ty,
+                        attrs: None,
+                    })
+                    .collect(),
+            },
+        }
+    }
+
+    fn deserialize<K: TypeMapKind>(opts: &JsonSerializationOpts) -> String {
+        let type_map = K::new_map();
+        let inner = definition("Inner", vec![("value", IdlType::U16)]);
+        let outer = definition(
+            "Outer",
+            vec![
+                ("inner", IdlType::Defined("Inner".to_string())),
+                ("flag", IdlType::Bool),
+            ],
+        );
+        K::insert(
+            &type_map,
+            "Inner".to_string(),
+            JsonIdlTypeDefinitionDeserializer::<K>::with_type_map(
+                &inner,
+                type_map.clone(),
+                opts,
+            ),
+        );
+        let outer_de = JsonIdlTypeDefinitionDeserializer::<K>::with_type_map(
+            &outer,
+            type_map.clone(),
+            opts,
+        );
+
+        let mut json = String::new();
+        outer_de
+            .deserialize(&BorshDeserializer, &mut json, &mut &[1u8, 2, 1][..])
+            .unwrap();
+        K::with_map(&type_map, |map| map.clear());
+        json
+    }
+
+    #[test]
+    fn local_and_shared_type_maps_deserialize_the_same() {
+        let opts = JsonSerializationOpts::default();
+        let shared = deserialize::<SharedTypeMap>(&opts);
+        let local = deserialize::<LocalTypeMap>(&opts);
+        assert_eq!(shared, r#"{"inner":{"value":513},"flag":true}"#);
+        assert_eq!(local, shared);
+    }
+}
@@ -0,0 +1,311 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+};
+
+use solana_idl::{
+    EnumFields, IdlEnumVariant, IdlField, IdlType, IdlTypeDefinition,
+    IdlTypeDefinitionTy,
+};
+
+use super::{
+    JsonIdlTypeDefinitionDeserializer, JsonTypeDefinitionDeserializerMap,
+    OptsRef,
+};
+use crate::hash::sha256;
+
+/// Stores the deserializers of defined types once for all IDLs that include them, i.e. for
+/// programs of a suite that share types via a common SDK.
+///
+/// Each type is interned by its definition together with the definitions of all types it
+/// references, thus types with the same name but different definitions are kept apart.
+/// The first definition of a type is stored under its name, later ones that differ under
+/// `<name>#<n>`, and references to them are rewritten accordingly.
+pub struct TypeRegistry<'opts> {
+    type_de_map: JsonTypeDefinitionDeserializerMap<'opts>,
+    /// Keys of the interned types by the hash of their definitions.
+    keys: HashMap<[u8; 32], String>,
+    opts: OptsRef<'opts>,
+}
+
+impl<'opts> TypeRegistry<'opts> {
+    pub fn new(opts: impl Into<OptsRef<'opts>>) -> Self {
+        let opts = opts.into();
+        Self {
+            type_de_map: Arc::new(Mutex::new(HashMap::new())),
+            keys: HashMap::new(),
+            opts,
+        }
+    }
+
+    /// The number of distinct types that are stored.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// The map holding the deserializers of all interned types keyed by their interned name.
+    pub fn type_de_map(&self) -> JsonTypeDefinitionDeserializerMap<'opts> {
+        self.type_de_map.clone()
+    }
+
+    /// Interns the [definitions] of one IDL, adding deserializers for the ones that weren't
+    /// stored yet.
+    /// If multiple definitions share a name the last one wins, i.e. types over accounts.
+    ///
+    /// Returns the key of each type inside the registry by type name.
+    pub fn intern(
+        &mut self,
+        definitions: &[IdlTypeDefinition],
+    ) -> HashMap<String, String> {
+        let by_name = definitions
+            .iter()
+            .map(|def| (def.name.as_str(), def))
+            .collect::<BTreeMap<_, _>>();
+
+        let mut new_keys = Vec::new();
+        let mut keys = HashMap::new();
+        for name in by_name.keys() {
+            let fingerprint = fingerprint(name, &by_name);
+            let key = match self.keys.get(&fingerprint) {
+                Some(key) => key.clone(),
+                None => {
+                    let key = self.unused_key(name);
+                    self.keys.insert(fingerprint, key.clone());
+                    new_keys.push((*name, key.clone()));
+                    key
+                }
+            };
+            keys.insert(name.to_string(), key);
+        }
+
+        for (name, key) in new_keys {
+            let mut definition = rewrite_definition(by_name[name], &keys);
+            definition.name = key.clone();
+            let mut deserializer = JsonIdlTypeDefinitionDeserializer::new(
+                &definition,
+                self.type_de_map.clone(),
+                self.opts.clone(),
+            );
+            // Errors and visitors refer to the type by the name the IDL declares
+            deserializer.name = name.to_string();
+            self.type_de_map.lock().unwrap().insert(key, deserializer);
+        }
+        keys
+    }
+
+    fn unused_key(&self, name: &str) -> String {
+        let taken = |key: &str| self.keys.values().any(|k| k == key);
+        if !taken(name) {
+            return name.to_string();
+        }
+        (1..)
+            .map(|n| format!("{name}#{n}"))
+            .find(|key| !taken(key))
+            .expect("finds an unused key")
+    }
+}
+
+// The [type_de_map] can hold circular references and thus leaks memory if not cleared.
+impl Drop for TypeRegistry<'_> {
+    fn drop(&mut self) {
+        self.type_de_map.lock().unwrap().clear();
+    }
+}
+
+/// Hashes the definition of the type with the given [name] along with the definitions of all
+/// types it references directly or indirectly.
+fn fingerprint(
+    name: &str,
+    by_name: &BTreeMap<&str, &IdlTypeDefinition>,
+) -> [u8; 32] {
+    let mut closure = BTreeMap::new();
+    let mut pending = vec![name.to_string()];
+    while let Some(name) = pending.pop() {
+        if closure.contains_key(&name) {
+            continue;
+        }
+        let ty = by_name.get(name.as_str()).map(|def| &def.ty);
+        if let Some(ty) = ty {
+            definition_references(ty, &mut pending);
+        }
+        closure.insert(name, ty);
+    }
+    let json = serde_json::to_string(&(name, closure))
+        .expect("type definitions serialize to JSON");
+    sha256(&[json.as_bytes()])
+}
+
+fn definition_references(ty: &IdlTypeDefinitionTy, out: &mut Vec<String>) {
+    match ty {
+        IdlTypeDefinitionTy::Struct { fields } => {
+            fields.iter().for_each(|f| type_references(&f.ty, out))
+        }
+        IdlTypeDefinitionTy::Enum { variants } => {
+            for variant in variants {
+                match &variant.fields {
+                    Some(EnumFields::Named(fields)) => {
+                        fields.iter().for_each(|f| type_references(&f.ty, out))
+                    }
+                    Some(EnumFields::Tuple(types)) => {
+                        types.iter().for_each(|ty| type_references(ty, out))
+                    }
+                    None => {}
+                }
+            }
+        }
+    }
+}
+
+fn type_references(ty: &IdlType, out: &mut Vec<String>) {
+    use IdlType::*;
+    match ty {
+        Defined(name) => out.push(name.to_string()),
+        Option(inner)
+        | COption(inner)
+        | Vec(inner)
+        | Array(inner, _)
+        | HashSet(inner)
+        | BTreeSet(inner) => type_references(inner, out),
+        HashMap(key, val) | BTreeMap(key, val) => {
+            type_references(key, out);
+            type_references(val, out);
+        }
+        Tuple(inners) => inners.iter().for_each(|ty| type_references(ty, out)),
+        _ => {}
+    }
+}
+
+/// Rewrites the references to defined types inside the [definition] to the [keys] they were
+/// interned under.
+pub(crate) fn rewrite_definition(
+    definition: &IdlTypeDefinition,
+    keys: &HashMap<String, String>,
+) -> IdlTypeDefinition {
+    let fields = |fields: &[IdlField]| {
+        fields
+            .iter()
+            .map(|f| IdlField {
+                ty: rewrite_type(&f.ty, keys),
+                ..f.clone()
+            })
+            .collect()
+    };
+    let ty = match &definition.ty {
+        IdlTypeDefinitionTy::Struct { fields: fs } => {
+            IdlTypeDefinitionTy::Struct { fields: fields(fs) }
+        }
+        IdlTypeDefinitionTy::Enum { variants } => IdlTypeDefinitionTy::Enum {
+            variants: variants
+                .iter()
+                .map(|variant| IdlEnumVariant {
+                    name: variant.name.clone(),
+                    fields: variant.fields.as_ref().map(|f| match f {
+                        EnumFields::Named(fs) => EnumFields::Named(fields(fs)),
+                        EnumFields::Tuple(types) => EnumFields::Tuple(
+                            types
+                                .iter()
+                                .map(|ty| rewrite_type(ty, keys))
+                                .collect(),
+                        ),
+                    }),
+                })
+                .collect(),
+        },
+    };
+    IdlTypeDefinition {
+        name: definition.name.clone(),
+        ty,
+    }
+}
+
+fn rewrite_type(ty: &IdlType, keys: &HashMap<String, String>) -> IdlType {
+    use IdlType::*;
+    let boxed = |ty: &IdlType| Box::new(rewrite_type(ty, keys));
+    match ty {
+        Defined(name) => {
+            Defined(keys.get(name).cloned().unwrap_or_else(|| name.clone()))
+        }
+        Option(inner) => Option(boxed(inner)),
+        COption(inner) => COption(boxed(inner)),
+        Vec(inner) => Vec(boxed(inner)),
+        Array(inner, len) => Array(boxed(inner), *len),
+        HashSet(inner) => HashSet(boxed(inner)),
+        BTreeSet(inner) => BTreeSet(boxed(inner)),
+        HashMap(key, val) => HashMap(boxed(key), boxed(val)),
+        BTreeMap(key, val) => BTreeMap(boxed(key), boxed(val)),
+        Tuple(inners) => {
+            Tuple(inners.iter().map(|ty| rewrite_type(ty, keys)).collect())
+        }
+        ty => ty.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{JsonSerializationOpts, SharedTypeMap, TypeMapKind};
+
+    fn definition(
+        name: &str,
+        fields: Vec<(&str, IdlType)>,
+    ) -> IdlTypeDefinition {
+        IdlTypeDefinition {
+            name: name.to_string(),
+            ty: IdlTypeDefinitionTy::Struct {
+                fields: fields
+                    .into_iter()
+                    .map(|(name, ty)| IdlField {
+                        name: name.to_string(),
+                        ty,
+                        attrs: None,
+                    })
+                    .collect(),
+            },
+        }
+    }
+
+    #[test]
+    fn intern_identical_and_differing_types() {
+        let opts = JsonSerializationOpts::default();
+        let mut registry = TypeRegistry::new(&opts);
+        let amount = definition("Amount", vec![("value", IdlType::U64)]);
+        let wrapper = definition(
+            "Wrapper",
+            vec![("amount", IdlType::Defined("Amount".to_string()))],
+        );
+
+        let keys = registry.intern(&[amount.clone(), wrapper.clone()]);
+        assert_eq!(keys["Amount"], "Amount");
+        assert_eq!(keys["Wrapper"], "Wrapper");
+        assert_eq!(registry.len(), 2);
+
+        // Same definitions are stored once
+        let keys = registry.intern(&[wrapper.clone(), amount]);
+        assert_eq!(keys["Wrapper"], "Wrapper");
+        assert_eq!(registry.len(), 2);
+
+        // A differing Amount also changes the Wrapper referencing it
+        let amount = definition("Amount", vec![("value", IdlType::U32)]);
+        let keys = registry.intern(&[amount, wrapper]);
+        assert_eq!(keys["Amount"], "Amount#1");
+        assert_eq!(keys["Wrapper"], "Wrapper#1");
+        assert_eq!(registry.len(), 4);
+
+        let wrapper_de =
+            SharedTypeMap::get(&registry.type_de_map(), "Wrapper#1").unwrap();
+        assert_eq!(wrapper_de.name, "Wrapper");
+        let mut json = String::new();
+        wrapper_de
+            .deserialize(
+                &crate::borsh::BorshDeserializer,
+                &mut json,
+                &mut &[1u8, 0, 0, 0][..],
+            )
+            .unwrap();
+        assert_eq!(json, r#"{"amount":{"value":1}}"#);
+    }
+}
@@ -0,0 +1,312 @@
+use std::{collections::HashSet, fmt::Write};
+
+use solana_idl::{
+    EnumFields, Idl, IdlEnumVariant, IdlField, IdlType, IdlTypeDefinition,
+    IdlTypeDefinitionTy,
+};
+
+use super::{
+    json_idl_field_de::field_enum_ty, BytesRepr, JsonSerializationOpts,
+    NonFiniteRepr,
+};
+
+/// Generates TypeScript definitions for the accounts and types of the [idl] which match the
+/// JSON that accounts are deserialized to with the given [opts].
+///
+/// Structs become interfaces and enums become unions of their variants, i.e. `"Scalar"`,
+/// `{ Named: { value: number } }` or `{ Tuple: [number] }`.
+/// Accounts that are structs include the entries added via
+/// [JsonSerializationOpts::include_account_name] and
+/// [JsonSerializationOpts::include_discriminator], the latter as optional since only accounts
+/// prefixed with a discriminator include it.
+/// Accounts sharing their name with a type are skipped since the type is used to deserialize
+/// them when they are nested.
+pub fn generate_typescript(idl: &Idl, opts: &JsonSerializationOpts) -> String {
+    let type_names = idl
+        .types
+        .iter()
+        .map(|ty| ty.name.as_str())
+        .collect::<HashSet<_>>();
+
+    let mut definitions = Vec::new();
+    for account in &idl.accounts {
+        if !type_names.contains(account.name.as_str()) {
+            definitions.push(ts_definition(account, true, opts));
+        }
+    }
+    for ty in &idl.types {
+        definitions.push(ts_definition(ty, false, opts));
+    }
+    definitions.join("\n")
+}
+
+fn ts_definition(
+    definition: &IdlTypeDefinition,
+    is_account: bool,
+    opts: &JsonSerializationOpts,
+) -> String {
+    let name = &definition.name;
+    let mut ts = String::new();
+    match &definition.ty {
+        IdlTypeDefinitionTy::Struct { fields } => {
+            let mut members = Vec::new();
+            if is_account && opts.include_account_name {
+                members.push(format!(
+                    "{}: {}",
+                    ts_key(&opts.account_name_key),
+                    ts_string(name)
+                ));
+            }
+            if is_account && opts.include_discriminator {
+                members.push(format!(
+                    "{}?: string",
+                    ts_key(&opts.discriminator_key)
+                ));
+            }
+            members.extend(ts_members(fields, opts));
+
+            writeln!(ts, "export interface {name} {{").unwrap();
+            for member in members {
+                writeln!(ts, "  {member};").unwrap();
+            }
+            writeln!(ts, "}}").unwrap();
+        }
+        IdlTypeDefinitionTy::Enum { variants } if variants.is_empty() => {
+            writeln!(ts, "export type {name} = never;").unwrap();
+        }
+        IdlTypeDefinitionTy::Enum { variants } => {
+            writeln!(ts, "export type {name} =").unwrap();
+            let last = variants.len() - 1;
+            for (i, variant) in variants.iter().enumerate() {
+                let end = if i == last { ";" } else { "" };
+                writeln!(ts, "  | {}{end}", ts_variant(variant, opts)).unwrap();
+            }
+        }
+    }
+    ts
+}
+
+/// Same representation as the JSON of enum variants, see
+/// [super::JsonIdlEnumVariantDeserializer::deserialize].
+fn ts_variant(
+    variant: &IdlEnumVariant,
+    opts: &JsonSerializationOpts,
+) -> String {
+    let key = ts_key(&variant.name);
+    match &variant.fields {
+        Some(EnumFields::Named(fields)) => {
+            format!("{{ {key}: {} }}", ts_object(fields, opts))
+        }
+        Some(EnumFields::Tuple(types)) => {
+            format!("{{ {key}: {} }}", ts_tuple(types, opts))
+        }
+        None => ts_string(&variant.name),
+    }
+}
+
+fn ts_members(
+    fields: &[IdlField],
+    opts: &JsonSerializationOpts,
+) -> Vec<String> {
+    fields
+        .iter()
+        .filter(|field| !opts.skip_fields.contains(&field.name))
+        .map(|field| {
+            let ty = match field_enum_ty(field) {
+                Some(enum_ty) => enum_ty,
+                None => ts_type(&field.ty, opts),
+            };
+            format!("{}: {ty}", ts_key(&field.name))
+        })
+        .collect()
+}
+
+fn ts_object(fields: &[IdlField], opts: &JsonSerializationOpts) -> String {
+    let members = ts_members(fields, opts);
+    if members.is_empty() {
+        "{}".to_string()
+    } else {
+        format!("{{ {} }}", members.join("; "))
+    }
+}
+
+fn ts_tuple(types: &[IdlType], opts: &JsonSerializationOpts) -> String {
+    let types = types.iter().map(|ty| ts_type(ty, opts)).collect::<Vec<_>>();
+    format!("[{}]", types.join(", "))
+}
+
+/// Resolves the TypeScript type of the JSON that values of the [ty] are deserialized to.
+fn ts_type(ty: &IdlType, opts: &JsonSerializationOpts) -> String {
+    use IdlType::*;
+    match ty {
+        U8 | U16 | U32 | I8 | I16 | I32 => "number".to_string(),
+        U64 | I64 if opts.n64_as_string => "string".to_string(),
+        U128 | I128 if opts.n128_as_string || opts.n128_as_hex => {
+            "string".to_string()
+        }
+        U64 | I64 | U128 | I128 => "number".to_string(),
+        F32 | F64 => match opts.non_finite {
+            NonFiniteRepr::Literal => "number".to_string(),
+            NonFiniteRepr::Null => "number | null".to_string(),
+            NonFiniteRepr::String => "number | string".to_string(),
+        },
+        Bool => "boolean".to_string(),
+        String => "string".to_string(),
+        PublicKey if opts.pubkey_as_base58 => "string".to_string(),
+        PublicKey => "number[]".to_string(),
+        Bytes => ts_bytes(opts),
+        Array(inner, _) | Vec(inner) if **inner == U8 => ts_bytes(opts),
+        Array(inner, _) | Vec(inner) | HashSet(inner) | BTreeSet(inner) => {
+            let inner = ts_type(inner, opts);
+            if inner.contains(' ') {
+                format!("({inner})[]")
+            } else {
+                format!("{inner}[]")
+            }
+        }
+        Tuple(inners) => ts_tuple(inners, opts),
+        HashMap(_, val) | BTreeMap(_, val) => {
+            format!("Record<string, {}>", ts_type(val, opts))
+        }
+        Option(inner) | COption(inner) => {
+            format!("{} | null", ts_type(inner, opts))
+        }
+        Defined(name) => name.to_string(),
+    }
+}
+
+fn ts_bytes(opts: &JsonSerializationOpts) -> String {
+    match opts.bytes_as {
+        BytesRepr::Array => "number[]".to_string(),
+        BytesRepr::Hex | BytesRepr::Base64 => "string".to_string(),
+    }
+}
+
+fn ts_string(s: &str) -> String {
+    serde_json::to_string(s).expect("serializing a str to JSON never fails")
+}
+
+/// Quotes the [name] unless it is a valid identifier.
+fn ts_key(name: &str) -> String {
+    let mut chars = name.chars();
+    let is_ident = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if is_ident {
+        name.to_string()
+    } else {
+        ts_string(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IDL_JSON: &str = r#"{
+      "version": "0.1.0",
+      "name": "ledger",
+      "instructions": [],
+      "accounts": [
+        {
+          "name": "Ledger",
+          "type": {
+            "kind": "struct",
+            "fields": [
+              { "name": "owner", "type": "publicKey" },
+              { "name": "balance", "type": "u64" },
+              { "name": "hash", "type": { "array": ["u8", 32] } },
+              { "name": "state", "type": { "defined": "State" } },
+              { "name": "kind", "type": "u8", "attrs": ["enum(Kind)"] },
+              { "name": "closedAt", "type": { "option": "i64" } },
+              { "name": "labels", "type": { "hashMap": ["u32", "string"] } },
+              { "name": "entries", "type": { "vec": { "option": "u16" } } },
+              { "name": "padding", "type": { "array": ["u8", 8] } }
+            ]
+          }
+        }
+      ],
+      "types": [
+        {
+          "name": "State",
+          "type": {
+            "kind": "enum",
+            "variants": [
+              { "name": "Open" },
+              { "name": "Moved", "fields": [{ "name": "to", "type": "publicKey" }] },
+              { "name": "Frozen", "fields": ["i8", "f32"] }
+            ]
+          }
+        },
+        {
+          "name": "Kind",
+          "type": { "kind": "enum", "variants": [{ "name": "A" }, { "name": "B" }] }
+        }
+      ]
+    }"#;
+
+    #[test]
+    fn typescript_with_default_opts() {
+        let idl: Idl = serde_json::from_str(IDL_JSON).unwrap();
+        let ts = generate_typescript(&idl, &JsonSerializationOpts::default());
+        assert_eq!(
+            ts,
+            r#"export interface Ledger {
+  owner: string;
+  balance: number;
+  hash: number[];
+  state: State;
+  kind: Kind;
+  closedAt: number | null;
+  labels: Record<string, string>;
+  entries: (number | null)[];
+  padding: number[];
+}
+
+export type State =
+  | "Open"
+  | { Moved: { to: string } }
+  | { Frozen: [number, number] };
+
+export type Kind =
+  | "A"
+  | "B";
+"#
+        );
+    }
+
+    #[test]
+    fn typescript_per_opts() {
+        let idl: Idl = serde_json::from_str(IDL_JSON).unwrap();
+        let opts = JsonSerializationOpts {
+            pubkey_as_base58: false,
+            n64_as_string: true,
+            bytes_as: BytesRepr::Hex,
+            non_finite: NonFiniteRepr::Null,
+            include_account_name: true,
+            include_discriminator: true,
+            account_name_key: "$type".to_string(),
+            skip_fields: HashSet::from(["padding".to_string()]),
+            ..Default::default()
+        };
+        let ts = generate_typescript(&idl, &opts);
+        assert!(ts.starts_with(
+            r#"export interface Ledger {
+  $type: "Ledger";
+  _discriminator?: string;
+  owner: number[];
+  balance: string;
+  hash: string;
+  state: State;
+  kind: Kind;
+  closedAt: string | null;
+  labels: Record<string, string>;
+  entries: (number | null)[];
+}
+"#
+        ));
+        assert!(ts.contains(r#"  | { Moved: { to: number[] } }"#));
+        assert!(ts.contains(r#"  | { Frozen: [number, number | null] };"#));
+    }
+}
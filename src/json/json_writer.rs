@@ -0,0 +1,141 @@
+use std::{fmt, io};
+
+/// A [fmt::Write] writer that the JSON deserializers write to.
+///
+/// Writers that forward their output, i.e. to a socket, can set [JsonWrite::STREAMING] and
+/// implement [JsonWrite::flush] in order to pass on elements of large collections as soon as
+/// they were written instead of once the entire account was deserialized.
+pub trait JsonWrite: fmt::Write {
+    /// Opts into [JsonWrite::flush] being invoked, writers that only collect their output leave
+    /// this off so that writing large collections doesn't pay for a flush per element.
+    const STREAMING: bool = false;
+
+    /// Invoked after each element of a `Vec` was written if [JsonWrite::STREAMING] is set.
+    fn flush(&mut self) -> fmt::Result {
+        Ok(())
+    }
+}
+
+impl JsonWrite for String {}
+
+impl<W: JsonWrite + ?Sized> JsonWrite for &mut W {
+    const STREAMING: bool = W::STREAMING;
+
+    fn flush(&mut self) -> fmt::Result {
+        (**self).flush()
+    }
+}
+
+/// Adapts any [fmt::Write] writer so the JSON deserializers can write to it.
+/// Flushes do nothing since the output is only collected, use [IoJsonWriter] to stream it.
+pub struct FmtJsonWriter<W: fmt::Write>(pub W);
+
+impl<W: fmt::Write> fmt::Write for FmtJsonWriter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.write_str(s)
+    }
+
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        self.0.write_char(c)
+    }
+}
+
+impl<W: fmt::Write> JsonWrite for FmtJsonWriter<W> {}
+
+/// Adapts an [io::Write] writer, i.e. `std::io::stdout()` or a socket connection, so the JSON
+/// deserializers can write to it.
+/// Flushes are forwarded to the [io::Write] writer.
+pub struct IoJsonWriter<W: io::Write> {
+    inner: W,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> IoJsonWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self { inner, error: None }
+    }
+
+    /// Returns the [io::Error] that caused the last write or flush to fail, since [fmt::Write]
+    /// cannot surface it.
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: io::Write> fmt::Write for IoJsonWriter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
+        })
+    }
+}
+
+impl<W: io::Write> JsonWrite for IoJsonWriter<W> {
+    const STREAMING: bool = true;
+
+    fn flush(&mut self) -> fmt::Result {
+        self.inner.flush().map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
+        })
+    }
+}
+
+/// Wraps a [JsonWrite] writer and fails once more than [max] bytes would be written to it, i.e.
+/// to bound the JSON produced for crafted account data with many nested collections.
+pub struct LimitedJsonWriter<W: JsonWrite> {
+    inner: W,
+    max: usize,
+    written: usize,
+    exceeded: bool,
+}
+
+impl<W: JsonWrite> LimitedJsonWriter<W> {
+    pub fn new(inner: W, max: usize) -> Self {
+        Self {
+            inner,
+            max,
+            written: 0,
+            exceeded: false,
+        }
+    }
+
+    /// The number of bytes written to the inner writer so far.
+    pub fn written(&self) -> usize {
+        self.written
+    }
+
+    /// Returns `true` if a write failed since it would have exceeded the max size, since
+    /// [fmt::Write] cannot surface the reason.
+    pub fn exceeded(&self) -> bool {
+        self.exceeded
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: JsonWrite> fmt::Write for LimitedJsonWriter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if s.len() > self.max - self.written {
+            self.exceeded = true;
+            return Err(fmt::Error);
+        }
+        self.written += s.len();
+        self.inner.write_str(s)
+    }
+}
+
+impl<W: JsonWrite> JsonWrite for LimitedJsonWriter<W> {
+    const STREAMING: bool = W::STREAMING;
+
+    fn flush(&mut self) -> fmt::Result {
+        self.inner.flush()
+    }
+}
@@ -1,21 +1,48 @@
 mod discriminator;
 mod json_accounts_deserializer;
 mod json_common;
+mod json_field_info;
+mod json_flatten;
 mod json_idl_enum_variant_de;
+mod json_idl_event_de;
 mod json_idl_field_de;
+mod json_idl_instruction_de;
 mod json_idl_type_de;
 mod json_idl_type_def_de;
+mod json_idl_type_ser;
 mod json_serialization_opts;
-
-use std::{
-    collections::HashMap,
-    sync::{Arc, Mutex},
-};
+mod json_tlv_de;
+mod json_type_map;
+mod json_type_registry;
+mod json_typescript;
+mod json_writer;
 
 pub use discriminator::PrefixDiscriminator;
 pub use json_accounts_deserializer::JsonAccountsDeserializer;
+pub(crate) use json_common::{
+    write_bool, write_bytes, write_float, write_int, write_int_hex,
+    write_int_quoted, write_quoted,
+};
+pub use json_field_info::{FieldInfo, FieldKind};
+pub use json_flatten::flatten_json_value;
+pub use json_idl_event_de::JsonIdlEventDeserializer;
+pub use json_idl_field_de::JsonIdlFieldDeserializer;
+pub use json_idl_instruction_de::JsonIdlInstructionDeserializer;
 pub use json_idl_type_def_de::JsonIdlTypeDefinitionDeserializer;
-pub use json_serialization_opts::JsonSerializationOpts;
+pub use json_idl_type_ser::JsonIdlTypeSerializer;
+pub use json_serialization_opts::{
+    BytesRepr, JsonSerializationOpts, NonFiniteRepr, OptsRef, UnknownPolicy,
+};
+pub use json_tlv_de::{tlv_entries, JsonTlvDeserializer};
+pub use json_type_map::{
+    JsonTypeDefinitionDeserializers, LocalTypeMap, SharedTypeMap, TypeMapKind,
+};
+pub(crate) use json_type_registry::rewrite_definition;
+pub use json_type_registry::TypeRegistry;
+pub use json_typescript::generate_typescript;
+pub use json_writer::{
+    FmtJsonWriter, IoJsonWriter, JsonWrite, LimitedJsonWriter,
+};
 
-pub type JsonTypeDefinitionDeserializerMap<'opts> =
-    Arc<Mutex<HashMap<String, JsonIdlTypeDefinitionDeserializer<'opts>>>>;
+pub type JsonTypeDefinitionDeserializerMap<'opts, K = SharedTypeMap> =
+    <K as TypeMapKind>::Map<'opts>;
@@ -1,14 +1,24 @@
 mod api;
 mod deserializer;
 pub mod errors;
+pub mod events;
+pub mod format;
+mod hash;
 pub mod ixs;
 pub mod json;
+pub mod pubkey;
+pub mod rpc;
+pub mod serializer;
+pub mod typed;
+mod versioned_deserializer;
 
 pub mod discriminator;
 pub mod idl;
 
 pub use api::*;
 pub use deserializer::*;
+pub use idl::parse_idl_json;
+pub use versioned_deserializer::VersionedDeserializer;
 
 pub mod de;
 pub mod traits;
@@ -0,0 +1,287 @@
+//! A minimal public key type that this crate depends on instead of the Solana SDK one in order to
+//! keep it independent of the SDK.
+//! Conversions from and to the SDK type are provided with the `solana-sdk` feature.
+
+use std::{
+    array::TryFromSliceError,
+    fmt,
+    str::FromStr,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::{errors::ChainparserError, hash::sha256};
+
+/// The length of a public key in bytes.
+pub const PUBKEY_BYTES: usize = 32;
+/// The maximum length of a seed used to derive an address.
+pub const MAX_SEED_LEN: usize = 32;
+/// The maximum number of seeds used to derive a program address.
+pub const MAX_SEEDS: usize = 16;
+
+const PDA_MARKER: &[u8; 21] = b"ProgramDerivedAddress";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum PubkeyError {
+    #[error("Length of the seed is too long for address generation")]
+    MaxSeedLengthExceeded,
+    #[error("Provided seeds do not result in a valid address")]
+    InvalidSeeds,
+    #[error("Provided owner is not allowed")]
+    IllegalOwner,
+}
+
+/// A 32 byte public key which is displayed and parsed as base58.
+#[derive(
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+pub struct Pubkey([u8; PUBKEY_BYTES]);
+
+impl Pubkey {
+    pub const fn new_from_array(bytes: [u8; PUBKEY_BYTES]) -> Self {
+        Self(bytes)
+    }
+
+    pub const fn to_bytes(self) -> [u8; PUBKEY_BYTES] {
+        self.0
+    }
+
+    /// Returns a different pubkey each time it is called, i.e. to use in tests.
+    pub fn new_unique() -> Self {
+        static COUNTER: AtomicU64 = AtomicU64::new(1);
+        let i = COUNTER.fetch_add(1, Ordering::Relaxed);
+        Self(sha256(&[b"chainparser unique pubkey", &i.to_be_bytes()]))
+    }
+
+    /// Returns `true` if the pubkey is a point on the ed25519 curve, i.e. it has a private key.
+    pub fn is_on_curve(&self) -> bool {
+        curve25519_dalek::edwards::CompressedEdwardsY(self.0)
+            .decompress()
+            .is_some()
+    }
+
+    /// Derives an address from the [base] pubkey, the [seed] and the [owner] the same way the
+    /// system program does.
+    pub fn create_with_seed(
+        base: &Pubkey,
+        seed: &str,
+        owner: &Pubkey,
+    ) -> Result<Pubkey, PubkeyError> {
+        if seed.len() > MAX_SEED_LEN {
+            return Err(PubkeyError::MaxSeedLengthExceeded);
+        }
+        if owner.0.ends_with(PDA_MARKER) {
+            return Err(PubkeyError::IllegalOwner);
+        }
+        Ok(Self(sha256(&[&base.0, seed.as_bytes(), &owner.0])))
+    }
+
+    /// Creates the program address for the [seeds] which include the bump and the [program_id].
+    /// Fails with [PubkeyError::InvalidSeeds] if the result lies on the ed25519 curve.
+    pub fn create_program_address(
+        seeds: &[&[u8]],
+        program_id: &Pubkey,
+    ) -> Result<Pubkey, PubkeyError> {
+        if seeds.len() > MAX_SEEDS {
+            return Err(PubkeyError::MaxSeedLengthExceeded);
+        }
+        if seeds.iter().any(|seed| seed.len() > MAX_SEED_LEN) {
+            return Err(PubkeyError::MaxSeedLengthExceeded);
+        }
+        let parts = seeds
+            .iter()
+            .copied()
+            .chain([program_id.0.as_slice(), PDA_MARKER.as_slice()])
+            .collect::<Vec<_>>();
+        let address = Self(sha256(&parts));
+        if address.is_on_curve() {
+            return Err(PubkeyError::InvalidSeeds);
+        }
+        Ok(address)
+    }
+
+    /// Finds the program address for the [seeds] and the [program_id] along with its bump, trying
+    /// bumps from 255 down until the address lies off the ed25519 curve.
+    pub fn try_find_program_address(
+        seeds: &[&[u8]],
+        program_id: &Pubkey,
+    ) -> Option<(Pubkey, u8)> {
+        (0..=u8::MAX).rev().find_map(|bump| {
+            let bump_seed = [bump];
+            let seeds_with_bump = seeds
+                .iter()
+                .copied()
+                .chain([bump_seed.as_slice()])
+                .collect::<Vec<_>>();
+            Self::create_program_address(&seeds_with_bump, program_id)
+                .ok()
+                .map(|address| (address, bump))
+        })
+    }
+
+    /// Same as [Pubkey::try_find_program_address], but panics if no address is found, which is
+    /// statistically impossible for valid seeds.
+    pub fn find_program_address(
+        seeds: &[&[u8]],
+        program_id: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Self::try_find_program_address(seeds, program_id)
+            .expect("Unable to find a viable program address bump seed")
+    }
+}
+
+impl AsRef<[u8]> for Pubkey {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl TryFrom<&[u8]> for Pubkey {
+    type Error = TryFromSliceError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        <[u8; PUBKEY_BYTES]>::try_from(bytes).map(Self)
+    }
+}
+
+impl FromStr for Pubkey {
+    type Err = ChainparserError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = bs58::decode(s).into_vec().map_err(|e| {
+            ChainparserError::InvalidPubkey(format!("{s} ({e})"))
+        })?;
+        Self::try_from(bytes.as_slice()).map_err(|_| {
+            ChainparserError::InvalidPubkey(format!(
+                "{s} decodes to {} instead of {PUBKEY_BYTES} bytes",
+                bytes.len()
+            ))
+        })
+    }
+}
+
+impl fmt::Display for Pubkey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", bs58::encode(self.0).into_string())
+    }
+}
+
+impl fmt::Debug for Pubkey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl From<[u8; PUBKEY_BYTES]> for Pubkey {
+    fn from(bytes: [u8; PUBKEY_BYTES]) -> Self {
+        Self(bytes)
+    }
+}
+
+#[cfg(feature = "solana-sdk")]
+impl From<solana_sdk::pubkey::Pubkey> for Pubkey {
+    fn from(pubkey: solana_sdk::pubkey::Pubkey) -> Self {
+        Self(pubkey.to_bytes())
+    }
+}
+
+#[cfg(feature = "solana-sdk")]
+impl From<Pubkey> for solana_sdk::pubkey::Pubkey {
+    fn from(pubkey: Pubkey) -> Self {
+        Self::new_from_array(pubkey.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "solana-sdk")]
+    #[test]
+    fn pubkey_base58_round_trip() {
+        let sdk_pubkey = solana_sdk::pubkey::Pubkey::new_unique();
+        let pubkey = Pubkey::from(sdk_pubkey);
+        assert_eq!(pubkey.to_string(), sdk_pubkey.to_string());
+        assert_eq!(format!("{pubkey:?}"), sdk_pubkey.to_string());
+        assert_eq!(Pubkey::from_str(&sdk_pubkey.to_string()).unwrap(), pubkey);
+        assert_eq!(solana_sdk::pubkey::Pubkey::from(pubkey), sdk_pubkey);
+    }
+
+    #[test]
+    fn invalid_pubkeys() {
+        assert!(matches!(
+            Pubkey::from_str("not base58 0OIl"),
+            Err(ChainparserError::InvalidPubkey(_))
+        ));
+        assert!(matches!(
+            Pubkey::from_str("Ldp"),
+            Err(ChainparserError::InvalidPubkey(_))
+        ));
+        assert!(Pubkey::try_from([1u8; 31].as_slice()).is_err());
+    }
+
+    #[cfg(feature = "solana-sdk")]
+    #[test]
+    fn derives_the_same_addresses_as_the_sdk() {
+        type SdkPubkey = solana_sdk::pubkey::Pubkey;
+
+        let program_id = SdkPubkey::new_unique();
+        let base = SdkPubkey::new_unique();
+        let seeds: &[&[u8]] = &[b"anchor:idl", &[]];
+
+        let (address, bump) =
+            Pubkey::find_program_address(seeds, &program_id.into());
+        assert_eq!(
+            (address.into(), bump),
+            SdkPubkey::find_program_address(seeds, &program_id)
+        );
+        assert!(!address.is_on_curve());
+        assert!(Pubkey::from(base).is_on_curve() == base.is_on_curve());
+
+        assert_eq!(
+            Pubkey::create_with_seed(
+                &address,
+                "anchor:idl",
+                &program_id.into()
+            )
+            .map(SdkPubkey::from)
+            .unwrap(),
+            SdkPubkey::create_with_seed(
+                &address.into(),
+                "anchor:idl",
+                &program_id
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            Pubkey::create_with_seed(
+                &base.into(),
+                &"x".repeat(33),
+                &program_id.into()
+            ),
+            Err(PubkeyError::MaxSeedLengthExceeded)
+        );
+
+        let mut marker_owner = [0u8; PUBKEY_BYTES];
+        marker_owner[PUBKEY_BYTES - PDA_MARKER.len()..]
+            .copy_from_slice(PDA_MARKER);
+        assert_eq!(
+            Pubkey::create_with_seed(
+                &base.into(),
+                "seed",
+                &marker_owner.into()
+            ),
+            Err(PubkeyError::IllegalOwner)
+        );
+    }
+}
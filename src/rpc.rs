@@ -0,0 +1,83 @@
+//! Types to deserialize accounts straight from the responses of the Solana JSON-RPC API.
+
+use base64::{prelude::BASE64_STANDARD, Engine as _};
+use serde::Deserialize;
+
+use crate::errors::{ChainparserError, ChainparserResult};
+
+/// The `value` of a `getAccountInfo` response or the `account` of each entry of a
+/// `getProgramAccounts` response.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcAccountInfo {
+    /// The account data along with its encoding, i.e. `["<data>", "base64"]`.
+    pub data: (String, String),
+    /// The program id of the program owning the account in base58.
+    pub owner: String,
+    pub lamports: u64,
+    pub executable: bool,
+    pub rent_epoch: u64,
+    #[serde(default)]
+    pub space: Option<u64>,
+}
+
+impl RpcAccountInfo {
+    /// Parses the account info from the `value` of a `getAccountInfo` response.
+    pub fn from_value(value: &serde_json::Value) -> ChainparserResult<Self> {
+        Ok(Self::deserialize(value)?)
+    }
+
+    /// Decodes the account data which needs to be either `base64` or `base58` encoded.
+    pub fn decode_data(&self) -> ChainparserResult<Vec<u8>> {
+        let (data, encoding) = &self.data;
+        match encoding.as_str() {
+            "base64" => Ok(BASE64_STANDARD.decode(data)?),
+            "base58" => bs58::decode(data).into_vec().map_err(|_| {
+                ChainparserError::UnsupportedRpcDataEncoding(
+                    encoding.to_string(),
+                )
+            }),
+            _ => Err(ChainparserError::UnsupportedRpcDataEncoding(
+                encoding.to_string(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn decode_rpc_account_data() {
+        let value = json!({
+            "data": ["AQID", "base64"],
+            "executable": false,
+            "lamports": 1_000_000,
+            "owner": "11111111111111111111111111111111",
+            "rentEpoch": u64::MAX,
+            "space": 3
+        });
+        let account = RpcAccountInfo::from_value(&value).unwrap();
+        assert_eq!(account.rent_epoch, u64::MAX);
+        assert_eq!(account.decode_data().unwrap(), vec![1, 2, 3]);
+
+        let account = RpcAccountInfo {
+            data: ("Ldp".to_string(), "base58".to_string()),
+            ..account
+        };
+        assert_eq!(account.decode_data().unwrap(), vec![1, 2, 3]);
+
+        let account = RpcAccountInfo {
+            data: ("".to_string(), "base64+zstd".to_string()),
+            ..account
+        };
+        assert!(matches!(
+            account.decode_data(),
+            Err(ChainparserError::UnsupportedRpcDataEncoding(ref encoding))
+                if encoding == "base64+zstd"
+        ));
+    }
+}
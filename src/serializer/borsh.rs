@@ -0,0 +1,108 @@
+use solana_idl::IdlType;
+
+use super::ChainparserSerialize;
+use crate::{
+    errors::{ChainparserError, ChainparserResult as Result},
+    pubkey::Pubkey,
+};
+
+#[derive(Clone, Copy)]
+pub struct BorshSerializer;
+
+impl ChainparserSerialize for BorshSerializer {
+    fn u8(&self, buf: &mut Vec<u8>, value: u8) -> Result<()> {
+        buf.push(value);
+        Ok(())
+    }
+
+    fn u16(&self, buf: &mut Vec<u8>, value: u16) -> Result<()> {
+        buf.extend_from_slice(&value.to_le_bytes());
+        Ok(())
+    }
+
+    fn u32(&self, buf: &mut Vec<u8>, value: u32) -> Result<()> {
+        buf.extend_from_slice(&value.to_le_bytes());
+        Ok(())
+    }
+
+    fn u64(&self, buf: &mut Vec<u8>, value: u64) -> Result<()> {
+        buf.extend_from_slice(&value.to_le_bytes());
+        Ok(())
+    }
+
+    fn u128(&self, buf: &mut Vec<u8>, value: u128) -> Result<()> {
+        buf.extend_from_slice(&value.to_le_bytes());
+        Ok(())
+    }
+
+    fn i8(&self, buf: &mut Vec<u8>, value: i8) -> Result<()> {
+        buf.extend_from_slice(&value.to_le_bytes());
+        Ok(())
+    }
+
+    fn i16(&self, buf: &mut Vec<u8>, value: i16) -> Result<()> {
+        buf.extend_from_slice(&value.to_le_bytes());
+        Ok(())
+    }
+
+    fn i32(&self, buf: &mut Vec<u8>, value: i32) -> Result<()> {
+        buf.extend_from_slice(&value.to_le_bytes());
+        Ok(())
+    }
+
+    fn i64(&self, buf: &mut Vec<u8>, value: i64) -> Result<()> {
+        buf.extend_from_slice(&value.to_le_bytes());
+        Ok(())
+    }
+
+    fn i128(&self, buf: &mut Vec<u8>, value: i128) -> Result<()> {
+        buf.extend_from_slice(&value.to_le_bytes());
+        Ok(())
+    }
+
+    fn f32(&self, buf: &mut Vec<u8>, value: f32) -> Result<()> {
+        buf.extend_from_slice(&value.to_le_bytes());
+        Ok(())
+    }
+
+    fn f64(&self, buf: &mut Vec<u8>, value: f64) -> Result<()> {
+        buf.extend_from_slice(&value.to_le_bytes());
+        Ok(())
+    }
+
+    fn bool(&self, buf: &mut Vec<u8>, value: bool) -> Result<()> {
+        buf.push(value as u8);
+        Ok(())
+    }
+
+    fn string(&self, buf: &mut Vec<u8>, value: &str) -> Result<()> {
+        self.bytes(buf, value.as_bytes())
+    }
+
+    fn bytes(&self, buf: &mut Vec<u8>, value: &[u8]) -> Result<()> {
+        self.u32(buf, value.len() as u32)?;
+        buf.extend_from_slice(value);
+        Ok(())
+    }
+
+    fn pubkey(&self, buf: &mut Vec<u8>, value: &Pubkey) -> Result<()> {
+        buf.extend_from_slice(value.as_ref());
+        Ok(())
+    }
+
+    fn option(&self, buf: &mut Vec<u8>, is_some: bool) -> Result<()> {
+        self.u8(buf, is_some as u8)
+    }
+
+    fn coption(
+        &self,
+        _buf: &mut Vec<u8>,
+        _is_some: bool,
+        _inner: &IdlType,
+    ) -> Result<()> {
+        Err(ChainparserError::SerializerDoesNotSupportType(
+            "borsh".to_string(),
+            "coption".to_string(),
+        ))
+    }
+}
@@ -0,0 +1,86 @@
+mod borsh;
+mod spl;
+
+use solana_idl::{Idl, IdlType};
+
+pub use self::{borsh::BorshSerializer, spl::SplSerializer};
+pub use crate::errors::ChainparserResult as Result;
+use crate::{errors::ChainparserError, pubkey::Pubkey};
+
+/// The counterpart to [crate::ChainparserDeserialize] which encodes values into the bytes
+/// that the respective deserializer consumes.
+pub trait ChainparserSerialize: Clone {
+    fn u8(&self, buf: &mut Vec<u8>, value: u8) -> Result<()>;
+    fn u16(&self, buf: &mut Vec<u8>, value: u16) -> Result<()>;
+    fn u32(&self, buf: &mut Vec<u8>, value: u32) -> Result<()>;
+    fn u64(&self, buf: &mut Vec<u8>, value: u64) -> Result<()>;
+    fn u128(&self, buf: &mut Vec<u8>, value: u128) -> Result<()>;
+
+    fn i8(&self, buf: &mut Vec<u8>, value: i8) -> Result<()>;
+    fn i16(&self, buf: &mut Vec<u8>, value: i16) -> Result<()>;
+    fn i32(&self, buf: &mut Vec<u8>, value: i32) -> Result<()>;
+    fn i64(&self, buf: &mut Vec<u8>, value: i64) -> Result<()>;
+    fn i128(&self, buf: &mut Vec<u8>, value: i128) -> Result<()>;
+
+    fn f32(&self, buf: &mut Vec<u8>, value: f32) -> Result<()>;
+    fn f64(&self, buf: &mut Vec<u8>, value: f64) -> Result<()>;
+
+    fn bool(&self, buf: &mut Vec<u8>, value: bool) -> Result<()>;
+    fn string(&self, buf: &mut Vec<u8>, value: &str) -> Result<()>;
+
+    fn bytes(&self, buf: &mut Vec<u8>, value: &[u8]) -> Result<()>;
+    fn pubkey(&self, buf: &mut Vec<u8>, value: &Pubkey) -> Result<()>;
+
+    /// Writes the tag of an option, the inner value is written separately if [is_some].
+    fn option(&self, buf: &mut Vec<u8>, is_some: bool) -> Result<()>;
+
+    /// Writes the tag of a coption, the inner value is written separately if [is_some].
+    /// Since coptions are constant size the space of the [inner] value is zero filled in case
+    /// of `None`.
+    fn coption(
+        &self,
+        buf: &mut Vec<u8>,
+        is_some: bool,
+        inner: &IdlType,
+    ) -> Result<()>;
+}
+
+#[derive(Clone)]
+pub enum SerializeProvider {
+    Borsh(BorshSerializer),
+    Spl(SplSerializer),
+}
+
+impl TryFrom<Option<&str>> for SerializeProvider {
+    type Error = ChainparserError;
+
+    fn try_from(label: Option<&str>) -> std::result::Result<Self, Self::Error> {
+        let label = label.unwrap_or("borsh");
+        match label {
+            "borsh" => Ok(Self::Borsh(BorshSerializer)),
+            "spl" => Ok(Self::Spl(SplSerializer::new())),
+            _ => Err(ChainparserError::UnsupportedDeserializer(
+                label.to_string(),
+            )),
+        }
+    }
+}
+
+impl TryFrom<&Idl> for SerializeProvider {
+    type Error = ChainparserError;
+
+    fn try_from(idl: &Idl) -> std::result::Result<Self, Self::Error> {
+        let label = idl.metadata.as_ref().and_then(|m| m.serializer.as_deref());
+        label.try_into()
+    }
+}
+
+impl SerializeProvider {
+    pub fn borsh() -> Self {
+        Self::Borsh(BorshSerializer)
+    }
+
+    pub fn spl() -> Self {
+        Self::Spl(SplSerializer::new())
+    }
+}
@@ -0,0 +1,120 @@
+use solana_idl::IdlType;
+
+use super::{borsh::BorshSerializer, ChainparserSerialize};
+use crate::{
+    errors::{ChainparserError, ChainparserResult as Result},
+    idl,
+    pubkey::Pubkey,
+};
+
+#[derive(Clone, Copy)]
+pub struct SplSerializer {
+    borsh: BorshSerializer,
+}
+
+impl SplSerializer {
+    pub(crate) fn new() -> Self {
+        Self {
+            borsh: BorshSerializer,
+        }
+    }
+}
+
+impl ChainparserSerialize for SplSerializer {
+    fn u8(&self, buf: &mut Vec<u8>, value: u8) -> Result<()> {
+        self.borsh.u8(buf, value)
+    }
+
+    fn u16(&self, buf: &mut Vec<u8>, value: u16) -> Result<()> {
+        self.borsh.u16(buf, value)
+    }
+
+    fn u32(&self, buf: &mut Vec<u8>, value: u32) -> Result<()> {
+        self.borsh.u32(buf, value)
+    }
+
+    fn u64(&self, buf: &mut Vec<u8>, value: u64) -> Result<()> {
+        self.borsh.u64(buf, value)
+    }
+
+    fn u128(&self, buf: &mut Vec<u8>, value: u128) -> Result<()> {
+        self.borsh.u128(buf, value)
+    }
+
+    fn i8(&self, buf: &mut Vec<u8>, value: i8) -> Result<()> {
+        self.borsh.i8(buf, value)
+    }
+
+    fn i16(&self, buf: &mut Vec<u8>, value: i16) -> Result<()> {
+        self.borsh.i16(buf, value)
+    }
+
+    fn i32(&self, buf: &mut Vec<u8>, value: i32) -> Result<()> {
+        self.borsh.i32(buf, value)
+    }
+
+    fn i64(&self, buf: &mut Vec<u8>, value: i64) -> Result<()> {
+        self.borsh.i64(buf, value)
+    }
+
+    fn i128(&self, buf: &mut Vec<u8>, value: i128) -> Result<()> {
+        self.borsh.i128(buf, value)
+    }
+
+    fn f32(&self, buf: &mut Vec<u8>, value: f32) -> Result<()> {
+        self.borsh.f32(buf, value)
+    }
+
+    fn f64(&self, buf: &mut Vec<u8>, value: f64) -> Result<()> {
+        self.borsh.f64(buf, value)
+    }
+
+    fn bool(&self, buf: &mut Vec<u8>, value: bool) -> Result<()> {
+        self.borsh.bool(buf, value)
+    }
+
+    fn string(&self, buf: &mut Vec<u8>, value: &str) -> Result<()> {
+        self.borsh.string(buf, value)
+    }
+
+    fn bytes(&self, buf: &mut Vec<u8>, value: &[u8]) -> Result<()> {
+        self.borsh.bytes(buf, value)
+    }
+
+    fn pubkey(&self, buf: &mut Vec<u8>, value: &Pubkey) -> Result<()> {
+        self.borsh.pubkey(buf, value)
+    }
+
+    fn option(&self, _buf: &mut Vec<u8>, _is_some: bool) -> Result<()> {
+        Err(ChainparserError::SerializerDoesNotSupportType(
+            "spl".to_string(),
+            "option".to_string(),
+        ))
+    }
+
+    fn coption(
+        &self,
+        buf: &mut Vec<u8>,
+        is_some: bool,
+        inner: &IdlType,
+    ) -> Result<()> {
+        if is_some {
+            buf.extend_from_slice(&[1, 0, 0, 0]);
+            return Ok(());
+        }
+        // COption is constant size, meaning None takes the same space as Some, filled with
+        // `0`s. Thus we need to know the size of the inner type.
+        match idl::idl_type_bytes(inner, None) {
+            Some(byte_len) => {
+                buf.extend_from_slice(&[0, 0, 0, 0]);
+                buf.resize(buf.len() + byte_len, 0);
+                Ok(())
+            }
+            None => Err(ChainparserError::InvalidDataToSerialize(
+                "coption".to_string(),
+                "byte size of inner type needs to be known when it is None"
+                    .to_string(),
+            )),
+        }
+    }
+}
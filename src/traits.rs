@@ -1,5 +1,48 @@
-use solana_sdk::{account::Account, pubkey::Pubkey};
+use crate::pubkey::Pubkey;
+
+/// An account as it is stored on chain.
+/// With the `solana-sdk` feature it converts from and to the account of the Solana SDK.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Account {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+    pub rent_epoch: u64,
+}
+
+#[cfg(feature = "solana-sdk")]
+impl From<solana_sdk::account::Account> for Account {
+    fn from(account: solana_sdk::account::Account) -> Self {
+        Self {
+            lamports: account.lamports,
+            data: account.data,
+            owner: account.owner.into(),
+            executable: account.executable,
+            rent_epoch: account.rent_epoch,
+        }
+    }
+}
+
+#[cfg(feature = "solana-sdk")]
+impl From<Account> for solana_sdk::account::Account {
+    fn from(account: Account) -> Self {
+        Self {
+            lamports: account.lamports,
+            data: account.data,
+            owner: account.owner.into(),
+            executable: account.executable,
+            rent_epoch: account.rent_epoch,
+        }
+    }
+}
 
 pub trait AccountProvider {
     fn get_account(&self, pubkey: &Pubkey) -> Option<(Account, u64)>;
 }
+
+impl<T: AccountProvider + ?Sized> AccountProvider for Box<T> {
+    fn get_account(&self, pubkey: &Pubkey) -> Option<(Account, u64)> {
+        (**self).get_account(pubkey)
+    }
+}
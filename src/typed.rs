@@ -0,0 +1,109 @@
+//! Support for typed account structs which are generated from an IDL via the `#[idl_account]`
+//! attribute of the `chainparser-derive` crate.
+//! The accounts are deserialized to JSON via the IDL and then into the generated struct, thus
+//! the same machinery as for the untyped JSON output is used.
+
+use std::sync::OnceLock;
+
+use serde::de::DeserializeOwned;
+
+use crate::{
+    discriminator::account_discriminator,
+    errors::{ChainparserError, ChainparserResult},
+    idl::IdlProvider,
+    ChainparserDeserializer, JsonSerializationOpts,
+};
+
+/// Items referenced by the code generated via `#[idl_account]` so that crates using it don't
+/// need to depend on them directly.
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::pubkey::Pubkey;
+    pub use serde;
+}
+
+/// The id under which the IDL of a typed account is added to its deserializer.
+const TYPED_IDL_ID: &str = "typed";
+
+/// Implemented by the structs generated via `#[idl_account]`.
+pub trait IdlAccount: DeserializeOwned {
+    /// The name of the account in the IDL.
+    const ACCOUNT_NAME: &'static str;
+
+    /// The IDL JSON that defines the account.
+    const IDL_JSON: &'static str;
+
+    /// The provider of the IDL which determines if the account data is prefixed with a
+    /// discriminator.
+    const IDL_PROVIDER: IdlProvider;
+
+    /// Holds the deserializer for the IDL of this account which is created the first time an
+    /// account is deserialized.
+    fn deserializer_cell() -> &'static OnceLock<ChainparserDeserializer<'static>>;
+
+    /// Deserializes the account from its [data] which for anchor accounts is expected to be
+    /// prefixed with the discriminator of this account.
+    fn from_account_data(data: &[u8]) -> ChainparserResult<Self> {
+        let deserializer = deserializer::<Self>()?;
+        let data = match Self::IDL_PROVIDER {
+            IdlProvider::Anchor => {
+                strip_discriminator(Self::ACCOUNT_NAME, data)?
+            }
+            IdlProvider::Shank | IdlProvider::Codama => data,
+        };
+
+        let mut json = String::new();
+        deserializer.deserialize_account_to_json_by_name(
+            TYPED_IDL_ID,
+            Self::ACCOUNT_NAME,
+            &mut &data[..],
+            &mut json,
+        )?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
+
+/// Returns the deserializer of the account [T], creating it if needed.
+fn deserializer<T: IdlAccount>(
+) -> ChainparserResult<&'static ChainparserDeserializer<'static>> {
+    let cell = T::deserializer_cell();
+    if let Some(deserializer) = cell.get() {
+        return Ok(deserializer);
+    }
+    // 128-bit integers exceed what JSON numbers reliably hold, the generated structs parse
+    // them from strings instead
+    let opts = JsonSerializationOpts {
+        n128_as_string: true,
+        ..Default::default()
+    };
+    let mut deserializer = ChainparserDeserializer::with_owned_opts(opts);
+    deserializer.add_idl_json(
+        TYPED_IDL_ID.to_string(),
+        T::IDL_JSON,
+        T::IDL_PROVIDER,
+    )?;
+    Ok(cell.get_or_init(|| deserializer))
+}
+
+/// Strips the discriminator of the account with the given [account_name] from its [data].
+fn strip_discriminator<'a>(
+    account_name: &str,
+    data: &'a [u8],
+) -> ChainparserResult<&'a [u8]> {
+    let discriminator = account_discriminator(account_name);
+    if data.len() < discriminator.len() {
+        return Err(
+            ChainparserError::AccountDataTooShortForDiscriminatorBytes(
+                data.len(),
+                discriminator.len(),
+            ),
+        );
+    }
+    let (prefix, rest) = data.split_at(discriminator.len());
+    if prefix != discriminator {
+        return Err(ChainparserError::AccountDiscriminatorMismatch(
+            account_name.to_string(),
+        ));
+    }
+    Ok(rest)
+}
@@ -0,0 +1,150 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+};
+
+use solana_idl::Idl;
+
+use crate::{
+    errors::{ChainparserError, ChainparserResult},
+    idl::IdlProvider,
+    json::OptsRef,
+    ChainparserDeserializer,
+};
+
+/// Manages multiple versions of the IDL of each program keyed by the slot at which each version
+/// was uploaded.
+/// When deserializing an account observed at a given slot, the IDL with the greatest slot that
+/// is less than or equal to that slot is used.
+pub struct VersionedDeserializer<'opts> {
+    /// Holds a deserializer for each IDL version keyed by `<program_id>@<slot>`.
+    chainparser: ChainparserDeserializer<'opts>,
+
+    /// The ids of the IDL versions added to the [chainparser] for each program keyed by slot.
+    ids_by_program: HashMap<String, BTreeMap<u64, String>>,
+}
+
+impl<'opts> VersionedDeserializer<'opts> {
+    /// Creates an instance of a [VersionedDeserializer].
+    ///
+    /// - [json_serialization_opts] specifying how specific data types should be deserialized.
+    pub fn new(json_serialization_opts: impl Into<OptsRef<'opts>>) -> Self {
+        let json_serialization_opts = json_serialization_opts.into();
+        Self {
+            chainparser: ChainparserDeserializer::new(json_serialization_opts),
+            ids_by_program: HashMap::new(),
+        }
+    }
+
+    /// Adds the [idl] of the [program_id] which was uploaded at the given [slot].
+    pub fn add_idl(
+        &mut self,
+        program_id: &str,
+        slot: u64,
+        idl: Idl,
+        provider: IdlProvider,
+    ) -> ChainparserResult<()> {
+        let id = versioned_id(program_id, slot);
+        self.chainparser.add_idl(id.clone(), idl, provider)?;
+        self.register(program_id, slot, id);
+        Ok(())
+    }
+
+    /// Parses the [idl_json] of the [program_id] which was uploaded at the given [slot] and adds
+    /// it.
+    pub fn add_idl_json(
+        &mut self,
+        program_id: &str,
+        slot: u64,
+        idl_json: &str,
+        provider: IdlProvider,
+    ) -> ChainparserResult<()> {
+        let id = versioned_id(program_id, slot);
+        self.chainparser
+            .add_idl_json(id.clone(), idl_json, provider)?;
+        self.register(program_id, slot, id);
+        Ok(())
+    }
+
+    /// Resolves the id of the IDL version of the [program_id] to use for accounts observed at
+    /// the given [slot], which is the one with the greatest slot that is `<= slot`.
+    pub fn resolve_id(&self, program_id: &str, slot: u64) -> Option<&str> {
+        self.ids_by_program
+            .get(program_id)
+            .and_then(|ids| ids.range(..=slot).next_back())
+            .map(|(_, id)| id.as_str())
+    }
+
+    /// Returns the slots at which IDLs were added for the [program_id] in ascending order.
+    pub fn idl_slots(&self, program_id: &str) -> Vec<u64> {
+        self.ids_by_program
+            .get(program_id)
+            .map(|ids| ids.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Resolves the account name of the [account_data] observed at the given [slot].
+    pub fn account_name(
+        &self,
+        program_id: &str,
+        slot: u64,
+        account_data: &[u8],
+    ) -> Option<&str> {
+        self.resolve_id(program_id, slot)
+            .and_then(|id| self.chainparser.account_name(id, account_data))
+    }
+
+    /// Deserializes an account observed at the given [slot] to a JSON string using the IDL
+    /// version that was active at that slot.
+    pub fn deserialize_account_to_json_string(
+        &self,
+        program_id: &str,
+        slot: u64,
+        account_data: &mut &[u8],
+    ) -> ChainparserResult<String> {
+        let mut f = String::new();
+        self.deserialize_account_to_json(
+            program_id,
+            slot,
+            account_data,
+            &mut f,
+        )?;
+        Ok(f)
+    }
+
+    /// Deserializes an account observed at the given [slot] using the IDL version that was
+    /// active at that slot and writes the resulting JSON to the provided writer [f].
+    pub fn deserialize_account_to_json<W: Write>(
+        &self,
+        program_id: &str,
+        slot: u64,
+        account_data: &mut &[u8],
+        f: &mut W,
+    ) -> ChainparserResult<()> {
+        let id = self.resolve_id(program_id, slot).ok_or_else(|| {
+            ChainparserError::CannotFindIdlVersionForSlot(
+                program_id.to_string(),
+                slot,
+            )
+        })?;
+        self.chainparser
+            .deserialize_account_to_json(id, account_data, f)
+    }
+
+    /// Provides access to the underlying [ChainparserDeserializer] whose ids are formatted as
+    /// `<program_id>@<slot>`.
+    pub fn chainparser(&self) -> &ChainparserDeserializer<'opts> {
+        &self.chainparser
+    }
+
+    fn register(&mut self, program_id: &str, slot: u64, id: String) {
+        self.ids_by_program
+            .entry(program_id.to_string())
+            .or_default()
+            .insert(slot, id);
+    }
+}
+
+fn versioned_id(program_id: &str, slot: u64) -> String {
+    format!("{program_id}@{slot}")
+}
@@ -0,0 +1,2812 @@
+use std::{collections::HashMap, str::FromStr};
+
+use base64::{prelude::BASE64_STANDARD, Engine as _};
+use chainparser::{
+    discriminator::{
+        account_discriminator, account_discriminator_with_namespace,
+        match_discriminator::{AmbiguousMatchPolicy, MatchConfidence},
+        DiscriminatorBytes, DiscriminatorCollision, DiscriminatorStrategy,
+    },
+    errors::ChainparserError,
+    idl::{
+        encode_idl_account_json, FieldTypeOverrides, IdlProvider,
+        SPL_TOKEN_2022_PROGRAM_ID, SPL_TOKEN_PROGRAM_ID,
+    },
+    json::{FieldInfo, FieldKind, JsonWrite, NonFiniteRepr, UnknownPolicy},
+    pubkey::Pubkey,
+    rpc::RpcAccountInfo,
+    traits::{Account, AccountProvider},
+    ChainparserDeserializer, JsonSerializationOpts, VersionedDeserializer,
+};
+use solana_idl::{Idl, IdlType};
+
+const VAULT_IDL_JSON: &str = r#"{
+  "version": "0.1.0",
+  "name": "vault",
+  "constants": [
+    { "name": "VAULT_SEED", "type": "string", "value": "\"vault\"" },
+    { "name": "MAX_DEPOSITORS", "type": "u8", "value": "16" }
+  ],
+  "instructions": [],
+  "accounts": [
+    {
+      "name": "VaultInfo",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          { "name": "authority", "type": "publicKey" },
+          { "name": "balance", "type": "u64" }
+        ]
+      }
+    }
+  ]
+}"#;
+
+const VAULT_PROGRAM_ID: &str = "Vau1t11111111111111111111111111111111111111";
+
+#[test]
+fn idl_constants() {
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_json(
+            VAULT_PROGRAM_ID.to_string(),
+            VAULT_IDL_JSON,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+
+    let seed = chainparser
+        .idl_constant(VAULT_PROGRAM_ID, "VAULT_SEED")
+        .expect("should find seed constant");
+    assert_eq!(seed.value, "\"vault\"");
+
+    let max = chainparser
+        .idl_constant(VAULT_PROGRAM_ID, "MAX_DEPOSITORS")
+        .expect("should find max constant");
+    assert_eq!(max.value, "16");
+
+    assert!(chainparser
+        .idl_constant(VAULT_PROGRAM_ID, "UNKNOWN")
+        .is_none());
+    assert!(chainparser.idl_constant("unknown", "VAULT_SEED").is_none());
+}
+
+#[test]
+fn deserialize_account_at_offset() {
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_json(
+            VAULT_PROGRAM_ID.to_string(),
+            VAULT_IDL_JSON,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+    let authority = Pubkey::new_unique();
+    let data = [
+        &[9u8, 9, 9][..],
+        &account_discriminator("VaultInfo"),
+        &authority.to_bytes(),
+        &5u64.to_le_bytes(),
+    ]
+    .concat();
+
+    assert_eq!(
+        chainparser
+            .deserialize_account_at(VAULT_PROGRAM_ID, &data, 3)
+            .unwrap(),
+        format!(r#"{{"authority":"{authority}","balance":5}}"#)
+    );
+    assert!(matches!(
+        chainparser.deserialize_account_at(VAULT_PROGRAM_ID, &data, 0),
+        Err(ChainparserError::UnknownDiscriminatedAccount(_))
+    ));
+    assert!(matches!(
+        chainparser.deserialize_account_at(
+            VAULT_PROGRAM_ID,
+            &data,
+            data.len() - 4
+        ),
+        Err(ChainparserError::AccountDataTooShortForDiscriminatorBytes(
+            4, 8
+        ))
+    ));
+    assert!(matches!(
+        chainparser.deserialize_account_at(
+            VAULT_PROGRAM_ID,
+            &data,
+            data.len() + 1
+        ),
+        Err(ChainparserError::AccountDataOffsetOutOfBounds(offset, len))
+            if offset == data.len() + 1 && len == data.len()
+    ));
+}
+
+#[test]
+fn deserialize_unknown_accounts_per_policy() {
+    let data = [&[1u8, 2, 3, 4, 5, 6, 7, 8][..], &[0xab; 4]].concat();
+    for (policy, expected) in [
+        (
+            UnknownPolicy::HexDump,
+            r#"{"_unknown":true,"data":"0102030405060708abababab"}"#,
+        ),
+        (UnknownPolicy::Skip, "null"),
+    ] {
+        let opts = JsonSerializationOpts {
+            unknown_account: policy,
+            ..Default::default()
+        };
+        let mut chainparser = ChainparserDeserializer::new(&opts);
+        chainparser
+            .add_idl_json(
+                VAULT_PROGRAM_ID.to_string(),
+                VAULT_IDL_JSON,
+                IdlProvider::Anchor,
+            )
+            .expect("failed adding IDL JSON");
+        let json = chainparser
+            .deserialize_account_to_json_string(
+                VAULT_PROGRAM_ID,
+                &mut &data[..],
+            )
+            .unwrap();
+        assert_eq!(json, expected, "{policy:?}");
+    }
+
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_json(
+            VAULT_PROGRAM_ID.to_string(),
+            VAULT_IDL_JSON,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+    assert!(matches!(
+        chainparser.deserialize_account_to_json_string(
+            VAULT_PROGRAM_ID,
+            &mut &data[..]
+        ),
+        Err(ChainparserError::UnknownDiscriminatedAccount(_))
+    ));
+}
+
+#[test]
+fn deserialize_account_exceeding_max_output_bytes() {
+    let data = [
+        account_discriminator("VaultInfo").to_vec(),
+        Pubkey::new_unique().to_bytes().to_vec(),
+        5u64.to_le_bytes().to_vec(),
+    ]
+    .concat();
+    let deserialize = |max_output_bytes: Option<usize>| {
+        let opts = JsonSerializationOpts {
+            max_output_bytes,
+            ..Default::default()
+        };
+        let mut chainparser = ChainparserDeserializer::new(&opts);
+        chainparser
+            .add_idl_json(
+                VAULT_PROGRAM_ID.to_string(),
+                VAULT_IDL_JSON,
+                IdlProvider::Anchor,
+            )
+            .expect("failed adding IDL JSON");
+        chainparser.deserialize_account_to_json_string(
+            VAULT_PROGRAM_ID,
+            &mut &data[..],
+        )
+    };
+
+    let json = deserialize(None).unwrap();
+    assert_eq!(deserialize(Some(json.len())).unwrap(), json);
+    assert!(matches!(
+        deserialize(Some(json.len() - 1)),
+        Err(ChainparserError::OutputTooLarge(max)) if max == json.len() - 1
+    ));
+}
+
+#[test]
+fn field_offsets_of_fixed_size_accounts() {
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_json(
+            VAULT_PROGRAM_ID.to_string(),
+            VAULT_IDL_JSON,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+    chainparser.add_spl_token_idl().unwrap();
+
+    // Anchor accounts are prefixed with the discriminator
+    let offsets = chainparser
+        .field_offsets(VAULT_PROGRAM_ID, "VaultInfo")
+        .unwrap();
+    assert_eq!(
+        offsets,
+        vec![
+            ("authority".to_string(), 8..40),
+            ("balance".to_string(), 40..48)
+        ]
+    );
+
+    let balance = 5u64;
+    let data = [
+        account_discriminator("VaultInfo").to_vec(),
+        Pubkey::new_unique().to_bytes().to_vec(),
+        balance.to_le_bytes().to_vec(),
+    ]
+    .concat();
+    let range = offsets[1].1.clone();
+    assert_eq!(u64::from_le_bytes(data[range].try_into().unwrap()), balance);
+
+    let offsets = chainparser
+        .field_offsets(SPL_TOKEN_PROGRAM_ID, "Mint")
+        .unwrap();
+    assert_eq!(offsets[0], ("mintAuthority".to_string(), 0..36));
+    assert_eq!(offsets.last().unwrap().1.end, 82);
+
+    assert!(chainparser
+        .field_offsets(VAULT_PROGRAM_ID, "Unknown")
+        .is_none());
+    assert!(chainparser.field_offsets("unknown", "VaultInfo").is_none());
+}
+
+#[test]
+fn versioned_deserializer_resolves_idl_by_slot() {
+    let v2_idl_json = VAULT_IDL_JSON.replace(
+        r#"{ "name": "balance", "type": "u64" }"#,
+        r#"{ "name": "balance", "type": "u64" },
+          { "name": "bump", "type": "u8" }"#,
+    );
+
+    let opts = JsonSerializationOpts::default();
+    let mut versioned = VersionedDeserializer::new(&opts);
+    versioned
+        .add_idl_json(
+            VAULT_PROGRAM_ID,
+            100,
+            VAULT_IDL_JSON,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding v1 IDL JSON");
+    versioned
+        .add_idl_json(VAULT_PROGRAM_ID, 200, &v2_idl_json, IdlProvider::Anchor)
+        .expect("failed adding v2 IDL JSON");
+
+    assert_eq!(versioned.idl_slots(VAULT_PROGRAM_ID), vec![100, 200]);
+    assert_eq!(versioned.resolve_id(VAULT_PROGRAM_ID, 99), None);
+    assert_eq!(
+        versioned.resolve_id(VAULT_PROGRAM_ID, 100),
+        Some(format!("{VAULT_PROGRAM_ID}@100").as_str())
+    );
+    assert_eq!(
+        versioned.resolve_id(VAULT_PROGRAM_ID, 199),
+        Some(format!("{VAULT_PROGRAM_ID}@100").as_str())
+    );
+    assert_eq!(
+        versioned.resolve_id(VAULT_PROGRAM_ID, 1_000),
+        Some(format!("{VAULT_PROGRAM_ID}@200").as_str())
+    );
+
+    let authority = Pubkey::new_unique();
+    let data = [
+        account_discriminator("VaultInfo").to_vec(),
+        authority.to_bytes().to_vec(),
+        5u64.to_le_bytes().to_vec(),
+        vec![254],
+    ]
+    .concat();
+
+    let json = versioned
+        .deserialize_account_to_json_string(
+            VAULT_PROGRAM_ID,
+            150,
+            &mut data.as_slice(),
+        )
+        .unwrap();
+    assert_eq!(
+        json,
+        format!(r#"{{"authority":"{authority}","balance":5}}"#)
+    );
+
+    let json = versioned
+        .deserialize_account_to_json_string(
+            VAULT_PROGRAM_ID,
+            250,
+            &mut data.as_slice(),
+        )
+        .unwrap();
+    assert_eq!(
+        json,
+        format!(r#"{{"authority":"{authority}","balance":5,"bump":254}}"#)
+    );
+
+    assert!(matches!(
+        versioned.deserialize_account_to_json_string(
+            VAULT_PROGRAM_ID,
+            50,
+            &mut data.as_slice(),
+        ),
+        Err(ChainparserError::CannotFindIdlVersionForSlot(_, 50))
+    ));
+}
+
+#[test]
+fn deserialize_snapshot_json() {
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_json(
+            VAULT_PROGRAM_ID.to_string(),
+            VAULT_IDL_JSON,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+
+    let vault = Pubkey::new_unique();
+    let authority = Pubkey::new_unique();
+    let vault_data = [
+        account_discriminator("VaultInfo").to_vec(),
+        authority.to_bytes().to_vec(),
+        5u64.to_le_bytes().to_vec(),
+    ]
+    .concat();
+    let unknown = Pubkey::new_unique();
+    let unknown_data = [0u8; 16];
+
+    let json = chainparser
+        .deserialize_snapshot_json(
+            VAULT_PROGRAM_ID,
+            &[(vault, &vault_data), (unknown, &unknown_data)],
+        )
+        .unwrap();
+
+    let value: serde_json::Value =
+        serde_json::from_str(&json).expect("should be valid JSON");
+    assert_eq!(
+        value[vault.to_string()],
+        serde_json::json!({ "authority": authority.to_string(), "balance": 5 })
+    );
+    assert!(value[unknown.to_string()]["error"].is_string());
+
+    assert!(matches!(
+        chainparser.deserialize_snapshot_json("unknown", &[]),
+        Err(ChainparserError::CannotFindAccountDeserializerForProgramId(
+            _
+        ))
+    ));
+}
+
+#[test]
+fn deserialize_back_to_back_accounts_counted() {
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_json(
+            VAULT_PROGRAM_ID.to_string(),
+            VAULT_IDL_JSON,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+    chainparser.add_spl_token_idl().unwrap();
+
+    let authorities = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let data = authorities
+        .iter()
+        .enumerate()
+        .flat_map(|(i, authority)| {
+            [
+                account_discriminator("VaultInfo").to_vec(),
+                authority.to_bytes().to_vec(),
+                (i as u64).to_le_bytes().to_vec(),
+            ]
+            .concat()
+        })
+        .collect::<Vec<_>>();
+
+    let mut offset = 0;
+    for (i, authority) in authorities.iter().enumerate() {
+        let (json, consumed) = chainparser
+            .deserialize_account_to_json_string_counted(
+                VAULT_PROGRAM_ID,
+                &data[offset..],
+            )
+            .unwrap();
+        assert_eq!(consumed, 48);
+        assert_eq!(
+            json,
+            format!(r#"{{"authority":"{authority}","balance":{i}}}"#)
+        );
+        offset += consumed;
+    }
+    assert_eq!(offset, data.len());
+
+    // Accounts resolved without a discriminator
+    let mint = [0; 82];
+    let (_, consumed) = chainparser
+        .deserialize_account_to_json_string_counted(SPL_TOKEN_PROGRAM_ID, &mint)
+        .unwrap();
+    assert_eq!(consumed, 82);
+}
+
+#[test]
+fn deserialize_account_selected_fields() {
+    let idl_json = VAULT_IDL_JSON.replace(
+        r#"{ "name": "balance", "type": "u64" }"#,
+        r#"{ "name": "padding", "type": { "array": ["u8", 256] } },
+          { "name": "balance", "type": "u64" },
+          { "name": "label", "type": "string" },
+          { "name": "history", "type": { "vec": "u64" } }"#,
+    );
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_json(
+            VAULT_PROGRAM_ID.to_string(),
+            &idl_json,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+
+    let authority = Pubkey::new_unique();
+    let data = [
+        account_discriminator("VaultInfo").to_vec(),
+        authority.to_bytes().to_vec(),
+        vec![7; 256],
+        5u64.to_le_bytes().to_vec(),
+        3u32.to_le_bytes().to_vec(),
+        b"abc".to_vec(),
+        // history is truncated, but never read
+        vec![9],
+    ]
+    .concat();
+
+    let fields = chainparser
+        .deserialize_account_selected_fields(
+            VAULT_PROGRAM_ID,
+            &data,
+            &["label", "authority", "unknown"],
+        )
+        .unwrap();
+    assert_eq!(
+        serde_json::Value::Object(fields),
+        serde_json::json!({ "authority": authority.to_string(), "label": "abc" })
+    );
+
+    let fields = chainparser
+        .deserialize_account_selected_fields(VAULT_PROGRAM_ID, &data, &[])
+        .unwrap();
+    assert!(fields.is_empty());
+
+    assert!(chainparser
+        .deserialize_account_selected_fields(
+            VAULT_PROGRAM_ID,
+            &data,
+            &["history"]
+        )
+        .is_err());
+    assert!(matches!(
+        chainparser.deserialize_account_selected_fields("unknown", &data, &[]),
+        Err(ChainparserError::CannotFindAccountDeserializerForProgramId(
+            _
+        ))
+    ));
+}
+
+#[test]
+fn discriminator_collisions() {
+    let idl_json = VAULT_IDL_JSON.replace(
+        r#""accounts": ["#,
+        r#""accounts": [
+    {
+      "name": "VaultInfo",
+      "type": {
+        "kind": "struct",
+        "fields": [{ "name": "bump", "type": "u8" }]
+      }
+    },"#,
+    );
+
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_json(
+            VAULT_PROGRAM_ID.to_string(),
+            VAULT_IDL_JSON,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+    chainparser
+        .add_idl_json("colliding".to_string(), &idl_json, IdlProvider::Anchor)
+        .expect("failed adding colliding IDL JSON");
+
+    assert_eq!(
+        chainparser.discriminator_collisions(VAULT_PROGRAM_ID),
+        Some(&[][..])
+    );
+    assert_eq!(
+        chainparser.discriminator_collisions("colliding"),
+        Some(
+            &[DiscriminatorCollision {
+                discriminator: account_discriminator("VaultInfo").to_vec(),
+                first: "VaultInfo".to_string(),
+                second: "VaultInfo".to_string(),
+            }][..]
+        )
+    );
+    assert_eq!(chainparser.discriminator_collisions("unknown"), None);
+
+    // The account defined first is used to deserialize
+    let json = chainparser
+        .deserialize_account_to_json_string(
+            "colliding",
+            &mut [account_discriminator("VaultInfo").to_vec(), vec![254]]
+                .concat()
+                .as_slice(),
+        )
+        .unwrap();
+    assert_eq!(json, r#"{"bump":254}"#);
+}
+
+#[test]
+fn serialize_account_json_round_trip() {
+    let opts = JsonSerializationOpts {
+        n64_as_string: true,
+        ..Default::default()
+    };
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_json(
+            VAULT_PROGRAM_ID.to_string(),
+            VAULT_IDL_JSON,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+
+    let data = [
+        account_discriminator("VaultInfo").to_vec(),
+        Pubkey::new_unique().to_bytes().to_vec(),
+        u64::MAX.to_le_bytes().to_vec(),
+    ]
+    .concat();
+
+    let json = chainparser
+        .deserialize_account_to_json_string(VAULT_PROGRAM_ID, &mut &data[..])
+        .unwrap();
+    let bytes = chainparser
+        .serialize_account_json(VAULT_PROGRAM_ID, "VaultInfo", &json)
+        .unwrap();
+    assert_eq!(bytes, data);
+
+    assert!(matches!(
+        chainparser.serialize_account_json(
+            VAULT_PROGRAM_ID,
+            "VaultInfo",
+            r#"{ "balance": 1 }"#
+        ),
+        Err(ChainparserError::StructSerializeError(_, _))
+    ));
+}
+
+#[test]
+fn deserialize_account_with_name_and_discriminator_envelope() {
+    let opts = JsonSerializationOpts {
+        include_account_name: true,
+        include_discriminator: true,
+        account_name_key: "$type".to_string(),
+        ..Default::default()
+    };
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_json(
+            VAULT_PROGRAM_ID.to_string(),
+            VAULT_IDL_JSON,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+    chainparser.add_spl_token_idl().unwrap();
+
+    let authority = Pubkey::new_unique();
+    let discriminator = account_discriminator("VaultInfo");
+    let data = [
+        discriminator.to_vec(),
+        authority.to_bytes().to_vec(),
+        5u64.to_le_bytes().to_vec(),
+    ]
+    .concat();
+    let hex = discriminator
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>();
+    let json = chainparser
+        .deserialize_account_to_json_string(VAULT_PROGRAM_ID, &mut &data[..])
+        .unwrap();
+    assert_eq!(
+        json,
+        format!(
+            r#"{{"$type":"VaultInfo","_discriminator":"{hex}","authority":"{authority}","balance":5}}"#
+        )
+    );
+
+    // Accounts resolved by matching their shape have no discriminator to include
+    let mint = [vec![0u8; 36], 7u64.to_le_bytes().to_vec(), vec![0, 1]]
+        .concat()
+        .into_iter()
+        .chain([0u8; 36])
+        .collect::<Vec<_>>();
+    let json = chainparser
+        .deserialize_account_to_json_string(
+            SPL_TOKEN_PROGRAM_ID,
+            &mut &mint[..],
+        )
+        .unwrap();
+    assert_eq!(
+        json,
+        r#"{"$type":"Mint","mintAuthority":null,"supply":7,"decimals":0,"isInitialized":true,"freezeAuthority":null}"#
+    );
+}
+
+#[test]
+fn classify_accounts_counts_per_account_type() {
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_json(
+            VAULT_PROGRAM_ID.to_string(),
+            VAULT_IDL_JSON,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+
+    let vault = [
+        account_discriminator("VaultInfo").to_vec(),
+        Pubkey::new_unique().to_bytes().to_vec(),
+        5u64.to_le_bytes().to_vec(),
+    ]
+    .concat();
+    let unknown = [0u8; 48];
+    let accounts: [&[u8]; 3] = [&vault, &unknown, &vault];
+
+    let counts = chainparser.classify_accounts(VAULT_PROGRAM_ID, &accounts);
+    assert_eq!(counts.len(), 2);
+    assert_eq!(counts[&Some("VaultInfo".to_string())], 2);
+    assert_eq!(counts[&None], 1);
+
+    let counts = chainparser.classify_accounts("unknown program", &accounts);
+    assert_eq!(counts[&None], 3);
+}
+
+#[test]
+fn peek_account_name_with_discriminator() {
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_json(
+            VAULT_PROGRAM_ID.to_string(),
+            VAULT_IDL_JSON,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+    chainparser
+        .add_idl_json(
+            "shank vault".to_string(),
+            &VAULT_IDL_JSON.replace("VaultInfo", "Vault").replace(
+                r#""balance", "type": "u64""#,
+                r#""open", "type": "bool""#,
+            ),
+            IdlProvider::Shank,
+        )
+        .expect("failed adding IDL JSON");
+
+    let discriminator = account_discriminator("VaultInfo");
+    let vault = [
+        discriminator.to_vec(),
+        Pubkey::new_unique().to_bytes().to_vec(),
+        5u64.to_le_bytes().to_vec(),
+    ]
+    .concat();
+    let hex = discriminator
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>();
+    assert_eq!(
+        chainparser.peek_account_name(VAULT_PROGRAM_ID, &vault),
+        Some(("VaultInfo", Some(hex)))
+    );
+    assert_eq!(
+        chainparser.peek_account_name(VAULT_PROGRAM_ID, &vault[..4]),
+        None
+    );
+    assert_eq!(
+        chainparser.peek_account_name(VAULT_PROGRAM_ID, &[0; 48]),
+        None
+    );
+
+    assert_eq!(
+        chainparser
+            .peek_account_name("shank vault", &[&vault[8..40], &[1]].concat()),
+        Some(("Vault", None))
+    );
+    assert_eq!(chainparser.peek_account_name("unknown", &vault), None);
+
+    assert_eq!(
+        chainparser.account_name_with_confidence(VAULT_PROGRAM_ID, &vault),
+        Some(("VaultInfo", None))
+    );
+    let confidence = |data: &[u8]| {
+        chainparser
+            .account_name_with_confidence("shank vault", data)
+            .and_then(|(_, confidence)| confidence)
+    };
+    assert_eq!(
+        confidence(&[&vault[8..40], &[1]].concat()),
+        Some(MatchConfidence {
+            matchers: 1,
+            exact_size: true,
+            unchecked_fields: 0
+        })
+    );
+    assert_eq!(
+        confidence(&[&vault[8..40], &[1, 0]].concat()),
+        Some(MatchConfidence {
+            matchers: 1,
+            exact_size: false,
+            unchecked_fields: 0
+        })
+    );
+}
+
+#[test]
+fn explain_error_log_resolves_idl_errors() {
+    let idl_json = r#"{
+      "version": "0.1.0",
+      "name": "vault",
+      "instructions": [],
+      "errors": [
+        { "code": 6000, "name": "Overflow" },
+        { "code": 6001, "name": "Unauthorized", "msg": "Not allowed" }
+      ]
+    }"#;
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_json(
+            VAULT_PROGRAM_ID.to_string(),
+            idl_json,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+
+    let log = format!(
+        "Program {VAULT_PROGRAM_ID} failed: custom program error: 0x1771"
+    );
+    assert_eq!(
+        chainparser.explain_error_log(VAULT_PROGRAM_ID, &log),
+        Some(("Unauthorized", Some("Not allowed")))
+    );
+    let log = format!(
+        "Program {VAULT_PROGRAM_ID} failed: custom program error: 6000"
+    );
+    assert_eq!(
+        chainparser.explain_error_log(VAULT_PROGRAM_ID, &log),
+        Some(("Overflow", None))
+    );
+    let log =
+        format!("Program {VAULT_PROGRAM_ID} failed: custom program error: 0x1");
+    assert_eq!(chainparser.explain_error_log(VAULT_PROGRAM_ID, &log), None);
+    assert_eq!(chainparser.explain_error_log("unknown", &log), None);
+}
+
+#[test]
+fn deserialize_rpc_account_info() {
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_json(
+            VAULT_PROGRAM_ID.to_string(),
+            VAULT_IDL_JSON,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+
+    let authority = Pubkey::new_unique();
+    let data = [
+        account_discriminator("VaultInfo").to_vec(),
+        authority.to_bytes().to_vec(),
+        5u64.to_le_bytes().to_vec(),
+    ]
+    .concat();
+    let value = serde_json::json!({
+        "data": [BASE64_STANDARD.encode(&data), "base64"],
+        "executable": false,
+        "lamports": 1_000_000,
+        "owner": VAULT_PROGRAM_ID,
+        "rentEpoch": 361,
+        "space": data.len()
+    });
+    let account_info = RpcAccountInfo::from_value(&value).unwrap();
+    let json = chainparser
+        .deserialize_rpc_account_to_json_string(VAULT_PROGRAM_ID, &account_info)
+        .unwrap();
+    assert_eq!(
+        json,
+        format!(r#"{{"authority":"{authority}","balance":5}}"#)
+    );
+
+    let account_info = RpcAccountInfo {
+        owner: SPL_TOKEN_PROGRAM_ID.to_string(),
+        ..account_info
+    };
+    let err = chainparser
+        .deserialize_rpc_account_to_json_string(VAULT_PROGRAM_ID, &account_info)
+        .expect_err("should reject account with other owner");
+    assert!(matches!(
+        err,
+        ChainparserError::OwnerMismatch(ref expected, ref owner)
+            if expected == VAULT_PROGRAM_ID && owner == SPL_TOKEN_PROGRAM_ID
+    ));
+}
+
+struct MapAccountProvider(HashMap<Pubkey, Account>);
+
+impl AccountProvider for MapAccountProvider {
+    fn get_account(&self, pubkey: &Pubkey) -> Option<(Account, u64)> {
+        self.0.get(pubkey).map(|account| (account.clone(), 0))
+    }
+}
+
+#[test]
+fn deserialize_owned_account_checks_owner() {
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_json(
+            VAULT_PROGRAM_ID.to_string(),
+            VAULT_IDL_JSON,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+
+    let program_id = Pubkey::from_str(VAULT_PROGRAM_ID).unwrap();
+    let authority = Pubkey::new_unique();
+    let data = [
+        account_discriminator("VaultInfo").to_vec(),
+        authority.to_bytes().to_vec(),
+        5u64.to_le_bytes().to_vec(),
+    ]
+    .concat();
+    let owned = Pubkey::new_unique();
+    let foreign = Pubkey::new_unique();
+    let other_program = Pubkey::new_unique();
+    let account = |owner: Pubkey| Account {
+        lamports: 1_000_000,
+        data: data.clone(),
+        owner,
+        executable: false,
+        rent_epoch: 0,
+    };
+    let provider = MapAccountProvider(HashMap::from([
+        (owned, account(program_id)),
+        (foreign, account(other_program)),
+    ]));
+
+    let json = chainparser
+        .deserialize_owned_account(&provider, &program_id, &owned)
+        .unwrap();
+    assert_eq!(
+        json,
+        format!(r#"{{"authority":"{authority}","balance":5}}"#)
+    );
+
+    let err = chainparser
+        .deserialize_owned_account(&provider, &program_id, &foreign)
+        .expect_err("should reject account owned by another program");
+    assert!(matches!(
+        err,
+        ChainparserError::OwnerMismatch(ref expected, ref actual)
+            if *expected == program_id.to_string()
+                && *actual == other_program.to_string()
+    ));
+
+    let missing = Pubkey::new_unique();
+    let err = chainparser
+        .deserialize_owned_account(&provider, &program_id, &missing)
+        .expect_err("should fail for missing account");
+    assert!(matches!(err, ChainparserError::AccountNotFound(_)));
+}
+
+#[test]
+fn deserialize_account_resolving_idl_via_account_provider() {
+    let program_id = Pubkey::from_str(VAULT_PROGRAM_ID).unwrap();
+    let idl_address =
+        chainparser::idl::try_idl_address(&IdlProvider::Anchor, &program_id)
+            .unwrap();
+    let idl_account = Account {
+        lamports: 1_000_000,
+        data: encode_idl_account_json(&program_id, VAULT_IDL_JSON).unwrap(),
+        owner: program_id,
+        executable: false,
+        rent_epoch: 0,
+    };
+
+    let authority = Pubkey::new_unique();
+    let data = [
+        account_discriminator("VaultInfo").to_vec(),
+        authority.to_bytes().to_vec(),
+        5u64.to_le_bytes().to_vec(),
+    ]
+    .concat();
+
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+
+    // Without an account provider IDLs are not fetched
+    assert!(!chainparser.resolve_idl(VAULT_PROGRAM_ID).unwrap());
+    assert!(matches!(
+        chainparser.deserialize_account_to_json_resolving(
+            VAULT_PROGRAM_ID,
+            &mut &data[..],
+            &mut String::new(),
+        ),
+        Err(ChainparserError::CannotFindAccountDeserializerForProgramId(
+            _
+        ))
+    ));
+
+    chainparser.set_account_provider(MapAccountProvider(HashMap::from([(
+        idl_address,
+        idl_account,
+    )])));
+    let mut json = String::new();
+    chainparser
+        .deserialize_account_to_json_resolving(
+            VAULT_PROGRAM_ID,
+            &mut &data[..],
+            &mut json,
+        )
+        .unwrap();
+    assert_eq!(
+        json,
+        format!(r#"{{"authority":"{authority}","balance":5}}"#)
+    );
+    assert!(chainparser.has_idl(VAULT_PROGRAM_ID));
+
+    // Programs without an IDL account still fail the same way
+    let other_program_id = Pubkey::new_unique().to_string();
+    assert!(!chainparser.resolve_idl(&other_program_id).unwrap());
+    assert!(matches!(
+        chainparser.resolve_idl("not a pubkey"),
+        Err(ChainparserError::FailedToParseIdlProgramPubkey(..))
+    ));
+}
+
+#[test]
+fn deserialize_account_referencing_types_of_another_idl() {
+    const SHARED_IDL_JSON: &str = r#"{
+      "version": "0.1.0",
+      "name": "shared",
+      "instructions": [],
+      "types": [
+        {
+          "name": "SharedState",
+          "type": {
+            "kind": "struct",
+            "fields": [
+              { "name": "fee", "type": { "defined": "Fee" } },
+              { "name": "paused", "type": "bool" }
+            ]
+          }
+        },
+        {
+          "name": "Fee",
+          "type": {
+            "kind": "struct",
+            "fields": [{ "name": "bps", "type": "u16" }]
+          }
+        }
+      ]
+    }"#;
+    const POOL_IDL_JSON: &str = r#"{
+      "version": "0.1.0",
+      "name": "pool",
+      "instructions": [],
+      "accounts": [
+        {
+          "name": "Pool",
+          "type": {
+            "kind": "struct",
+            "fields": [
+              { "name": "bump", "type": "u8" },
+              { "name": "state", "type": { "defined": "shared::SharedState" } }
+            ]
+          }
+        }
+      ],
+      "types": [
+        {
+          "name": "Fee",
+          "type": {
+            "kind": "struct",
+            "fields": [{ "name": "lamports", "type": "u64" }]
+          }
+        }
+      ]
+    }"#;
+    const SHARED_PROGRAM_ID: &str = "11111111111111111111111111111111";
+
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_json(
+            SHARED_PROGRAM_ID.to_string(),
+            SHARED_IDL_JSON,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+    chainparser
+        .add_idl_json(
+            VAULT_PROGRAM_ID.to_string(),
+            POOL_IDL_JSON,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+
+    let data = [
+        account_discriminator("Pool").to_vec(),
+        vec![1],
+        30u16.to_le_bytes().to_vec(),
+        vec![0],
+    ]
+    .concat();
+    assert!(chainparser
+        .deserialize_account_to_json_string(VAULT_PROGRAM_ID, &mut &data[..])
+        .is_err());
+
+    // The imported SharedState resolves the Fee of its own IDL, not the one of the pool
+    chainparser
+        .import_idl_types(VAULT_PROGRAM_ID, SHARED_PROGRAM_ID)
+        .unwrap();
+    let json = chainparser
+        .deserialize_account_to_json_string(VAULT_PROGRAM_ID, &mut &data[..])
+        .unwrap();
+    assert_eq!(
+        json,
+        r#"{"bump":1,"state":{"fee":{"bps":30},"paused":false}}"#
+    );
+
+    assert!(matches!(
+        chainparser.import_idl_types(VAULT_PROGRAM_ID, "unknown"),
+        Err(ChainparserError::CannotFindAccountDeserializerForProgramId(
+            _
+        ))
+    ));
+}
+
+#[test]
+fn add_idl_from_file() {
+    let dir = std::env::temp_dir();
+    let idl_path = dir.join(format!("vault-idl-{}.json", std::process::id()));
+    let malformed_path =
+        dir.join(format!("malformed-idl-{}.json", std::process::id()));
+    std::fs::write(&idl_path, VAULT_IDL_JSON).unwrap();
+    std::fs::write(&malformed_path, "{ \"version\": ").unwrap();
+
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_file(
+            VAULT_PROGRAM_ID.to_string(),
+            &idl_path,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL file");
+
+    let authority = Pubkey::new_unique();
+    let data = [
+        account_discriminator("VaultInfo").to_vec(),
+        authority.to_bytes().to_vec(),
+        5u64.to_le_bytes().to_vec(),
+    ]
+    .concat();
+    let json = chainparser
+        .deserialize_account_to_json_string(VAULT_PROGRAM_ID, &mut &data[..])
+        .unwrap();
+    assert_eq!(
+        json,
+        format!(r#"{{"authority":"{authority}","balance":5}}"#)
+    );
+
+    let res = chainparser.add_idl_file(
+        "missing".to_string(),
+        dir.join("does-not-exist.json"),
+        IdlProvider::Anchor,
+    );
+    assert!(matches!(res, Err(ChainparserError::IdlFileReadError(..))));
+
+    let res = chainparser.add_idl_file(
+        "malformed".to_string(),
+        &malformed_path,
+        IdlProvider::Anchor,
+    );
+    assert!(matches!(res, Err(ChainparserError::IdlFileError(..))));
+    assert!(!chainparser.has_idl("malformed"));
+
+    std::fs::remove_file(idl_path).unwrap();
+    std::fs::remove_file(malformed_path).unwrap();
+}
+
+#[test]
+fn try_add_idl_for_program_caches_programs_without_idl() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct CountingAccountProvider {
+        accounts: HashMap<Pubkey, Account>,
+        lookups: AtomicUsize,
+    }
+    impl AccountProvider for CountingAccountProvider {
+        fn get_account(&self, pubkey: &Pubkey) -> Option<(Account, u64)> {
+            self.lookups.fetch_add(1, Ordering::SeqCst);
+            self.accounts
+                .get(pubkey)
+                .map(|account| (account.clone(), 0))
+        }
+    }
+
+    let program_id = Pubkey::from_str(VAULT_PROGRAM_ID).unwrap();
+    let mut provider = CountingAccountProvider {
+        accounts: HashMap::new(),
+        lookups: AtomicUsize::new(0),
+    };
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+
+    assert!(chainparser
+        .try_add_idl_for_program(&provider, &program_id)
+        .unwrap()
+        .is_none());
+    let lookups = provider.lookups.load(Ordering::SeqCst);
+    assert!(lookups > 0);
+
+    // The miss is cached
+    assert!(chainparser
+        .try_add_idl_for_program(&provider, &program_id)
+        .unwrap()
+        .is_none());
+    assert_eq!(provider.lookups.load(Ordering::SeqCst), lookups);
+
+    // Once the program uploaded its IDL it is found after invalidating the miss
+    let idl_address =
+        chainparser::idl::try_idl_address(&IdlProvider::Anchor, &program_id)
+            .unwrap();
+    provider.accounts.insert(
+        idl_address,
+        Account {
+            lamports: 1_000_000,
+            data: encode_idl_account_json(&program_id, VAULT_IDL_JSON).unwrap(),
+            owner: program_id,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    assert!(chainparser
+        .try_add_idl_for_program(&provider, &program_id)
+        .unwrap()
+        .is_none());
+    assert!(chainparser.invalidate(&program_id));
+    assert!(!chainparser.invalidate(&program_id));
+    assert!(matches!(
+        chainparser.try_add_idl_for_program(&provider, &program_id),
+        Ok(Some(IdlProvider::Anchor))
+    ));
+    assert!(chainparser.has_idl(VAULT_PROGRAM_ID));
+}
+
+#[test]
+fn deserialize_accounts_into_reused_string() {
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_json(
+            VAULT_PROGRAM_ID.to_string(),
+            VAULT_IDL_JSON,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+
+    let authority = Pubkey::new_unique();
+    let mut out = String::with_capacity(256);
+    for balance in [1u64, 1_000] {
+        let data = [
+            account_discriminator("VaultInfo").to_vec(),
+            authority.to_bytes().to_vec(),
+            balance.to_le_bytes().to_vec(),
+        ]
+        .concat();
+        chainparser
+            .deserialize_account_into(
+                VAULT_PROGRAM_ID,
+                &mut &data[..],
+                &mut out,
+            )
+            .unwrap();
+        assert_eq!(
+            out,
+            format!(r#"{{"authority":"{authority}","balance":{balance}}}"#)
+        );
+        assert_eq!(out.capacity(), 256);
+    }
+}
+
+#[test]
+fn parse_idl_json_classic() {
+    let idl = chainparser::parse_idl_json(VAULT_IDL_JSON).unwrap();
+    assert_eq!(idl.name, "vault");
+    assert_eq!(idl.accounts[0].name, "VaultInfo");
+    assert!(chainparser::parse_idl_json("{").is_err());
+}
+
+#[test]
+fn deserialize_error_includes_account_name() {
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_json(
+            VAULT_PROGRAM_ID.to_string(),
+            VAULT_IDL_JSON,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+
+    // Missing the balance
+    let data = [
+        account_discriminator("VaultInfo").to_vec(),
+        Pubkey::new_unique().to_bytes().to_vec(),
+    ]
+    .concat();
+
+    let err = chainparser
+        .deserialize_named_account(VAULT_PROGRAM_ID, &mut &data[..])
+        .expect_err("should fail to deserialize truncated account");
+    assert!(matches!(
+        err,
+        ChainparserError::AccountDeserializeError(ref name, _) if name == "VaultInfo"
+    ));
+    assert_eq!(err.account_name(), Some("VaultInfo"));
+    assert!(err.to_string().contains("VaultInfo"));
+    assert!(std::error::Error::source(&err).is_some());
+
+    // The other entry points keep returning the error of the failed value
+    let err = chainparser
+        .deserialize_account_to_json_string(VAULT_PROGRAM_ID, &mut &data[..])
+        .expect_err("should fail to deserialize truncated account");
+    assert_eq!(err.account_name(), None);
+
+    let data = [
+        account_discriminator("VaultInfo").to_vec(),
+        Pubkey::new_unique().to_bytes().to_vec(),
+        7u64.to_le_bytes().to_vec(),
+    ]
+    .concat();
+    let (name, _) = chainparser
+        .deserialize_named_account(VAULT_PROGRAM_ID, &mut &data[..])
+        .unwrap();
+    assert_eq!(name.as_deref(), Some("VaultInfo"));
+}
+
+#[test]
+fn shank_accounts_with_declared_discriminators() {
+    // Both accounts have the same shape and are told apart by their 1 byte account kind tag
+    let idl_json = r#"{
+      "version": "0.1.0",
+      "name": "tagged",
+      "instructions": [],
+      "accounts": [
+        {
+          "name": "Pool",
+          "discriminator": [1],
+          "type": {
+            "kind": "struct",
+            "fields": [{ "name": "amount", "type": "u64" }]
+          }
+        },
+        {
+          "name": "Position",
+          "discriminant": { "type": "u8", "value": 2 },
+          "type": {
+            "kind": "struct",
+            "fields": [{ "name": "amount", "type": "u64" }]
+          }
+        }
+      ]
+    }"#;
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_json("tagged".to_string(), idl_json, IdlProvider::Shank)
+        .expect("failed adding IDL JSON");
+
+    let position = [vec![2], 7u64.to_le_bytes().to_vec()].concat();
+    assert_eq!(
+        chainparser.account_name("tagged", &position),
+        Some("Position")
+    );
+    let (json, consumed) = chainparser
+        .deserialize_account_to_json_string_counted("tagged", &position)
+        .unwrap();
+    assert_eq!(json, r#"{"amount":7}"#);
+    assert_eq!(consumed, 9);
+
+    let pool = [vec![1], 5u64.to_le_bytes().to_vec()].concat();
+    assert_eq!(chainparser.account_name("tagged", &pool), Some("Pool"));
+    assert!(chainparser
+        .deserialize_account_to_json_string("tagged", &mut &[3u8; 9][..])
+        .is_err());
+}
+
+#[test]
+fn ambiguous_shank_account_match() {
+    let idl_json = r#"{
+      "version": "0.1.0",
+      "name": "ambiguous",
+      "instructions": [],
+      "accounts": [
+        {
+          "name": "First",
+          "type": {
+            "kind": "struct",
+            "fields": [
+              { "name": "flag", "type": "bool" },
+              { "name": "value", "type": "u64" }
+            ]
+          }
+        },
+        {
+          "name": "Second",
+          "type": {
+            "kind": "struct",
+            "fields": [
+              { "name": "enabled", "type": "bool" },
+              { "name": "amount", "type": "u64" }
+            ]
+          }
+        }
+      ]
+    }"#;
+
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_json("ambiguous".to_string(), idl_json, IdlProvider::Shank)
+        .expect("failed adding IDL JSON");
+    let data = [vec![1], 5u64.to_le_bytes().to_vec()].concat();
+
+    assert!(chainparser
+        .deserialize_account_to_json_string("ambiguous", &mut &data[..])
+        .is_ok());
+
+    chainparser
+        .set_ambiguous_match_policy("ambiguous", AmbiguousMatchPolicy::Error)
+        .unwrap();
+    match chainparser
+        .deserialize_account_to_json_string("ambiguous", &mut &data[..])
+    {
+        Err(ChainparserError::AmbiguousAccountMatch(mut names)) => {
+            names.sort();
+            assert_eq!(names, vec!["First", "Second"]);
+        }
+        res => panic!("expected ambiguous match error, got {res:?}"),
+    }
+    assert_eq!(chainparser.account_name("ambiguous", &data), None);
+}
+
+#[test]
+fn match_and_size_shank_account_per_repr_c_layout() {
+    let idl_json = r#"{
+      "version": "0.1.0",
+      "name": "zero_copy",
+      "instructions": [],
+      "accounts": [
+        {
+          "name": "Pool",
+          "type": {
+            "kind": "struct",
+            "fields": [
+              { "name": "open", "type": "bool" },
+              { "name": "amount", "type": "u64" },
+              { "name": "locked", "type": "bool" }
+            ]
+          }
+        }
+      ]
+    }"#;
+
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_json("zero_copy".to_string(), idl_json, IdlProvider::Shank)
+        .expect("failed adding IDL JSON");
+
+    // Padded layout: open at 0, amount at 8..16, locked at 16, 24 bytes in total
+    let amount = 0x0500u64;
+    let data = [
+        vec![1, 0, 0, 0, 0, 0, 0, 0],
+        amount.to_le_bytes().to_vec(),
+        vec![1, 0, 0, 0, 0, 0, 0, 0],
+    ]
+    .concat();
+
+    // Per the packed layout the byte at 9 is an invalid bool
+    assert_eq!(chainparser.account_name("zero_copy", &data), None);
+    assert_eq!(
+        chainparser.field_offsets("zero_copy", "Pool").unwrap()[2],
+        ("locked".to_string(), 9..10)
+    );
+
+    chainparser.set_type_repr_c("zero_copy", "Pool", 1).unwrap();
+
+    assert_eq!(chainparser.account_name("zero_copy", &data), Some("Pool"));
+    let json = chainparser
+        .deserialize_account_to_json_string("zero_copy", &mut &data[..])
+        .unwrap();
+    assert_eq!(json, r#"{"open":true,"amount":1280,"locked":true}"#);
+    assert_eq!(
+        chainparser.field_offsets("zero_copy", "Pool").unwrap(),
+        vec![
+            ("open".to_string(), 0..1),
+            ("amount".to_string(), 8..16),
+            ("locked".to_string(), 16..17)
+        ]
+    );
+}
+
+#[test]
+fn add_idl_with_cyclic_types_fails() {
+    let idl_json = r#"{
+      "version": "0.1.0",
+      "name": "cyclic",
+      "instructions": [],
+      "accounts": [
+        {
+          "name": "Chain",
+          "type": {
+            "kind": "struct",
+            "fields": [
+              { "name": "link", "type": { "defined": "Link" } },
+              { "name": "next", "type": { "option": { "defined": "Chain" } } }
+            ]
+          }
+        }
+      ],
+      "types": [
+        {
+          "name": "Link",
+          "type": {
+            "kind": "struct",
+            "fields": [{ "name": "chain", "type": { "defined": "Chain" } }]
+          }
+        }
+      ]
+    }"#;
+
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    match chainparser.add_idl_json(
+        "cyclic".to_string(),
+        idl_json,
+        IdlProvider::Shank,
+    ) {
+        Err(ChainparserError::CyclicTypeDefinitions(name, types)) => {
+            assert_eq!(name, "cyclic");
+            assert_eq!(types, vec!["Chain", "Link"]);
+        }
+        res => panic!("expected cyclic types error, got {res:?}"),
+    }
+    let idl: Idl = serde_json::from_str(idl_json).unwrap();
+    assert!(matches!(
+        chainparser.add_idl("cyclic".to_string(), idl, IdlProvider::Shank),
+        Err(ChainparserError::CyclicTypeDefinitions(_, _))
+    ));
+    assert!(!chainparser.has_idl("cyclic"));
+}
+
+#[test]
+fn deserialize_shank_enum_account() {
+    let idl_json = r#"{
+      "version": "0.1.0",
+      "name": "vaults",
+      "instructions": [],
+      "accounts": [
+        {
+          "name": "Config",
+          "type": {
+            "kind": "struct",
+            "fields": [
+              { "name": "authority", "type": "publicKey" },
+              { "name": "paused", "type": "bool" }
+            ]
+          }
+        },
+        {
+          "name": "Vault",
+          "type": {
+            "kind": "enum",
+            "variants": [
+              { "name": "Uninitialized" },
+              {
+                "name": "Active",
+                "fields": [
+                  { "name": "owner", "type": "publicKey" },
+                  { "name": "amount", "type": "u64" }
+                ]
+              },
+              { "name": "Frozen", "fields": ["i64"] }
+            ]
+          }
+        }
+      ]
+    }"#;
+
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_json("vaults".to_string(), idl_json, IdlProvider::Shank)
+        .expect("failed adding IDL JSON");
+    let owner = Pubkey::new_from_array([7; 32]);
+    let deserialize = |data: &[u8]| {
+        chainparser.deserialize_account_to_json_string("vaults", &mut &data[..])
+    };
+
+    let active = [&[1u8][..], &owner.to_bytes(), &5u64.to_le_bytes()].concat();
+    assert_eq!(chainparser.account_name("vaults", &active), Some("Vault"));
+    assert_eq!(
+        deserialize(&active).unwrap(),
+        format!(r#"{{"Active":{{"owner":"{owner}","amount":5}}}}"#)
+    );
+    assert_eq!(deserialize(&[0]).unwrap(), r#""Uninitialized""#);
+    assert_eq!(
+        deserialize(&[&[2u8][..], &(-1i64).to_le_bytes()].concat()).unwrap(),
+        r#"{"Frozen":[-1]}"#
+    );
+
+    let config = [&owner.to_bytes()[..], &[1]].concat();
+    assert_eq!(chainparser.account_name("vaults", &config), Some("Config"));
+    assert!(matches!(
+        deserialize(&[3; 9]),
+        Err(ChainparserError::CannotFindDeserializerForAccount)
+    ));
+}
+
+#[test]
+fn add_idl_from_bytes_and_account_data() {
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+
+    chainparser
+        .add_idl_bytes(
+            "from_bytes".to_string(),
+            VAULT_IDL_JSON.as_bytes(),
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL bytes");
+
+    let program_id = Pubkey::from_str(VAULT_PROGRAM_ID).unwrap();
+    let account_data = encode_idl_account_json(&program_id, VAULT_IDL_JSON)
+        .expect("failed encoding IDL account");
+    chainparser
+        .add_idl_from_account_data(
+            VAULT_PROGRAM_ID.to_string(),
+            &account_data,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL from account data");
+
+    let authority = Pubkey::new_unique();
+    let data = [
+        account_discriminator("VaultInfo").to_vec(),
+        authority.to_bytes().to_vec(),
+        5u64.to_le_bytes().to_vec(),
+    ]
+    .concat();
+    for id in ["from_bytes", VAULT_PROGRAM_ID] {
+        let json = chainparser
+            .deserialize_account_to_json_string(id, &mut &data[..])
+            .unwrap();
+        assert_eq!(
+            json,
+            format!(r#"{{"authority":"{authority}","balance":5}}"#)
+        );
+    }
+
+    assert!(chainparser
+        .add_idl_from_account_data(
+            "invalid".to_string(),
+            &[0; 4],
+            IdlProvider::Anchor,
+        )
+        .is_err());
+}
+
+#[test]
+fn add_new_format_idl_from_bytes_and_account_data() {
+    // Anchor >= 0.30 IDLs declare the discriminator of each account which may be custom
+    const NEW_VAULT_IDL_JSON: &str = r#"{
+      "address": "Vau1t6sLNxnzB7ZDsef8TLbPLfyZMYXH8WTNqUdm9g8",
+      "metadata": { "name": "vault", "version": "0.1.0", "spec": "0.1.0" },
+      "instructions": [],
+      "accounts": [
+        { "name": "VaultInfo", "discriminator": [1, 2, 3, 4, 5, 6, 7, 8] }
+      ],
+      "types": [
+        {
+          "name": "VaultInfo",
+          "type": {
+            "kind": "struct",
+            "fields": [
+              { "name": "authority", "type": "pubkey" },
+              { "name": "balance", "type": "u64" }
+            ]
+          }
+        }
+      ]
+    }"#;
+
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_bytes(
+            "from_bytes".to_string(),
+            NEW_VAULT_IDL_JSON.as_bytes(),
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding new format IDL bytes");
+
+    let program_id = Pubkey::from_str(VAULT_PROGRAM_ID).unwrap();
+    let account_data = encode_idl_account_json(&program_id, NEW_VAULT_IDL_JSON)
+        .expect("failed encoding IDL account");
+    chainparser
+        .add_idl_from_account_data(
+            VAULT_PROGRAM_ID.to_string(),
+            &account_data,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding new format IDL from account data");
+
+    let authority = Pubkey::new_unique();
+    let data = [
+        vec![1, 2, 3, 4, 5, 6, 7, 8],
+        authority.to_bytes().to_vec(),
+        5u64.to_le_bytes().to_vec(),
+    ]
+    .concat();
+    for id in ["from_bytes", VAULT_PROGRAM_ID] {
+        assert_eq!(chainparser.account_name(id, &data), Some("VaultInfo"));
+        let json = chainparser
+            .deserialize_account_to_json_string(id, &mut &data[..])
+            .unwrap();
+        assert_eq!(
+            json,
+            format!(r#"{{"authority":"{authority}","balance":5}}"#)
+        );
+    }
+
+    assert!(matches!(
+        chainparser.add_idl_bytes(
+            "invalid".to_string(),
+            &[0xff, 0xfe],
+            IdlProvider::Anchor,
+        ),
+        Err(ChainparserError::IdlInvalidUtf8(_))
+    ));
+}
+
+#[test]
+fn reject_conflicting_serialization_opts() {
+    let opts = JsonSerializationOpts {
+        n128_as_hex: true,
+        n128_as_string: true,
+        ..Default::default()
+    };
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    let res = chainparser.add_idl_json(
+        VAULT_PROGRAM_ID.to_string(),
+        VAULT_IDL_JSON,
+        IdlProvider::Anchor,
+    );
+    assert!(matches!(
+        res,
+        Err(ChainparserError::ConflictingSerializationOpts(a, b))
+            if a == "n128_as_hex" && b == "n128_as_string"
+    ));
+    assert!(!chainparser.has_idl(VAULT_PROGRAM_ID));
+
+    assert!(JsonSerializationOpts {
+        n128_as_hex: true,
+        ..Default::default()
+    }
+    .validate()
+    .is_ok());
+}
+
+#[test]
+fn deserialize_account_to_flat_map() {
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_json(
+            VAULT_PROGRAM_ID.to_string(),
+            VAULT_IDL_JSON,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+
+    let authority = Pubkey::new_unique();
+    let data = [
+        account_discriminator("VaultInfo").to_vec(),
+        authority.to_bytes().to_vec(),
+        5u64.to_le_bytes().to_vec(),
+    ]
+    .concat();
+
+    let flat = chainparser
+        .deserialize_account_to_flat_map(VAULT_PROGRAM_ID, &mut &data[..])
+        .unwrap();
+    assert_eq!(flat.len(), 2);
+    assert_eq!(flat["authority"], authority.to_string());
+    assert_eq!(flat["balance"], 5);
+}
+
+#[test]
+fn add_idl_with_field_type_overrides() {
+    // The published IDL declares the balance as u32 while a u64 is stored
+    let idl_json = VAULT_IDL_JSON.replace(
+        r#"{ "name": "balance", "type": "u64" }"#,
+        r#"{ "name": "balance", "type": "u32" }"#,
+    );
+    let idl: Idl = serde_json::from_str(&idl_json).unwrap();
+
+    let authority = Pubkey::new_unique();
+    let balance = u32::MAX as u64 + 1;
+    let data = [
+        account_discriminator("VaultInfo").to_vec(),
+        authority.to_bytes().to_vec(),
+        balance.to_le_bytes().to_vec(),
+    ]
+    .concat();
+
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    let overrides = FieldTypeOverrides::from([(
+        ("VaultInfo".to_string(), "balance".to_string()),
+        IdlType::U64,
+    )]);
+    chainparser
+        .add_idl_with_overrides(
+            VAULT_PROGRAM_ID.to_string(),
+            idl,
+            IdlProvider::Anchor,
+            &overrides,
+        )
+        .expect("failed adding IDL with overrides");
+
+    let json = chainparser
+        .deserialize_account_to_json_string(VAULT_PROGRAM_ID, &mut &data[..])
+        .unwrap();
+    assert_eq!(
+        json,
+        format!(r#"{{"authority":"{authority}","balance":{balance}}}"#)
+    );
+}
+
+/// Records the chunks of JSON that were written between flushes.
+#[derive(Default)]
+struct FlushRecorder {
+    pending: Vec<u8>,
+    chunks: Vec<String>,
+}
+
+impl std::io::Write for FlushRecorder {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        let chunk = String::from_utf8(std::mem::take(&mut self.pending))
+            .expect("valid utf8");
+        self.chunks.push(chunk);
+        Ok(())
+    }
+}
+
+#[test]
+fn deserialize_account_to_io_writer_flushes_vec_elements() {
+    const LEDGER_IDL_JSON: &str = r#"{
+      "version": "0.1.0",
+      "name": "ledger",
+      "instructions": [],
+      "accounts": [
+        {
+          "name": "Ledger",
+          "type": {
+            "kind": "struct",
+            "fields": [{ "name": "entries", "type": { "vec": "u64" } }]
+          }
+        }
+      ]
+    }"#;
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_json(
+            VAULT_PROGRAM_ID.to_string(),
+            LEDGER_IDL_JSON,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+
+    let len = 10_000u64;
+    let mut data = account_discriminator("Ledger").to_vec();
+    data.extend_from_slice(&(len as u32).to_le_bytes());
+    for i in 0..len {
+        data.extend_from_slice(&i.to_le_bytes());
+    }
+
+    let mut recorder = FlushRecorder::default();
+    chainparser
+        .deserialize_account_to_io_writer(
+            VAULT_PROGRAM_ID,
+            &mut &data[..],
+            &mut recorder,
+        )
+        .unwrap();
+
+    // One flush per element plus the final one once the account is written
+    assert_eq!(recorder.chunks.len(), len as usize + 1);
+    assert_eq!(recorder.chunks[0], r#"{"entries":[0, "#);
+    assert_eq!(recorder.chunks[1], "1, ");
+    assert_eq!(recorder.chunks[len as usize - 1], "9999");
+    assert_eq!(recorder.chunks[len as usize], "]}");
+
+    let json = recorder.chunks.concat();
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["entries"].as_array().unwrap().len(), len as usize);
+}
+
+/// A [std::fmt::Write] writer other than `String` which counts the writes.
+#[derive(Default)]
+struct CountingFmtWriter {
+    json: String,
+    writes: usize,
+}
+
+impl std::fmt::Write for CountingFmtWriter {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.writes += 1;
+        self.json.push_str(s);
+        Ok(())
+    }
+}
+
+/// A [JsonWrite] writer which counts the flushes, which it only receives when [STREAMING].
+#[derive(Default)]
+struct CountingJsonWriter<const STREAMING: bool> {
+    json: String,
+    flushes: usize,
+}
+
+impl<const STREAMING: bool> std::fmt::Write for CountingJsonWriter<STREAMING> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.json.push_str(s);
+        Ok(())
+    }
+}
+
+impl<const STREAMING: bool> JsonWrite for CountingJsonWriter<STREAMING> {
+    const STREAMING: bool = STREAMING;
+
+    fn flush(&mut self) -> std::fmt::Result {
+        self.flushes += 1;
+        Ok(())
+    }
+}
+
+#[test]
+fn deserialize_account_to_fmt_and_json_writers() {
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_json(
+            VAULT_PROGRAM_ID.to_string(),
+            r#"{
+              "version": "0.1.0",
+              "name": "ledger",
+              "instructions": [],
+              "accounts": [
+                {
+                  "name": "Ledger",
+                  "type": {
+                    "kind": "struct",
+                    "fields": [{ "name": "entries", "type": { "vec": "u64" } }]
+                  }
+                }
+              ]
+            }"#,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+
+    let mut data = account_discriminator("Ledger").to_vec();
+    data.extend_from_slice(&3u32.to_le_bytes());
+    for i in 0..3u64 {
+        data.extend_from_slice(&i.to_le_bytes());
+    }
+    let expected = r#"{"entries":[0, 1, 2]}"#;
+
+    let mut fmt_writer = CountingFmtWriter::default();
+    chainparser
+        .deserialize_account_to_json(
+            VAULT_PROGRAM_ID,
+            &mut &data[..],
+            &mut fmt_writer,
+        )
+        .unwrap();
+    assert_eq!(fmt_writer.json, expected);
+    assert!(fmt_writer.writes > 0);
+
+    let mut json_writer = CountingJsonWriter::<true>::default();
+    chainparser
+        .deserialize_account_to_json_writer(
+            VAULT_PROGRAM_ID,
+            &mut &data[..],
+            &mut json_writer,
+        )
+        .unwrap();
+    assert_eq!(json_writer.json, expected);
+    assert_eq!(json_writer.flushes, 3);
+
+    // Writers that don't opt into streaming are never flushed
+    let mut json_writer = CountingJsonWriter::<false>::default();
+    chainparser
+        .deserialize_account_to_json_writer(
+            VAULT_PROGRAM_ID,
+            &mut &data[..],
+            &mut json_writer,
+        )
+        .unwrap();
+    assert_eq!(json_writer.json, expected);
+    assert_eq!(json_writer.flushes, 0);
+}
+
+/// Uses the name of the account padded with zeros as its discriminator.
+struct NameDiscriminatorStrategy;
+
+impl DiscriminatorStrategy for NameDiscriminatorStrategy {
+    fn discriminator(&self, name: &str) -> DiscriminatorBytes {
+        let mut discriminator = [0u8; 8];
+        for (dst, src) in discriminator.iter_mut().zip(name.bytes()) {
+            *dst = src;
+        }
+        discriminator
+    }
+}
+
+#[test]
+fn deserialize_account_with_custom_discriminator_strategy() {
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_json(
+            VAULT_PROGRAM_ID.to_string(),
+            VAULT_IDL_JSON,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+    chainparser
+        .set_account_discriminator_strategy(
+            VAULT_PROGRAM_ID,
+            &NameDiscriminatorStrategy,
+        )
+        .unwrap();
+
+    let authority = Pubkey::new_unique();
+    let data = [
+        b"VaultInf".to_vec(),
+        authority.to_bytes().to_vec(),
+        5u64.to_le_bytes().to_vec(),
+    ]
+    .concat();
+    assert_eq!(
+        chainparser.account_name(VAULT_PROGRAM_ID, &data),
+        Some("VaultInfo")
+    );
+    let json = chainparser
+        .deserialize_account_to_json_string(VAULT_PROGRAM_ID, &mut &data[..])
+        .unwrap();
+    assert_eq!(
+        json,
+        format!(r#"{{"authority":"{authority}","balance":5}}"#)
+    );
+
+    // The anchor discriminator no longer resolves the account
+    let data = [
+        account_discriminator("VaultInfo").to_vec(),
+        authority.to_bytes().to_vec(),
+        5u64.to_le_bytes().to_vec(),
+    ]
+    .concat();
+    assert_eq!(chainparser.account_name(VAULT_PROGRAM_ID, &data), None);
+}
+
+#[test]
+fn deserialize_account_with_custom_discriminator_namespace() {
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_json(
+            VAULT_PROGRAM_ID.to_string(),
+            VAULT_IDL_JSON,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+    chainparser
+        .set_account_discriminator_namespace(VAULT_PROGRAM_ID, "state")
+        .unwrap();
+
+    let authority = Pubkey::new_unique();
+    let account_data = |discriminator: [u8; 8]| {
+        [
+            discriminator.to_vec(),
+            authority.to_bytes().to_vec(),
+            5u64.to_le_bytes().to_vec(),
+        ]
+        .concat()
+    };
+
+    let data = account_data(account_discriminator_with_namespace(
+        "state",
+        "VaultInfo",
+    ));
+    assert_eq!(
+        chainparser.account_name(VAULT_PROGRAM_ID, &data),
+        Some("VaultInfo")
+    );
+    let json = chainparser
+        .deserialize_account_to_json_string(VAULT_PROGRAM_ID, &mut &data[..])
+        .unwrap();
+    assert_eq!(
+        json,
+        format!(r#"{{"authority":"{authority}","balance":5}}"#)
+    );
+
+    // The anchor discriminator no longer resolves the account
+    let data = account_data(account_discriminator("VaultInfo"));
+    assert_eq!(chainparser.account_name(VAULT_PROGRAM_ID, &data), None);
+
+    // Serialized accounts are prefixed with the custom discriminator
+    let serialized = chainparser
+        .serialize_account_json(VAULT_PROGRAM_ID, "VaultInfo", &json)
+        .unwrap();
+    assert_eq!(
+        serialized[..8],
+        account_discriminator_with_namespace("state", "VaultInfo")
+    );
+}
+
+#[test]
+fn deserialize_account_embedding_another_account() {
+    const POOL_IDL_JSON: &str = r#"{
+      "version": "0.1.0",
+      "name": "pool",
+      "instructions": [],
+      "accounts": [
+        {
+          "name": "Pool",
+          "type": {
+            "kind": "struct",
+            "fields": [
+              { "name": "bump", "type": "u8" },
+              { "name": "config", "type": { "defined": "PoolConfig" } }
+            ]
+          }
+        },
+        {
+          "name": "PoolConfig",
+          "type": {
+            "kind": "struct",
+            "fields": [{ "name": "fee", "type": "u16" }]
+          }
+        }
+      ]
+    }"#;
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_json(
+            VAULT_PROGRAM_ID.to_string(),
+            POOL_IDL_JSON,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+
+    // The embedded account is not prefixed with its discriminator
+    let data = [
+        account_discriminator("Pool").to_vec(),
+        vec![1],
+        30u16.to_le_bytes().to_vec(),
+    ]
+    .concat();
+    let json = chainparser
+        .deserialize_account_to_json_string(VAULT_PROGRAM_ID, &mut &data[..])
+        .unwrap();
+    assert_eq!(json, r#"{"bump":1,"config":{"fee":30}}"#);
+
+    // The embedded account can still be deserialized on its own
+    let data = [
+        account_discriminator("PoolConfig").to_vec(),
+        30u16.to_le_bytes().to_vec(),
+    ]
+    .concat();
+    let json = chainparser
+        .deserialize_account_to_json_string(VAULT_PROGRAM_ID, &mut &data[..])
+        .unwrap();
+    assert_eq!(json, r#"{"fee":30}"#);
+}
+
+#[test]
+fn deserialize_spl_token_accounts_without_idl() {
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser.add_spl_token_idl().unwrap();
+
+    let none = [0u8; 4].to_vec();
+    let some = [1u8, 0, 0, 0].to_vec();
+
+    let mint_authority = Pubkey::new_unique();
+    let mint = [
+        some.clone(),
+        mint_authority.to_bytes().to_vec(),
+        1_000u64.to_le_bytes().to_vec(),
+        vec![6, 1],
+        none.clone(),
+        vec![0; 32],
+    ]
+    .concat();
+    assert_eq!(mint.len(), 82);
+    assert_eq!(
+        chainparser.account_name(SPL_TOKEN_PROGRAM_ID, &mint),
+        Some("Mint")
+    );
+    let json = chainparser
+        .deserialize_account_to_json_string(
+            SPL_TOKEN_PROGRAM_ID,
+            &mut &mint[..],
+        )
+        .unwrap();
+    assert_eq!(
+        json,
+        format!(
+            r#"{{"mintAuthority":"{mint_authority}","supply":1000,"decimals":6,"isInitialized":true,"freezeAuthority":null}}"#
+        )
+    );
+
+    let (token_mint, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let account = [
+        token_mint.to_bytes().to_vec(),
+        owner.to_bytes().to_vec(),
+        50u64.to_le_bytes().to_vec(),
+        none.clone(),
+        vec![0; 32],
+        vec![2],
+        some,
+        2_039_280u64.to_le_bytes().to_vec(),
+        0u64.to_le_bytes().to_vec(),
+        none,
+        vec![0; 32],
+    ]
+    .concat();
+    assert_eq!(account.len(), 165);
+    assert_eq!(
+        chainparser.account_name(SPL_TOKEN_PROGRAM_ID, &account),
+        Some("Account")
+    );
+    let json = chainparser
+        .deserialize_account_to_json_string(
+            SPL_TOKEN_PROGRAM_ID,
+            &mut &account[..],
+        )
+        .unwrap();
+    assert_eq!(
+        json,
+        format!(
+            r#"{{"mint":"{token_mint}","owner":"{owner}","amount":50,"delegate":null,"state":"Frozen","isNative":2039280,"delegatedAmount":0,"closeAuthority":null}}"#
+        )
+    );
+}
+
+#[test]
+fn deserialize_spl_token_coption_authorities() {
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser.add_spl_token_idl().unwrap();
+
+    let none = [0u8; 4].to_vec();
+    let some = [1u8, 0, 0, 0].to_vec();
+
+    // Revoking an authority only resets the tag and leaves its pubkey bytes behind
+    let (revoked, freeze_authority) =
+        (Pubkey::new_unique(), Pubkey::new_unique());
+    let mint = [
+        none.clone(),
+        revoked.to_bytes().to_vec(),
+        1_000u64.to_le_bytes().to_vec(),
+        vec![9, 1],
+        some.clone(),
+        freeze_authority.to_bytes().to_vec(),
+    ]
+    .concat();
+    assert_eq!(
+        chainparser.account_name(SPL_TOKEN_PROGRAM_ID, &mint),
+        Some("Mint")
+    );
+    let json = chainparser
+        .deserialize_account_to_json_string(
+            SPL_TOKEN_PROGRAM_ID,
+            &mut &mint[..],
+        )
+        .unwrap();
+    assert_eq!(
+        json,
+        format!(
+            r#"{{"mintAuthority":null,"supply":1000,"decimals":9,"isInitialized":true,"freezeAuthority":"{freeze_authority}"}}"#
+        )
+    );
+    // Serializing writes zeros for the revoked authority
+    let serialized = chainparser
+        .serialize_account_json(SPL_TOKEN_PROGRAM_ID, "Mint", &json)
+        .unwrap();
+    assert_eq!(serialized[..36], [0; 36]);
+    assert_eq!(serialized[36..], mint[36..]);
+
+    let (token_mint, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (delegate, close_authority) =
+        (Pubkey::new_unique(), Pubkey::new_unique());
+    let account = [
+        token_mint.to_bytes().to_vec(),
+        owner.to_bytes().to_vec(),
+        50u64.to_le_bytes().to_vec(),
+        some.clone(),
+        delegate.to_bytes().to_vec(),
+        vec![1],
+        none,
+        vec![0; 8],
+        20u64.to_le_bytes().to_vec(),
+        some,
+        close_authority.to_bytes().to_vec(),
+    ]
+    .concat();
+    assert_eq!(
+        chainparser.account_name(SPL_TOKEN_PROGRAM_ID, &account),
+        Some("Account")
+    );
+    let json = chainparser
+        .deserialize_account_to_json_string(
+            SPL_TOKEN_PROGRAM_ID,
+            &mut &account[..],
+        )
+        .unwrap();
+    assert_eq!(
+        json,
+        format!(
+            r#"{{"mint":"{token_mint}","owner":"{owner}","amount":50,"delegate":"{delegate}","state":"Initialized","isNative":null,"delegatedAmount":20,"closeAuthority":"{close_authority}"}}"#
+        )
+    );
+}
+
+#[test]
+fn deserialize_spl_token_2022_accounts_with_extensions() {
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser.add_spl_token_2022_idl().unwrap();
+
+    let tlv_entry = |ty: u16, value: &[u8]| {
+        [
+            &ty.to_le_bytes()[..],
+            &(value.len() as u16).to_le_bytes(),
+            value,
+        ]
+        .concat()
+    };
+
+    let (mint_authority, close_authority) =
+        (Pubkey::new_unique(), Pubkey::new_unique());
+    let mint = [
+        vec![1, 0, 0, 0],
+        mint_authority.to_bytes().to_vec(),
+        1_000u64.to_le_bytes().to_vec(),
+        vec![6, 1],
+        vec![0; 36],
+        // padding up to the account type
+        vec![0; 83],
+        vec![1],
+        tlv_entry(3, &close_authority.to_bytes()),
+        tlv_entry(99, &[0xab, 0xcd]),
+        vec![0; 4],
+    ]
+    .concat();
+    assert_eq!(
+        chainparser.account_name(SPL_TOKEN_2022_PROGRAM_ID, &mint),
+        Some("Mint")
+    );
+    let json = chainparser
+        .deserialize_account_to_json_string(
+            SPL_TOKEN_2022_PROGRAM_ID,
+            &mut &mint[..],
+        )
+        .unwrap();
+    assert_eq!(
+        json,
+        format!(
+            r#"{{"mintAuthority":"{mint_authority}","supply":1000,"decimals":6,"isInitialized":true,"freezeAuthority":null,"tlv":[{{"type":3,"name":"MintCloseAuthority","value":{{"closeAuthority":"{close_authority}"}}}}, {{"type":99,"data":"abcd"}}]}}"#
+        )
+    );
+
+    let (token_mint, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let account = [
+        token_mint.to_bytes().to_vec(),
+        owner.to_bytes().to_vec(),
+        50u64.to_le_bytes().to_vec(),
+        vec![0; 36],
+        vec![1],
+        vec![0; 12],
+        0u64.to_le_bytes().to_vec(),
+        vec![0; 36],
+        vec![2],
+        tlv_entry(7, &[]),
+        tlv_entry(2, &7u64.to_le_bytes()),
+    ]
+    .concat();
+    assert_eq!(
+        chainparser.account_name(SPL_TOKEN_2022_PROGRAM_ID, &account),
+        Some("Account")
+    );
+    let json = chainparser
+        .deserialize_account_to_json_string(
+            SPL_TOKEN_2022_PROGRAM_ID,
+            &mut &account[..],
+        )
+        .unwrap();
+    assert_eq!(
+        json,
+        format!(
+            r#"{{"mint":"{token_mint}","owner":"{owner}","amount":50,"delegate":null,"state":"Initialized","isNative":null,"delegatedAmount":0,"closeAuthority":null,"tlv":[{{"type":7,"name":"ImmutableOwner","value":{{}}}}, {{"type":2,"name":"TransferFeeAmount","value":{{"withheldAmount":7}}}}]}}"#
+        )
+    );
+
+    // Accounts without extensions have no account type
+    let json = chainparser
+        .deserialize_account_to_json_string(
+            SPL_TOKEN_2022_PROGRAM_ID,
+            &mut &account[..165],
+        )
+        .unwrap();
+    assert!(json.ends_with(r#""closeAuthority":null,"tlv":[]}"#));
+}
+
+#[test]
+fn deserialize_account_fields_with_metadata() {
+    const PRIMITIVES_IDL_JSON: &str = r#"{
+      "version": "0.1.0",
+      "name": "primitives",
+      "instructions": [],
+      "accounts": [
+        {
+          "name": "Primitives",
+          "type": {
+            "kind": "struct",
+            "fields": [
+              { "name": "flag", "type": "bool" },
+              { "name": "count", "type": "u16" },
+              { "name": "authority", "type": "publicKey" },
+              { "name": "label", "type": "string" },
+              { "name": "kind", "type": { "defined": "Kind" } },
+              { "name": "scores", "type": { "vec": "u32" } }
+            ]
+          }
+        }
+      ],
+      "types": [
+        {
+          "name": "Kind",
+          "type": {
+            "kind": "enum",
+            "variants": [{ "name": "Small" }, { "name": "Large" }]
+          }
+        }
+      ]
+    }"#;
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_json(
+            VAULT_PROGRAM_ID.to_string(),
+            PRIMITIVES_IDL_JSON,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+
+    let authority = Pubkey::new_unique();
+    let data = [
+        account_discriminator("Primitives").to_vec(),
+        vec![1],
+        7u16.to_le_bytes().to_vec(),
+        authority.to_bytes().to_vec(),
+        3u32.to_le_bytes().to_vec(),
+        b"foo".to_vec(),
+        vec![1],
+        1u32.to_le_bytes().to_vec(),
+        9u32.to_le_bytes().to_vec(),
+    ]
+    .concat();
+
+    let fields = chainparser
+        .deserialize_account_fields(VAULT_PROGRAM_ID, "Primitives", &data)
+        .unwrap();
+    let field = |name: &str, ty, value, offset, len, kind| FieldInfo {
+        name: name.to_string(),
+        ty,
+        value,
+        offset,
+        len,
+        kind,
+    };
+    assert_eq!(
+        fields,
+        vec![
+            field("flag", IdlType::Bool, true.into(), 8, 1, FieldKind::Bool),
+            field("count", IdlType::U16, 7.into(), 9, 2, FieldKind::Integer),
+            field(
+                "authority",
+                IdlType::PublicKey,
+                authority.to_string().into(),
+                11,
+                32,
+                FieldKind::PublicKey
+            ),
+            field(
+                "label",
+                IdlType::String,
+                "foo".into(),
+                43,
+                7,
+                FieldKind::String
+            ),
+            field(
+                "kind",
+                IdlType::Defined("Kind".to_string()),
+                "Large".into(),
+                50,
+                1,
+                FieldKind::Enum
+            ),
+            field(
+                "scores",
+                IdlType::Vec(Box::new(IdlType::U32)),
+                serde_json::json!([9]),
+                51,
+                8,
+                FieldKind::Vec
+            ),
+        ]
+    );
+
+    assert!(matches!(
+        chainparser.deserialize_account_fields(
+            VAULT_PROGRAM_ID,
+            "Unknown",
+            &data
+        ),
+        Err(ChainparserError::UnknownAccount(_))
+    ));
+}
+
+#[test]
+fn deserialize_account_fields_with_non_finite_floats() {
+    const FLOATS_IDL_JSON: &str = r#"{
+      "version": "0.1.0",
+      "name": "floats",
+      "instructions": [],
+      "accounts": [
+        {
+          "name": "Floats",
+          "type": {
+            "kind": "struct",
+            "fields": [
+              { "name": "ratio", "type": "f64" },
+              { "name": "limit", "type": "f32" },
+              { "name": "scale", "type": "f32" }
+            ]
+          }
+        }
+      ]
+    }"#;
+    let data = [
+        account_discriminator("Floats").to_vec(),
+        f64::NAN.to_le_bytes().to_vec(),
+        f32::NAN.to_le_bytes().to_vec(),
+        1.5f32.to_le_bytes().to_vec(),
+    ]
+    .concat();
+
+    for (non_finite, nan) in [
+        (NonFiniteRepr::Literal, "NaN".into()),
+        (NonFiniteRepr::Null, serde_json::Value::Null),
+        (NonFiniteRepr::String, "NaN".into()),
+    ] {
+        let opts = JsonSerializationOpts {
+            non_finite,
+            ..Default::default()
+        };
+        let mut chainparser = ChainparserDeserializer::new(&opts);
+        chainparser
+            .add_idl_json(
+                VAULT_PROGRAM_ID.to_string(),
+                FLOATS_IDL_JSON,
+                IdlProvider::Anchor,
+            )
+            .expect("failed adding IDL JSON");
+        let values = chainparser
+            .deserialize_account_fields(VAULT_PROGRAM_ID, "Floats", &data)
+            .unwrap()
+            .into_iter()
+            .map(|field| field.value)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            vec![nan.clone(), nan, serde_json::Value::from(1.5)]
+        );
+    }
+}
+
+#[test]
+fn deserialize_accounts_in_batch() {
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_json(
+            VAULT_PROGRAM_ID.to_string(),
+            VAULT_IDL_JSON,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+
+    let authority = Pubkey::new_unique();
+    let vault = |balance: u64| {
+        [
+            account_discriminator("VaultInfo").to_vec(),
+            authority.to_bytes().to_vec(),
+            balance.to_le_bytes().to_vec(),
+        ]
+        .concat()
+    };
+    let (first, second) = (vault(1), vault(2));
+    let truncated = &first[..20];
+
+    let results = chainparser
+        .deserialize_accounts(VAULT_PROGRAM_ID, &[&first, truncated, &second]);
+    assert_eq!(results.len(), 3);
+    assert_eq!(
+        results[0].as_ref().unwrap(),
+        &format!(r#"{{"authority":"{authority}","balance":1}}"#)
+    );
+    // Failing accounts don't abort the batch
+    assert!(results[1].is_err());
+    assert_eq!(
+        results[2].as_ref().unwrap(),
+        &format!(r#"{{"authority":"{authority}","balance":2}}"#)
+    );
+
+    let results = chainparser.deserialize_accounts("unknown", &[&first]);
+    assert!(matches!(
+        results[0],
+        Err(ChainparserError::CannotFindAccountDeserializerForProgramId(
+            _
+        ))
+    ));
+}
+
+#[test]
+fn deserialize_account_base64() {
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::new(&opts);
+    chainparser
+        .add_idl_json(
+            VAULT_PROGRAM_ID.to_string(),
+            VAULT_IDL_JSON,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+
+    let authority = Pubkey::new_unique();
+    let vault_data =
+        [authority.to_bytes().to_vec(), 5u64.to_le_bytes().to_vec()].concat();
+    let expected = format!(r#"{{"authority":"{authority}","balance":5}}"#);
+
+    let data = [
+        account_discriminator("VaultInfo").to_vec(),
+        vault_data.clone(),
+    ]
+    .concat();
+    let json = chainparser
+        .deserialize_account_base64(
+            VAULT_PROGRAM_ID,
+            &BASE64_STANDARD.encode(&data),
+        )
+        .unwrap();
+    assert_eq!(json, expected);
+
+    let json = chainparser
+        .deserialize_account_base64_by_name(
+            VAULT_PROGRAM_ID,
+            "VaultInfo",
+            &BASE64_STANDARD.encode(&vault_data),
+        )
+        .unwrap();
+    assert_eq!(json, expected);
+
+    assert!(matches!(
+        chainparser.deserialize_account_base64(VAULT_PROGRAM_ID, "not base64!"),
+        Err(ChainparserError::Base64DecodeError(_))
+    ));
+}
+
+#[test]
+fn share_deserializer_across_threads() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<ChainparserDeserializer<'static>>();
+
+    let mut chainparser = ChainparserDeserializer::with_owned_opts(
+        JsonSerializationOpts::default(),
+    );
+    chainparser
+        .add_idl_json(
+            VAULT_PROGRAM_ID.to_string(),
+            VAULT_IDL_JSON,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+    let chainparser = std::sync::Arc::new(chainparser);
+
+    let handles = (0..4u64)
+        .map(|balance| {
+            let chainparser = chainparser.clone();
+            std::thread::spawn(move || {
+                let authority = Pubkey::new_unique();
+                let data = [
+                    account_discriminator("VaultInfo").to_vec(),
+                    authority.to_bytes().to_vec(),
+                    balance.to_le_bytes().to_vec(),
+                ]
+                .concat();
+                let json = chainparser
+                    .deserialize_account_to_json_string(
+                        VAULT_PROGRAM_ID,
+                        &mut &data[..],
+                    )
+                    .unwrap();
+                assert_eq!(
+                    json,
+                    format!(
+                        r#"{{"authority":"{authority}","balance":{balance}}}"#
+                    )
+                );
+            })
+        })
+        .collect::<Vec<_>>();
+    for handle in handles {
+        handle.join().expect("thread panicked");
+    }
+}
+
+#[test]
+fn deserialize_accounts_with_types_shared_across_idls() {
+    fn pool_idl_json(amount_ty: &str) -> String {
+        format!(
+            r#"{{
+              "version": "0.1.0",
+              "name": "pool",
+              "instructions": [],
+              "accounts": [
+                {{
+                  "name": "Pool",
+                  "type": {{
+                    "kind": "struct",
+                    "fields": [
+                      {{ "name": "config", "type": {{ "defined": "Config" }} }},
+                      {{ "name": "amount", "type": {{ "defined": "Amount" }} }}
+                    ]
+                  }}
+                }}
+              ],
+              "types": [
+                {{
+                  "name": "Config",
+                  "type": {{
+                    "kind": "struct",
+                    "fields": [{{ "name": "fee", "type": "u16" }}]
+                  }}
+                }},
+                {{
+                  "name": "Amount",
+                  "type": {{
+                    "kind": "struct",
+                    "fields": [{{ "name": "value", "type": "{amount_ty}" }}]
+                  }}
+                }}
+              ]
+            }}"#
+        )
+    }
+    const OTHER_PROGRAM_ID: &str = "11111111111111111111111111111111";
+
+    let opts = JsonSerializationOpts::default();
+    let mut chainparser = ChainparserDeserializer::with_shared_types(&opts);
+    assert_eq!(chainparser.shared_type_count(), Some(0));
+    chainparser
+        .add_idl_json(
+            VAULT_PROGRAM_ID.to_string(),
+            &pool_idl_json("u64"),
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+    assert_eq!(chainparser.shared_type_count(), Some(3));
+
+    // Only Config is identical while Pool differs since it includes the differing Amount
+    chainparser
+        .add_idl_json(
+            OTHER_PROGRAM_ID.to_string(),
+            &pool_idl_json("u32"),
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+    assert_eq!(chainparser.shared_type_count(), Some(5));
+
+    // Adding the same IDL again doesn't add any types
+    chainparser
+        .add_idl_json(
+            "pool-copy".to_string(),
+            &pool_idl_json("u64"),
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+    assert_eq!(chainparser.shared_type_count(), Some(5));
+
+    let data = [
+        account_discriminator("Pool").to_vec(),
+        30u16.to_le_bytes().to_vec(),
+        1_000u64.to_le_bytes().to_vec(),
+    ]
+    .concat();
+    let json = chainparser
+        .deserialize_account_to_json_string(VAULT_PROGRAM_ID, &mut &data[..])
+        .unwrap();
+    assert_eq!(json, r#"{"config":{"fee":30},"amount":{"value":1000}}"#);
+
+    let data = [
+        account_discriminator("Pool").to_vec(),
+        30u16.to_le_bytes().to_vec(),
+        1_000u32.to_le_bytes().to_vec(),
+    ]
+    .concat();
+    let json = chainparser
+        .deserialize_account_to_json_string(OTHER_PROGRAM_ID, &mut &data[..])
+        .unwrap();
+    assert_eq!(json, r#"{"config":{"fee":30},"amount":{"value":1000}}"#);
+
+    assert!(matches!(
+        chainparser.set_type_serializer(VAULT_PROGRAM_ID, "Config", "spl"),
+        Err(ChainparserError::SharedTypeCannotBeModified(_))
+    ));
+}
@@ -1,20 +1,31 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
 
 use borsh::BorshSerialize;
 use serde::{Deserialize, Serialize};
 use solana_idl::{
-    EnumFields, IdlEnumVariant, IdlType, IdlTypeDefinition, IdlTypeDefinitionTy,
+    EnumFields, IdlEnumVariant, IdlField, IdlType, IdlTypeDefinition,
+    IdlTypeDefinitionTy,
 };
 use solana_sdk::pubkey::Pubkey;
 
 mod utils;
 pub use chainparser::{
+    big_endian::BigEndianDeserializer,
+    borsh::BorshDeserializer,
     de::{
         i128_from_string, i64_from_string, opt_pubkey_from_base58,
         pubkey_from_base58, u128_from_string, u64_from_string,
         vec_pubkey_from_base58,
     },
-    json::JsonSerializationOpts,
+    json::{
+        BytesRepr, JsonIdlTypeDefinitionDeserializer, JsonIdlTypeSerializer,
+        JsonSerializationOpts, JsonTlvDeserializer,
+    },
+    serializer::SerializeProvider,
+    DeserializeProvider,
 };
 
 use crate::utils::{
@@ -235,6 +246,489 @@ fn deserialize_struct_with_composites() {
             None,
         )
     }
+
+    let t = "Strings with Quotes, Newlines and Control Characters";
+    {
+        let instance = Person {
+            name: "John \"Johnny\" Doe".to_string(),
+            age: 30,
+            ns: vec![],
+            map: vec![(1, "line 1\nline 2".to_string())]
+                .into_iter()
+                .collect(),
+            set: vec!["C:\\temp".to_string(), "tab\tbell\u{7}".to_string()]
+                .into_iter()
+                .collect(),
+            opt: None,
+            bytes: Vec::new(),
+            tuple: (0, "\r\n".to_string(), None),
+            vec_tuple: vec![(1, "\"\\\"".to_string())],
+            pubkey: Pubkey::default(),
+        };
+        let mut writer = String::new();
+        process_test_case_json(
+            t,
+            &[&idl_type_def],
+            instance.clone(),
+            ty_name,
+            &mut writer,
+            None,
+            None,
+        )
+    }
+}
+
+#[test]
+fn deserialize_maps_with_special_keys() {
+    let ty_name = "Labels";
+
+    #[derive(
+        Clone, Debug, Serialize, Deserialize, BorshSerialize, Eq, PartialEq,
+    )]
+    pub struct Labels {
+        pub by_name: HashMap<String, u16>,
+    }
+
+    let idl_type_def = IdlTypeDefinition {
+        name: ty_name.to_string(),
+        ty: IdlTypeDefinitionTy::Struct {
+            fields: vec![to_if(
+                "by_name",
+                IdlType::HashMap(
+                    Box::new(IdlType::String),
+                    Box::new(IdlType::U16),
+                ),
+            )],
+        },
+    };
+
+    let t = "String Keys with Quotes, Backslashes and Newlines";
+    {
+        let instance = Labels {
+            by_name: vec![
+                ("\"quoted\"".to_string(), 1),
+                ("back\\slash".to_string(), 2),
+                ("new\nline".to_string(), 3),
+            ]
+            .into_iter()
+            .collect(),
+        };
+        let mut writer = String::new();
+        process_test_case_json(
+            t,
+            &[&idl_type_def],
+            instance.clone(),
+            ty_name,
+            &mut writer,
+            None,
+            None,
+        )
+    }
+
+    let t = "Struct Keys are rendered as JSON strings";
+    {
+        let point_def = IdlTypeDefinition {
+            name: "Point".to_string(),
+            ty: IdlTypeDefinitionTy::Struct {
+                fields: vec![to_if("x", IdlType::U8), to_if("y", IdlType::U8)],
+            },
+        };
+        let points_def = IdlTypeDefinition {
+            name: "Points".to_string(),
+            ty: IdlTypeDefinitionTy::Struct {
+                fields: vec![to_if(
+                    "points",
+                    IdlType::BTreeMap(
+                        Box::new(IdlType::Defined("Point".to_string())),
+                        Box::new(IdlType::U8),
+                    ),
+                )],
+            },
+        };
+        let mut writer = String::new();
+        process_test_case_json_compare_str(
+            t,
+            &[&point_def, &points_def],
+            "Points",
+            &mut writer,
+            None,
+            //   len         x  y  value
+            vec![1, 0, 0, 0, 1, 2, 3],
+            r#"{"points":{"{\"x\":1,\"y\":2}": 3}}"#,
+        )
+    }
+
+    let t = "Pubkey Keys are not quoted twice";
+    {
+        let balances_def = IdlTypeDefinition {
+            name: "Balances".to_string(),
+            ty: IdlTypeDefinitionTy::Struct {
+                fields: vec![to_if(
+                    "balances",
+                    IdlType::HashMap(
+                        Box::new(IdlType::PublicKey),
+                        Box::new(IdlType::U64),
+                    ),
+                )],
+            },
+        };
+        let owner = Pubkey::new_unique();
+        let balances = HashMap::from([(owner, 42u64)]);
+        let mut writer = String::new();
+        process_test_case_json_compare_str(
+            t,
+            &[&balances_def],
+            "Balances",
+            &mut writer,
+            None,
+            balances.try_to_vec().unwrap(),
+            &format!(r#"{{"balances":{{"{owner}": 42}}}}"#),
+        )
+    }
+
+    let t = "Number Keys are quoted";
+    {
+        let names_def = IdlTypeDefinition {
+            name: "Names".to_string(),
+            ty: IdlTypeDefinitionTy::Struct {
+                fields: vec![to_if(
+                    "names",
+                    IdlType::HashMap(
+                        Box::new(IdlType::U64),
+                        Box::new(IdlType::String),
+                    ),
+                )],
+            },
+        };
+        let names = HashMap::from([(42u64, "answer".to_string())]);
+        // also when numbers are already rendered as strings
+        for opts in [
+            None,
+            Some(JsonSerializationOpts {
+                n64_as_string: true,
+                ..Default::default()
+            }),
+        ] {
+            let mut writer = String::new();
+            process_test_case_json_compare_str(
+                t,
+                &[&names_def],
+                "Names",
+                &mut writer,
+                opts,
+                names.try_to_vec().unwrap(),
+                r#"{"names":{"42": "answer"}}"#,
+            )
+        }
+    }
+}
+
+#[test]
+fn deserialize_maps_with_sorted_keys() {
+    let lookup_def = IdlTypeDefinition {
+        name: "Lookup".to_string(),
+        ty: IdlTypeDefinitionTy::Struct {
+            fields: vec![
+                to_if(
+                    "map",
+                    IdlType::HashMap(
+                        Box::new(IdlType::String),
+                        Box::new(IdlType::U8),
+                    ),
+                ),
+                to_if("set", IdlType::HashSet(Box::new(IdlType::U8))),
+            ],
+        },
+    };
+    let buf = [
+        // map len 3 with entries b: 2, c: 3, a: 1
+        vec![3, 0, 0, 0],
+        vec![1, 0, 0, 0, b'b', 2],
+        vec![1, 0, 0, 0, b'c', 3],
+        vec![1, 0, 0, 0, b'a', 1],
+        // set len 3 with items 3, 1, 2
+        vec![3, 0, 0, 0, 3, 1, 2],
+    ]
+    .concat();
+
+    for (sort_map_keys, expected) in [
+        (false, r#"{"map":{"b": 2, "c": 3, "a": 1},"set":[3, 1, 2]}"#),
+        (true, r#"{"map":{"a": 1, "b": 2, "c": 3},"set":[1, 2, 3]}"#),
+    ] {
+        let mut writer = String::new();
+        process_test_case_json_compare_str(
+            &format!("sort_map_keys: {sort_map_keys}"),
+            &[&lookup_def],
+            "Lookup",
+            &mut writer,
+            Some(JsonSerializationOpts {
+                sort_map_keys,
+                ..Default::default()
+            }),
+            buf.clone(),
+            expected,
+        )
+    }
+}
+
+#[test]
+fn deserialize_empty_composites() {
+    let empty_def = IdlTypeDefinition {
+        name: "Empty".to_string(),
+        ty: IdlTypeDefinitionTy::Struct {
+            fields: vec![
+                to_if("array", IdlType::Array(Box::new(IdlType::U16), 0)),
+                to_if("bytes", IdlType::Array(Box::new(IdlType::U8), 0)),
+                to_if("vec", IdlType::Vec(Box::new(IdlType::U16))),
+                to_if(
+                    "map",
+                    IdlType::HashMap(
+                        Box::new(IdlType::String),
+                        Box::new(IdlType::U8),
+                    ),
+                ),
+                to_if("set", IdlType::HashSet(Box::new(IdlType::U8))),
+                to_if("tuple", IdlType::Tuple(vec![])),
+                to_if("last", IdlType::U8),
+            ],
+        },
+    };
+    // Only the vec, map and set are prefixed with their length
+    let buf = [vec![0; 3 * 4], vec![9]].concat();
+
+    for sort_map_keys in [false, true] {
+        let mut writer = String::new();
+        process_test_case_json_compare_str(
+            &format!("sort_map_keys: {sort_map_keys}"),
+            &[&empty_def],
+            "Empty",
+            &mut writer,
+            Some(JsonSerializationOpts {
+                sort_map_keys,
+                ..Default::default()
+            }),
+            buf.clone(),
+            r#"{"array":[],"bytes":[],"vec":[],"map":{},"set":[],"tuple":[],"last":9}"#,
+        )
+    }
+}
+
+#[test]
+fn deserialize_collections_with_lengths_exceeding_data() {
+    let ty_name = "Collections";
+    for (t, ty) in [
+        ("Vec", IdlType::Vec(Box::new(IdlType::U16))),
+        ("HashSet", IdlType::HashSet(Box::new(IdlType::U16))),
+        (
+            "HashMap",
+            IdlType::HashMap(Box::new(IdlType::U8), Box::new(IdlType::U8)),
+        ),
+    ] {
+        let idl_type_def = IdlTypeDefinition {
+            name: ty_name.to_string(),
+            ty: IdlTypeDefinitionTy::Struct {
+                fields: vec![to_if("items", ty)],
+            },
+        };
+        for sort_map_keys in [false, true] {
+            let opts = JsonSerializationOpts {
+                sort_map_keys,
+                ..Default::default()
+            };
+            let de = JsonIdlTypeDefinitionDeserializer::new(
+                &idl_type_def,
+                Arc::new(Mutex::new(HashMap::new())),
+                &opts,
+            );
+            for len in [u32::MAX, 2] {
+                let buf = [len.to_le_bytes().to_vec(), vec![1, 2, 3]].concat();
+                let mut writer = String::new();
+                let err = de
+                    .deserialize(&BorshDeserializer, &mut writer, &mut &buf[..])
+                    .expect_err("Should reject the length");
+                assert!(
+                    err.to_string().contains(&format!(
+                        "{len} elements of at least 2 bytes exceed the 3 remaining bytes"
+                    )),
+                    "{t}: {err}"
+                );
+            }
+        }
+    }
+
+    let t = "Vecs of zero sized elements";
+    {
+        let empty_def = IdlTypeDefinition {
+            name: "Empty".to_string(),
+            ty: IdlTypeDefinitionTy::Struct { fields: vec![] },
+        };
+        let idl_type_def = IdlTypeDefinition {
+            name: ty_name.to_string(),
+            ty: IdlTypeDefinitionTy::Struct {
+                fields: vec![
+                    to_if(
+                        "empties",
+                        IdlType::Vec(Box::new(IdlType::Defined(
+                            "Empty".to_string(),
+                        ))),
+                    ),
+                    to_if(
+                        "units",
+                        IdlType::Vec(Box::new(IdlType::Tuple(vec![]))),
+                    ),
+                    to_if(
+                        "arrays",
+                        IdlType::Vec(Box::new(IdlType::Array(
+                            Box::new(IdlType::U8),
+                            0,
+                        ))),
+                    ),
+                ],
+            },
+        };
+        let buf = [2u32, 1, 3]
+            .iter()
+            .flat_map(|len| len.to_le_bytes())
+            .collect::<Vec<_>>();
+        let mut writer = String::new();
+        process_test_case_json_compare_str(
+            t,
+            &[&empty_def, &idl_type_def],
+            ty_name,
+            &mut writer,
+            None,
+            buf,
+            r#"{"empties":[{}, {}],"units":[[]],"arrays":[[], [], []]}"#,
+        );
+    }
+}
+
+#[test]
+fn deserialize_scaled_decimals() {
+    let usd_def = IdlTypeDefinition {
+        name: "Usd".to_string(),
+        ty: IdlTypeDefinitionTy::Struct {
+            fields: vec![to_if("value", IdlType::U64)],
+        },
+    };
+    let delta_def = IdlTypeDefinition {
+        name: "Delta".to_string(),
+        ty: IdlTypeDefinitionTy::Struct {
+            fields: vec![to_if("value", IdlType::I32)],
+        },
+    };
+    let order_def = IdlTypeDefinition {
+        name: "Order".to_string(),
+        ty: IdlTypeDefinitionTy::Struct {
+            fields: vec![
+                to_if("price", IdlType::Defined("Usd".to_string())),
+                to_if("change", IdlType::Defined("Delta".to_string())),
+            ],
+        },
+    };
+    let buf = [
+        1_500_000u64.to_le_bytes().to_vec(),
+        (-25i32).to_le_bytes().to_vec(),
+    ]
+    .concat();
+    let type_defs = [&usd_def, &delta_def, &order_def];
+
+    let t = "Without scaled decimals";
+    {
+        let mut writer = String::new();
+        process_test_case_json_compare_str(
+            t,
+            &type_defs,
+            "Order",
+            &mut writer,
+            None,
+            buf.clone(),
+            r#"{"price":{"value":1500000},"change":{"value":-25}}"#,
+        )
+    }
+
+    let t = "With scaled decimals";
+    {
+        let mut writer = String::new();
+        process_test_case_json_compare_str(
+            t,
+            &type_defs,
+            "Order",
+            &mut writer,
+            Some(JsonSerializationOpts {
+                scaled_decimals: HashMap::from([
+                    ("Usd".to_string(), 6),
+                    ("Delta".to_string(), 3),
+                ]),
+                ..Default::default()
+            }),
+            buf,
+            r#"{"price":"1.500000","change":"-0.025"}"#,
+        )
+    }
+}
+
+#[test]
+fn serialize_bytes_and_scaled_decimals_rendered_as_strings() {
+    let usd_def = IdlTypeDefinition {
+        name: "Usd".to_string(),
+        ty: IdlTypeDefinitionTy::Struct {
+            fields: vec![to_if("value", IdlType::I64)],
+        },
+    };
+    let vault_def = IdlTypeDefinition {
+        name: "Vault".to_string(),
+        ty: IdlTypeDefinitionTy::Struct {
+            fields: vec![
+                to_if("seed", IdlType::Array(Box::new(IdlType::U8), 4)),
+                to_if("blob", IdlType::Bytes),
+                to_if("data", IdlType::Vec(Box::new(IdlType::U8))),
+                to_if("balance", IdlType::Defined("Usd".to_string())),
+            ],
+        },
+    };
+    let defs = [usd_def, vault_def];
+    let buf = [
+        vec![1, 2, 3, 4],
+        vec![2, 0, 0, 0, 0xab, 0xcd],
+        vec![3, 0, 0, 0, 7, 8, 9],
+        (-1_500_000i64).to_le_bytes().to_vec(),
+    ]
+    .concat();
+
+    for bytes_as in [BytesRepr::Array, BytesRepr::Hex, BytesRepr::Base64] {
+        let opts = JsonSerializationOpts {
+            bytes_as,
+            scaled_decimals: HashMap::from([("Usd".to_string(), 6)]),
+            ..Default::default()
+        };
+        let type_map = Arc::new(Mutex::new(HashMap::new()));
+        for def in &defs {
+            let de = JsonIdlTypeDefinitionDeserializer::new(
+                def,
+                type_map.clone(),
+                &opts,
+            );
+            type_map.lock().unwrap().insert(de.name.clone(), de);
+        }
+        let vault_de = type_map.lock().unwrap().remove("Vault").unwrap();
+        let mut json = String::new();
+        vault_de
+            .deserialize(&BorshDeserializer, &mut json, &mut &buf[..])
+            .expect("Failed to deserialize");
+        type_map.lock().unwrap().clear();
+
+        let value = serde_json::from_str(&json).unwrap();
+        let SerializeProvider::Borsh(ser) = SerializeProvider::borsh() else {
+            unreachable!()
+        };
+        let mut bytes = Vec::new();
+        JsonIdlTypeSerializer::new(&defs)
+            .with_opts(&opts)
+            .serialize_defined(&ser, "Vault", &value, &mut bytes)
+            .expect("Failed to serialize");
+        assert_eq!(bytes, buf, "{json}");
+    }
 }
 
 #[test]
@@ -312,6 +806,30 @@ fn deserialize_large_nums() {
             None,
         );
     }
+
+    let t = "Opts to render u128/i128 as hex";
+    {
+        let buf = [
+            u64::MAX.to_le_bytes().to_vec(),
+            i64::MIN.to_le_bytes().to_vec(),
+            (1u128 << 64).to_le_bytes().to_vec(),
+            (-31i128).to_le_bytes().to_vec(),
+        ]
+        .concat();
+        let mut writer = String::new();
+        process_test_case_json_compare_str(
+            t,
+            &[&idl_type_def],
+            ty_name,
+            &mut writer,
+            Some(JsonSerializationOpts {
+                n128_as_hex: true,
+                ..Default::default()
+            }),
+            buf,
+            r#"{"large_unsigned":18446744073709551615,"large_signed":-9223372036854775808,"very_large_unsigned":"0x10000000000000000","very_large_signed":"-0x1f"}"#,
+        );
+    }
 }
 
 #[test]
@@ -738,3 +1256,1030 @@ fn deserialize_mixed_enum() {
         None,
     );
 }
+
+#[test]
+fn deserialize_enum_valued_integer() {
+    let ty_status = "Status";
+    let itd_status = IdlTypeDefinition {
+        name: ty_status.to_string(),
+        ty: IdlTypeDefinitionTy::Enum {
+            variants: vec![
+                IdlEnumVariant {
+                    name: "Uninitialized".to_string(),
+                    fields: None,
+                },
+                IdlEnumVariant {
+                    name: "Active".to_string(),
+                    fields: None,
+                },
+                IdlEnumVariant {
+                    name: "Closed".to_string(),
+                    fields: None,
+                },
+            ],
+        },
+    };
+
+    let ty_has_status = "HasStatus";
+    #[derive(BorshSerialize)]
+    struct HasStatus {
+        status: u8,
+        amount: u8,
+    }
+
+    let itd_has_status = IdlTypeDefinition {
+        name: ty_has_status.to_string(),
+        ty: IdlTypeDefinitionTy::Struct {
+            fields: vec![
+                IdlField {
+                    name: "status".to_string(),
+                    ty: IdlType::U8,
+                    attrs: Some(vec![format!("enum({ty_status})")]),
+                },
+                to_if("amount", IdlType::U8),
+            ],
+        },
+    };
+    let idl_type_defs = [&itd_status, &itd_has_status];
+
+    let t = "Integer rendered as variant name";
+    {
+        let instance = HasStatus {
+            status: 1,
+            amount: 1,
+        };
+        let mut writer = String::new();
+        process_test_case_json_compare_str(
+            t,
+            &idl_type_defs,
+            ty_has_status,
+            &mut writer,
+            None,
+            instance.try_to_vec().unwrap(),
+            r#"{"status":"Active","amount":1}"#,
+        );
+    }
+}
+
+#[test]
+fn deserialize_defined_type_with_custom_serializer() {
+    let ty_inner = "SplInner";
+    let itd_inner = IdlTypeDefinition {
+        name: ty_inner.to_string(),
+        ty: IdlTypeDefinitionTy::Struct {
+            fields: vec![
+                to_if(
+                    "authority",
+                    IdlType::COption(Box::new(IdlType::PublicKey)),
+                ),
+                to_if("amount", IdlType::U64),
+            ],
+        },
+    };
+    let ty_outer = "BorshOuter";
+    let itd_outer = IdlTypeDefinition {
+        name: ty_outer.to_string(),
+        ty: IdlTypeDefinitionTy::Struct {
+            fields: vec![
+                to_if("id", IdlType::U8),
+                to_if("inner", IdlType::Defined(ty_inner.to_string())),
+                to_if("label", IdlType::String),
+            ],
+        },
+    };
+
+    let opts = JsonSerializationOpts::default();
+    let type_map = Arc::new(Mutex::new(HashMap::new()));
+    let inner_de = JsonIdlTypeDefinitionDeserializer::new(
+        &itd_inner,
+        type_map.clone(),
+        &opts,
+    )
+    .with_de_provider(DeserializeProvider::spl());
+    let outer_de = JsonIdlTypeDefinitionDeserializer::new(
+        &itd_outer,
+        type_map.clone(),
+        &opts,
+    );
+    type_map
+        .lock()
+        .unwrap()
+        .insert(ty_inner.to_string(), inner_de);
+
+    let authority = Pubkey::new_unique();
+    let label = "outer".to_string().try_to_vec().unwrap();
+
+    let t = "COption Some";
+    {
+        let buf = [
+            vec![7],
+            vec![1, 0, 0, 0],
+            authority.to_bytes().to_vec(),
+            42u64.to_le_bytes().to_vec(),
+            label.clone(),
+        ]
+        .concat();
+        let mut writer = String::new();
+        outer_de
+            .deserialize(&BorshDeserializer, &mut writer, &mut &buf[..])
+            .expect("Failed to deserialize");
+        assert_eq!(
+            writer,
+            format!(
+                r#"{{"id":7,"inner":{{"authority":"{authority}","amount":42}},"label":"outer"}}"#
+            ),
+            "{t}"
+        );
+    }
+
+    let t = "COption None";
+    {
+        let buf = [
+            vec![7],
+            vec![0, 0, 0, 0],
+            vec![0; 32],
+            42u64.to_le_bytes().to_vec(),
+            label,
+        ]
+        .concat();
+        let mut writer = String::new();
+        outer_de
+            .deserialize(&BorshDeserializer, &mut writer, &mut &buf[..])
+            .expect("Failed to deserialize");
+        assert_eq!(
+            writer,
+            r#"{"id":7,"inner":{"authority":null,"amount":42},"label":"outer"}"#,
+            "{t}"
+        );
+    }
+
+    type_map.lock().unwrap().clear();
+}
+
+#[test]
+fn deserialize_big_endian_struct() {
+    let ty_name = "Bridged";
+    let idl_type_def = IdlTypeDefinition {
+        name: ty_name.to_string(),
+        ty: IdlTypeDefinitionTy::Struct {
+            fields: vec![
+                to_if("nonce", IdlType::U32),
+                to_if("amount", IdlType::I64),
+                to_if("hops", IdlType::Vec(Box::new(IdlType::U16))),
+            ],
+        },
+    };
+    let opts = JsonSerializationOpts::default();
+    let type_map = Arc::new(Mutex::new(HashMap::new()));
+    let deser =
+        JsonIdlTypeDefinitionDeserializer::new(&idl_type_def, type_map, &opts);
+
+    let le_data = [
+        7u32.to_le_bytes().to_vec(),
+        (-5i64).to_le_bytes().to_vec(),
+        1u32.to_le_bytes().to_vec(),
+        258u16.to_le_bytes().to_vec(),
+    ]
+    .concat();
+    let be_data = [
+        7u32.to_be_bytes().to_vec(),
+        (-5i64).to_be_bytes().to_vec(),
+        1u32.to_be_bytes().to_vec(),
+        258u16.to_be_bytes().to_vec(),
+    ]
+    .concat();
+    let expected = r#"{"nonce":7,"amount":-5,"hops":[258]}"#;
+
+    let mut le_json = String::new();
+    deser
+        .deserialize(&BorshDeserializer, &mut le_json, &mut &le_data[..])
+        .unwrap();
+    assert_eq!(le_json, expected);
+
+    let mut be_json = String::new();
+    deser
+        .deserialize(
+            &BigEndianDeserializer::default(),
+            &mut be_json,
+            &mut &be_data[..],
+        )
+        .unwrap();
+    assert_eq!(be_json, expected);
+
+    // the same bytes decode to different values depending on the byte order
+    let mut json = String::new();
+    deser
+        .deserialize(
+            &BigEndianDeserializer::default(),
+            &mut json,
+            &mut &[7u32.to_le_bytes(), [0; 4], [0; 4], [0; 4]].concat()[..],
+        )
+        .unwrap();
+    assert_eq!(json, r#"{"nonce":117440512,"amount":0,"hops":[]}"#);
+}
+
+#[test]
+fn deserialize_repr_c_struct_with_padding() {
+    let ty_name = "ZeroCopy";
+    let idl_type_def = IdlTypeDefinition {
+        name: ty_name.to_string(),
+        ty: IdlTypeDefinitionTy::Struct {
+            fields: vec![
+                to_if("small", IdlType::U16),
+                to_if("large", IdlType::U64),
+                to_if("flag", IdlType::U8),
+            ],
+        },
+    };
+
+    let opts = JsonSerializationOpts::default();
+    let type_map = Arc::new(Mutex::new(HashMap::new()));
+    let data = [
+        7u16.to_le_bytes().to_vec(),
+        vec![0xff; 6],
+        42u64.to_le_bytes().to_vec(),
+        vec![1],
+    ]
+    .concat();
+
+    let t = "repr(C) pads the struct to its largest field alignment";
+    {
+        let de = JsonIdlTypeDefinitionDeserializer::new(
+            &idl_type_def,
+            type_map.clone(),
+            &opts,
+        )
+        .with_repr_c(1);
+        let buf = [data.clone(), vec![0xff; 7], vec![9]].concat();
+        let buf = &mut &buf[..];
+        let mut writer = String::new();
+        de.deserialize(&BorshDeserializer, &mut writer, buf)
+            .expect("Failed to deserialize");
+        assert_eq!(writer, r#"{"small":7,"large":42,"flag":1}"#, "{t}");
+        assert_eq!(*buf, &[9], "{t}");
+    }
+
+    let t = "repr(C, align(16)) pads the struct to the declared alignment";
+    {
+        let de = JsonIdlTypeDefinitionDeserializer::new(
+            &idl_type_def,
+            type_map.clone(),
+            &opts,
+        )
+        .with_repr_c(16);
+        let buf = [data.clone(), vec![0xff; 15], vec![9]].concat();
+        let buf = &mut &buf[..];
+        let mut writer = String::new();
+        de.deserialize(&BorshDeserializer, &mut writer, buf)
+            .expect("Failed to deserialize");
+        assert_eq!(writer, r#"{"small":7,"large":42,"flag":1}"#, "{t}");
+        assert_eq!(*buf, &[9], "{t}");
+    }
+
+    let t = "Missing trailing padding";
+    {
+        let de = JsonIdlTypeDefinitionDeserializer::new(
+            &idl_type_def,
+            type_map,
+            &opts,
+        )
+        .with_repr_c(1);
+        let mut writer = String::new();
+        let res =
+            de.deserialize(&BorshDeserializer, &mut writer, &mut &data[..]);
+        assert!(res.is_err(), "{t}");
+    }
+}
+
+#[test]
+fn deserialize_struct_followed_by_tlv_region() {
+    let ty_name = "Extensible";
+    let idl_type_def = IdlTypeDefinition {
+        name: ty_name.to_string(),
+        ty: IdlTypeDefinitionTy::Struct {
+            fields: vec![to_if("supply", IdlType::U32)],
+        },
+    };
+    let entries = HashMap::from([(
+        7u16,
+        IdlTypeDefinition {
+            name: "TransferFee".to_string(),
+            ty: IdlTypeDefinitionTy::Struct {
+                fields: vec![to_if("bps", IdlType::U16)],
+            },
+        },
+    )]);
+
+    let opts = JsonSerializationOpts::default();
+    let type_map = Arc::new(Mutex::new(HashMap::new()));
+    let tlv = JsonTlvDeserializer::new(&entries, type_map.clone(), &opts);
+    let de =
+        JsonIdlTypeDefinitionDeserializer::new(&idl_type_def, type_map, &opts)
+            .with_tlv(tlv);
+
+    let buf = [
+        1000u32.to_le_bytes().to_vec(),
+        // known entry
+        7u16.to_le_bytes().to_vec(),
+        2u16.to_le_bytes().to_vec(),
+        50u16.to_le_bytes().to_vec(),
+        // unknown entry
+        9u16.to_le_bytes().to_vec(),
+        3u16.to_le_bytes().to_vec(),
+        vec![0xde, 0xad, 0x01],
+        // uninitialized remainder
+        vec![0; 6],
+    ]
+    .concat();
+
+    let mut writer = String::new();
+    de.deserialize(&BorshDeserializer, &mut writer, &mut &buf[..])
+        .expect("Failed to deserialize");
+    assert_eq!(
+        writer,
+        r#"{"supply":1000,"tlv":[{"type":7,"name":"TransferFee","value":{"bps":50}}, {"type":9,"data":"dead01"}]}"#
+    );
+
+    let t = "Entry length exceeds data";
+    {
+        let buf = [
+            1000u32.to_le_bytes().to_vec(),
+            9u16.to_le_bytes().to_vec(),
+            8u16.to_le_bytes().to_vec(),
+            vec![1, 2],
+        ]
+        .concat();
+        let mut writer = String::new();
+        let res =
+            de.deserialize(&BorshDeserializer, &mut writer, &mut &buf[..]);
+        assert!(res.is_err(), "{t}");
+    }
+}
+
+#[test]
+fn serialize_fixed_layout_struct_round_trip() {
+    let ty_name = "Fixed";
+    let idl_type_def = IdlTypeDefinition {
+        name: ty_name.to_string(),
+        ty: IdlTypeDefinitionTy::Struct {
+            fields: vec![
+                to_if("u8", IdlType::U8),
+                to_if("i16", IdlType::I16),
+                to_if("u32", IdlType::U32),
+                to_if("i64", IdlType::I64),
+                to_if("u128", IdlType::U128),
+                to_if("bool", IdlType::Bool),
+                to_if("f64", IdlType::F64),
+                to_if("key", IdlType::PublicKey),
+                to_if("arr", IdlType::Array(Box::new(IdlType::U16), 3)),
+                to_if(
+                    "authority",
+                    IdlType::COption(Box::new(IdlType::PublicKey)),
+                ),
+                to_if("delegate", IdlType::COption(Box::new(IdlType::U64))),
+            ],
+        },
+    };
+
+    let buf = [
+        vec![255],
+        (-2i16).to_le_bytes().to_vec(),
+        7u32.to_le_bytes().to_vec(),
+        i64::MIN.to_le_bytes().to_vec(),
+        // 128 bit numbers exceeding 64 bits only round trip when stringified
+        (u64::MAX as u128).to_le_bytes().to_vec(),
+        vec![1],
+        1.5f64.to_le_bytes().to_vec(),
+        Pubkey::new_unique().to_bytes().to_vec(),
+        [1u16, 2, 3]
+            .iter()
+            .flat_map(|n| n.to_le_bytes())
+            .collect::<Vec<_>>(),
+        vec![1, 0, 0, 0],
+        Pubkey::new_unique().to_bytes().to_vec(),
+        vec![0; 12],
+    ]
+    .concat();
+
+    for opts in [
+        JsonSerializationOpts::default(),
+        JsonSerializationOpts {
+            pubkey_as_base58: false,
+            n64_as_string: true,
+            n128_as_string: true,
+            ..Default::default()
+        },
+    ] {
+        let type_map = Arc::new(Mutex::new(HashMap::new()));
+        let de = JsonIdlTypeDefinitionDeserializer::new(
+            &idl_type_def,
+            type_map,
+            &opts,
+        )
+        .with_de_provider(DeserializeProvider::spl());
+        let mut json = String::new();
+        de.deserialize(&BorshDeserializer, &mut json, &mut &buf[..])
+            .expect("Failed to deserialize");
+
+        let value = serde_json::from_str(&json).unwrap();
+        let SerializeProvider::Spl(ser) = SerializeProvider::spl() else {
+            unreachable!()
+        };
+        let mut bytes = Vec::new();
+        JsonIdlTypeSerializer::new(std::slice::from_ref(&idl_type_def))
+            .serialize_defined(&ser, ty_name, &value, &mut bytes)
+            .expect("Failed to serialize");
+        assert_eq!(bytes, buf, "{json}");
+    }
+}
+
+#[test]
+fn deserialize_ring_buffer_array() {
+    let ty_name = "Observations";
+    let idl_type_def = IdlTypeDefinition {
+        name: ty_name.to_string(),
+        ty: IdlTypeDefinitionTy::Struct {
+            fields: vec![
+                to_if("head", IdlType::U16),
+                to_if("count", IdlType::U8),
+                IdlField {
+                    attrs: Some(vec![
+                        "ring_buffer(head = head, count = count)".to_string(),
+                    ]),
+                    ..to_if(
+                        "observations",
+                        IdlType::Array(Box::new(IdlType::U32), 5),
+                    )
+                },
+                to_if("last", IdlType::U8),
+            ],
+        },
+    };
+
+    let opts = JsonSerializationOpts::default();
+    let type_map = Arc::new(Mutex::new(HashMap::new()));
+    let de =
+        JsonIdlTypeDefinitionDeserializer::new(&idl_type_def, type_map, &opts);
+
+    let raw = |head: u16, count: u8| {
+        [
+            head.to_le_bytes().to_vec(),
+            vec![count],
+            [40u32, 50, 0, 20, 30]
+                .iter()
+                .flat_map(|n| n.to_le_bytes())
+                .collect::<Vec<_>>(),
+            vec![9],
+        ]
+        .concat()
+    };
+
+    let t = "Wrapping active entries";
+    {
+        let mut writer = String::new();
+        de.deserialize(&BorshDeserializer, &mut writer, &mut &raw(3, 4)[..])
+            .expect("Failed to deserialize");
+        assert_eq!(
+            writer,
+            r#"{"head":3,"count":4,"observations":[20, 30, 40, 50],"last":9}"#,
+            "{t}"
+        );
+    }
+
+    let t = "No active entries";
+    {
+        let mut writer = String::new();
+        de.deserialize(&BorshDeserializer, &mut writer, &mut &raw(0, 0)[..])
+            .expect("Failed to deserialize");
+        assert_eq!(
+            writer, r#"{"head":0,"count":0,"observations":[],"last":9}"#,
+            "{t}"
+        );
+    }
+
+    let t = "Head at the max u64";
+    {
+        let idl_type_def = IdlTypeDefinition {
+            name: ty_name.to_string(),
+            ty: IdlTypeDefinitionTy::Struct {
+                fields: vec![
+                    to_if("head", IdlType::U64),
+                    IdlField {
+                        attrs: Some(vec![
+                            "ring_buffer(head = head)".to_string()
+                        ]),
+                        ..to_if(
+                            "observations",
+                            IdlType::Array(Box::new(IdlType::U8), 3),
+                        )
+                    },
+                ],
+            },
+        };
+        let de = JsonIdlTypeDefinitionDeserializer::new(
+            &idl_type_def,
+            Arc::new(Mutex::new(HashMap::new())),
+            &opts,
+        );
+        let raw = [u64::MAX.to_le_bytes().to_vec(), vec![1, 2, 3]].concat();
+        let mut writer = String::new();
+        de.deserialize(&BorshDeserializer, &mut writer, &mut &raw[..])
+            .expect("Failed to deserialize");
+        // u64::MAX % 3 == 0
+        assert_eq!(
+            writer, r#"{"head":18446744073709551615,"observations":[1, 2, 3]}"#,
+            "{t}"
+        );
+    }
+}
+
+#[test]
+fn deserialize_self_referential_type_exceeding_max_depth() {
+    let ty_name = "Node";
+    let idl_type_def = IdlTypeDefinition {
+        name: ty_name.to_string(),
+        ty: IdlTypeDefinitionTy::Struct {
+            fields: vec![
+                to_if("value", IdlType::U8),
+                to_if(
+                    "next",
+                    IdlType::Option(Box::new(IdlType::Defined(
+                        ty_name.to_string(),
+                    ))),
+                ),
+            ],
+        },
+    };
+    // Each node is its value followed by the option tag of the next node
+    let list = |len: u8| {
+        let mut buf = (0..len).flat_map(|i| [i, 1]).collect::<Vec<_>>();
+        *buf.last_mut().unwrap() = 0;
+        buf
+    };
+
+    let opts = JsonSerializationOpts {
+        max_depth: 8,
+        ..Default::default()
+    };
+    let type_map = Arc::new(Mutex::new(HashMap::new()));
+    let de = JsonIdlTypeDefinitionDeserializer::new(
+        &idl_type_def,
+        type_map.clone(),
+        &opts,
+    );
+    type_map
+        .lock()
+        .unwrap()
+        .insert(ty_name.to_string(), de.clone());
+
+    let t = "Nesting within max depth";
+    {
+        let mut writer = String::new();
+        de.deserialize(&BorshDeserializer, &mut writer, &mut &list(3)[..])
+            .expect("Failed to deserialize");
+        assert_eq!(
+            writer,
+            r#"{"value":0,"next":{"value":1,"next":{"value":2,"next":null}}}"#,
+            "{t}"
+        );
+    }
+
+    let t = "Nesting beyond max depth";
+    {
+        let mut writer = String::new();
+        let err = de
+            .deserialize(&BorshDeserializer, &mut writer, &mut &list(200)[..])
+            .expect_err("Should exceed max depth");
+        assert!(
+            err.to_string()
+                .contains("Exceeded the max depth of 8 nested defined types"),
+            "{t}: {err}"
+        );
+    }
+
+    let t = "Depth is reset after failing";
+    {
+        let mut writer = String::new();
+        de.deserialize(&BorshDeserializer, &mut writer, &mut &list(8)[..])
+            .expect("Failed to deserialize");
+        assert_eq!(writer.matches("value").count(), 8, "{t}");
+    }
+
+    type_map.lock().unwrap().clear();
+}
+
+#[test]
+fn deserialize_lenient_bools() {
+    let ty_name = "Flags";
+    let idl_type_def = IdlTypeDefinition {
+        name: ty_name.to_string(),
+        ty: IdlTypeDefinitionTy::Struct {
+            fields: vec![
+                to_if("on", IdlType::Bool),
+                to_if("off", IdlType::Bool),
+                to_if("maybe", IdlType::Option(Box::new(IdlType::Bool))),
+            ],
+        },
+    };
+    let data = [0xffu8, 0, 1, 0xff];
+
+    let t = "Strict bools reject 0xff";
+    {
+        let opts = JsonSerializationOpts::default();
+        let de = JsonIdlTypeDefinitionDeserializer::new(
+            &idl_type_def,
+            Arc::new(Mutex::new(HashMap::new())),
+            &opts,
+        );
+        let mut writer = String::new();
+        let res =
+            de.deserialize(&BorshDeserializer, &mut writer, &mut &data[..]);
+        assert!(res.is_err(), "{t}");
+    }
+
+    let t = "Lenient bools treat any nonzero byte as true";
+    {
+        let opts = JsonSerializationOpts {
+            lenient_bool: true,
+            ..Default::default()
+        };
+        let de = JsonIdlTypeDefinitionDeserializer::new(
+            &idl_type_def,
+            Arc::new(Mutex::new(HashMap::new())),
+            &opts,
+        );
+        let mut writer = String::new();
+        de.deserialize(&BorshDeserializer, &mut writer, &mut &data[..])
+            .expect("Failed to deserialize");
+        assert_eq!(writer, r#"{"on":true,"off":false,"maybe":true}"#, "{t}");
+    }
+}
+
+#[test]
+fn deserialize_lossy_strings() {
+    let ty_name = "Labels";
+    let idl_type_def = IdlTypeDefinition {
+        name: ty_name.to_string(),
+        ty: IdlTypeDefinitionTy::Struct {
+            fields: vec![
+                to_if("name", IdlType::String),
+                to_if(
+                    "tags",
+                    IdlType::BTreeMap(
+                        Box::new(IdlType::String),
+                        Box::new(IdlType::U8),
+                    ),
+                ),
+            ],
+        },
+    };
+    let invalid = b"ab\xffc".to_vec();
+    let data = [
+        invalid.try_to_vec().unwrap(),
+        1u32.to_le_bytes().to_vec(),
+        invalid.try_to_vec().unwrap(),
+        vec![9],
+    ]
+    .concat();
+
+    let deserialize = |opts: &JsonSerializationOpts| {
+        let de = JsonIdlTypeDefinitionDeserializer::new(
+            &idl_type_def,
+            Arc::new(Mutex::new(HashMap::new())),
+            opts,
+        );
+        let mut writer = String::new();
+        de.deserialize(&BorshDeserializer, &mut writer, &mut &data[..])
+            .map(|_| writer)
+    };
+
+    let t = "Strict strings reject invalid UTF-8";
+    {
+        let opts = JsonSerializationOpts::default();
+        assert!(deserialize(&opts).is_err(), "{t}");
+    }
+
+    let t = "Lossy strings replace invalid UTF-8";
+    {
+        let opts = JsonSerializationOpts {
+            lossy_strings: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            deserialize(&opts).unwrap(),
+            "{\"name\":\"ab\u{fffd}c\",\"tags\":{\"ab\u{fffd}c\": 9}}",
+            "{t}"
+        );
+    }
+}
+
+#[test]
+fn deserialize_best_effort_fixed_size_fields() {
+    let ty_kind = "Kind";
+    let itd_kind = IdlTypeDefinition {
+        name: ty_kind.to_string(),
+        ty: IdlTypeDefinitionTy::Enum {
+            variants: vec![
+                IdlEnumVariant {
+                    name: "A".to_string(),
+                    fields: None,
+                },
+                IdlEnumVariant {
+                    name: "B".to_string(),
+                    fields: None,
+                },
+            ],
+        },
+    };
+    let ty_inner = "Inner";
+    let itd_inner = IdlTypeDefinition {
+        name: ty_inner.to_string(),
+        ty: IdlTypeDefinitionTy::Struct {
+            fields: vec![to_if("ok", IdlType::Bool), to_if("n", IdlType::U16)],
+        },
+    };
+    let ty_outer = "Outer";
+    let itd_outer = IdlTypeDefinition {
+        name: ty_outer.to_string(),
+        ty: IdlTypeDefinitionTy::Struct {
+            fields: vec![
+                to_if("flag", IdlType::Bool),
+                to_if("inner", IdlType::Defined(ty_inner.to_string())),
+                to_if("kind", IdlType::Defined(ty_kind.to_string())),
+                to_if("pair", IdlType::Array(Box::new(IdlType::Bool), 2)),
+                to_if("label", IdlType::String),
+                to_if("maybe", IdlType::Option(Box::new(IdlType::U8))),
+            ],
+        },
+    };
+
+    let deserialize = |opts: &JsonSerializationOpts, buf: &[u8]| {
+        let type_map = Arc::new(Mutex::new(HashMap::new()));
+        for itd in [&itd_kind, &itd_inner] {
+            let de = JsonIdlTypeDefinitionDeserializer::new(
+                itd,
+                type_map.clone(),
+                opts,
+            );
+            type_map.lock().unwrap().insert(itd.name.clone(), de);
+        }
+        let outer_de = JsonIdlTypeDefinitionDeserializer::new(
+            &itd_outer,
+            type_map.clone(),
+            opts,
+        );
+        let mut writer = String::new();
+        let data = &mut &buf[..];
+        outer_de
+            .deserialize(&BorshDeserializer, &mut writer, data)
+            .map(|_| {
+                assert!(data.is_empty(), "all bytes are consumed");
+                writer
+            })
+    };
+    let data =
+        |flag: u8, ok: u8, kind: u8, pair: [u8; 2], label: &[u8], maybe: u8| {
+            [
+                vec![flag, ok],
+                7u16.to_le_bytes().to_vec(),
+                vec![kind],
+                pair.to_vec(),
+                label.to_vec().try_to_vec().unwrap(),
+                vec![maybe],
+            ]
+            .concat()
+        };
+    let valid = data(1, 0, 1, [0, 1], b"x", 0);
+    let invalid_fixed = data(2, 3, 2, [0, 9], b"x", 0);
+    let best_effort = JsonSerializationOpts {
+        best_effort: true,
+        ..Default::default()
+    };
+
+    let t = "Valid data is the same in best effort mode";
+    {
+        let expected = r#"{"flag":true,"inner":{"ok":false,"n":7},"kind":"B","pair":[false, true],"label":"x","maybe":null}"#;
+        assert_eq!(
+            deserialize(&JsonSerializationOpts::default(), &valid).unwrap(),
+            expected,
+            "{t}"
+        );
+        assert_eq!(deserialize(&best_effort, &valid).unwrap(), expected, "{t}");
+    }
+
+    let t = "Failing fixed size fields fail by default";
+    {
+        let res =
+            deserialize(&JsonSerializationOpts::default(), &invalid_fixed);
+        assert!(res.is_err(), "{t}");
+    }
+
+    let t = "Failing fixed size fields are null in best effort mode";
+    {
+        assert_eq!(
+            deserialize(&best_effort, &invalid_fixed).unwrap(),
+            r#"{"flag":null,"inner":{"ok":null,"n":7},"kind":null,"pair":null,"label":"x","maybe":null}"#,
+            "{t}"
+        );
+    }
+
+    let t = "Failing variable size fields fail in best effort mode";
+    {
+        let invalid_string = data(1, 0, 1, [0, 1], b"\xff", 0);
+        let res = deserialize(
+            &JsonSerializationOpts {
+                best_effort: true,
+                lossy_strings: false,
+                ..Default::default()
+            },
+            &invalid_string,
+        );
+        assert!(res.is_err(), "{t}");
+
+        let invalid_option = data(1, 0, 1, [0, 1], b"x", 2);
+        assert!(deserialize(&best_effort, &invalid_option).is_err(), "{t}");
+    }
+
+    let t = "Fixed size fields exceeding the data fail in best effort mode";
+    {
+        assert!(deserialize(&best_effort, &valid[..2]).is_err(), "{t}");
+    }
+}
+
+#[test]
+fn deserialize_struct_with_skipped_fields() {
+    let ty_inner = "Inner";
+    let itd_inner = IdlTypeDefinition {
+        name: ty_inner.to_string(),
+        ty: IdlTypeDefinitionTy::Struct {
+            fields: vec![
+                to_if("padding", IdlType::Array(Box::new(IdlType::U8), 4)),
+                to_if("value", IdlType::U16),
+            ],
+        },
+    };
+    let ty_outer = "Outer";
+    let itd_outer = IdlTypeDefinition {
+        name: ty_outer.to_string(),
+        ty: IdlTypeDefinitionTy::Struct {
+            fields: vec![
+                to_if("padding", IdlType::Array(Box::new(IdlType::U8), 8)),
+                to_if("id", IdlType::U8),
+                to_if("inner", IdlType::Defined(ty_inner.to_string())),
+                to_if("label", IdlType::String),
+                to_if("reserved", IdlType::Vec(Box::new(IdlType::U8))),
+            ],
+        },
+    };
+
+    let buf = [
+        vec![1; 8],
+        vec![7],
+        vec![2; 4],
+        500u16.to_le_bytes().to_vec(),
+        "outer".to_string().try_to_vec().unwrap(),
+        vec![3u8, 4, 5].try_to_vec().unwrap(),
+    ]
+    .concat();
+
+    let deserialize = |opts: &JsonSerializationOpts| {
+        let type_map = Arc::new(Mutex::new(HashMap::new()));
+        let inner_de = JsonIdlTypeDefinitionDeserializer::new(
+            &itd_inner,
+            type_map.clone(),
+            opts,
+        );
+        let outer_de = JsonIdlTypeDefinitionDeserializer::new(
+            &itd_outer,
+            type_map.clone(),
+            opts,
+        );
+        type_map
+            .lock()
+            .unwrap()
+            .insert(ty_inner.to_string(), inner_de);
+
+        let mut writer = String::new();
+        let data = &mut &buf[..];
+        outer_de
+            .deserialize(&BorshDeserializer, &mut writer, data)
+            .expect("Failed to deserialize");
+        assert!(data.is_empty(), "all bytes are consumed");
+        writer
+    };
+
+    let t = "Skipping leading, nested and trailing fields";
+    {
+        let opts = JsonSerializationOpts {
+            skip_fields: HashSet::from([
+                "padding".to_string(),
+                "reserved".to_string(),
+            ]),
+            ..Default::default()
+        };
+        assert_eq!(
+            deserialize(&opts),
+            r#"{"id":7,"inner":{"value":500},"label":"outer"}"#,
+            "{t}"
+        );
+    }
+
+    let t = "Skipping all fields of a nested struct";
+    {
+        let opts = JsonSerializationOpts {
+            skip_fields: HashSet::from([
+                "padding".to_string(),
+                "value".to_string(),
+                "label".to_string(),
+                "reserved".to_string(),
+            ]),
+            ..Default::default()
+        };
+        assert_eq!(deserialize(&opts), r#"{"id":7,"inner":{}}"#, "{t}");
+    }
+
+    let t = "Skipping no fields";
+    {
+        let opts = JsonSerializationOpts::default();
+        assert_eq!(
+            deserialize(&opts),
+            r#"{"padding":[1, 1, 1, 1, 1, 1, 1, 1],"id":7,"inner":{"padding":[2, 2, 2, 2],"value":500},"label":"outer","reserved":[3, 4, 5]}"#,
+            "{t}"
+        );
+    }
+}
+
+#[test]
+fn deserialize_u8_arrays_per_bytes_repr() {
+    let ty_name = "Seeds";
+    let idl_type_def = IdlTypeDefinition {
+        name: ty_name.to_string(),
+        ty: IdlTypeDefinitionTy::Struct {
+            fields: vec![
+                to_if("hash", IdlType::Array(Box::new(IdlType::U8), 4)),
+                to_if("seed", IdlType::Vec(Box::new(IdlType::U8))),
+                to_if("data", IdlType::Bytes),
+                to_if("weights", IdlType::Array(Box::new(IdlType::U16), 2)),
+            ],
+        },
+    };
+    let buf = [
+        vec![0xde, 0xad, 0xbe, 0xef],
+        vec![1u8, 2, 3].try_to_vec().unwrap(),
+        vec![255u8].try_to_vec().unwrap(),
+        [1u16, 2].try_to_vec().unwrap(),
+    ]
+    .concat();
+
+    let deserialize = |bytes_as: BytesRepr, buf: &[u8]| {
+        let opts = JsonSerializationOpts {
+            bytes_as,
+            ..Default::default()
+        };
+        let de = JsonIdlTypeDefinitionDeserializer::new(
+            &idl_type_def,
+            Arc::new(Mutex::new(HashMap::new())),
+            &opts,
+        );
+        let mut writer = String::new();
+        let data = &mut &buf[..];
+        de.deserialize(&BorshDeserializer, &mut writer, data)
+            .map(|_| {
+                assert!(data.is_empty(), "all bytes are consumed");
+                writer
+            })
+    };
+
+    let t = "Arrays of numbers by default";
+    {
+        assert_eq!(
+            deserialize(BytesRepr::Array, &buf).unwrap(),
+            r#"{"hash":[222, 173, 190, 239],"seed":[1, 2, 3],"data":[255],"weights":[1, 2]}"#,
+            "{t}"
+        );
+    }
+
+    let t = "Hex strings";
+    {
+        assert_eq!(
+            deserialize(BytesRepr::Hex, &buf).unwrap(),
+            r#"{"hash":"deadbeef","seed":"010203","data":"ff","weights":[1, 2]}"#,
+            "{t}"
+        );
+    }
+
+    let t = "Base64 strings";
+    {
+        assert_eq!(
+            deserialize(BytesRepr::Base64, &buf).unwrap(),
+            r#"{"hash":"3q2+7w==","seed":"AQID","data":"/w==","weights":[1, 2]}"#,
+            "{t}"
+        );
+    }
+
+    let t = "Array exceeding the data";
+    {
+        let res = deserialize(BytesRepr::Hex, &buf[..3]);
+        assert!(res.is_err(), "{t}");
+    }
+}
@@ -0,0 +1,421 @@
+use std::collections::BTreeMap;
+
+use borsh::BorshSerialize;
+use chainparser::{
+    discriminator::account_discriminator,
+    errors::ChainparserError,
+    errors::ChainparserResult,
+    format::{ChainparserVisitor, JsonFormatWriter},
+    idl::IdlProvider,
+    json::{BytesRepr, UnknownPolicy},
+    pubkey::Pubkey,
+    ChainparserDeserializer, JsonSerializationOpts,
+};
+
+const LEDGER_IDL_JSON: &str = include_str!("fixtures/ledger_idl.json");
+const LEDGER_PROGRAM_ID: &str = "Ledger1111111111111111111111111111111111111";
+
+#[derive(BorshSerialize)]
+struct Entry {
+    amount: u64,
+    note: Option<String>,
+}
+
+#[derive(BorshSerialize)]
+enum Status {
+    #[allow(unused)]
+    Idle,
+    #[allow(unused)]
+    Active {
+        since: i64,
+    },
+    Moved(u8, Pubkey),
+}
+
+#[derive(BorshSerialize)]
+struct Ledger {
+    owner: Pubkey,
+    bump: u8,
+    offset: i16,
+    balance: u64,
+    debt: i64,
+    total: u128,
+    rate: f32,
+    active: bool,
+    label: String,
+    memo: Vec<u8>,
+    signers: Vec<Pubkey>,
+    closed_at: Option<i64>,
+    weights: [u16; 3],
+    pair: (u8, bool),
+    limits: BTreeMap<String, u32>,
+    flags: BTreeMap<u8, bool>,
+    entries: Vec<Entry>,
+    status: Status,
+}
+
+fn ledger_data() -> Vec<u8> {
+    let ledger = Ledger {
+        owner: Pubkey::new_unique(),
+        bump: 254,
+        offset: -3,
+        balance: u64::MAX,
+        debt: i64::MIN,
+        total: u128::MAX,
+        rate: 1.5,
+        active: true,
+        label: "main \"ledger\"".to_string(),
+        memo: vec![1, 2, 3],
+        signers: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+        closed_at: None,
+        weights: [1, 2, 3],
+        pair: (7, false),
+        limits: BTreeMap::from([
+            ("daily".to_string(), 100),
+            ("weekly".to_string(), 500),
+        ]),
+        flags: BTreeMap::from([(1, true), (2, false)]),
+        entries: vec![
+            Entry {
+                amount: 10,
+                note: Some("first".to_string()),
+            },
+            Entry {
+                amount: 20,
+                note: None,
+            },
+        ],
+        status: Status::Moved(3, Pubkey::new_unique()),
+    };
+    [
+        account_discriminator("Ledger").to_vec(),
+        ledger.try_to_vec().unwrap(),
+    ]
+    .concat()
+}
+
+fn chainparser(opts: &JsonSerializationOpts) -> ChainparserDeserializer<'_> {
+    let mut chainparser = ChainparserDeserializer::new(opts);
+    chainparser
+        .add_idl_json(
+            LEDGER_PROGRAM_ID.to_string(),
+            LEDGER_IDL_JSON,
+            IdlProvider::Anchor,
+        )
+        .expect("failed adding IDL JSON");
+    chainparser
+}
+
+#[test]
+fn json_format_writer_matches_json_deserializer() {
+    let data = ledger_data();
+    for opts in [
+        JsonSerializationOpts::default(),
+        JsonSerializationOpts {
+            pubkey_as_base58: false,
+            n64_as_string: true,
+            n128_as_string: true,
+            bytes_as: BytesRepr::Base64,
+            ..Default::default()
+        },
+        JsonSerializationOpts {
+            n128_as_hex: true,
+            ..Default::default()
+        },
+        JsonSerializationOpts {
+            include_account_name: true,
+            include_discriminator: true,
+            sort_map_keys: true,
+            ..Default::default()
+        },
+    ] {
+        let chainparser = chainparser(&opts);
+        let expected = chainparser
+            .deserialize_account_to_json_string(
+                LEDGER_PROGRAM_ID,
+                &mut &data[..],
+            )
+            .unwrap();
+
+        let mut json = String::new();
+        let mut w = JsonFormatWriter::new(&mut json, &opts);
+        chainparser
+            .deserialize_account_with_visitor(
+                LEDGER_PROGRAM_ID,
+                &mut &data[..],
+                &mut w,
+            )
+            .unwrap();
+        assert_eq!(json, expected);
+    }
+}
+
+#[test]
+fn visitor_handles_unknown_accounts_per_opts() {
+    let data = [vec![1; 8], vec![2]].concat();
+    for (unknown_account, expected) in [
+        (
+            UnknownPolicy::HexDump,
+            r#"{"_unknown":true,"data":"010101010101010102"}"#,
+        ),
+        (UnknownPolicy::Skip, "null"),
+    ] {
+        let opts = JsonSerializationOpts {
+            unknown_account,
+            ..Default::default()
+        };
+        let chainparser = chainparser(&opts);
+        let mut json = String::new();
+        let mut w = JsonFormatWriter::new(&mut json, &opts);
+        chainparser
+            .deserialize_account_with_visitor(
+                LEDGER_PROGRAM_ID,
+                &mut &data[..],
+                &mut w,
+            )
+            .unwrap();
+        assert_eq!(json, expected);
+    }
+
+    let opts = JsonSerializationOpts::default();
+    let chainparser = chainparser(&opts);
+    let mut json = String::new();
+    let mut w = JsonFormatWriter::new(&mut json, &opts);
+    assert!(matches!(
+        chainparser.deserialize_account_with_visitor(
+            LEDGER_PROGRAM_ID,
+            &mut &data[..],
+            &mut w,
+        ),
+        Err(ChainparserError::UnknownDiscriminatedAccount(_))
+    ));
+    assert!(matches!(
+        chainparser.deserialize_account_with_visitor(
+            "unknown",
+            &mut &ledger_data()[..],
+            &mut w,
+        ),
+        Err(ChainparserError::CannotFindAccountDeserializerForProgramId(
+            _
+        ))
+    ));
+}
+
+/// Collects the public keys of an account along with the path of fields leading to each.
+#[derive(Default)]
+struct PubkeyCollector {
+    path: Vec<String>,
+    pubkeys: Vec<(String, Pubkey)>,
+    integers: usize,
+}
+
+impl ChainparserVisitor for PubkeyCollector {
+    fn begin_struct(&mut self, _len: usize) -> ChainparserResult<()> {
+        self.path.push(String::new());
+        Ok(())
+    }
+    fn field_name(&mut self, name: &str) -> ChainparserResult<()> {
+        *self.path.last_mut().unwrap() = name.to_string();
+        Ok(())
+    }
+    fn end_struct(&mut self) -> ChainparserResult<()> {
+        self.path.pop();
+        Ok(())
+    }
+    fn begin_seq(&mut self, _len: usize) -> ChainparserResult<()> {
+        Ok(())
+    }
+    fn end_seq(&mut self) -> ChainparserResult<()> {
+        Ok(())
+    }
+    fn begin_map(&mut self, _len: usize) -> ChainparserResult<()> {
+        Ok(())
+    }
+    fn begin_map_key(&mut self) -> ChainparserResult<()> {
+        Ok(())
+    }
+    fn end_map_key(&mut self) -> ChainparserResult<()> {
+        Ok(())
+    }
+    fn end_map(&mut self) -> ChainparserResult<()> {
+        Ok(())
+    }
+    fn null_value(&mut self) -> ChainparserResult<()> {
+        Ok(())
+    }
+    fn bool_value(&mut self, _b: bool) -> ChainparserResult<()> {
+        Ok(())
+    }
+    fn u32_value(&mut self, _n: u32) -> ChainparserResult<()> {
+        self.integers += 1;
+        Ok(())
+    }
+    fn u64_value(&mut self, _n: u64) -> ChainparserResult<()> {
+        self.integers += 1;
+        Ok(())
+    }
+    fn u128_value(&mut self, _n: u128) -> ChainparserResult<()> {
+        self.integers += 1;
+        Ok(())
+    }
+    fn i32_value(&mut self, _n: i32) -> ChainparserResult<()> {
+        self.integers += 1;
+        Ok(())
+    }
+    fn i64_value(&mut self, _n: i64) -> ChainparserResult<()> {
+        self.integers += 1;
+        Ok(())
+    }
+    fn i128_value(&mut self, _n: i128) -> ChainparserResult<()> {
+        self.integers += 1;
+        Ok(())
+    }
+    fn f64_value(&mut self, _n: f64) -> ChainparserResult<()> {
+        Ok(())
+    }
+    fn str_value(&mut self, _s: &str) -> ChainparserResult<()> {
+        Ok(())
+    }
+    fn pubkey_value(
+        &mut self,
+        pubkey: &chainparser::pubkey::Pubkey,
+    ) -> ChainparserResult<()> {
+        self.pubkeys.push((self.path.join("."), *pubkey));
+        Ok(())
+    }
+}
+
+#[test]
+fn deserialize_account_with_custom_visitor() {
+    let data = ledger_data();
+    let opts = JsonSerializationOpts::default();
+    let parser = chainparser(&opts);
+
+    let mut visitor = PubkeyCollector::default();
+    parser
+        .deserialize_account_with_visitor(
+            LEDGER_PROGRAM_ID,
+            &mut &data[..],
+            &mut visitor,
+        )
+        .unwrap();
+
+    let json = parser
+        .deserialize_account_to_json_string(LEDGER_PROGRAM_ID, &mut &data[..])
+        .unwrap();
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let paths = visitor
+        .pubkeys
+        .iter()
+        .map(|(path, pubkey)| (path.as_str(), pubkey.to_string()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        paths,
+        vec![
+            ("owner", value["owner"].as_str().unwrap().to_string()),
+            ("signers", value["signers"][0].as_str().unwrap().to_string()),
+            ("signers", value["signers"][1].as_str().unwrap().to_string()),
+            (
+                "status.Moved",
+                value["status"]["Moved"][1].as_str().unwrap().to_string()
+            ),
+        ]
+    );
+    // bump, offset, balance, debt, total, 3 weights, pair.0, 2 limits, 2 flag keys,
+    // 2 entry amounts, the Moved u8 and 3 memo bytes
+    assert_eq!(visitor.integers, 19);
+}
+
+#[cfg(feature = "msgpack")]
+#[test]
+fn deserialize_account_to_msgpack() {
+    let data = ledger_data();
+    let opts = JsonSerializationOpts {
+        n128_as_string: true,
+        ..Default::default()
+    };
+    let chainparser = chainparser(&opts);
+
+    let msgpack = chainparser
+        .deserialize_account_to_msgpack(LEDGER_PROGRAM_ID, &mut &data[..])
+        .unwrap();
+    let json = chainparser
+        .deserialize_account_to_json_string(LEDGER_PROGRAM_ID, &mut &data[..])
+        .unwrap();
+    assert!(msgpack.len() < json.len());
+
+    // bytes are encoded as binary which has no JSON counterpart
+    let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    value.as_object_mut().unwrap().remove("memo");
+    let mut decoded: BTreeMap<String, rmpv::Value> =
+        rmp_serde::from_slice(&msgpack).unwrap();
+    assert_eq!(
+        decoded.remove("memo"),
+        Some(rmpv::Value::Binary(vec![1, 2, 3]))
+    );
+    assert_eq!(serde_json::to_value(decoded).unwrap(), value);
+}
+
+#[cfg(feature = "bson")]
+#[test]
+fn deserialize_account_to_bson() {
+    use bson::{spec::BinarySubtype, Bson};
+
+    let data = ledger_data();
+    let opts = JsonSerializationOpts::default();
+    let parser = chainparser(&opts);
+
+    let doc = parser
+        .deserialize_account_to_bson(LEDGER_PROGRAM_ID, &mut &data[..])
+        .unwrap();
+    let json = parser
+        .deserialize_account_to_json_string(LEDGER_PROGRAM_ID, &mut &data[..])
+        .unwrap();
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(doc.get_str("owner").unwrap(), value["owner"]);
+    assert_eq!(doc.get("bump"), Some(&Bson::Int32(254)));
+    assert_eq!(doc.get("offset"), Some(&Bson::Int32(-3)));
+    assert!(matches!(doc.get("balance"), Some(Bson::Decimal128(_))));
+    assert_eq!(
+        doc.get("balance").unwrap().to_string(),
+        u64::MAX.to_string()
+    );
+    assert_eq!(doc.get("debt"), Some(&Bson::Int64(i64::MIN)));
+    assert_eq!(doc.get_str("total").unwrap(), u128::MAX.to_string());
+    assert_eq!(doc.get("rate"), Some(&Bson::Double(1.5)));
+    assert_eq!(doc.get("closedAt"), Some(&Bson::Null));
+    let memo = doc.get_binary_generic("memo").unwrap();
+    assert_eq!(memo, &vec![1, 2, 3]);
+
+    let limits = doc.get_document("limits").unwrap();
+    assert_eq!(limits.get("weekly"), Some(&Bson::Int32(500)));
+    let flags = doc.get_document("flags").unwrap();
+    assert_eq!(flags.get("2"), Some(&Bson::Boolean(false)));
+    let entries = doc.get_array("entries").unwrap();
+    assert_eq!(
+        entries[1].as_document().unwrap().get("note"),
+        Some(&Bson::Null)
+    );
+    let moved = doc
+        .get_document("status")
+        .unwrap()
+        .get_array("Moved")
+        .unwrap();
+    assert_eq!(moved[1].as_str().unwrap(), value["status"]["Moved"][1]);
+
+    let opts = JsonSerializationOpts {
+        pubkey_as_base58: false,
+        ..Default::default()
+    };
+    let doc = chainparser(&opts)
+        .deserialize_account_to_bson(LEDGER_PROGRAM_ID, &mut &data[..])
+        .unwrap();
+    match doc.get("owner") {
+        Some(Bson::Binary(binary)) => {
+            assert_eq!(binary.subtype, BinarySubtype::Generic);
+            assert_eq!(binary.bytes.len(), 32);
+        }
+        owner => panic!("expected binary owner, got {owner:?}"),
+    }
+}
@@ -0,0 +1,82 @@
+use borsh::BorshSerialize;
+use chainparser::{
+    discriminator::account_discriminator, errors::ChainparserError,
+    pubkey::Pubkey,
+};
+use chainparser_derive::idl_account;
+
+#[idl_account(idl = "tests/fixtures/vault_idl.json")]
+pub struct VaultInfo;
+
+#[idl_account(idl = "tests/fixtures/vault_idl.json", account = "VaultInfo")]
+struct RenamedVaultInfo;
+
+#[derive(BorshSerialize)]
+struct VaultInfoData {
+    authority: Pubkey,
+    balance: u64,
+    total_deposited: u128,
+    label: String,
+    depositors: Vec<Pubkey>,
+    delegate: Option<Pubkey>,
+    fees: Vec<u16>,
+    locked_until: Option<i64>,
+    ty: u8,
+}
+
+fn vault_data() -> (VaultInfoData, Vec<u8>) {
+    let vault = VaultInfoData {
+        authority: Pubkey::new_unique(),
+        balance: 5,
+        total_deposited: u128::MAX,
+        label: "main \"vault\"".to_string(),
+        depositors: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+        delegate: Some(Pubkey::new_unique()),
+        fees: vec![30, 50],
+        locked_until: None,
+        ty: 2,
+    };
+    let data = [
+        account_discriminator("VaultInfo").to_vec(),
+        vault.try_to_vec().unwrap(),
+    ]
+    .concat();
+    (vault, data)
+}
+
+#[test]
+fn idl_account_from_account_data() {
+    let (expected, data) = vault_data();
+    let vault = VaultInfo::from_account_data(&data).unwrap();
+    assert_eq!(vault.authority, expected.authority);
+    assert_eq!(vault.balance, expected.balance);
+    assert_eq!(vault.total_deposited, expected.total_deposited);
+    assert_eq!(vault.label, expected.label);
+    assert_eq!(vault.depositors, expected.depositors);
+    assert_eq!(vault.delegate, expected.delegate);
+    assert_eq!(vault.fees, expected.fees);
+    assert_eq!(vault.locked_until, expected.locked_until);
+    assert_eq!(vault.r#type, expected.ty);
+
+    // Deserializing again reuses the deserializer created the first time
+    assert_eq!(VaultInfo::from_account_data(&data).unwrap(), vault);
+
+    let renamed = RenamedVaultInfo::from_account_data(&data).unwrap();
+    assert_eq!(renamed.authority, expected.authority);
+}
+
+#[test]
+fn idl_account_from_account_data_with_wrong_discriminator() {
+    let (_, mut data) = vault_data();
+    data[0] ^= 0xff;
+    assert!(matches!(
+        VaultInfo::from_account_data(&data),
+        Err(ChainparserError::AccountDiscriminatorMismatch(name)) if name == "VaultInfo"
+    ));
+    assert!(matches!(
+        VaultInfo::from_account_data(&data[..4]),
+        Err(ChainparserError::AccountDataTooShortForDiscriminatorBytes(
+            4, 8
+        ))
+    ));
+}